@@ -2,6 +2,11 @@ use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use rand::prelude::*;
 use rustzss::LZSS;
 
+// Count allocations like the autotuner's benchmarks do
+#[cfg(feature = "alloc-count")]
+#[global_allocator]
+static ALLOCATOR: rustzss::autotune::alloc::CountingAllocator = rustzss::autotune::alloc::CountingAllocator;
+
 fn generate_random_data(size: usize) -> Vec<u8> {
     let mut rng = rand::thread_rng();
     let mut data = Vec::with_capacity(size);
@@ -55,15 +60,15 @@ fn basic_benchmark(c: &mut Criterion) {
     let lzss = LZSS::new(4096, 3);
     
     // Random data - 10KB
-    let random_data = generate_random_data(1000_000);
+    let random_data = generate_random_data(1_000_000);
     let compressed_random = lzss.compress(&random_data);
     
     // Repeated data - 10KB  
-    let repeated_data = generate_repeated_data(1000_000);
+    let repeated_data = generate_repeated_data(1_000_000);
     let compressed_repeated = lzss.compress(&repeated_data);
     
     // Text data - 10KB
-    let text_data = generate_text_data(1000_000);
+    let text_data = generate_text_data(1_000_000);
     let compressed_text = lzss.compress(&text_data);
     
     // Compression benchmarks
@@ -81,15 +86,15 @@ fn basic_benchmark(c: &mut Criterion) {
     
     // Decompression benchmarks
     c.bench_function("decompress_random", |b| {
-        b.iter(|| lzss.decompress(black_box(&compressed_random)))
+        b.iter(|| lzss.try_decompress(black_box(&compressed_random)).unwrap())
     });
     
     c.bench_function("decompress_repeated", |b| {
-        b.iter(|| lzss.decompress(black_box(&compressed_repeated)))
+        b.iter(|| lzss.try_decompress(black_box(&compressed_repeated)).unwrap())
     });
     
     c.bench_function("decompress_text", |b| {
-        b.iter(|| lzss.decompress(black_box(&compressed_text)))
+        b.iter(|| lzss.try_decompress(black_box(&compressed_text)).unwrap())
     });
     
     // Print compression ratios
@@ -139,7 +144,7 @@ fn window_size_benchmark(c: &mut Criterion) {
         
         // Measure decompression
         group.bench_function(format!("decompress_window_{}", window_size), |b| {
-            b.iter(|| lzss.decompress(black_box(&compressed)))
+            b.iter(|| lzss.try_decompress(black_box(&compressed)).unwrap())
         });
     }
     
@@ -0,0 +1,23 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // Generate and compile the C++ side of the cxx bridge
+    #[cfg(feature = "cxx")]
+    {
+        cxx_build::bridge("src/cxx_bridge.rs").std("c++20").compile("rustzss-cxx");
+        println!("cargo:rerun-if-changed=src/cxx_bridge.rs");
+        println!("cargo:rerun-if-changed=include/rustzss_cxx.hpp");
+    }
+
+    // Regenerate the C header from the extern "C" functions in src/ffi.rs
+    #[cfg(feature = "c-header")]
+    {
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let config = cbindgen::Config::from_file(format!("{crate_dir}/cbindgen.toml")).expect("invalid cbindgen.toml");
+        cbindgen::generate_with_config(&crate_dir, config)
+            .expect("failed to generate the C header")
+            .write_to_file(format!("{crate_dir}/include/rustzss.h"));
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+    }
+}
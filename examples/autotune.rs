@@ -1,6 +1,9 @@
+// Most of this example only exists with the autotune feature enabled
+#![cfg_attr(not(feature = "autotune"), allow(unused_imports, dead_code))]
+
 #[cfg(feature = "autotune")]
 use rustzss::autotune::{
-    AssetInfo, AssetType, Tuner, TunerConfig, quick_benchmark, scan_directory
+    codegen, AssetInfo, AssetType, CompressionParameters, Tuner, TunerConfig, quick_benchmark, scan_directory
 };
 use std::collections::HashMap;
 use std::env;
@@ -10,6 +13,11 @@ use std::path::Path;
 use std::process;
 use std::time::Duration;
 
+// Lets benchmark results report allocation counts
+#[cfg(feature = "alloc-count")]
+#[global_allocator]
+static ALLOCATOR: rustzss::autotune::alloc::CountingAllocator = rustzss::autotune::alloc::CountingAllocator;
+
 #[cfg(feature = "autotune")]
 fn main() -> io::Result<()> {
     // Parse command line arguments
@@ -39,8 +47,11 @@ fn main() -> io::Result<()> {
             let ratio_priority = args.get(3)
                 .map(|s| s.parse().unwrap_or(0.5))
                 .unwrap_or(0.5);
+            
+            // Parse optional storage throughput for load-time estimates
+            let disk_mb_per_sec = args.get(4).and_then(|s| s.parse().ok());
                 
-            tune_directory(&args[2], ratio_priority)?;
+            tune_directory(&args[2], ratio_priority, disk_mb_per_sec)?;
         }
         "profile" => {
             if args.len() < 3 {
@@ -49,8 +60,12 @@ fn main() -> io::Result<()> {
                 process::exit(1);
             }
             
-            // Generate profile
-            profile_asset_types(&args[2])?;
+            // Generate profile, grouping by directory if requested
+            if args.get(3).map(String::as_str) == Some("--by-directory") {
+                profile_directories(&args[2])?;
+            } else {
+                profile_asset_types(&args[2])?;
+            }
         }
         "help" | "--help" | "-h" => {
             print_usage();
@@ -76,11 +91,13 @@ fn print_usage() {
     println!("\nUsage:");
     println!("  autotune benchmark <directory> [max_files]");
     println!("    - Run benchmark on assets in the directory with default parameters");
-    println!("  autotune tune <directory> [ratio_priority]");
+    println!("  autotune tune <directory> [ratio_priority] [disk_mb_per_sec]");
     println!("    - Tune parameters for assets in the directory");
     println!("    - ratio_priority: A value between 0.0 (prioritize speed) and 1.0 (prioritize compression ratio)");
-    println!("  autotune profile <directory>");
+    println!("    - disk_mb_per_sec: Storage read throughput used to estimate asset load times");
+    println!("  autotune profile <directory> [--by-directory]");
     println!("    - Generate optimal parameter profiles for different asset types");
+    println!("    - --by-directory: Group assets by subdirectory instead of asset type");
     println!("  autotune help");
     println!("    - Display this help message");
 }
@@ -139,7 +156,7 @@ fn benchmark_directory(dir_path: &str, max_files: Option<usize>) -> io::Result<(
 }
 
 #[cfg(feature = "autotune")]
-fn tune_directory(dir_path: &str, ratio_priority: f64) -> io::Result<()> {
+fn tune_directory(dir_path: &str, ratio_priority: f64, disk_mb_per_sec: Option<f64>) -> io::Result<()> {
     println!("Scanning directory {} for assets...", dir_path);
     let mut assets = scan_directory(dir_path, Some(50))?; // Limit to 50 files for reasonable tuning time
     
@@ -157,6 +174,7 @@ fn tune_directory(dir_path: &str, ratio_priority: f64) -> io::Result<()> {
         ratio_priority,
         random_seed: None,
         parallel: true,
+        ..TunerConfig::default()
     };
     
     println!("Starting parameter tuning with ratio_priority = {:.2}", ratio_priority);
@@ -172,22 +190,39 @@ fn tune_directory(dir_path: &str, ratio_priority: f64) -> io::Result<()> {
     println!("Parameters tested: {}", result.iterations);
     
     println!("\nBest Overall Parameters:");
-    println!("Window Size: {}, Min Match Length: {}", 
+    println!("Window Size: {}, Min Match Length: {}, Nice Length: {}", 
              result.best_parameters.window_size, 
-             result.best_parameters.min_match_length);
+             result.best_parameters.min_match_length,
+             result.best_parameters.nice_length);
     println!("Compression Ratio: {:.2}%", result.best_result.compression_ratio_percent());
     println!("Compression Throughput: {:.2} MB/s", result.best_result.compression_throughput());
     println!("Decompression Throughput: {:.2} MB/s", result.best_result.decompression_throughput());
     
     println!("\nBest Ratio Parameters (regardless of speed):");
-    println!("Window Size: {}, Min Match Length: {}", 
+    println!("Window Size: {}, Min Match Length: {}, Nice Length: {}", 
              result.best_ratio_parameters.window_size, 
-             result.best_ratio_parameters.min_match_length);
+             result.best_ratio_parameters.min_match_length,
+             result.best_ratio_parameters.nice_length);
     
     println!("\nBest Speed Parameters (regardless of ratio):");
-    println!("Window Size: {}, Min Match Length: {}", 
+    println!("Window Size: {}, Min Match Length: {}, Nice Length: {}", 
              result.best_speed_parameters.window_size, 
-             result.best_speed_parameters.min_match_length);
+             result.best_speed_parameters.min_match_length,
+             result.best_speed_parameters.nice_length);
+    
+    println!("\nRationale:");
+    for explanation in &result.explanations {
+        println!("- {}", explanation);
+    }
+    
+    // Estimate load times if the storage throughput was given
+    let load_report = disk_mb_per_sec.map(|mb_per_sec| (mb_per_sec, result.load_time_report(mb_per_sec)));
+    if let Some((mb_per_sec, report)) = &load_report {
+        println!("\nEstimated Load Times at {:.1} MB/s:", mb_per_sec);
+        for estimate in report {
+            println!("{}", estimate);
+        }
+    }
     
     // Save tuning results to a file
     let output_path = Path::new(dir_path).join("rustzss_tuning_results.txt");
@@ -204,9 +239,27 @@ fn tune_directory(dir_path: &str, ratio_priority: f64) -> io::Result<()> {
     writeln!(file, "\nRecommended Parameters:")?;
     writeln!(file, "Window Size: {}", result.best_parameters.window_size)?;
     writeln!(file, "Min Match Length: {}", result.best_parameters.min_match_length)?;
+    writeln!(file, "Nice Length: {}", result.best_parameters.nice_length)?;
     writeln!(file, "Compression Ratio: {:.2}%", result.best_result.compression_ratio_percent())?;
     
+    writeln!(file, "\nRationale:")?;
+    for explanation in &result.explanations {
+        writeln!(file, "- {}", explanation)?;
+    }
+    
+    if let Some((mb_per_sec, report)) = &load_report {
+        writeln!(file, "\nEstimated Load Times at {:.1} MB/s:", mb_per_sec)?;
+        for estimate in report {
+            writeln!(file, "{}", estimate)?;
+        }
+    }
+    
+    // Embed the results in a Rust module
+    let module_path = Path::new(dir_path).join("rustzss_profile.rs");
+    result.write_rust_module(&module_path)?;
+    
     println!("\nResults saved to {}", output_path.display());
+    println!("Rust profile module written to {}", module_path.display());
     
     Ok(())
 }
@@ -230,6 +283,7 @@ fn profile_asset_types(dir_path: &str) -> io::Result<()> {
         ratio_priority: 0.5, // Balanced approach
         random_seed: None,
         parallel: true,
+        ..TunerConfig::default()
     };
     
     println!("Generating asset type profiles...");
@@ -259,23 +313,70 @@ fn profile_asset_types(dir_path: &str) -> io::Result<()> {
         writeln!(file, "{:?}:", asset_type)?;
         writeln!(file, "  Window Size: {}", params.window_size)?;
         writeln!(file, "  Min Match Length: {}", params.min_match_length)?;
+        writeln!(file, "  Nice Length: {}", params.nice_length)?;
     }
     
-    // Generate code snippet
-    writeln!(file, "\n// Code snippet for easy integration:")?;
-    writeln!(file, "fn get_optimal_parameters(asset_type: AssetType) -> (usize, usize) {{")?;
-    writeln!(file, "    match asset_type {{")?;
+    // Generate a Rust module for easy integration
+    let module_path = Path::new(dir_path).join("rustzss_profiles.rs");
+    codegen::write_rust_module(&module_path, &profiles, CompressionParameters::new(4096, 3))?;
+    println!("\nRust profile module written to {}", module_path.display());
     
-    for (asset_type, params) in &profiles {
-        writeln!(file, "        AssetType::{:?} => ({}, {}),", 
-                 asset_type, params.window_size, params.min_match_length)?;
+    println!("\nProfiles saved to {}", output_path.display());
+    
+    Ok(())
+}
+
+#[cfg(feature = "autotune")]
+fn profile_directories(dir_path: &str) -> io::Result<()> {
+    println!("Scanning directory {} for assets...", dir_path);
+    let mut assets = scan_directory(dir_path, Some(100))?; // Limit to 100 files
+    
+    println!("Found {} assets for profiling", assets.len());
+    if assets.is_empty() {
+        println!("No assets found to profile");
+        return Ok(());
     }
     
-    writeln!(file, "        _ => (4096, 3), // Default parameters")?;
-    writeln!(file, "    }}")?;
-    writeln!(file, "}}")?;
+    // Configure tuner
+    let config = TunerConfig {
+        benchmark_runs: 2,
+        max_tuning_time: Some(Duration::from_secs(600)), // 10 minute limit
+        max_iterations: 15,  // Fewer iterations per directory
+        ratio_priority: 0.5, // Balanced approach
+        ..TunerConfig::default()
+    };
+    
+    println!("Generating directory profiles...");
+    println!("This may take several minutes...");
+    
+    // Create tuner and generate profiles
+    let mut tuner = Tuner::new(config);
+    let profiles = tuner.generate_directory_profiles(&mut assets, Path::new(dir_path));
+    
+    // Print and save results
+    println!("\nDirectory Profiles:");
+    
+    let output_path = Path::new(dir_path).join("rustzss_directory_profiles.txt");
+    let mut file = File::create(&output_path)?;
+    
+    writeln!(file, "RustLZSS Directory Profiles")?;
+    writeln!(file, "===========================")?;
+    writeln!(file, "Assets directory: {}", dir_path)?;
+    writeln!(file, "Number of assets analyzed: {}", assets.len())?;
+    
+    writeln!(file, "\nOptimal parameters for each directory:")?;
+    
+    for (directory, params) in &profiles {
+        println!("{}/: Window Size = {}, Min Match Length = {}", 
+                 directory.display(), params.window_size, params.min_match_length);
+        
+        writeln!(file, "{}/:", directory.display())?;
+        writeln!(file, "  Window Size: {}", params.window_size)?;
+        writeln!(file, "  Min Match Length: {}", params.min_match_length)?;
+        writeln!(file, "  Nice Length: {}", params.nice_length)?;
+    }
     
     println!("\nProfiles saved to {}", output_path.display());
     
     Ok(())
-}
\ No newline at end of file
+}
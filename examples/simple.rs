@@ -1,15 +1,85 @@
 use rustzss::LZSS;
-use std::io::{self, Read, Write};
+use rustzss::fs::{write_atomic, WriteOptions};
+use std::io::{self, Read};
 use std::fs::File;
 use std::env;
 
+/// Digest algorithms for `--hash`
+#[derive(Clone, Copy)]
+enum HashAlgorithm {
+    Xxh3,
+    Crc32,
+}
+
+impl HashAlgorithm {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "xxh3" => Some(HashAlgorithm::Xxh3),
+            "crc32" => Some(HashAlgorithm::Crc32),
+            _ => None,
+        }
+    }
+    
+    fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Xxh3 => "xxh3",
+            HashAlgorithm::Crc32 => "crc32",
+        }
+    }
+    
+    /// Hex digest of `data`
+    fn digest(self, data: &[u8]) -> String {
+        match self {
+            HashAlgorithm::Xxh3 => format!("{:016x}", xxhash_rust::xxh3::xxh3_64(data)),
+            HashAlgorithm::Crc32 => format!("{:08x}", crc32fast::hash(data)),
+        }
+    }
+}
+
+fn usage(program: &str) -> ! {
+    eprintln!("Usage: {} <compress|decompress> <input_file> <output_file> [window_size] [--hash xxh3|crc32] [--expect-hash <hex>] [--fsync]", program);
+    eprintln!("\nWindow size is optional (default: 4096). Recommended values: 4096, 8192, 16384, 32768");
+    eprintln!("--hash prints digests of the input and output; --expect-hash fails if the decompressed output differs");
+    eprintln!("--fsync flushes the output to disk before exiting");
+    std::process::exit(1);
+}
+
 fn main() -> io::Result<()> {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    
+    // Pull the options out, leaving the positional arguments
+    let mut hash = None;
+    let mut expected_hash = None;
+    let mut write_options = WriteOptions::default();
+    let mut i = 1;
+    while i < args.len() {
+        let option = args[i].clone();
+        if option == "--fsync" {
+            write_options = write_options.with_fsync(true);
+            args.remove(i);
+            continue;
+        }
+        if option != "--hash" && option != "--expect-hash" {
+            i += 1;
+            continue;
+        }
+        if i + 1 >= args.len() {
+            usage(&args[0]);
+        }
+        let value = args.remove(i + 1);
+        args.remove(i);
+        if option == "--hash" {
+            hash = Some(HashAlgorithm::parse(&value).unwrap_or_else(|| {
+                eprintln!("Unknown hash algorithm: {}. Use 'xxh3' or 'crc32'", value);
+                std::process::exit(1);
+            }));
+        } else {
+            expected_hash = Some(value.to_ascii_lowercase());
+        }
+    }
     
     if args.len() < 4 {
-        eprintln!("Usage: {} <compress|decompress> <input_file> <output_file> [window_size]", args[0]);
-        eprintln!("\nWindow size is optional (default: 4096). Recommended values: 4096, 8192, 16384, 32768");
-        std::process::exit(1);
+        usage(&args[0]);
     }
     
     let mode = &args[1];
@@ -31,6 +101,11 @@ fn main() -> io::Result<()> {
     
     let min_match_length = 3;
     
+    if expected_hash.is_some() && mode != "decompress" {
+        eprintln!("--expect-hash only applies to decompress");
+        std::process::exit(1);
+    }
+    
     // Read input file
     let mut input_file = File::open(input_filename)?;
     let mut input_data = Vec::new();
@@ -54,7 +129,13 @@ fn main() -> io::Result<()> {
         "decompress" => {
             println!("Decompressing {} to {} with window size {}", input_filename, output_filename, window_size);
             let start = std::time::Instant::now();
-            let decompressed = lzss.decompress(&input_data);
+            let decompressed = match lzss.try_decompress(&input_data) {
+                Ok(decompressed) => decompressed,
+                Err(e) => {
+                    eprintln!("Error decompressing {}: {}", input_filename, e);
+                    std::process::exit(1);
+                }
+            };
             let duration = start.elapsed();
             println!("Decompressed {} bytes to {} bytes in {:.2?}",
                 input_data.len(), decompressed.len(), duration);
@@ -66,9 +147,23 @@ fn main() -> io::Result<()> {
         }
     };
     
-    // Write output file
-    let mut output_file = File::create(output_filename)?;
-    output_file.write_all(&output_data)?;
+    // Print digests, and check the decompressed one before writing anything
+    let algorithm = hash.unwrap_or(HashAlgorithm::Xxh3);
+    if hash.is_some() {
+        println!("Input {}: {}", algorithm.name(), algorithm.digest(&input_data));
+        println!("Output {}: {}", algorithm.name(), algorithm.digest(&output_data));
+    }
+    if let Some(expected) = expected_hash {
+        let actual = algorithm.digest(&output_data);
+        if actual != expected {
+            eprintln!("Hash mismatch: expected {} {}, got {}", algorithm.name(), expected, actual);
+            std::process::exit(2);
+        }
+    }
+    
+    // Write output file (via a temporary file, so an interrupted run leaves
+    // no truncated output behind)
+    write_atomic(output_filename, &output_data, &write_options)?;
     
     Ok(())
-}
\ No newline at end of file
+}
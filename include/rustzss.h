@@ -1,73 +1,509 @@
 #ifndef RUSTZSS_H
 #define RUSTZSS_H
 
-#ifdef __cplusplus
-extern "C" {
-#endif
+/* Generated by cbindgen from src/ffi.rs; do not edit by hand */
 
 #include <stdint.h>
 #include <stddef.h>
 
 /**
- * Opaque struct representing an LZSS context
+ * Checksum stored in a frame header
+ */
+typedef enum LzssChecksum {
+  /**
+   * No checksum
+   */
+  LZSS_CHECKSUM_NONE = 0,
+  /**
+   * CRC32 (IEEE)
+   */
+  LZSS_CHECKSUM_CRC32 = 1,
+  /**
+   * xxHash3, 64-bit
+   */
+  LZSS_CHECKSUM_XXH3 = 2,
+} LzssChecksum;
+
+/**
+ * Capabilities reported by `lzss_features`, one bit each
+ */
+typedef enum LzssFeature {
+  /**
+   * Self-describing frames are decoded with their own parameters
+   */
+  LZSS_FEATURE_FRAMES = 1,
+  /**
+   * Frame checksums: CRC32
+   */
+  LZSS_FEATURE_CRC32 = 2,
+  /**
+   * Frame checksums: xxHash3
+   */
+  LZSS_FEATURE_XXH3 = 4,
+  /**
+   * Preset dictionaries (`lzss_load_dictionary`)
+   */
+  LZSS_FEATURE_DICTIONARIES = 8,
+  /**
+   * Huffman-coded streams (Rust API only)
+   */
+  LZSS_FEATURE_HUFFMAN = 16,
+  /**
+   * Built for small targets: windows are capped at 4096 bytes
+   */
+  LZSS_FEATURE_SMALL_TARGET = 32,
+} LzssFeature;
+
+/**
+ * Layout of a compressed buffer, as reported by `lzss_get_frame_info`
+ */
+typedef enum LzssFormat {
+  /**
+   * Self-describing frame
+   */
+  LZSS_FORMAT_FRAMED = 0,
+  /**
+   * Multi-block stream with per-block parameters
+   */
+  LZSS_FORMAT_BLOCKS = 1,
+  /**
+   * Raw stream without parameters
+   */
+  LZSS_FORMAT_RAW = 2,
+  /**
+   * Microsoft SZDD file
+   */
+  LZSS_FORMAT_SZDD = 3,
+  /**
+   * Nintendo LZ10 data
+   */
+  LZSS_FORMAT_LZ10 = 4,
+  /**
+   * Nintendo LZ11 data
+   */
+  LZSS_FORMAT_LZ11 = 5,
+} LzssFormat;
+
+/**
+ * Status codes returned by the functions that report success or failure
+ */
+typedef enum LzssStatus {
+  /**
+   * The call succeeded
+   */
+  LZSS_STATUS_OK = 0,
+  /**
+   * A required pointer was null or a parameter was out of range
+   */
+  LZSS_STATUS_INVALID_PARAMETERS = -1,
+  /**
+   * The output buffer is too small for the result
+   */
+  LZSS_STATUS_BUFFER_TOO_SMALL = -2,
+  /**
+   * The input is corrupt or does not decode to the expected size
+   */
+  LZSS_STATUS_CORRUPT_INPUT = -3,
+  /**
+   * The progress callback cancelled the operation
+   */
+  LZSS_STATUS_CANCELLED = -4,
+} LzssStatus;
+
+/**
+ * Opaque struct to hold the LZSS compressor instance
  */
 typedef struct LzssContext LzssContext;
 
+/**
+ * Opaque struct holding the windows of a packet stream, such as one
+ * network connection
+ *
+ * Each packet may reference the packets before it, so both ends must
+ * process every packet in order. Outgoing and incoming packets keep
+ * separate windows.
+ */
+typedef struct LzssStream LzssStream;
+
+/**
+ * Summary of a compressed buffer, filled in by `lzss_get_frame_info`
+ *
+ * Fields a format doesn't record are 0.
+ */
+typedef struct LzssFrameInfo {
+  /**
+   * Detected layout
+   */
+  enum LzssFormat format;
+  /**
+   * Layout version (frames and multi-block streams)
+   */
+  int version;
+  /**
+   * Uncompressed content size in bytes
+   */
+  unsigned long original_size;
+  /**
+   * Size of the compressed buffer in bytes
+   */
+  unsigned long compressed_size;
+  /**
+   * Minimum match length used by the encoder (frames)
+   */
+  int min_match_length;
+  /**
+   * Bytes per match distance (frames)
+   */
+  int offset_bytes;
+  /**
+   * How far back matches can reach (frames that record it)
+   */
+  unsigned long window_size;
+  /**
+   * Checksum of the content (frames)
+   */
+  enum LzssChecksum checksum;
+  /**
+   * Number of independently decodable blocks
+   */
+  unsigned long block_count;
+} LzssFrameInfo;
+
+/**
+ * Progress callback for long operations
+ *
+ * Called with the caller's user data, the number of bytes processed so far
+ * and the total. Returning nonzero cancels the operation.
+ */
+typedef int (*LzssProgress)(void *user_data, uint64_t done, uint64_t total);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Version of the library, e.g. "0.1.0"
+ *
+ * Lets hosts that load the library dynamically check which version they
+ * got. The string is static and must not be freed.
+ */
+const char *lzss_version(void);
+
+/**
+ * Features the library was built with, as a bitmask of `LzssFeature`
+ */
+unsigned int lzss_features(void);
+
 /**
  * Create a new LZSS context with specified parameters
  *
- * @param window_size Size of the sliding window (up to 65535)
- * @param min_match_length Minimum match length for encoding
- * @return Pointer to the LZSS context or NULL on failure
+ * # Parameters
+ * * `window_size` - Size of the sliding window (up to 65535, or 4096 in
+ *   `small-target` builds)
+ * * `min_match_length` - Minimum match length for encoding
+ *
+ * # Returns
+ * Pointer to the LZSS context or null on failure
  */
-LzssContext* lzss_create(int window_size, int min_match_length);
+struct LzssContext *lzss_create(int window_size, int min_match_length);
 
 /**
  * Free resources used by the LZSS context
  *
- * @param context LZSS context created with lzss_create
+ * # Parameters
+ * * `context` - LZSS context created with lzss_create
  */
-void lzss_destroy(LzssContext* context);
+void lzss_destroy(struct LzssContext *context);
+
+/**
+ * Load a preset dictionary into the context
+ *
+ * Subsequent `lzss_compress` and `lzss_decompress` calls on the context use
+ * the dictionary, so data compressed with it can only be decompressed by a
+ * context holding the same dictionary. The bytes are copied. Passing a
+ * null pointer or zero length removes the dictionary.
+ *
+ * # Parameters
+ * * `context` - LZSS context created with lzss_create
+ * * `dictionary` - Pointer to the dictionary bytes
+ * * `dictionary_size` - Size of the dictionary in bytes
+ *
+ * # Returns
+ * 0 on success, a negative `LzssStatus` on failure
+ */
+int lzss_load_dictionary(struct LzssContext *context,
+                         const unsigned char *dictionary,
+                         unsigned long dictionary_size);
 
 /**
  * Compress data using LZSS algorithm
  *
- * @param context LZSS context created with lzss_create
- * @param input Pointer to input data buffer
- * @param input_size Size of the input data in bytes
- * @param output Pointer to output buffer (must be pre-allocated)
- * @param output_size Size of the output buffer in bytes
- * @param compressed_size Pointer to where the actual compressed size will be stored
- * @return 0 on success, negative error code on failure
+ * # Parameters
+ * * `context` - LZSS context created with lzss_create
+ * * `input` - Pointer to input data buffer
+ * * `input_size` - Size of the input data in bytes
+ * * `output` - Pointer to output buffer (must be pre-allocated)
+ * * `output_size` - Size of the output buffer in bytes
+ * * `compressed_size` - Pointer to where the actual compressed size will be stored,
+ *   or the size the output buffer needs if it is too small
+ *
+ * # Returns
+ * 0 on success, a negative `LzssStatus` on failure
  */
-int lzss_compress(
-    const LzssContext* context,
-    const unsigned char* input,
-    unsigned long input_size,
-    unsigned char* output,
-    unsigned long output_size,
-    unsigned long* compressed_size
-);
+int lzss_compress(const struct LzssContext *context,
+                  const unsigned char *input,
+                  unsigned long input_size,
+                  unsigned char *output,
+                  unsigned long output_size,
+                  unsigned long *compressed_size);
 
 /**
  * Decompress data using LZSS algorithm
  *
- * @param context LZSS context created with lzss_create
- * @param input Pointer to compressed data buffer
- * @param input_size Size of the compressed data in bytes
- * @param output Pointer to output buffer (must be pre-allocated)
- * @param output_size Size of the output buffer in bytes
- * @param decompressed_size Pointer to where the actual decompressed size will be stored
- * @return 0 on success, negative error code on failure
+ * # Parameters
+ * * `context` - LZSS context created with lzss_create
+ * * `input` - Pointer to compressed data buffer
+ * * `input_size` - Size of the compressed data in bytes
+ * * `output` - Pointer to output buffer (must be pre-allocated)
+ * * `output_size` - Size of the output buffer in bytes
+ * * `decompressed_size` - Pointer to where the actual decompressed size will be stored,
+ *   or the size the output buffer needs if it is too small
+ *
+ * # Returns
+ * 0 on success, a negative `LzssStatus` on failure
+ */
+int lzss_decompress(const struct LzssContext *context,
+                    const unsigned char *input,
+                    unsigned long input_size,
+                    unsigned char *output,
+                    unsigned long output_size,
+                    unsigned long *decompressed_size);
+
+/**
+ * Decompress data into a caller's buffer without trusting its size header
+ *
+ * Unlike `lzss_decompress`, the size in the header is checked against
+ * `output_size` before anything is decoded or allocated, so a forged or
+ * corrupt header can't make the library reserve more memory than the
+ * caller's buffer.
+ *
+ * # Parameters
+ * * `context` - LZSS context created with lzss_create
+ * * `input` - Pointer to compressed data buffer
+ * * `input_size` - Size of the compressed data in bytes
+ * * `output` - Pointer to output buffer
+ * * `output_size` - Size of the output buffer in bytes
+ * * `decompressed_size` - Pointer to where the actual decompressed size will be stored,
+ *   or the size the output buffer needs if it is too small
+ *
+ * # Returns
+ * 0 on success, -1 for invalid parameters, -2 if the output buffer is too
+ * small, -3 for corrupt input (the output may then have been partly
+ * written)
+ */
+int lzss_decompress_bounded(const struct LzssContext *context,
+                            const unsigned char *input,
+                            unsigned long input_size,
+                            unsigned char *output,
+                            unsigned long output_size,
+                            unsigned long *decompressed_size);
+
+/**
+ * Compress data against a dictionary given for this call only
+ *
+ * Like `lzss_compress`, but uses `dictionary` instead of one loaded into
+ * the context, so one context can serve payloads with different
+ * dictionaries, from several threads at once. Decompress the output with
+ * `lzss_decompress_with_dict` and the same dictionary.
+ *
+ * # Parameters
+ * * `context` - LZSS context created with lzss_create
+ * * `dictionary` - Pointer to the dictionary bytes (null for none)
+ * * `dictionary_size` - Size of the dictionary in bytes
+ * * `input` - Pointer to input data buffer
+ * * `input_size` - Size of the input data in bytes
+ * * `output` - Pointer to output buffer (must be pre-allocated)
+ * * `output_size` - Size of the output buffer in bytes
+ * * `compressed_size` - Pointer to where the actual compressed size will be stored,
+ *   or the size the output buffer needs if it is too small
+ *
+ * # Returns
+ * 0 on success, a negative `LzssStatus` on failure
+ */
+int lzss_compress_with_dict(const struct LzssContext *context,
+                            const unsigned char *dictionary,
+                            unsigned long dictionary_size,
+                            const unsigned char *input,
+                            unsigned long input_size,
+                            unsigned char *output,
+                            unsigned long output_size,
+                            unsigned long *compressed_size);
+
+/**
+ * Decompress data compressed against a dictionary given for this call only
+ *
+ * Like `lzss_decompress_bounded`, but uses `dictionary` instead of one
+ * loaded into the context.
+ *
+ * # Parameters
+ * * `context` - LZSS context created with lzss_create
+ * * `dictionary` - Pointer to the dictionary bytes (null for none)
+ * * `dictionary_size` - Size of the dictionary in bytes
+ * * `input` - Pointer to compressed data buffer
+ * * `input_size` - Size of the compressed data in bytes
+ * * `output` - Pointer to output buffer
+ * * `output_size` - Size of the output buffer in bytes
+ * * `decompressed_size` - Pointer to where the actual decompressed size will be stored,
+ *   or the size the output buffer needs if it is too small
+ *
+ * # Returns
+ * 0 on success, a negative `LzssStatus` on failure
+ */
+int lzss_decompress_with_dict(const struct LzssContext *context,
+                              const unsigned char *dictionary,
+                              unsigned long dictionary_size,
+                              const unsigned char *input,
+                              unsigned long input_size,
+                              unsigned char *output,
+                              unsigned long output_size,
+                              unsigned long *decompressed_size);
+
+/**
+ * Compress data in one call, without a context
+ *
+ * Compression keeps no state between calls, so this is the same as
+ * `lzss_compress` on a fresh context, with nothing to create or destroy.
+ * It is safe to call from any number of threads at once.
+ *
+ * # Parameters
+ * * `window_size` - Size of the sliding window (as for lzss_create)
+ * * `min_match_length` - Minimum match length for encoding
+ * * `input` - Pointer to input data buffer
+ * * `input_size` - Size of the input data in bytes
+ * * `output` - Pointer to output buffer (must be pre-allocated)
+ * * `output_size` - Size of the output buffer in bytes
+ * * `compressed_size` - Pointer to where the actual compressed size will be stored,
+ *   or the size the output buffer needs if it is too small
+ *
+ * # Returns
+ * 0 on success, a negative `LzssStatus` on failure
+ */
+int lzss_compress_simple(int window_size,
+                         int min_match_length,
+                         const unsigned char *input,
+                         unsigned long input_size,
+                         unsigned char *output,
+                         unsigned long output_size,
+                         unsigned long *compressed_size);
+
+/**
+ * Decompress data in one call, without a context
+ *
+ * The same as `lzss_decompress_bounded` on a fresh context: the size
+ * header is checked against `output_size` before decoding. It is safe to
+ * call from any number of threads at once.
+ *
+ * # Parameters
+ * * `window_size` - Size of the sliding window used for compression
+ * * `min_match_length` - Minimum match length used for compression
+ * * `input` - Pointer to compressed data buffer
+ * * `input_size` - Size of the compressed data in bytes
+ * * `output` - Pointer to output buffer
+ * * `output_size` - Size of the output buffer in bytes
+ * * `decompressed_size` - Pointer to where the actual decompressed size will be stored,
+ *   or the size the output buffer needs if it is too small
+ *
+ * # Returns
+ * 0 on success, a negative `LzssStatus` on failure
+ */
+int lzss_decompress_simple(int window_size,
+                           int min_match_length,
+                           const unsigned char *input,
+                           unsigned long input_size,
+                           unsigned char *output,
+                           unsigned long output_size,
+                           unsigned long *decompressed_size);
+
+/**
+ * Compress data into a buffer allocated by the library
+ *
+ * Like `lzss_compress`, without sizing the output buffer up front. The
+ * buffer must be released with `lzss_free`.
+ *
+ * # Parameters
+ * * `context` - LZSS context created with lzss_create
+ * * `input` - Pointer to input data buffer
+ * * `input_size` - Size of the input data in bytes
+ * * `output` - Pointer to where the address of the compressed data will be stored
+ * * `output_size` - Pointer to where the compressed size will be stored
+ *
+ * # Returns
+ * 0 on success, a negative `LzssStatus` on failure
+ */
+int lzss_compress_alloc(const struct LzssContext *context,
+                        const unsigned char *input,
+                        unsigned long input_size,
+                        unsigned char **output,
+                        unsigned long *output_size);
+
+/**
+ * Decompress data into a buffer allocated by the library
+ *
+ * Like `lzss_decompress`, without sizing the output buffer up front. The
+ * input is validated before anything is allocated, so a forged size header
+ * can't force a large allocation. The buffer must be released with
+ * `lzss_free`.
+ *
+ * # Parameters
+ * * `context` - LZSS context created with lzss_create
+ * * `input` - Pointer to compressed data buffer
+ * * `input_size` - Size of the compressed data in bytes
+ * * `output` - Pointer to where the address of the decompressed data will be stored
+ * * `output_size` - Pointer to where the decompressed size will be stored
+ *
+ * # Returns
+ * 0 on success, a negative `LzssStatus` on failure
+ */
+int lzss_decompress_alloc(const struct LzssContext *context,
+                          const unsigned char *input,
+                          unsigned long input_size,
+                          unsigned char **output,
+                          unsigned long *output_size);
+
+/**
+ * Free a buffer returned by `lzss_compress_alloc` or `lzss_decompress_alloc`
+ *
+ * # Parameters
+ * * `data` - Buffer returned by the library (null is ignored)
+ * * `size` - Size of the buffer, as returned alongside it
+ */
+void lzss_free(unsigned char *data, unsigned long size);
+
+/**
+ * Decompress into a buffer of exactly the decompressed size
+ *
+ * Matches the memory-to-memory shape of engine compression plugins such as
+ * an Unreal `FCompressionFormat`'s `Uncompress`: nothing is allocated, and
+ * the call fails unless the data decodes cleanly to exactly `output_size`
+ * bytes. Neither buffer needs any particular alignment; frames written
+ * with an alignment option are passed whole, padding included.
+ *
+ * # Parameters
+ * * `context` - LZSS context created with lzss_create
+ * * `input` - Pointer to compressed data buffer
+ * * `input_size` - Size of the compressed data in bytes
+ * * `output` - Pointer to output buffer
+ * * `output_size` - Expected decompressed size in bytes
+ *
+ * # Returns
+ * 0 on success, -1 for invalid parameters, -3 for corrupt input or a size
+ * mismatch (the output may then have been partly written)
  */
-int lzss_decompress(
-    const LzssContext* context,
-    const unsigned char* input,
-    unsigned long input_size,
-    unsigned char* output,
-    unsigned long output_size,
-    unsigned long* decompressed_size
-);
+int lzss_decompress_exact(const struct LzssContext *context,
+                          const unsigned char *input,
+                          unsigned long input_size,
+                          unsigned char *output,
+                          unsigned long output_size);
 
 /**
  * Estimate the maximum compressed size for a given input size
@@ -76,27 +512,233 @@ int lzss_decompress(
  * In the worst case, LZSS compression can result in slight expansion,
  * especially for incompressible data (e.g., already compressed or random data).
  *
- * @param input_size Size of the input data in bytes
- * @return Estimated maximum compressed size in bytes
+ * # Parameters
+ * * `input_size` - Size of the input data in bytes
+ *
+ * # Returns
+ * Estimated maximum compressed size in bytes
  */
 unsigned long lzss_max_compressed_size(unsigned long input_size);
 
 /**
  * Get the original size of compressed data without decompressing it
  *
- * This function extracts the original size from the header of the compressed data
+ * This function extracts the original size from the header of the compressed data,
+ * skipping the frame header of self-describing frames
+ *
+ * # Parameters
+ * * `compressed_data` - Pointer to compressed data buffer
+ * * `compressed_size` - Size of the compressed data in bytes
+ *
+ * # Returns
+ * Original uncompressed size in bytes, or 0 if invalid
+ */
+unsigned long lzss_get_original_size(const unsigned char *compressed_data,
+                                     unsigned long compressed_size);
+
+/**
+ * Inspect a compressed buffer without decompressing it
+ *
+ * Reads only headers, so asset packers can check the format, sizes and
+ * parameters of compressed data cheaply. Raw streams carry no parameters
+ * and are only recognized if their size header is plausible.
+ *
+ * # Parameters
+ * * `data` - Pointer to compressed data buffer
+ * * `size` - Size of the compressed data in bytes
+ * * `info` - Pointer to where the summary will be stored
+ *
+ * # Returns
+ * 0 on success, -1 for invalid parameters, -3 if the data isn't a
+ * recognized format
+ */
+int lzss_get_frame_info(const unsigned char *data, unsigned long size, struct LzssFrameInfo *info);
+
+/**
+ * Compress data like `lzss_compress`, reporting progress
+ *
+ * `progress` is called about every megabyte of input and once more when
+ * compression finishes, so GUIs can show progress on large inputs and
+ * let the user cancel. The return value of the final call is ignored.
+ *
+ * # Parameters
+ * * `context` - LZSS context created with lzss_create
+ * * `input` - Pointer to input data buffer
+ * * `input_size` - Size of the input data in bytes
+ * * `output` - Pointer to output buffer (must be pre-allocated)
+ * * `output_size` - Size of the output buffer in bytes
+ * * `compressed_size` - Pointer to where the actual compressed size will be stored,
+ *   or the size the output buffer needs if it is too small
+ * * `progress` - Callback receiving the input bytes compressed so far (may be null)
+ * * `user_data` - Pointer passed through to `progress`
+ *
+ * # Returns
+ * 0 on success, a negative `LzssStatus` on failure (`LZSS_STATUS_CANCELLED`
+ * if the callback cancelled)
+ */
+int lzss_compress_progress(const struct LzssContext *context,
+                           const unsigned char *input,
+                           unsigned long input_size,
+                           unsigned char *output,
+                           unsigned long output_size,
+                           unsigned long *compressed_size,
+                           LzssProgress progress,
+                           void *user_data);
+
+/**
+ * Decompress data like `lzss_decompress_bounded`, reporting progress
+ *
+ * `progress` is called about every megabyte of output and once more when
+ * decompression finishes.
+ *
+ * # Parameters
+ * * `context` - LZSS context created with lzss_create
+ * * `input` - Pointer to compressed data buffer
+ * * `input_size` - Size of the compressed data in bytes
+ * * `output` - Pointer to output buffer
+ * * `output_size` - Size of the output buffer in bytes
+ * * `decompressed_size` - Pointer to where the actual decompressed size will be stored,
+ *   or the size the output buffer needs if it is too small
+ * * `progress` - Callback receiving the bytes decompressed so far (may be null)
+ * * `user_data` - Pointer passed through to `progress`
+ *
+ * # Returns
+ * 0 on success, a negative `LzssStatus` on failure (`LZSS_STATUS_CANCELLED`
+ * if the callback cancelled)
+ */
+int lzss_decompress_progress(const struct LzssContext *context,
+                             const unsigned char *input,
+                             unsigned long input_size,
+                             unsigned char *output,
+                             unsigned long output_size,
+                             unsigned long *decompressed_size,
+                             LzssProgress progress,
+                             void *user_data);
+
+/**
+ * Create a packet stream with empty windows
+ *
+ * # Parameters
+ * * `window_size` - Size of the sliding window (as for lzss_create)
+ * * `min_match_length` - Minimum match length for encoding
+ *
+ * # Returns
+ * Pointer to the stream or null on failure
+ */
+struct LzssStream *lzss_stream_create(int window_size, int min_match_length);
+
+/**
+ * Free resources used by a packet stream
+ *
+ * # Parameters
+ * * `stream` - Stream created with lzss_stream_create
+ */
+void lzss_stream_destroy(struct LzssStream *stream);
+
+/**
+ * Start both windows of a stream from a preset dictionary
+ *
+ * Both windows are reset, so call this before the first packet, on both
+ * ends. The bytes are copied. Passing a null pointer or zero length
+ * removes the dictionary.
+ *
+ * # Parameters
+ * * `stream` - Stream created with lzss_stream_create
+ * * `dictionary` - Pointer to the dictionary bytes
+ * * `dictionary_size` - Size of the dictionary in bytes
+ *
+ * # Returns
+ * 0 on success, a negative `LzssStatus` on failure
+ */
+int lzss_stream_set_dictionary(struct LzssStream *stream,
+                               const unsigned char *dictionary,
+                               unsigned long dictionary_size);
+
+/**
+ * Keep the decompression window of a stream in memory the caller provides
+ *
+ * Lets embedded callers place the window (as large as the stream's
+ * window size, 4-64 KiB) in a specific memory region instead of the
+ * library's heap. The window is reset to the dictionary, so call this
+ * before the first incoming packet. `window` must stay valid, and must not
+ * be touched or overlap the buffers of `lzss_stream_decompress`, until the
+ * stream is destroyed or gets another window. Passing a null pointer or
+ * zero length goes back to a window allocated by the library.
+ *
+ * # Parameters
+ * * `stream` - Stream created with lzss_stream_create
+ * * `window` - Pointer to the window memory
+ * * `window_size` - Size of the window memory in bytes; at least the
+ *   stream's window size, of which only that much is used
+ *
+ * # Returns
+ * 0 on success, -1 for invalid parameters (including memory smaller than
+ * the stream's window)
+ */
+int lzss_stream_set_window(struct LzssStream *stream,
+                           unsigned char *window,
+                           unsigned long window_size);
+
+/**
+ * Forget all previous packets, returning both windows to the dictionary
+ *
+ * # Parameters
+ * * `stream` - Stream created with lzss_stream_create
+ */
+void lzss_stream_reset(struct LzssStream *stream);
+
+/**
+ * Compress the next outgoing packet of a stream
+ *
+ * The output buffer must hold the worst case for the packet (see
+ * `lzss_max_compressed_size`); otherwise nothing is compressed, the
+ * window is unchanged and `compressed_size` receives the size needed.
+ *
+ * # Parameters
+ * * `stream` - Stream created with lzss_stream_create
+ * * `input` - Pointer to input data buffer
+ * * `input_size` - Size of the input data in bytes
+ * * `output` - Pointer to output buffer
+ * * `output_size` - Size of the output buffer in bytes
+ * * `compressed_size` - Pointer to where the actual compressed size will be stored
+ *
+ * # Returns
+ * 0 on success, a negative `LzssStatus` on failure
+ */
+int lzss_stream_compress(struct LzssStream *stream,
+                         const unsigned char *input,
+                         unsigned long input_size,
+                         unsigned char *output,
+                         unsigned long output_size,
+                         unsigned long *compressed_size);
+
+/**
+ * Decompress the next incoming packet of a stream
+ *
+ * If the buffer is too small or the packet is corrupt, the window is
+ * unchanged, so the packet can be retried with a larger buffer.
+ *
+ * # Parameters
+ * * `stream` - Stream created with lzss_stream_create
+ * * `input` - Pointer to compressed data buffer
+ * * `input_size` - Size of the compressed data in bytes
+ * * `output` - Pointer to output buffer
+ * * `output_size` - Size of the output buffer in bytes
+ * * `decompressed_size` - Pointer to where the actual decompressed size will be stored,
+ *   or the size the output buffer needs if it is too small
  *
- * @param compressed_data Pointer to compressed data buffer
- * @param compressed_size Size of the compressed data in bytes
- * @return Original uncompressed size in bytes, or 0 if invalid
+ * # Returns
+ * 0 on success, a negative `LzssStatus` on failure
  */
-unsigned long lzss_get_original_size(
-    const unsigned char* compressed_data,
-    unsigned long compressed_size
-);
+int lzss_stream_decompress(struct LzssStream *stream,
+                           const unsigned char *input,
+                           unsigned long input_size,
+                           unsigned char *output,
+                           unsigned long output_size,
+                           unsigned long *decompressed_size);
 
 #ifdef __cplusplus
 }  // extern "C"
-#endif
+#endif  // __cplusplus
 
-#endif  // RUSTZSS_H
\ No newline at end of file
+#endif  /* RUSTZSS_H */
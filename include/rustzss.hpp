@@ -57,6 +57,19 @@ public:
         return *this;
     }
 
+    /**
+     * Load a preset dictionary used by subsequent compress/decompress calls
+     * 
+     * @param dictionary Dictionary bytes (empty to remove the dictionary)
+     * @throws std::runtime_error on failure
+     */
+    void loadDictionary(const std::vector<unsigned char>& dictionary) {
+        int status = lzss_load_dictionary(context_, dictionary.data(), dictionary.size());
+        if (status != 0) {
+            throw std::runtime_error("Loading dictionary failed with error code: " + std::to_string(status));
+        }
+    }
+
     /**
      * Compress data
      * 
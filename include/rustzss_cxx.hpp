@@ -0,0 +1,110 @@
+#ifndef RUSTZSS_CXX_HPP
+#define RUSTZSS_CXX_HPP
+
+// Requires the library to be built with the `cxx` feature. The bridge
+// headers are generated by cargo under
+// target/<profile>/build/rustzss-*/out/cxxbridge/include.
+#include "rust/cxx.h"
+#include "rustzss/src/cxx_bridge.rs.h"
+#include <cstddef>
+#include <cstdint>
+#include <span>
+#include <vector>
+
+namespace rustzss {
+
+namespace detail {
+
+inline rust::Slice<const std::uint8_t> toSlice(std::span<const std::uint8_t> data) {
+    return rust::Slice<const std::uint8_t>(data.data(), data.size());
+}
+
+inline std::vector<std::uint8_t> toVector(const rust::Vec<std::uint8_t>& data) {
+    return std::vector<std::uint8_t>(data.begin(), data.end());
+}
+
+} // namespace detail
+
+/**
+ * Compressor that keeps its history window between compress() calls
+ *
+ * Each output can only be decompressed by a Decompressor that has seen all
+ * previous outputs, in order, since the last window reset.
+ */
+class Compressor {
+public:
+    /**
+     * Constructor
+     *
+     * @param windowSize Size of the sliding window (up to 65535)
+     * @param minMatchLength Minimum match length for encoding (default: 3)
+     * @param dictionary Preset dictionary the window starts from (default: none)
+     */
+    explicit Compressor(std::size_t windowSize, std::size_t minMatchLength = 3,
+                        std::span<const std::uint8_t> dictionary = {})
+        : inner_(bridge::new_compressor(windowSize, minMatchLength, detail::toSlice(dictionary))) {}
+
+    /**
+     * Compress a buffer, referencing data from previous calls
+     *
+     * @param data Data to compress
+     * @return Compressed data
+     */
+    std::vector<std::uint8_t> compress(std::span<const std::uint8_t> data) {
+        return detail::toVector(inner_->compress(detail::toSlice(data)));
+    }
+
+    /**
+     * Forget all previous data, so the next output only depends on the
+     * preset dictionary
+     */
+    void resetWindow() {
+        inner_->reset_window();
+    }
+
+private:
+    rust::Box<bridge::Compressor> inner_;
+};
+
+/**
+ * Decompressor matching a Compressor's persistent window
+ */
+class Decompressor {
+public:
+    /**
+     * Constructor
+     *
+     * @param windowSize Size of the sliding window, as used by the Compressor
+     * @param minMatchLength Minimum match length, as used by the Compressor
+     * @param dictionary Preset dictionary, as used by the Compressor
+     */
+    explicit Decompressor(std::size_t windowSize, std::size_t minMatchLength = 3,
+                          std::span<const std::uint8_t> dictionary = {})
+        : inner_(bridge::new_decompressor(windowSize, minMatchLength, detail::toSlice(dictionary))) {}
+
+    /**
+     * Decompress the next output of the matching Compressor
+     *
+     * @param data Compressed data
+     * @return Decompressed data
+     * @throws rust::Error with the decoding error message on corrupt input;
+     *         the window is left unchanged
+     */
+    std::vector<std::uint8_t> decompress(std::span<const std::uint8_t> data) {
+        return detail::toVector(inner_->try_decompress(detail::toSlice(data)));
+    }
+
+    /**
+     * Forget all previous data, mirroring Compressor::resetWindow
+     */
+    void resetWindow() {
+        inner_->reset_window();
+    }
+
+private:
+    rust::Box<bridge::Decompressor> inner_;
+};
+
+} // namespace rustzss
+
+#endif // RUSTZSS_CXX_HPP
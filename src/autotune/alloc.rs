@@ -0,0 +1,124 @@
+//! Allocation counting for benchmarks (`alloc-count` feature)
+//!
+//! `CountingAllocator` forwards to the system allocator and, only while a
+//! benchmark on the current thread is measuring, counts the allocations it
+//! serves. Counters are per thread, so benchmarks running in parallel don't
+//! see each other's allocations. The library doesn't install it: binaries
+//! that want counts make it their global allocator.
+
+#[cfg(feature = "alloc-count")]
+use std::alloc::{GlobalAlloc, Layout, System};
+#[cfg(feature = "alloc-count")]
+use std::cell::Cell;
+#[cfg(feature = "alloc-count")]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Allocations made during one measured operation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocationStats {
+    /// Number of allocations (a reallocation counts as one)
+    pub allocations: usize,
+    /// Total bytes requested by those allocations
+    pub bytes: usize,
+}
+
+impl AllocationStats {
+    /// Combined allocations of two measurements
+    pub fn combine(self, other: AllocationStats) -> AllocationStats {
+        AllocationStats {
+            allocations: self.allocations + other.allocations,
+            bytes: self.bytes + other.bytes,
+        }
+    }
+}
+
+#[cfg(feature = "alloc-count")]
+thread_local! {
+    static TRACKING: Cell<bool> = const { Cell::new(false) };
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    static BYTES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Set once `CountingAllocator` serves an allocation, which means it is
+/// the global allocator
+#[cfg(feature = "alloc-count")]
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// System allocator that counts allocations on threads that are measuring
+///
+/// Install it in the benchmarking binary to have `BenchmarkResult` report
+/// allocation counts:
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: rustzss::autotune::alloc::CountingAllocator = rustzss::autotune::alloc::CountingAllocator;
+/// ```
+#[cfg(feature = "alloc-count")]
+#[derive(Debug, Default)]
+pub struct CountingAllocator;
+
+#[cfg(feature = "alloc-count")]
+impl CountingAllocator {
+    fn record(size: usize) {
+        if !INSTALLED.load(Ordering::Relaxed) {
+            INSTALLED.store(true, Ordering::Relaxed);
+        }
+        // Thread-local storage may already be gone while a thread exits
+        let _ = TRACKING.try_with(|tracking| {
+            if tracking.get() {
+                ALLOCATIONS.with(|count| count.set(count.get() + 1));
+                BYTES.with(|bytes| bytes.set(bytes.get() + size));
+            }
+        });
+    }
+}
+
+#[cfg(feature = "alloc-count")]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Self::record(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        Self::record(layout.size());
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::record(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+/// Run `f` and count the allocations it makes on the current thread
+///
+/// Returns `None` for the statistics unless `CountingAllocator` is the
+/// global allocator.
+#[cfg(feature = "alloc-count")]
+pub(crate) fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, Option<AllocationStats>) {
+    ALLOCATIONS.with(|count| count.set(0));
+    BYTES.with(|bytes| bytes.set(0));
+    TRACKING.with(|tracking| tracking.set(true));
+    let value = f();
+    TRACKING.with(|tracking| tracking.set(false));
+    if !INSTALLED.load(Ordering::Relaxed) {
+        return (value, None);
+    }
+
+    let stats = AllocationStats {
+        allocations: ALLOCATIONS.with(Cell::get),
+        bytes: BYTES.with(Cell::get),
+    };
+    (value, Some(stats))
+}
+
+/// Run `f`; allocations aren't counted without the `alloc-count` feature
+#[cfg(not(feature = "alloc-count"))]
+pub(crate) fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, Option<AllocationStats>) {
+    (f(), None)
+}
@@ -1,9 +1,10 @@
+use std::fmt;
 use std::fs::{self, File};
 use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 
 /// Represents different types of game assets
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AssetType {
     Texture,
     Model,
@@ -13,6 +14,12 @@ pub enum AssetType {
     Unknown,
 }
 
+impl fmt::Display for AssetType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
 /// Holds information about a game asset
 #[derive(Debug)]
 pub struct AssetInfo {
@@ -1,7 +1,8 @@
-use crate::LZSS;
+use crate::{DEFAULT_NICE_LENGTH, LZSS};
 use std::time::{Duration, Instant};
 use std::fmt;
 
+use super::alloc::{count_allocations, AllocationStats};
 use super::asset_loader::AssetInfo;
 
 /// Results from a compression benchmark
@@ -15,6 +16,16 @@ pub struct BenchmarkResult {
     pub compression_time: Duration,
     /// Decompression time
     pub decompression_time: Duration,
+    /// Standard deviation of the compression time across runs
+    pub compression_time_stddev: Duration,
+    /// Standard deviation of the decompression time across runs
+    pub decompression_time_stddev: Duration,
+    /// Allocations made by one compression run (only counted when
+    /// `alloc::CountingAllocator` is the global allocator)
+    pub compression_allocations: Option<AllocationStats>,
+    /// Allocations made by one decompression run (only counted when
+    /// `alloc::CountingAllocator` is the global allocator)
+    pub decompression_allocations: Option<AllocationStats>,
     /// The compression parameters used
     pub parameters: CompressionParameters,
     /// Asset information
@@ -52,6 +63,41 @@ impl BenchmarkResult {
         }
     }
     
+    /// Relative standard deviation of the average throughput across runs
+    /// (0.02 means the measured speed varied by about 2%)
+    pub fn throughput_noise(&self) -> f64 {
+        let relative = |stddev: Duration, time: Duration| {
+            if time.is_zero() { 0.0 } else { stddev.as_secs_f64() / time.as_secs_f64() }
+        };
+        let compression = self.compression_throughput();
+        let decompression = self.decompression_throughput();
+        if compression + decompression <= 0.0 {
+            return 0.0;
+        }
+        
+        (compression * relative(self.compression_time_stddev, self.compression_time)
+            + decompression * relative(self.decompression_time_stddev, self.decompression_time))
+            / (compression + decompression)
+    }
+    
+    /// Estimate the end-to-end time to load this data from storage with the
+    /// given read throughput (MB/s): read the compressed bytes, then
+    /// decompress them
+    pub fn estimated_load_time(&self, disk_mb_per_sec: f64) -> LoadTimeEstimate {
+        let read_time = |bytes: usize| {
+            Duration::from_secs_f64(bytes as f64 / (1024.0 * 1024.0) / disk_mb_per_sec)
+        };
+        let read = read_time(self.compressed_size);
+        
+        LoadTimeEstimate {
+            parameters: self.parameters,
+            read_time: read,
+            decompression_time: self.decompression_time,
+            total_time: read + self.decompression_time,
+            uncompressed_read_time: read_time(self.original_size),
+        }
+    }
+    
     /// Combined score that balances compression ratio and speed
     /// Higher is better
     pub fn score(&self) -> f64 {
@@ -85,14 +131,58 @@ impl fmt::Display for BenchmarkResult {
         writeln!(f, "Original size: {} bytes", self.original_size)?;
         writeln!(f, "Compressed size: {} bytes", self.compressed_size)?;
         writeln!(f, "Compression ratio: {:.2}%", self.compression_ratio_percent())?;
-        writeln!(f, "Compression time: {:?}", self.compression_time)?;
-        writeln!(f, "Decompression time: {:?}", self.decompression_time)?;
+        writeln!(f, "Compression time: {:?} (± {:?})", self.compression_time, self.compression_time_stddev)?;
+        writeln!(f, "Decompression time: {:?} (± {:?})", self.decompression_time, self.decompression_time_stddev)?;
         writeln!(f, "Compression throughput: {:.2} MB/s", self.compression_throughput())?;
         writeln!(f, "Decompression throughput: {:.2} MB/s", self.decompression_throughput())?;
+        if let Some(stats) = self.compression_allocations {
+            writeln!(f, "Compression allocations: {} ({} bytes)", stats.allocations, stats.bytes)?;
+        }
+        if let Some(stats) = self.decompression_allocations {
+            writeln!(f, "Decompression allocations: {} ({} bytes)", stats.allocations, stats.bytes)?;
+        }
         write!(f, "Score: {:.2}", self.score())
     }
 }
 
+/// Estimated asset load time for one parameter set
+#[derive(Debug, Clone)]
+pub struct LoadTimeEstimate {
+    /// The compression parameters used
+    pub parameters: CompressionParameters,
+    /// Time to read the compressed data from storage
+    pub read_time: Duration,
+    /// Time to decompress the data
+    pub decompression_time: Duration,
+    /// Read plus decompression time
+    pub total_time: Duration,
+    /// Time to read the data if it were stored uncompressed
+    pub uncompressed_read_time: Duration,
+}
+
+impl LoadTimeEstimate {
+    /// How many times faster loading is than reading uncompressed data
+    pub fn speedup(&self) -> f64 {
+        let total = self.total_time.as_secs_f64();
+        if total > 0.0 {
+            self.uncompressed_read_time.as_secs_f64() / total
+        } else {
+            0.0
+        }
+    }
+}
+
+impl fmt::Display for LoadTimeEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: load {:?} (read {:?} + decompress {:?}), {:.2}x vs uncompressed {:?}",
+            self.parameters, self.total_time, self.read_time, self.decompression_time,
+            self.speedup(), self.uncompressed_read_time
+        )
+    }
+}
+
 /// Parameters for configuring the LZSS compression
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct CompressionParameters {
@@ -100,20 +190,29 @@ pub struct CompressionParameters {
     pub window_size: usize,
     /// Minimum match length
     pub min_match_length: usize,
+    /// Match length that ends the match search early
+    pub nice_length: usize,
 }
 
 impl CompressionParameters {
-    /// Create a new set of compression parameters
+    /// Create a new set of compression parameters with the default nice length
     pub fn new(window_size: usize, min_match_length: usize) -> Self {
         CompressionParameters {
             window_size,
             min_match_length,
+            nice_length: DEFAULT_NICE_LENGTH,
         }
     }
     
+    /// Change the match length that ends the match search early
+    pub fn with_nice_length(mut self, nice_length: usize) -> Self {
+        self.nice_length = nice_length;
+        self
+    }
+    
     /// Create an LZSS instance with these parameters
     pub fn create_lzss(&self) -> LZSS {
-        LZSS::new(self.window_size, self.min_match_length)
+        LZSS::new(self.window_size, self.min_match_length).with_nice_length(self.nice_length)
     }
 }
 
@@ -121,12 +220,25 @@ impl fmt::Display for CompressionParameters {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "win_size={}, min_match={}",
-            self.window_size, self.min_match_length
+            "win_size={}, min_match={}, nice_len={}",
+            self.window_size, self.min_match_length, self.nice_length
         )
     }
 }
 
+/// Sample standard deviation of run times (zero with fewer than two runs)
+fn std_dev(samples: &[Duration]) -> Duration {
+    if samples.len() < 2 {
+        return Duration::ZERO;
+    }
+    
+    let mean = samples.iter().map(Duration::as_secs_f64).sum::<f64>() / samples.len() as f64;
+    let variance = samples.iter()
+        .map(|sample| (sample.as_secs_f64() - mean).powi(2))
+        .sum::<f64>() / (samples.len() - 1) as f64;
+    Duration::from_secs_f64(variance.sqrt())
+}
+
 /// Runs a benchmark with the given data and compression parameters
 pub fn run_benchmark(
     data: &[u8], 
@@ -137,23 +249,28 @@ pub fn run_benchmark(
     let lzss = parameters.create_lzss();
     
     // Run multiple times for more reliable results
-    let mut total_compression_time = Duration::new(0, 0);
-    let mut total_decompression_time = Duration::new(0, 0);
+    let mut compression_times = Vec::with_capacity(runs);
+    let mut decompression_times = Vec::with_capacity(runs);
     let mut compressed = Vec::new();
+    let mut compression_allocations = None;
+    let mut decompression_allocations = None;
     
     for i in 0..runs {
         // Measure compression time
         let start = Instant::now();
-        compressed = lzss.compress(data);
+        (compressed, compression_allocations) = count_allocations(|| lzss.compress(data));
         let end = Instant::now();
-        total_compression_time += end.duration_since(start);
+        compression_times.push(end.duration_since(start));
         
-        // Measure decompression time (skip first run for warming up)
-        if i > 0 {
+        // Measure decompression time (skip first run for warming up,
+        // unless there is only one run)
+        if i > 0 || runs == 1 {
             let start = Instant::now();
-            let decompressed = lzss.decompress(&compressed);
+            let decompressed;
+            (decompressed, decompression_allocations) = count_allocations(|| lzss.try_decompress(&compressed));
+            let decompressed = decompressed.expect("Decompression failed");
             let end = Instant::now();
-            total_decompression_time += end.duration_since(start);
+            decompression_times.push(end.duration_since(start));
             
             // Verify correctness
             assert_eq!(decompressed.len(), data.len(), "Decompressed size mismatch");
@@ -161,15 +278,19 @@ pub fn run_benchmark(
         }
     }
     
-    // Calculate average times (divide by runs count, but skip first decompression run)
-    let avg_compression_time = total_compression_time / runs as u32;
-    let avg_decompression_time = total_decompression_time / (runs - 1) as u32;
+    // Calculate average times (the first decompression run was skipped)
+    let avg_compression_time = compression_times.iter().sum::<Duration>() / compression_times.len() as u32;
+    let avg_decompression_time = decompression_times.iter().sum::<Duration>() / decompression_times.len() as u32;
     
     BenchmarkResult {
         original_size: data.len(),
         compressed_size: compressed.len(),
         compression_time: avg_compression_time,
         decompression_time: avg_decompression_time,
+        compression_time_stddev: std_dev(&compression_times),
+        decompression_time_stddev: std_dev(&decompression_times),
+        compression_allocations,
+        decompression_allocations,
         parameters,
         asset_info: asset_info.map(|info| format!("{} ({})", info.filename(), info.asset_type)),
     }
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::asset_loader::AssetType;
+use super::benchmark::CompressionParameters;
+use super::tuner::TuningResult;
+
+/// Convert an asset type name like `LevelData` to `LEVEL_DATA`
+fn constant_name(asset_type: AssetType) -> String {
+    let mut name = String::new();
+    for (i, c) in format!("{:?}", asset_type).chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            name.push('_');
+        }
+        name.push(c.to_ascii_uppercase());
+    }
+    name
+}
+
+/// Emit a `CompressionParameters` constant with a doc comment
+fn write_constant(source: &mut String, name: &str, doc: &str, params: CompressionParameters) {
+    let _ = writeln!(source, "/// {}", doc);
+    let _ = writeln!(
+        source,
+        "pub const {}: CompressionParameters = CompressionParameters {{ window_size: {}, min_match_length: {}, nice_length: {} }};\n",
+        name, params.window_size, params.min_match_length, params.nice_length
+    );
+}
+
+/// Generate the source of a Rust module mapping asset types to parameters
+///
+/// The module defines a constant per profiled asset type plus `DEFAULT`,
+/// and a `params_for(asset_type)` function returning the matching constant.
+/// `extra_constants` are emitted as additional named constants.
+pub fn rust_module_source(
+    profiles: &HashMap<AssetType, CompressionParameters>,
+    default: CompressionParameters,
+    extra_constants: &[(&str, &str, CompressionParameters)],
+) -> String {
+    let mut source = String::new();
+    source.push_str("// Generated by the rustzss autotuner. Do not edit by hand.\n\n");
+    source.push_str("use rustzss::autotune::{AssetType, CompressionParameters};\n\n");
+
+    for &(name, doc, params) in extra_constants {
+        write_constant(&mut source, name, doc, params);
+    }
+
+    // Sort for stable output between runs
+    let mut profiles: Vec<(AssetType, CompressionParameters)> = profiles.iter().map(|(&t, &p)| (t, p)).collect();
+    profiles.sort_by_key(|(asset_type, _)| format!("{:?}", asset_type));
+
+    for &(asset_type, params) in &profiles {
+        let doc = format!("Tuned parameters for {:?} assets", asset_type);
+        write_constant(&mut source, &constant_name(asset_type), &doc, params);
+    }
+    write_constant(&mut source, "DEFAULT", "Parameters for asset types without a tuned profile", default);
+
+    source.push_str("/// Compression parameters to use for an asset type\n");
+    source.push_str("pub fn params_for(asset_type: AssetType) -> CompressionParameters {\n");
+    if profiles.is_empty() {
+        source.push_str("    let _ = asset_type;\n    DEFAULT\n");
+    } else {
+        source.push_str("    match asset_type {\n");
+        for &(asset_type, _) in &profiles {
+            let _ = writeln!(source, "        AssetType::{:?} => {},", asset_type, constant_name(asset_type));
+        }
+        source.push_str("        _ => DEFAULT,\n    }\n");
+    }
+    source.push_str("}\n");
+
+    source
+}
+
+/// Write a Rust module mapping asset types to parameters (see
+/// `rust_module_source`)
+pub fn write_rust_module<P: AsRef<Path>>(
+    path: P,
+    profiles: &HashMap<AssetType, CompressionParameters>,
+    default: CompressionParameters,
+) -> io::Result<()> {
+    fs::write(path, rust_module_source(profiles, default, &[]))
+}
+
+impl TuningResult {
+    /// Write a compilable Rust module (e.g. `profiles.rs`) embedding the
+    /// tuning results
+    ///
+    /// The module defines `BEST`, `BEST_RATIO` and `BEST_SPEED` constants
+    /// and a `params_for(asset_type)` function returning the best overall
+    /// parameters for every asset type.
+    pub fn write_rust_module<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let extra = [
+            ("BEST", "Best overall parameters found by the tuner", self.best_parameters),
+            ("BEST_RATIO", "Parameters with the best compression ratio", self.best_ratio_parameters),
+            ("BEST_SPEED", "Parameters with the best throughput", self.best_speed_parameters),
+        ];
+        fs::write(path, rust_module_source(&HashMap::new(), self.best_parameters, &extra))
+    }
+}
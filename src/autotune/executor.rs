@@ -0,0 +1,156 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use super::asset_loader::AssetInfo;
+use super::benchmark::{BenchmarkResult, CompressionParameters, run_benchmark};
+
+/// Runs compression benchmarks on behalf of the tuner
+///
+/// By default the tuner benchmarks in-process on the build machine. A custom
+/// executor can take the measurements on the shipping hardware instead
+/// (e.g. copy the data to a devkit over adb/ssh and run a small harness), so
+/// tuning decisions reflect the console or phone rather than the x86 host.
+pub trait BenchmarkExecutor: fmt::Debug + Send + Sync {
+    /// Benchmark compressing and decompressing `data` with `parameters`,
+    /// averaged over `runs` runs
+    fn execute(
+        &self,
+        data: &[u8],
+        parameters: CompressionParameters,
+        asset_info: Option<&AssetInfo>,
+        runs: usize,
+    ) -> io::Result<BenchmarkResult>;
+}
+
+/// Executor benchmarking in the current process
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalExecutor;
+
+impl BenchmarkExecutor for LocalExecutor {
+    fn execute(
+        &self,
+        data: &[u8],
+        parameters: CompressionParameters,
+        asset_info: Option<&AssetInfo>,
+        runs: usize,
+    ) -> io::Result<BenchmarkResult> {
+        Ok(run_benchmark(data, parameters, asset_info, runs))
+    }
+}
+
+/// Executor delegating each benchmark to an external command
+///
+/// The data is written to a temporary file, and the command is run with the
+/// placeholders `{input}`, `{window_size}`, `{min_match}`, `{nice_length}`
+/// and `{runs}` in its arguments replaced. This is typically a script that
+/// pushes the file to the device, runs a benchmark harness there and relays
+/// its output.
+///
+/// The command must print `key=value` lines on stdout with the average
+/// timings per run:
+///
+/// ```text
+/// compressed_size=12345
+/// compression_ns=1500000
+/// decompression_ns=300000
+/// ```
+///
+/// It may also report the standard deviations across runs as
+/// `compression_stddev_ns` and `decompression_stddev_ns` (zero if absent).
+#[derive(Debug, Clone)]
+pub struct CommandExecutor {
+    program: String,
+    args: Vec<String>,
+}
+
+// Distinguishes the temporary files of concurrent benchmarks
+static NEXT_INPUT_ID: AtomicUsize = AtomicUsize::new(0);
+
+impl CommandExecutor {
+    /// Create an executor running `program` with the given argument templates
+    pub fn new<S: Into<String>>(program: S, args: Vec<String>) -> Self {
+        CommandExecutor {
+            program: program.into(),
+            args,
+        }
+    }
+
+    fn run_command(&self, input: &str, parameters: CompressionParameters, runs: usize) -> io::Result<String> {
+        let args: Vec<String> = self.args.iter()
+            .map(|arg| {
+                arg.replace("{input}", input)
+                    .replace("{window_size}", &parameters.window_size.to_string())
+                    .replace("{min_match}", &parameters.min_match_length.to_string())
+                    .replace("{nice_length}", &parameters.nice_length.to_string())
+                    .replace("{runs}", &runs.to_string())
+            })
+            .collect();
+
+        let output = Command::new(&self.program).args(&args).output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "{} exited with {}: {}",
+                self.program, output.status, String::from_utf8_lossy(&output.stderr).trim(),
+            )));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// Find a `key=value` line in the command output and parse its value
+fn parse_value(output: &str, key: &str) -> io::Result<u64> {
+    output.lines()
+        .filter_map(|line| line.trim().split_once('='))
+        .find(|(k, _)| k.trim() == key)
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("benchmark output is missing a valid '{}' value", key),
+        ))
+}
+
+/// Like `parse_value`, but defaulting to zero if the key is absent
+fn parse_optional_value(output: &str, key: &str) -> io::Result<u64> {
+    if output.lines().any(|line| line.split_once('=').is_some_and(|(k, _)| k.trim() == key)) {
+        parse_value(output, key)
+    } else {
+        Ok(0)
+    }
+}
+
+impl BenchmarkExecutor for CommandExecutor {
+    fn execute(
+        &self,
+        data: &[u8],
+        parameters: CompressionParameters,
+        asset_info: Option<&AssetInfo>,
+        runs: usize,
+    ) -> io::Result<BenchmarkResult> {
+        let id = NEXT_INPUT_ID.fetch_add(1, Ordering::Relaxed);
+        let input_path = std::env::temp_dir()
+            .join(format!("rustzss-bench-{}-{}.bin", std::process::id(), id));
+        fs::write(&input_path, data)?;
+
+        let output = self.run_command(&input_path.to_string_lossy(), parameters, runs);
+        let _ = fs::remove_file(&input_path);
+        let output = output?;
+
+        Ok(BenchmarkResult {
+            original_size: data.len(),
+            compressed_size: parse_value(&output, "compressed_size")? as usize,
+            compression_time: Duration::from_nanos(parse_value(&output, "compression_ns")?),
+            decompression_time: Duration::from_nanos(parse_value(&output, "decompression_ns")?),
+            compression_time_stddev: Duration::from_nanos(parse_optional_value(&output, "compression_stddev_ns")?),
+            decompression_time_stddev: Duration::from_nanos(parse_optional_value(&output, "decompression_stddev_ns")?),
+            compression_allocations: None,
+            decompression_allocations: None,
+            parameters,
+            asset_info: asset_info.map(|info| format!("{} ({})", info.filename(), info.asset_type)),
+        })
+    }
+}
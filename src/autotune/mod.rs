@@ -1,7 +1,12 @@
+pub mod alloc;
 pub mod asset_loader;
 pub mod benchmark;
+pub mod codegen;
+pub mod executor;
 pub mod tuner;
 
+pub use alloc::AllocationStats;
 pub use asset_loader::{AssetInfo, AssetType, scan_directory};
-pub use benchmark::{BenchmarkResult, CompressionParameters, run_benchmark};
-pub use tuner::{Tuner, TunerConfig, TuningResult, quick_benchmark};
\ No newline at end of file
+pub use benchmark::{BenchmarkResult, CompressionParameters, LoadTimeEstimate, run_benchmark};
+pub use executor::{BenchmarkExecutor, CommandExecutor, LocalExecutor};
+pub use tuner::{DeviceProfile, DeviceRecommendation, QuickTuneResult, Tuner, TunerConfig, TuningResult, quick_benchmark};
\ No newline at end of file
@@ -1,10 +1,18 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
-use rand::{seq::SliceRandom, Rng};
+use rand::rngs::StdRng;
+use rand::{seq::SliceRandom, Rng, SeedableRng};
 use rayon::prelude::*;
 
+use crate::block::BlockStats;
+use super::alloc::AllocationStats;
 use super::asset_loader::AssetInfo;
-use super::benchmark::{BenchmarkResult, CompressionParameters, run_benchmark};
+use super::benchmark::{BenchmarkResult, CompressionParameters, LoadTimeEstimate, run_benchmark};
+use super::executor::BenchmarkExecutor;
 
 /// Configuration for parameter tuning
 #[derive(Debug, Clone)]
@@ -21,6 +29,22 @@ pub struct TunerConfig {
     pub random_seed: Option<u64>,
     /// Whether to enable parallel tuning
     pub parallel: bool,
+    /// Number of assets sampled per group when generating profiles
+    pub profile_sample_size: usize,
+    /// Minimum number of assets a group needs to get a profile
+    pub min_profile_assets: usize,
+    /// Minimum total size of the sampled assets in bytes; more assets are
+    /// sampled until it is reached, and smaller groups get no profile
+    pub min_profile_bytes: usize,
+    /// Custom benchmark executor, e.g. one running on the target device
+    /// (benchmarks run in-process when `None`)
+    pub executor: Option<Arc<dyn BenchmarkExecutor>>,
+    /// Score differences below this fraction of the best score are treated
+    /// as noise even when the runs agree closely, since they are unlikely
+    /// to reproduce on another machine
+    pub min_significant_difference: f64,
+    /// Target devices to recommend parameters for from the same tuning run
+    pub device_profiles: Vec<DeviceProfile>,
 }
 
 impl Default for TunerConfig {
@@ -32,6 +56,102 @@ impl Default for TunerConfig {
             ratio_priority: 0.5,
             random_seed: None,
             parallel: true,
+            profile_sample_size: 5,
+            min_profile_assets: 1,
+            min_profile_bytes: 0,
+            executor: None,
+            min_significant_difference: 0.01,
+            device_profiles: Vec::new(),
+        }
+    }
+}
+
+/// Performance envelope of a target device, relative to the machine the
+/// benchmarks run on
+#[derive(Debug, Clone)]
+pub struct DeviceProfile {
+    /// Name used in reports (e.g. "PC", "Switch", "mobile")
+    pub name: String,
+    /// How much slower the device decompresses than the benchmark machine
+    /// (2.0 means decompression takes twice as long)
+    pub cpu_scale: f64,
+    /// Storage read throughput in MB/s
+    pub disk_mb_per_sec: f64,
+    /// Largest window in bytes the device can keep in memory while
+    /// decoding (no limit when `None`)
+    pub memory_cap: Option<usize>,
+}
+
+impl DeviceProfile {
+    /// Create a profile without a memory cap
+    pub fn new(name: impl Into<String>, cpu_scale: f64, disk_mb_per_sec: f64) -> Self {
+        DeviceProfile {
+            name: name.into(),
+            cpu_scale,
+            disk_mb_per_sec,
+            memory_cap: None,
+        }
+    }
+    
+    /// Limit the window size the device can afford
+    pub fn with_memory_cap(mut self, bytes: usize) -> Self {
+        self.memory_cap = Some(bytes);
+        self
+    }
+    
+    /// Whether the parameters fit within the device's memory cap
+    pub fn fits(&self, params: CompressionParameters) -> bool {
+        self.memory_cap.is_none_or(|cap| params.window_size <= cap)
+    }
+    
+    /// Estimate the load time of a benchmarked parameter set on this device
+    pub fn estimated_load_time(&self, result: &BenchmarkResult) -> LoadTimeEstimate {
+        let mut estimate = result.estimated_load_time(self.disk_mb_per_sec);
+        estimate.decompression_time = result.decompression_time.mul_f64(self.cpu_scale);
+        estimate.total_time = estimate.read_time + estimate.decompression_time;
+        estimate
+    }
+    
+    /// Pick the parameter set with the fastest estimated load among those
+    /// that fit the memory cap, preferring smaller output on ties
+    pub fn recommend(&self, results: &[BenchmarkResult]) -> DeviceRecommendation {
+        let best = results
+            .iter()
+            .filter(|result| self.fits(result.parameters))
+            .min_by(|a, b| {
+                let (load_a, load_b) = (self.estimated_load_time(a), self.estimated_load_time(b));
+                load_a.total_time.cmp(&load_b.total_time).then(a.compressed_size.cmp(&b.compressed_size))
+            });
+        
+        DeviceRecommendation {
+            device: self.name.clone(),
+            estimate: best.map(|result| self.estimated_load_time(result)),
+        }
+    }
+}
+
+/// Parameters recommended for one device profile
+#[derive(Debug, Clone)]
+pub struct DeviceRecommendation {
+    /// Name of the device profile
+    pub device: String,
+    /// Estimated load time of the recommended parameters on the device, or
+    /// `None` if no tested parameter set fits its memory cap
+    pub estimate: Option<LoadTimeEstimate>,
+}
+
+impl DeviceRecommendation {
+    /// The recommended parameters, if any tested set fits the device
+    pub fn parameters(&self) -> Option<CompressionParameters> {
+        self.estimate.as_ref().map(|estimate| estimate.parameters)
+    }
+}
+
+impl fmt::Display for DeviceRecommendation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.estimate {
+            Some(estimate) => write!(f, "{}: {}", self.device, estimate),
+            None => write!(f, "{}: no tested parameters fit the memory cap", self.device),
         }
     }
 }
@@ -53,6 +173,70 @@ pub struct TuningResult {
     pub tuning_time: Duration,
     /// How many iterations were performed
     pub iterations: usize,
+    /// Rationale for each recommendation, derived from the recorded results
+    pub explanations: Vec<String>,
+    /// Parameter sets whose score is within measurement noise of the best,
+    /// so the recommendation over them may not reproduce
+    pub within_noise: Vec<CompressionParameters>,
+    /// Recommendations for each of `TunerConfig::device_profiles`, in order
+    pub device_recommendations: Vec<DeviceRecommendation>,
+}
+
+impl TuningResult {
+    /// Whether the best parameters are clearly ahead of every other
+    /// tested parameter set, beyond measurement noise
+    pub fn is_significant(&self) -> bool {
+        self.within_noise.is_empty()
+    }
+    
+    /// Estimate end-to-end load times (read compressed + decompress) for
+    /// every tested parameter set, given the storage read throughput in MB/s
+    ///
+    /// Estimates are sorted from fastest to slowest load.
+    pub fn load_time_report(&self, disk_mb_per_sec: f64) -> Vec<LoadTimeEstimate> {
+        let mut estimates: Vec<LoadTimeEstimate> = self.all_results
+            .iter()
+            .map(|result| result.estimated_load_time(disk_mb_per_sec))
+            .collect();
+        estimates.sort_by_key(|estimate| estimate.total_time);
+        estimates
+    }
+}
+
+/// Parameters recommended by `Tuner::quick_tune`, with the statistics they
+/// were derived from
+#[derive(Debug, Clone)]
+pub struct QuickTuneResult {
+    /// Recommended parameters
+    pub parameters: CompressionParameters,
+    /// Shannon entropy of the sampled bytes in bits per byte
+    pub entropy: f64,
+    /// Fraction of sampled bytes covered by repeats of 4+ bytes
+    pub match_coverage: f64,
+    /// Average length of the repeats found
+    pub average_match_length: f64,
+    /// Repeat counts by distance: bucket `b` counts distances in
+    /// `[2^b, 2^(b+1))`, the last bucket those beyond the 64KB window limit
+    pub distance_histogram: [usize; 17],
+}
+
+/// Bytes examined by `quick_tune`
+const QUICK_TUNE_SAMPLE: usize = 4 * 1024 * 1024;
+
+/// 16-bit hash of the first 4 bytes of `bytes`
+fn hash4(bytes: &[u8]) -> usize {
+    let key = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    (key.wrapping_mul(0x9E37_79B1) >> 16) as usize
+}
+
+/// Standard deviation of a sum of independent measurements
+fn sum_stddev(stddevs: impl Iterator<Item = Duration>) -> Duration {
+    Duration::from_secs_f64(stddevs.map(|stddev| stddev.as_secs_f64().powi(2)).sum::<f64>().sqrt())
+}
+
+/// Total allocations of several measurements, or `None` if any is missing
+fn sum_allocations(stats: impl Iterator<Item = Option<AllocationStats>>) -> Option<AllocationStats> {
+    stats.reduce(|a, b| Some(a?.combine(b?)))?
 }
 
 /// Parameter tuner for finding optimal LZSS parameters
@@ -67,6 +251,8 @@ pub struct Tuner {
     best_speed_parameters: Option<CompressionParameters>,
     parameter_space: Vec<CompressionParameters>,
     tested_parameters: HashMap<CompressionParameters, BenchmarkResult>,
+    /// Combined score and its standard deviation for each tested parameter set
+    scores: HashMap<CompressionParameters, (f64, f64)>,
 }
 
 impl Tuner {
@@ -74,23 +260,32 @@ impl Tuner {
     pub fn new(config: TunerConfig) -> Self {
         let mut parameter_space = Vec::new();
         
-        // Generate parameter space (window sizes and min match lengths)
+        // Generate parameter space (window sizes, min match lengths and nice lengths)
         let window_sizes = [
             256, 512, 1024, 2048, 4096, 8192, 16384, 32768, 65535
         ];
         
         let min_match_lengths = [2, 3, 4, 5, 6, 8];
         
+        // Short nice lengths favor speed on binary data, long ones find the
+        // long repeats of text
+        let nice_lengths = [8, 16, 64];
+        
         for &window_size in &window_sizes {
             for &min_match in &min_match_lengths {
-                parameter_space.push(CompressionParameters::new(window_size, min_match));
+                for &nice_length in &nice_lengths {
+                    if nice_length < min_match {
+                        continue;
+                    }
+                    parameter_space.push(CompressionParameters::new(window_size, min_match).with_nice_length(nice_length));
+                }
             }
         }
         
         // Shuffle the parameter space for better exploration
         let mut rng = match config.random_seed {
-            Some(seed) => rand::rngs::StdRng::seed_from_u64(seed),
-            None => rand::thread_rng(),
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
         };
         
         parameter_space.shuffle(&mut rng);
@@ -106,30 +301,148 @@ impl Tuner {
             best_speed_parameters: None,
             parameter_space,
             tested_parameters: HashMap::new(),
+            scores: HashMap::new(),
+        }
+    }
+    
+    /// Recommend parameters from match statistics without benchmarking
+    ///
+    /// Builds a histogram of repeat distances and lengths plus an entropy
+    /// estimate over (up to) the first 4MB of `data`, with no repeated
+    /// compression, so it finishes well under a second. The window is the
+    /// smallest one covering most repeats (more of them the higher the
+    /// ratio priority), and the minimum match length follows the kind of
+    /// data and the typical repeat length.
+    pub fn quick_tune(&self, data: &[u8]) -> QuickTuneResult {
+        let sample = &data[..data.len().min(QUICK_TUNE_SAMPLE)];
+        let stats = BlockStats::analyze(sample);
+        
+        // Greedy scan for 4-byte repeats via a single-entry hash table
+        let mut table = vec![usize::MAX; 1 << 16];
+        let mut distance_histogram = [0usize; 17];
+        let mut matched_bytes = 0;
+        let mut match_count = 0;
+        let mut pos = 0;
+        while pos + 4 <= sample.len() {
+            let hash = hash4(&sample[pos..]);
+            let candidate = table[hash];
+            table[hash] = pos;
+            
+            if candidate != usize::MAX && sample[candidate..candidate + 4] == sample[pos..pos + 4] {
+                let mut len = 4;
+                while pos + len < sample.len() && len < 258 && sample[candidate + len] == sample[pos + len] {
+                    len += 1;
+                }
+                
+                let distance = pos - candidate;
+                let bucket = if distance > 65535 { 16 } else { distance.ilog2() as usize };
+                distance_histogram[bucket] += 1;
+                matched_bytes += len;
+                match_count += 1;
+                
+                // Index the matched bytes so later repeats find the nearest copy
+                let end = pos + len;
+                pos += 1;
+                while pos < end && pos + 4 <= sample.len() {
+                    table[hash4(&sample[pos..])] = pos;
+                    pos += 1;
+                }
+                pos = end;
+            } else {
+                pos += 1;
+            }
+        }
+        
+        let average_match_length = if match_count > 0 { matched_bytes as f64 / match_count as f64 } else { 0.0 };
+        let match_coverage = if sample.is_empty() { 0.0 } else { matched_bytes as f64 / sample.len() as f64 };
+        
+        // Smallest window covering the target share of reachable repeats
+        let reachable: usize = distance_histogram[..16].iter().sum();
+        let target = (0.75 + 0.2 * self.config.ratio_priority.clamp(0.0, 1.0)) * reachable as f64;
+        let mut window_size = 256;
+        let mut covered = 0;
+        for (bucket, &count) in distance_histogram[..16].iter().enumerate() {
+            if covered as f64 >= target {
+                break;
+            }
+            covered += count;
+            window_size = (2usize << bucket).clamp(256, 65535);
+        }
+        
+        let min_match_length = if stats.entropy > 7.5 || match_coverage < 0.05 {
+            // Hardly compressible: the longest minimum match is fastest
+            8
+        } else if stats.text_fraction > 0.9 {
+            3
+        } else if average_match_length >= 24.0 {
+            6
+        } else if average_match_length >= 12.0 {
+            4
+        } else {
+            3
+        };
+        
+        QuickTuneResult {
+            parameters: CompressionParameters::new(window_size, min_match_length),
+            entropy: stats.entropy,
+            match_coverage,
+            average_match_length,
+            distance_histogram,
         }
     }
     
     /// Tune parameters for a single asset
     pub fn tune_for_asset(&mut self, asset: &mut AssetInfo) -> TuningResult {
         let data = match asset.data() {
-            Ok(data) => data,
+            Ok(data) => data.to_vec(),
             Err(e) => {
                 eprintln!("Error loading asset {}: {}", asset.filename(), e);
                 return self.empty_result();
             }
         };
         
-        self.tune_for_data(data, Some(asset))
+        self.tune_for_data(&data, Some(asset))
+    }
+    
+    /// Benchmark one parameter set, using the configured executor if any
+    fn benchmark(&self, data: &[u8], params: CompressionParameters, asset: Option<&AssetInfo>) -> Option<BenchmarkResult> {
+        match &self.config.executor {
+            Some(executor) => match executor.execute(data, params, asset, self.config.benchmark_runs) {
+                Ok(result) => Some(result),
+                Err(e) => {
+                    eprintln!("Error running benchmark with {}: {}", params, e);
+                    None
+                }
+            },
+            None => Some(run_benchmark(data, params, asset, self.config.benchmark_runs)),
+        }
     }
     
     /// Tune parameters for a collection of assets
     pub fn tune_for_assets(&mut self, assets: &mut [AssetInfo]) -> TuningResult {
+        // Load all asset data first to avoid IO during benchmarking
+        let loaded: Vec<(Vec<u8>, &AssetInfo)> = assets
+            .iter_mut()
+            .filter_map(|asset| {
+                let data = asset.data().ok()?.to_vec();
+                let asset: &AssetInfo = asset;
+                Some((data, asset))
+            })
+            .collect();
+        
+        self.tune_for_loaded(&loaded)
+    }
+    
+    /// Tune parameters for assets whose data is already loaded
+    fn tune_for_loaded(&mut self, assets: &[(Vec<u8>, &AssetInfo)]) -> TuningResult {
         let start_time = std::time::Instant::now();
         let mut iterations = 0;
         
         // Try each parameter set on all assets
-        while iterations < self.config.max_iterations && !self.parameter_space.is_empty() {
-            let params = self.parameter_space.remove(0);
+        let mut i = 0;
+        while i < self.parameter_space.len() && iterations < self.config.max_iterations {
+            let params = self.parameter_space[i];
+            i += 1;
             
             // Skip if we've already tested these parameters
             if self.tested_parameters.contains_key(&params) {
@@ -138,24 +451,12 @@ impl Tuner {
             
             // Run benchmarks for each asset with these parameters
             let results: Vec<BenchmarkResult> = if self.config.parallel {
-                // Load all asset data first to avoid IO during parallel execution
-                let asset_data: Vec<_> = assets
-                    .iter_mut()
-                    .filter_map(|asset| asset.data().ok().map(|data| (data.to_vec(), asset)))
-                    .collect();
-                
-                asset_data.par_iter()
-                    .map(|(data, asset)| {
-                        run_benchmark(data, params, Some(*asset), self.config.benchmark_runs)
-                    })
+                assets.par_iter()
+                    .filter_map(|(data, asset)| self.benchmark(data, params, Some(*asset)))
                     .collect()
             } else {
-                assets.iter_mut()
-                    .filter_map(|asset| {
-                        asset.data().ok().map(|data| {
-                            run_benchmark(data, params, Some(asset), self.config.benchmark_runs)
-                        })
-                    })
+                assets.iter()
+                    .filter_map(|(data, asset)| self.benchmark(data, params, Some(*asset)))
                     .collect()
             };
             
@@ -194,12 +495,21 @@ impl Tuner {
             let aggregate_result = BenchmarkResult {
                 original_size: results.iter().map(|r| r.original_size).sum(),
                 compressed_size: results.iter().map(|r| r.compressed_size).sum(),
-                compression_time: Duration::from_secs_f64(results.iter().map(|r| r.compression_time.as_secs_f64()).sum::<f64>() / results.len() as f64),
-                decompression_time: Duration::from_secs_f64(results.iter().map(|r| r.decompression_time.as_secs_f64()).sum::<f64>() / results.len() as f64),
+                // Total times, so throughputs and load times cover all assets
+                compression_time: results.iter().map(|r| r.compression_time).sum(),
+                decompression_time: results.iter().map(|r| r.decompression_time).sum(),
+                // Assets are measured independently, so their variances add up
+                compression_time_stddev: sum_stddev(results.iter().map(|r| r.compression_time_stddev)),
+                decompression_time_stddev: sum_stddev(results.iter().map(|r| r.decompression_time_stddev)),
+                // Allocations of all assets, if every benchmark counted them
+                compression_allocations: sum_allocations(results.iter().map(|r| r.compression_allocations)),
+                decompression_allocations: sum_allocations(results.iter().map(|r| r.decompression_allocations)),
                 parameters: params,
                 asset_info: Some(format!("Aggregate of {} assets", results.len())),
             };
             
+            let score_noise = speed_score * (1.0 - self.config.ratio_priority) * aggregate_result.throughput_noise();
+            self.scores.insert(params, (combined_score, score_noise));
             self.results.push(aggregate_result.clone());
             self.tested_parameters.insert(params, aggregate_result);
             
@@ -234,7 +544,10 @@ impl Tuner {
             }
             
             // Run benchmark with these parameters
-            let result = run_benchmark(data, params, asset, self.config.benchmark_runs);
+            let result = match self.benchmark(data, params, asset) {
+                Some(result) => result,
+                None => continue,
+            };
             
             // Calculate scores
             let ratio = result.compression_ratio();
@@ -263,6 +576,8 @@ impl Tuner {
                 self.best_speed_parameters = Some(params);
             }
             
+            let score_noise = speed_score * (1.0 - self.config.ratio_priority) * result.throughput_noise();
+            self.scores.insert(params, (combined_score, score_noise));
             self.results.push(result.clone());
             self.tested_parameters.insert(params, result);
             
@@ -285,6 +600,16 @@ impl Tuner {
         if let (Some(best_params), Some(best_ratio_params), Some(best_speed_params)) = 
             (self.best_parameters, self.best_ratio_parameters, self.best_speed_parameters) {
             
+            let within_noise = self.within_noise(best_params);
+            let mut explanations = self.explain(best_params, best_ratio_params, best_speed_params);
+            if !within_noise.is_empty() {
+                let alternatives: Vec<String> = within_noise.iter().map(|params| params.to_string()).collect();
+                explanations.push(format!(
+                    "{} is not a clear winner: {} scored within measurement noise and may be as good on another machine",
+                    best_params, alternatives.join("; ")
+                ));
+            }
+            
             TuningResult {
                 best_parameters: best_params,
                 best_result: self.tested_parameters[&best_params].clone(),
@@ -293,6 +618,12 @@ impl Tuner {
                 best_speed_parameters: best_speed_params,
                 tuning_time: duration,
                 iterations,
+                explanations,
+                within_noise,
+                device_recommendations: self.config.device_profiles
+                    .iter()
+                    .map(|profile| profile.recommend(&self.results))
+                    .collect(),
             }
         } else {
             self.empty_result()
@@ -310,6 +641,10 @@ impl Tuner {
                 compressed_size: 0,
                 compression_time: Duration::new(0, 0),
                 decompression_time: Duration::new(0, 0),
+                compression_time_stddev: Duration::new(0, 0),
+                decompression_time_stddev: Duration::new(0, 0),
+                compression_allocations: None,
+                decompression_allocations: None,
                 parameters: default_params,
                 asset_info: None,
             },
@@ -318,51 +653,170 @@ impl Tuner {
             best_speed_parameters: default_params,
             tuning_time: Duration::new(0, 0),
             iterations: 0,
+            explanations: Vec::new(),
+            within_noise: Vec::new(),
+            device_recommendations: Vec::new(),
         }
     }
     
+    /// Parameter sets whose score can't be told apart from the best one
+    ///
+    /// Two scores are within noise if they differ by less than two standard
+    /// deviations of their difference, or by less than the configured
+    /// minimum significant difference.
+    fn within_noise(&self, best: CompressionParameters) -> Vec<CompressionParameters> {
+        let (best_score, best_noise) = self.scores[&best];
+        let mut close: Vec<(CompressionParameters, f64)> = self.scores
+            .iter()
+            .filter(|&(&params, &(score, noise))| {
+                let difference = best_score - score;
+                params != best
+                    && (difference < 2.0 * (best_noise.powi(2) + noise.powi(2)).sqrt()
+                        || difference < self.config.min_significant_difference * best_score)
+            })
+            .map(|(&params, &(score, _))| (params, score))
+            .collect();
+        
+        // Closest contenders first
+        close.sort_by(|a, b| b.1.total_cmp(&a.1));
+        close.into_iter().map(|(params, _)| params).collect()
+    }
+    
+    /// Explain the recommendations by comparing their recorded results
+    fn explain(
+        &self,
+        best: CompressionParameters,
+        best_ratio: CompressionParameters,
+        best_speed: CompressionParameters,
+    ) -> Vec<String> {
+        // Same speed measure as the tuning score
+        let speed = |r: &BenchmarkResult| (r.compression_throughput() + r.decompression_throughput()) / 2.0;
+        let best_result = &self.tested_parameters[&best];
+        let priority = self.config.ratio_priority;
+        
+        let mut explanations = vec![format!(
+            "{} recommended: ratio {:.2}%, {:.2} MB/s average throughput; best combined score of {} parameter sets at ratio priority {:.2}",
+            best, best_result.compression_ratio_percent(), speed(best_result), self.tested_parameters.len(), priority
+        )];
+        
+        if best_ratio != best {
+            let result = &self.tested_parameters[&best_ratio];
+            let gain = (1.0 - result.compression_ratio() / best_result.compression_ratio()) * 100.0;
+            let cost = (1.0 - speed(result) / speed(best_result)) * 100.0;
+            explanations.push(format!(
+                "{} gained {:.1}% ratio over {} but cost {:.1}% throughput; rejected by ratio priority {:.2}",
+                best_ratio, gain, best, cost, priority
+            ));
+        } else {
+            explanations.push(format!("{} also gives the best ratio of all tested parameters", best));
+        }
+        
+        if best_speed != best {
+            let result = &self.tested_parameters[&best_speed];
+            let gain = (speed(result) / speed(best_result) - 1.0) * 100.0;
+            let cost = (result.compression_ratio() / best_result.compression_ratio() - 1.0) * 100.0;
+            explanations.push(format!(
+                "{} was {:.1}% faster than {} but produced {:.1}% larger output; rejected by ratio priority {:.2}",
+                best_speed, gain, best, cost, priority
+            ));
+        } else {
+            explanations.push(format!("{} is also the fastest of all tested parameters", best));
+        }
+        
+        explanations
+    }
+    
+    /// Reset the tuning state so a new set of assets can be tuned
+    fn reset(&mut self) {
+        self.results.clear();
+        self.best_score = 0.0;
+        self.best_parameters = None;
+        self.best_ratio = f64::MAX;
+        self.best_ratio_parameters = None;
+        self.best_speed = 0.0;
+        self.best_speed_parameters = None;
+        self.tested_parameters.clear();
+        self.scores.clear();
+    }
+    
     /// Generate a set of optimal parameters for different asset types
     pub fn generate_asset_profiles(&mut self, assets: &mut [AssetInfo]) -> HashMap<super::asset_loader::AssetType, CompressionParameters> {
-        // Group assets by type
         use super::asset_loader::AssetType;
-        let mut asset_groups: HashMap<AssetType, Vec<&mut AssetInfo>> = HashMap::new();
         
-        for asset in assets {
-            asset_groups.entry(asset.asset_type).or_default().push(asset);
+        self.generate_profiles_by(assets, "asset type", |asset| {
+            // Unknown assets don't get a profile of their own
+            if asset.asset_type == AssetType::Unknown {
+                None
+            } else {
+                Some(asset.asset_type)
+            }
+        })
+    }
+    
+    /// Generate a set of optimal parameters for each directory under `root`
+    ///
+    /// Projects often encode asset semantics in paths rather than extensions
+    /// (e.g. `levels/` vs `audio/banks/`), so assets are grouped by their
+    /// parent directory relative to `root`.
+    pub fn generate_directory_profiles(&mut self, assets: &mut [AssetInfo], root: &Path) -> HashMap<PathBuf, CompressionParameters> {
+        self.generate_profiles_by(assets, "directory", |asset| {
+            let parent = asset.path.parent()?;
+            Some(parent.strip_prefix(root).unwrap_or(parent).to_path_buf())
+        })
+    }
+    
+    /// Group assets by `key` and tune parameters for each group
+    fn generate_profiles_by<K, F>(&mut self, assets: &mut [AssetInfo], group_name: &str, key: F) -> HashMap<K, CompressionParameters>
+    where
+        K: Eq + Hash + fmt::Debug,
+        F: Fn(&AssetInfo) -> Option<K>,
+    {
+        // Group assets by key
+        let mut asset_groups: HashMap<K, Vec<usize>> = HashMap::new();
+        
+        for (index, asset) in assets.iter().enumerate() {
+            if let Some(group_key) = key(asset) {
+                asset_groups.entry(group_key).or_default().push(index);
+            }
         }
         
-        // Tune parameters for each asset type
+        // Tune parameters for each group
         let mut profiles = HashMap::new();
         
-        for (asset_type, group) in asset_groups {
-            if asset_type == AssetType::Unknown || group.is_empty() {
+        for (group_key, group) in asset_groups {
+            // Skip groups too small to derive a representative profile from
+            let group_bytes: usize = group.iter().map(|&index| assets[index].size).sum();
+            if group.is_empty() || group.len() < self.config.min_profile_assets || group_bytes < self.config.min_profile_bytes {
+                println!("Skipping {} {:?}: {} assets, {} bytes is below the sampling minimums",
+                         group_name, group_key, group.len(), group_bytes);
                 continue;
             }
             
-            println!("Tuning for asset type: {:?} ({} assets)", asset_type, group.len());
+            println!("Tuning for {}: {:?} ({} assets)", group_name, group_key, group.len());
             
-            // Take a sample if the group is large
-            let sample: Vec<_> = if group.len() > 5 {
-                let mut rng = rand::thread_rng();
-                group.choose_multiple(&mut rng, 5).cloned().collect()
-            } else {
-                group
-            };
+            // Take a sample stratified by file size if the group is large
+            let sizes: Vec<(usize, usize)> = group.iter().map(|&index| (index, assets[index].size)).collect();
+            let sample = stratified_sample(
+                &sizes,
+                self.config.profile_sample_size.max(self.config.min_profile_assets),
+                self.config.min_profile_bytes,
+                &mut rand::thread_rng(),
+            );
             
-            // Reset tuner state
-            self.results.clear();
-            self.best_score = 0.0;
-            self.best_parameters = None;
-            self.best_ratio = f64::MAX;
-            self.best_ratio_parameters = None;
-            self.best_speed = 0.0;
-            self.best_speed_parameters = None;
-            self.tested_parameters.clear();
-            self.parameter_space = self.parameter_space.clone();
+            // Load the sampled assets
+            let sample_data: Vec<(Vec<u8>, usize)> = sample
+                .into_iter()
+                .filter_map(|index| Some((assets[index].data().ok()?.to_vec(), index)))
+                .collect();
+            let loaded: Vec<(Vec<u8>, &AssetInfo)> = sample_data
+                .into_iter()
+                .map(|(data, index)| (data, &assets[index]))
+                .collect();
             
-            // Tune for this asset type
-            let result = self.tune_for_assets(&mut sample.iter_mut().copied().collect::<Vec<_>>());
-            profiles.insert(asset_type, result.best_parameters);
+            // Tune for this group
+            self.reset();
+            let result = self.tune_for_loaded(&loaded);
+            profiles.insert(group_key, result.best_parameters);
             
             println!("  Best parameters: {}", result.best_parameters);
             println!("  Compression ratio: {:.2}%", result.best_result.compression_ratio_percent());
@@ -372,12 +826,47 @@ impl Tuner {
     }
 }
 
+/// Pick `count` assets from `(index, size)` pairs, one from each file-size
+/// stratum, then keep adding random assets until `min_bytes` is covered
+///
+/// Returns the selected indices.
+pub(crate) fn stratified_sample<R: Rng>(assets: &[(usize, usize)], count: usize, min_bytes: usize, rng: &mut R) -> Vec<usize> {
+    if assets.len() <= count {
+        return assets.iter().map(|&(index, _)| index).collect();
+    }
+    
+    // Split the size-sorted assets into `count` contiguous strata
+    let mut sorted = assets.to_vec();
+    sorted.sort_by_key(|&(_, size)| size);
+    let mut picked = vec![false; sorted.len()];
+    for stratum in 0..count {
+        let begin = stratum * sorted.len() / count;
+        let end = (stratum + 1) * sorted.len() / count;
+        picked[rng.gen_range(begin..end)] = true;
+    }
+    
+    // Top up with random assets until the byte minimum is reached
+    let mut total: usize = sorted.iter().zip(&picked).filter(|(_, &p)| p).map(|(&(_, size), _)| size).sum();
+    let mut remaining: Vec<usize> = (0..sorted.len()).filter(|&i| !picked[i]).collect();
+    remaining.shuffle(rng);
+    for i in remaining {
+        if total >= min_bytes {
+            break;
+        }
+        picked[i] = true;
+        total += sorted[i].1;
+    }
+    
+    sorted.iter().zip(&picked).filter(|(_, &p)| p).map(|(&(index, _), _)| index).collect()
+}
+
 /// Perform a quick benchmark with standard parameters on the given asset
 pub fn quick_benchmark(asset: &mut AssetInfo) -> Option<BenchmarkResult> {
     match asset.data() {
         Ok(data) => {
+            let data = data.to_vec();
             let params = CompressionParameters::new(4096, 3);
-            Some(run_benchmark(data, params, Some(asset), 1))
+            Some(run_benchmark(&data, params, Some(asset), 1))
         },
         Err(e) => {
             eprintln!("Error loading asset {}: {}", asset.filename(), e);
@@ -0,0 +1,456 @@
+//! Multi-block stream with per-block adaptive parameters
+//!
+//! The input is split into fixed-size blocks and each block is compressed
+//! with parameters picked from quick statistics of that block, so files
+//! mixing text and binary tables don't suffer from a single global setting.
+//!
+//! Stream layout:
+//!
+//! ```text
+//! magic "RLZB" | version (1 byte) | block*
+//! block: strategy (1 byte) | min_match (1 byte) | window_size (u16 LE)
+//!        | payload_len (u32 LE) | payload
+//! ```
+//!
+//! Every block carries its own parameter descriptor, so blocks compressed
+//! with different settings (e.g. different asset types in a solid archive)
+//! can be mixed freely in one stream.
+//!
+//! A stored block's payload is the raw bytes, an LZSS block's payload is a
+//! regular compressed stream (size header + tokens).
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+use crate::{LzssError, LZSS, MAX_MIN_MATCH_LENGTH};
+
+/// Magic bytes identifying a multi-block stream
+pub const BLOCK_MAGIC: [u8; 4] = *b"RLZB";
+
+/// Current version of the multi-block stream layout
+pub const BLOCK_VERSION: u8 = 1;
+
+/// Uncompressed size of each block produced by `compress_adaptive`
+pub const ADAPTIVE_BLOCK_SIZE: usize = 64 * 1024;
+
+/// Size of the per-block header in bytes
+const BLOCK_HEADER_SIZE: usize = 8;
+
+/// How a single block is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockStrategy {
+    /// The block is stored uncompressed
+    Stored,
+    /// The block is LZSS compressed
+    Lzss,
+}
+
+impl BlockStrategy {
+    fn to_byte(self) -> u8 {
+        match self {
+            BlockStrategy::Stored => 0,
+            BlockStrategy::Lzss => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(BlockStrategy::Stored),
+            1 => Some(BlockStrategy::Lzss),
+            _ => None,
+        }
+    }
+}
+
+/// Parameter descriptor stored in each block header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockParameters {
+    /// How the block is encoded
+    pub strategy: BlockStrategy,
+    /// Sliding window size (up to 65535)
+    pub window_size: usize,
+    /// Minimum match length (1 to `MAX_MIN_MATCH_LENGTH`)
+    pub min_match_length: usize,
+}
+
+impl BlockParameters {
+    /// Create a descriptor for an LZSS compressed block
+    pub fn new(window_size: usize, min_match_length: usize) -> Self {
+        BlockParameters {
+            strategy: BlockStrategy::Lzss,
+            window_size: window_size.min(65535),
+            min_match_length: min_match_length.clamp(1, MAX_MIN_MATCH_LENGTH),
+        }
+    }
+}
+
+/// Quick statistics used to pick the parameters of a block
+#[derive(Debug, Clone, Copy)]
+pub struct BlockStats {
+    /// Shannon entropy of the byte histogram in bits per byte
+    pub entropy: f64,
+    /// Fraction of printable ASCII and whitespace bytes
+    pub text_fraction: f64,
+}
+
+impl BlockStats {
+    /// Gather statistics for a block in a single pass
+    pub fn analyze(block: &[u8]) -> Self {
+        if block.is_empty() {
+            return BlockStats { entropy: 0.0, text_fraction: 0.0 };
+        }
+
+        let mut histogram = [0usize; 256];
+        for &byte in block {
+            histogram[byte as usize] += 1;
+        }
+
+        let len = block.len() as f64;
+        let mut entropy = 0.0;
+        let mut text = 0;
+        for (byte, &count) in histogram.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let p = count as f64 / len;
+            entropy -= p * p.log2();
+
+            let byte = byte as u8;
+            if byte.is_ascii_graphic() || byte.is_ascii_whitespace() {
+                text += count;
+            }
+        }
+
+        BlockStats {
+            entropy,
+            text_fraction: text as f64 / len,
+        }
+    }
+
+    /// Pick the strategy and minimum match length for the block
+    pub fn choose_parameters(&self) -> (BlockStrategy, usize) {
+        if self.entropy > 7.5 {
+            // Close to random, matches won't pay for their tokens
+            (BlockStrategy::Stored, 3)
+        } else if self.text_fraction > 0.9 {
+            // Text has many short repeats (words, syntax)
+            (BlockStrategy::Lzss, 3)
+        } else {
+            // Binary tables repeat in longer records, and requiring longer
+            // matches avoids wasting tokens on coincidental short repeats
+            (BlockStrategy::Lzss, 4)
+        }
+    }
+}
+
+impl LZSS {
+    /// Compress input as a multi-block stream with per-block parameters
+    ///
+    /// The window size of this instance is used for every block, while the
+    /// strategy and minimum match length are chosen per block and recorded
+    /// in the block headers. Blocks that would expand are stored.
+    pub fn compress_adaptive(&self, input: &[u8]) -> Vec<u8> {
+        LZSS::compress_blocks(input.chunks(ADAPTIVE_BLOCK_SIZE).map(|block| {
+            let (strategy, min_match) = BlockStats::analyze(block).choose_parameters();
+            let params = BlockParameters {
+                strategy,
+                ..BlockParameters::new(self.window_size, min_match)
+            };
+            (block, params)
+        }))
+    }
+
+    /// Compress a sequence of blocks, each with its own parameters
+    ///
+    /// Blocks that would expand are stored regardless of the requested
+    /// strategy.
+    pub fn compress_blocks<'a, I>(blocks: I) -> Vec<u8>
+    where
+        I: IntoIterator<Item = (&'a [u8], BlockParameters)>,
+    {
+        let mut output = Vec::new();
+        output.extend_from_slice(&BLOCK_MAGIC);
+        output.push(BLOCK_VERSION);
+
+        for (block, mut params) in blocks {
+            // Clamped like `LZSS::new` does, so the header matches the
+            // parameters the block is compressed with
+            params.window_size = params.window_size.min(65535);
+            params.min_match_length = params.min_match_length.clamp(1, MAX_MIN_MATCH_LENGTH);
+
+            let mut payload = Vec::new();
+            if params.strategy == BlockStrategy::Lzss {
+                payload = LZSS::new(params.window_size, params.min_match_length).compress(block);
+                if payload.len() >= block.len() {
+                    params.strategy = BlockStrategy::Stored;
+                }
+            }
+            let payload = match params.strategy {
+                BlockStrategy::Stored => block,
+                BlockStrategy::Lzss => &payload[..],
+            };
+
+            output.push(params.strategy.to_byte());
+            output.push(params.min_match_length as u8);
+            output.extend_from_slice(&(params.window_size as u16).to_le_bytes());
+            output.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            output.extend_from_slice(payload);
+        }
+
+        output
+    }
+
+    /// Decompress a multi-block stream produced by `compress_adaptive` or
+    /// `compress_blocks`
+    ///
+    /// All parameters are read from the block headers, so no configured
+    /// instance is needed. Corrupt input is an error: input without the
+    /// block magic (`InvalidHeader`), a stream that ends inside a block or
+    /// an unknown block strategy (`TruncatedInput`), or a block that fails
+    /// like `try_decompress`, including references reaching further back
+    /// than the block's declared window.
+    pub fn try_decompress_adaptive(input: &[u8]) -> Result<Vec<u8>, LzssError> {
+        let blocks = split_blocks(input).map_err(|(_, error)| error)?;
+        let mut output = Vec::new();
+        for (params, payload) in blocks {
+            output.extend_from_slice(&decode_block(params, payload)?);
+        }
+        Ok(output)
+    }
+
+    /// Decompress a multi-block stream, patching up corrupt input
+    ///
+    /// Invalid references are skipped, missing output is padded with zeros
+    /// and decoding stops at the first malformed block, so corruption goes
+    /// unnoticed.
+    #[deprecated(note = "hides corrupt input; use `try_decompress_adaptive`")]
+    pub fn decompress_adaptive(input: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+        for (params, payload) in BlockIter::new(input) {
+            match params.strategy {
+                BlockStrategy::Stored => output.extend_from_slice(payload),
+                BlockStrategy::Lzss => {
+                    let lzss = LZSS::new(params.window_size, params.min_match_length);
+                    output.extend_from_slice(&lzss.decompress_with_window(payload, params.window_size));
+                }
+            }
+        }
+        output
+    }
+}
+
+/// Errors from `LZSS::decompress_adaptive_verified`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError<E> {
+    /// A block is corrupt
+    Decode {
+        /// Index of the block in the stream
+        block: usize,
+        /// Decoding error
+        error: LzssError,
+    },
+    /// The verifier rejected a decoded block
+    Verify {
+        /// Index of the block in the stream
+        block: usize,
+        /// Error returned by the verifier
+        error: E,
+    },
+}
+
+impl<E: fmt::Display> fmt::Display for VerifyError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Decode { block, error } => write!(f, "block {} is corrupt: {}", block, error),
+            VerifyError::Verify { block, error } => write!(f, "block {} failed verification: {}", block, error),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for VerifyError<E> {}
+
+/// Parameters and payload of one block
+type Block<'a> = (BlockParameters, &'a [u8]);
+
+/// Split a multi-block stream into its blocks, failing with the index of
+/// the offending block if the magic is missing or a block is truncated or
+/// unknown
+fn split_blocks(input: &[u8]) -> Result<Vec<Block<'_>>, (usize, LzssError)> {
+    if !is_block_stream(input) {
+        return Err((0, LzssError::InvalidHeader));
+    }
+    let mut iter = BlockIter::new(input);
+    let blocks: Vec<_> = iter.by_ref().collect();
+    if iter.pos != input.len() {
+        return Err((blocks.len(), LzssError::TruncatedInput));
+    }
+    Ok(blocks)
+}
+
+/// Strictly decode one block
+fn decode_block(params: BlockParameters, payload: &[u8]) -> Result<Vec<u8>, LzssError> {
+    match params.strategy {
+        BlockStrategy::Stored => Ok(payload.to_vec()),
+        BlockStrategy::Lzss => {
+            // Validate first, so a corrupt size header can't force a huge
+            // allocation, and hold references to the block's declared window
+            let lzss = LZSS::new(params.window_size, params.min_match_length);
+            let stats = lzss.validate_stream_within(payload, 0, params.window_size)?;
+            let mut output = vec![0; stats.content_size];
+            lzss.decompress_into(payload, &mut output)?;
+            Ok(output)
+        }
+    }
+}
+
+impl LZSS {
+    /// Decompress a multi-block stream on `decode_threads` threads while
+    /// `verify_threads` other threads check the decoded blocks
+    ///
+    /// `verify` is called once per block with its index and content, as
+    /// soon as the block is decoded and in no particular order, so hashing
+    /// or validating installed data overlaps with decoding. Corrupt input is
+    /// reported as in `try_decompress_adaptive`, naming the block. The
+    /// first error stops the remaining work and is returned.
+    pub fn decompress_adaptive_verified<F, E>(
+        input: &[u8],
+        decode_threads: usize,
+        verify_threads: usize,
+        verify: F,
+    ) -> Result<Vec<u8>, VerifyError<E>>
+    where
+        F: Fn(usize, &[u8]) -> Result<(), E> + Sync,
+        E: Send,
+    {
+        let blocks = split_blocks(input).map_err(|(block, error)| VerifyError::Decode { block, error })?;
+        let mut decoded: Vec<Vec<u8>> = vec![Vec::new(); blocks.len()];
+        let mut first_error = None;
+
+        let next_block = AtomicUsize::new(0);
+        let failed = AtomicBool::new(false);
+        let (decoded_tx, decoded_rx) = mpsc::channel::<(usize, Vec<u8>)>();
+        let decoded_rx = Mutex::new(decoded_rx);
+        let (result_tx, result_rx) = mpsc::channel();
+
+        thread::scope(|scope| {
+            let (blocks, next_block, failed, decoded_rx, verify) =
+                (&blocks, &next_block, &failed, &decoded_rx, &verify);
+
+            for _ in 0..decode_threads.max(1) {
+                let decoded_tx = decoded_tx.clone();
+                let result_tx = result_tx.clone();
+                scope.spawn(move || {
+                    while !failed.load(Ordering::Relaxed) {
+                        let index = next_block.fetch_add(1, Ordering::Relaxed);
+                        let Some(&(params, payload)) = blocks.get(index) else {
+                            break;
+                        };
+                        match decode_block(params, payload) {
+                            Ok(block) => {
+                                let _ = decoded_tx.send((index, block));
+                            }
+                            Err(error) => {
+                                failed.store(true, Ordering::Relaxed);
+                                let _ = result_tx.send(Err(VerifyError::Decode { block: index, error }));
+                            }
+                        }
+                    }
+                });
+            }
+            // Verifiers stop once every decoder has dropped its sender
+            drop(decoded_tx);
+
+            for _ in 0..verify_threads.max(1) {
+                let result_tx = result_tx.clone();
+                scope.spawn(move || loop {
+                    let received = decoded_rx.lock().unwrap().recv();
+                    let Ok((index, block)) = received else {
+                        break;
+                    };
+                    if failed.load(Ordering::Relaxed) {
+                        continue;
+                    }
+                    match verify(index, &block) {
+                        Ok(()) => {
+                            let _ = result_tx.send(Ok((index, block)));
+                        }
+                        Err(error) => {
+                            failed.store(true, Ordering::Relaxed);
+                            let _ = result_tx.send(Err(VerifyError::Verify { block: index, error }));
+                        }
+                    }
+                });
+            }
+            drop(result_tx);
+
+            for result in result_rx {
+                match result {
+                    Ok((index, block)) => decoded[index] = block,
+                    Err(error) => {
+                        first_error.get_or_insert(error);
+                    }
+                }
+            }
+        });
+
+        match first_error {
+            Some(error) => Err(error),
+            None => Ok(decoded.concat()),
+        }
+    }
+}
+
+/// Iterator over the blocks of a multi-block stream
+///
+/// Yields each block's descriptor and payload without decompressing it,
+/// and stops at the first malformed block.
+pub struct BlockIter<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BlockIter<'a> {
+    /// Iterate over the blocks of `input` (yields nothing if it isn't a
+    /// multi-block stream)
+    pub fn new(input: &'a [u8]) -> Self {
+        let pos = if is_block_stream(input) { BLOCK_MAGIC.len() + 1 } else { input.len() };
+        BlockIter { input, pos }
+    }
+}
+
+impl<'a> Iterator for BlockIter<'a> {
+    type Item = (BlockParameters, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let input = self.input;
+        let pos = self.pos;
+        if pos + BLOCK_HEADER_SIZE > input.len() {
+            return None;
+        }
+
+        let strategy = BlockStrategy::from_byte(input[pos])?;
+        let min_match_length = input[pos + 1] as usize;
+        let window_size = u16::from_le_bytes([input[pos + 2], input[pos + 3]]) as usize;
+        let payload_len = u32::from_le_bytes([
+            input[pos + 4], input[pos + 5], input[pos + 6], input[pos + 7],
+        ]) as usize;
+
+        let start = pos + BLOCK_HEADER_SIZE;
+        if payload_len > input.len() - start {
+            return None; // Truncated block, `pos` stays at its header
+        }
+        self.pos = start + payload_len;
+
+        let params = BlockParameters { strategy, window_size, min_match_length };
+        Some((params, &input[start..start + payload_len]))
+    }
+}
+
+/// Check whether the input starts with a multi-block stream header
+pub fn is_block_stream(input: &[u8]) -> bool {
+    input.len() > BLOCK_MAGIC.len()
+        && input[..BLOCK_MAGIC.len()] == BLOCK_MAGIC
+        && input[BLOCK_MAGIC.len()] == BLOCK_VERSION
+}
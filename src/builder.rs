@@ -0,0 +1,201 @@
+//! Validated codec configuration
+//!
+//! `LzssBuilder` collects encoder options with chained setters and checks
+//! them together in `build`, so new options don't widen `LZSS::new` and
+//! invalid combinations are reported instead of producing broken streams.
+
+use crate::{CompressionLevel, ConfigError, LengthField, DEFAULT_NICE_LENGTH, LZSS, MAX_LONG_DISTANCE, MAX_MIN_MATCH_LENGTH, MAX_SHORT_DISTANCE};
+#[cfg(feature = "small-target")]
+use crate::MAX_WINDOW_SIZE;
+
+/// Builder for a validated `LZSS` configuration
+///
+/// Starts from a 4096-byte window and minimum match length 3, with the
+/// other options at their `LZSS::new` defaults.
+#[derive(Debug, Clone)]
+pub struct LzssBuilder {
+    window_size: usize,
+    min_match_length: usize,
+    max_match_length: Option<usize>,
+    search_depth: usize,
+    nice_length: usize,
+    lazy_matching: bool,
+    length_field: LengthField,
+    long_distance_history: Option<usize>,
+    overlapping_matches: bool,
+    skip_incompressible: bool,
+    short_offsets: bool,
+}
+
+impl Default for LzssBuilder {
+    fn default() -> Self {
+        LzssBuilder {
+            window_size: 4096,
+            min_match_length: 3,
+            max_match_length: None,
+            search_depth: usize::MAX,
+            nice_length: DEFAULT_NICE_LENGTH,
+            lazy_matching: false,
+            length_field: LengthField::OneByte,
+            long_distance_history: None,
+            overlapping_matches: true,
+            skip_incompressible: true,
+            short_offsets: false,
+        }
+    }
+}
+
+impl LzssBuilder {
+    /// Create a builder with the default configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sliding window size in bytes
+    pub fn window_size(mut self, window_size: usize) -> Self {
+        self.window_size = window_size;
+        self
+    }
+
+    /// Shortest match the encoder emits (at least 2)
+    pub fn min_match_length(mut self, min_match_length: usize) -> Self {
+        self.min_match_length = min_match_length;
+        self
+    }
+
+    /// Longest match the encoder emits (by default, the longest the length
+    /// field can represent)
+    pub fn max_match_length(mut self, max_match_length: usize) -> Self {
+        self.max_match_length = Some(max_match_length);
+        self
+    }
+
+    /// Most earlier positions examined per match search (see
+    /// `LZSS::with_search_depth`)
+    pub fn search_depth(mut self, depth: usize) -> Self {
+        self.search_depth = depth;
+        self
+    }
+
+    /// Match length that ends the match search early (see
+    /// `LZSS::with_nice_length`)
+    pub fn nice_length(mut self, nice_length: usize) -> Self {
+        self.nice_length = nice_length;
+        self
+    }
+
+    /// Enable lazy matching (see `LZSS::with_lazy_matching`)
+    pub fn lazy_matching(mut self, enabled: bool) -> Self {
+        self.lazy_matching = enabled;
+        self
+    }
+
+    /// Width of the match length field (see `LZSS::with_length_field`)
+    pub fn length_field(mut self, length_field: LengthField) -> Self {
+        self.length_field = length_field;
+        self
+    }
+
+    /// Enable long-distance matching over `history_size` bytes (see
+    /// `LZSS::with_long_distance_matching`)
+    pub fn long_distance_matching(mut self, history_size: usize) -> Self {
+        self.long_distance_history = Some(history_size);
+        self
+    }
+
+    /// Allow or forbid overlapping matches (see
+    /// `LZSS::with_overlapping_matches`)
+    pub fn overlapping_matches(mut self, allowed: bool) -> Self {
+        self.overlapping_matches = allowed;
+        self
+    }
+
+    /// Store near match distances in one byte (see
+    /// `LZSS::with_short_offsets`); the window can then reach half as far
+    pub fn short_offsets(mut self, enabled: bool) -> Self {
+        self.short_offsets = enabled;
+        self
+    }
+
+    /// Skip ahead through data without matches (see
+    /// `LZSS::with_skip_incompressible`)
+    pub fn skip_incompressible(mut self, enabled: bool) -> Self {
+        self.skip_incompressible = enabled;
+        self
+    }
+
+    /// Apply a compression level preset, replacing the window size, search
+    /// depth, nice length, lazy matching and skipping settings
+    pub fn level(mut self, level: CompressionLevel) -> Self {
+        let settings = level.settings();
+        let window_size = settings.window_size;
+        #[cfg(feature = "small-target")]
+        let window_size = window_size.min(MAX_WINDOW_SIZE);
+        self.window_size = window_size;
+        self.search_depth = settings.search_depth;
+        self.nice_length = settings.nice_length;
+        self.lazy_matching = settings.lazy_matching;
+        self.skip_incompressible = settings.skip_incompressible;
+        self
+    }
+
+    /// Check the configuration and create the codec
+    pub fn build(&self) -> Result<LZSS, ConfigError> {
+        let max_window = if self.long_distance_history.is_some() { MAX_LONG_DISTANCE } else { MAX_SHORT_DISTANCE };
+        // Short offsets spend a bit of the long form on the form flag
+        let max_window = if self.short_offsets { max_window >> 1 } else { max_window };
+        #[cfg(feature = "small-target")]
+        let max_window = max_window.min(MAX_WINDOW_SIZE);
+        if self.window_size == 0 || self.window_size > max_window {
+            return Err(ConfigError::InvalidWindowSize { window_size: self.window_size, max: max_window });
+        }
+        if !(2..=MAX_MIN_MATCH_LENGTH).contains(&self.min_match_length) {
+            return Err(ConfigError::InvalidMinMatchLength {
+                min_match_length: self.min_match_length,
+                max: MAX_MIN_MATCH_LENGTH,
+            });
+        }
+        if let Some(max_match_length) = self.max_match_length {
+            let representable = self.length_field.max_value().saturating_add(self.min_match_length);
+            if max_match_length < self.min_match_length || max_match_length > representable {
+                return Err(ConfigError::InvalidMaxMatchLength {
+                    max_match_length,
+                    min: self.min_match_length,
+                    max: representable,
+                });
+            }
+        }
+        if let Some(history_size) = self.long_distance_history {
+            let max_history = if self.short_offsets { MAX_LONG_DISTANCE >> 1 } else { MAX_LONG_DISTANCE };
+            #[cfg(feature = "small-target")]
+            let max_history = max_history.min(MAX_WINDOW_SIZE);
+            if history_size == 0 || history_size > max_history {
+                return Err(ConfigError::InvalidLongDistanceHistory { history_size, max: max_history });
+            }
+        }
+        if self.search_depth == 0 {
+            return Err(ConfigError::InvalidSearchDepth);
+        }
+
+        let mut lzss = LZSS::new(self.window_size, self.min_match_length)
+            .with_length_field(self.length_field)
+            .with_overlapping_matches(self.overlapping_matches)
+            .with_search_depth(self.search_depth)
+            .with_nice_length(self.nice_length)
+            .with_lazy_matching(self.lazy_matching)
+            .with_skip_incompressible(self.skip_incompressible)
+            .with_short_offsets(self.short_offsets);
+        if let Some(history_size) = self.long_distance_history {
+            lzss = lzss.with_long_distance_matching(history_size);
+        }
+        lzss.max_match_length = self.max_match_length;
+        Ok(lzss)
+    }
+}
+
+impl LZSS {
+    /// Start a validated configuration (see `LzssBuilder`)
+    pub fn builder() -> LzssBuilder {
+        LzssBuilder::new()
+    }
+}
@@ -0,0 +1,177 @@
+//! Standalone C decoder generation
+//!
+//! Emits a small, dependency-free C source file that decodes the streams of
+//! one codec configuration, so microcontroller firmware can read assets
+//! produced by the Rust encoder without linking this library. The
+//! parameters are baked in, which keeps the decoder tiny.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt::Write as _;
+
+use crate::frame::{
+    FLAG_CRC32, FLAG_DISTANCE_MINUS_ONE, FLAG_PADDED, FLAG_SHORT_OFFSETS, FLAG_SLOT_PADDED, FLAG_WINDOW_SIZE, FLAG_XXH3,
+    FRAME_MAGIC,
+};
+use crate::{LengthField, LZSS};
+
+/// Options for the generated C decoder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CDecoderOptions {
+    /// Name of the generated decode function
+    pub function_name: String,
+    /// Decode frames (checking that their parameters match) instead of raw
+    /// streams
+    pub frames: bool,
+}
+
+impl Default for CDecoderOptions {
+    fn default() -> Self {
+        CDecoderOptions {
+            function_name: "rlzss_decode".to_string(),
+            frames: false,
+        }
+    }
+}
+
+impl LZSS {
+    /// Generate the source of a C decoder for streams produced by this
+    /// configuration
+    ///
+    /// The generated function has the signature
+    /// `int name(const uint8_t *in, size_t in_len, uint8_t *out, size_t out_cap, size_t *out_len)`
+    /// and returns 0 on success, -1 for corrupt input (or a frame with other
+    /// parameters) and -2 if `out_cap` is too small.
+    pub fn c_decoder_source(&self, options: &CDecoderOptions) -> String {
+        let offset_bytes = self.offset_bytes();
+        let length_code = self.length_field.to_code();
+        let name = &options.function_name;
+        let mut c = String::new();
+
+        let _ = writeln!(c, "/* Generated by rustzss. Do not edit by hand.");
+        let _ = writeln!(
+            c,
+            " * Decodes {} with min_match={}, {}-byte distances{} and {:?} length fields. */",
+            if options.frames { "frames" } else { "raw streams" },
+            self.min_match_length,
+            offset_bytes,
+            if self.short_offsets { " (1 byte when near)" } else { "" },
+            self.length_field
+        );
+        c.push_str("#include <stddef.h>\n#include <stdint.h>\n\n");
+        let _ = writeln!(c, "/* Returns 0 on success, -1 on corrupt input, -2 if out_cap is too small */");
+        let _ = writeln!(
+            c,
+            "int {}(const uint8_t *in, size_t in_len, uint8_t *out, size_t out_cap, size_t *out_len)\n{{",
+            name
+        );
+        c.push_str("    size_t pos = 0, produced = 0, size, distance, length, bias = 0;\n");
+        c.push_str("    unsigned bit;\n    uint8_t control;\n");
+
+        if options.frames {
+            self.write_frame_header_check(&mut c, offset_bytes, length_code);
+        } else {
+            let _ = writeln!(c, "    bias = {};", self.distance_bias());
+        }
+
+        c.push_str(
+            "\n    if (in_len == pos) { /* Empty input compresses to nothing */\n        *out_len = 0;\n        return 0;\n    }\n",
+        );
+        c.push_str("    if (in_len - pos < 5) return -1;\n");
+        c.push_str("    size = (size_t)in[pos] | (size_t)in[pos + 1] << 8 | (size_t)in[pos + 2] << 16 | (size_t)in[pos + 3] << 24;\n");
+        c.push_str("    pos += 4;\n    if (size > out_cap) return -2;\n\n");
+
+        c.push_str("    while (produced < size) {\n");
+        c.push_str("        if (pos >= in_len) return -1;\n        control = in[pos++];\n");
+        c.push_str("        for (bit = 0; bit < 8 && produced < size; bit++) {\n");
+        c.push_str("            if (!(control & (1u << bit))) {\n");
+        c.push_str("                if (pos >= in_len) return -1;\n");
+        c.push_str("                out[produced++] = in[pos++];\n                continue;\n            }\n\n");
+
+        if self.short_offsets {
+            // One byte unless its lowest bit is set
+            c.push_str("            if (in_len - pos < 2) return -1;\n");
+            c.push_str("            if (!(in[pos] & 1)) {\n                distance = in[pos++] >> 1;\n            } else {\n");
+            self.write_long_distance(&mut c, offset_bytes, "    ");
+            c.push_str("                distance >>= 1;\n            }\n");
+            c.push_str("            distance += bias;\n");
+        } else {
+            self.write_long_distance(&mut c, offset_bytes, "");
+            c.push_str("            distance += bias;\n");
+        }
+
+        match self.length_field {
+            LengthField::OneByte => c.push_str("            length = in[pos++];\n"),
+            LengthField::TwoBytes => c.push_str(
+                "            if (in_len - pos < 2) return -1;\n            length = (size_t)in[pos] | (size_t)in[pos + 1] << 8;\n            pos += 2;\n",
+            ),
+            LengthField::Extended => c.push_str(
+                "            length = 0;\n            do {\n                if (pos >= in_len) return -1;\n                length += in[pos];\n            } while (in[pos++] == 255);\n",
+            ),
+        }
+        let _ = writeln!(c, "            length += {};", self.min_match_length);
+
+        c.push_str("\n            if (distance == 0 || distance > produced) return -1;\n");
+        c.push_str("            if (length > size - produced) length = size - produced;\n");
+        c.push_str("            while (length--) {\n                out[produced] = out[produced - distance];\n                produced++;\n            }\n");
+        c.push_str("        }\n    }\n\n    *out_len = size;\n    return 0;\n}\n");
+
+        c
+    }
+
+    /// Emit the reading of a full-width distance field, indented by
+    /// `indent` beyond the token level
+    fn write_long_distance(&self, c: &mut String, offset_bytes: usize, indent: &str) {
+        let _ = writeln!(c, "{}            if (in_len - pos < {}) return -1;", indent, offset_bytes + 1);
+        let _ = write!(c, "{}            distance = (size_t)in[pos] | (size_t)in[pos + 1] << 8", indent);
+        if offset_bytes == 3 {
+            c.push_str(" | (size_t)in[pos + 2] << 16");
+        }
+        let _ = writeln!(c, ";\n{}            pos += {};", indent, offset_bytes);
+    }
+
+    /// Emit the frame header parsing of the generated decoder
+    fn write_frame_header_check(&self, c: &mut String, offset_bytes: usize, length_code: u8) {
+        let magic = FRAME_MAGIC.map(|b| format!("'{}'", b as char));
+        c.push_str("    size_t padding = 0;\n\n    /* Frame header */\n");
+        let _ = writeln!(
+            c,
+            "    if (in_len < 7 || in[0] != {} || in[1] != {} || in[2] != {} || in[3] != {}) return -1;",
+            magic[0], magic[1], magic[2], magic[3]
+        );
+        let _ = writeln!(
+            c,
+            "    if (in[5] != {} || in[6] != {}) return -1; /* Encoded with other parameters */",
+            self.min_match_length, offset_bytes
+        );
+        if self.length_field == LengthField::OneByte && !self.short_offsets {
+            c.push_str("    if (in[4] == 1) {\n        pos = 7;\n    } else if (in[4] == 2) {\n");
+        } else {
+            // Version 1 frames always use one-byte length fields and
+            // full-width offsets
+            c.push_str("    if (in[4] == 2) {\n");
+        }
+        let _ = writeln!(c, "        if (in_len < 9 || in[7] != {}) return -1;", length_code);
+        let known = FLAG_DISTANCE_MINUS_ONE | FLAG_PADDED | FLAG_SLOT_PADDED | FLAG_CRC32 | FLAG_XXH3 | FLAG_WINDOW_SIZE;
+        if self.short_offsets {
+            // Frames without short offsets were encoded with other parameters
+            let _ = writeln!(c, "        if (!(in[8] & 0x{:02x})) return -1;", FLAG_SHORT_OFFSETS);
+            let _ = writeln!(c, "        if (in[8] & ~0x{:02x}) return -1;", known | FLAG_SHORT_OFFSETS);
+        } else {
+            let _ = writeln!(c, "        if (in[8] & ~0x{:02x}) return -1;", known);
+        }
+        let _ = writeln!(c, "        bias = in[8] & 0x{:02x} ? 1 : 0;", FLAG_DISTANCE_MINUS_ONE);
+        c.push_str("        pos = 9;\n");
+        let _ = writeln!(c, "        if (in[8] & 0x{:02x}) {{", FLAG_PADDED);
+        c.push_str("            if (in_len < pos + 4) return -1;\n");
+        c.push_str("            padding = (size_t)in[pos] | (size_t)in[pos + 1] << 8 | (size_t)in[pos + 2] << 16 | (size_t)in[pos + 3] << 24;\n");
+        c.push_str("            pos += 4;\n        }\n");
+        let _ = writeln!(c, "        if (in[8] & 0x{:02x}) pos += 8; /* Slot padding is part of the content */", FLAG_SLOT_PADDED);
+        let _ = writeln!(c, "        if (in[8] & 0x{:02x}) pos += 4; /* Checksums are not verified */", FLAG_CRC32);
+        let _ = writeln!(c, "        if (in[8] & 0x{:02x}) pos += 8;", FLAG_XXH3);
+        let _ = writeln!(c, "        if (in[8] & 0x{:02x}) pos += 4; /* Window size */", FLAG_WINDOW_SIZE);
+        c.push_str("        if (in_len < pos || padding > in_len - pos) return -1;\n");
+        c.push_str("        in_len -= padding;\n");
+        c.push_str("    } else {\n        return -1;\n    }\n");
+    }
+}
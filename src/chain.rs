@@ -0,0 +1,108 @@
+//! Hash-chain match finder
+//!
+//! Deflate-style chains: `head` holds the most recent position for each
+//! hash of the next 3 bytes (4 when the minimum match length is at least
+//! 4), and `prev` links every position to the previous one with the same
+//! hash. `prev` is a ring as large as the window (or the
+//! input, if shorter), so memory is fixed by the window and positions that
+//! fall out of it are overwritten instead of piling up.
+//!
+//! Hash collisions and stale links only yield extra candidates, which the
+//! encoder compares byte by byte like any other.
+
+use alloc::vec::Vec;
+
+/// Most hash bits (32K heads, or 4K in `small-target` builds); short inputs
+/// and small windows use fewer
+const MAX_HASH_BITS: u32 = if cfg!(feature = "small-target") { 12 } else { 15 };
+
+/// Fewest hash bits, so small inputs don't build long chains
+const MIN_HASH_BITS: u32 = 8;
+
+/// Position stored in the match finder's hash chains
+///
+/// `small-target` builds store positions modulo 2^16, which halves the
+/// tables on 32-bit targets. That is unambiguous because the window is far
+/// shorter than 64 KiB; older entries that alias into the window are only
+/// candidates and get compared byte by byte like any other.
+#[cfg(feature = "small-target")]
+type ChainPos = u16;
+#[cfg(not(feature = "small-target"))]
+type ChainPos = usize;
+
+/// Absolute position of the chain entry `stored`, seen from `pos` (`None`
+/// if a stale entry would lie before the input)
+#[cfg(feature = "small-target")]
+fn chain_position(pos: usize, stored: ChainPos) -> Option<usize> {
+    pos.checked_sub((pos as ChainPos).wrapping_sub(stored) as usize)
+}
+
+/// Absolute position of the chain entry `stored`, seen from `pos`
+#[cfg(not(feature = "small-target"))]
+fn chain_position(_pos: usize, stored: ChainPos) -> Option<usize> {
+    Some(stored)
+}
+
+#[derive(Default)]
+pub(crate) struct HashChains {
+    head: Vec<ChainPos>,
+    prev: Vec<ChainPos>,
+    hash_bits: u32,
+    /// Number of bytes hashed per position (3 or 4)
+    key_len: usize,
+}
+
+impl HashChains {
+    /// Empty the chains and size them for matches of at least
+    /// `min_match_length` bytes, up to `window_size` bytes back in
+    /// `input_len` bytes of input, reusing the tables' memory
+    ///
+    /// Hashing 4 bytes when matches are at least that long keeps
+    /// candidates that can't reach the minimum out of the chains, which
+    /// shortens them on binary data.
+    pub(crate) fn reset(&mut self, window_size: usize, input_len: usize, min_match_length: usize) {
+        let prev_len = window_size.min(input_len).max(1).next_power_of_two();
+        self.hash_bits = prev_len.trailing_zeros().clamp(MIN_HASH_BITS, MAX_HASH_BITS);
+        self.head.clear();
+        self.head.resize(1 << self.hash_bits, 0);
+        self.prev.clear();
+        self.prev.resize(prev_len, 0);
+        self.key_len = if min_match_length >= 4 { 4 } else { 3 };
+    }
+
+    /// Hash the key starting at `pos`, if the input holds a whole key there
+    fn hash(&self, input: &[u8], pos: usize) -> Option<usize> {
+        let key = input.get(pos..pos + self.key_len)?;
+        let key = key.iter().fold(0u32, |key, &byte| key << 8 | u32::from(byte));
+        Some((key.wrapping_mul(0x9E37_79B1) >> (32 - self.hash_bits)) as usize)
+    }
+
+    /// Add `pos`, unless the input ends before a whole key
+    pub(crate) fn insert(&mut self, input: &[u8], pos: usize) {
+        let Some(h) = self.hash(input, pos) else {
+            return;
+        };
+        let mask = self.prev.len() - 1;
+        self.prev[pos & mask] = self.head[h];
+        self.head[h] = pos as ChainPos;
+    }
+
+    /// Inserted positions before `pos` and not before `window_begin` that
+    /// may start with the same key as `pos`, most recent first
+    pub(crate) fn candidates<'a>(
+        &'a self,
+        input: &[u8],
+        pos: usize,
+        window_begin: usize,
+    ) -> impl Iterator<Item = usize> + 'a {
+        let mask = self.prev.len() - 1;
+        let first = self.hash(input, pos).and_then(|h| chain_position(pos, self.head[h]));
+        // Stop where a link doesn't lead further back (an unset or
+        // overwritten entry)
+        core::iter::successors(first, move |&candidate| {
+            chain_position(candidate, self.prev[candidate & mask]).filter(|&next| next < candidate)
+        })
+        .skip_while(move |&candidate| candidate >= pos)
+        .take_while(move |&candidate| candidate >= window_begin)
+    }
+}
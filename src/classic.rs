@@ -0,0 +1,254 @@
+//! Classic bit-packed LZSS
+//!
+//! The textbook token layout from Haruhiko Okumura's LZSS.C, which many
+//! existing tools and game formats read and write:
+//!
+//! ```text
+//! flags (1 byte, LSB first, 1 = literal) | up to 8 tokens | flags | ...
+//! literal: 1 byte
+//! match:   position low 8 bits | position high 4 bits << 4 | (length - 3)
+//! ```
+//!
+//! Matches are 3 to 18 bytes long and refer to a 4096-byte ring buffer by
+//! absolute position rather than distance. The ring starts filled with
+//! spaces and the first byte is written at position 4078, so streams from
+//! other encoders that match against the initial spaces decode too. There
+//! is no size header: the stream ends where the input does.
+//!
+//! With its 2-byte matches this layout beats the default one (3-byte
+//! matches) at window sizes up to 4096, at the cost of the short maximum
+//! match length.
+//!
+//! `BitLayout` generalizes the 12/4 split to the other splits retro-console
+//! and embedded formats use (e.g. 10/6 or 13/3, or 3-byte tokens). The
+//! low 8 position bits always fill the first byte, and the rest of the
+//! token holds the high position bits above the length bits (little-endian
+//! for 3-byte tokens). The ring is as large as the positions can address,
+//! the first byte goes `max_match` bytes before its end, and matches start
+//! at one byte more than a token takes.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::chain::HashChains;
+use crate::{ConfigError, LzssError};
+
+/// Size of the classic ring buffer
+pub const CLASSIC_WINDOW_SIZE: usize = 4096;
+
+/// Longest classic match (4-bit length field plus the minimum)
+pub const CLASSIC_MAX_MATCH: usize = 18;
+
+/// Shortest classic match; shorter repeats are cheaper as literals
+pub const CLASSIC_MIN_MATCH: usize = 3;
+
+/// Candidate positions examined per match search
+const SEARCH_DEPTH: usize = 128;
+
+/// Split of match tokens between ring buffer position and length bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BitLayout {
+    offset_bits: u32,
+    length_bits: u32,
+}
+
+impl Default for BitLayout {
+    fn default() -> Self {
+        BitLayout::CLASSIC
+    }
+}
+
+impl BitLayout {
+    /// 12-bit positions and 4-bit lengths, as in LZSS.C
+    pub const CLASSIC: BitLayout = BitLayout { offset_bits: 12, length_bits: 4 };
+
+    /// Tokens of `offset_bits` position bits and `length_bits` length bits
+    ///
+    /// The two must add up to 16 or 24 (2- or 3-byte tokens), with at least
+    /// 8 position bits, at least 1 length bit and a ring buffer larger than
+    /// the longest match; anything else is an `InvalidBitLayout` error.
+    pub fn new(offset_bits: u32, length_bits: u32) -> Result<Self, ConfigError> {
+        let layout = BitLayout { offset_bits, length_bits };
+        let valid = matches!(offset_bits.checked_add(length_bits), Some(16 | 24))
+            && offset_bits >= 8
+            && length_bits >= 1
+            && layout.max_match() < layout.window_size();
+        if valid {
+            Ok(layout)
+        } else {
+            Err(ConfigError::InvalidBitLayout { offset_bits, length_bits })
+        }
+    }
+
+    /// Number of ring buffer position bits
+    pub fn offset_bits(self) -> u32 {
+        self.offset_bits
+    }
+
+    /// Number of match length bits
+    pub fn length_bits(self) -> u32 {
+        self.length_bits
+    }
+
+    /// Size of the ring buffer
+    pub fn window_size(self) -> usize {
+        1 << self.offset_bits
+    }
+
+    /// Shortest match, one byte more than a token
+    pub fn min_match(self) -> usize {
+        self.token_bytes() + 1
+    }
+
+    /// Longest match
+    pub fn max_match(self) -> usize {
+        (1 << self.length_bits) - 1 + self.min_match()
+    }
+
+    fn token_bytes(self) -> usize {
+        ((self.offset_bits + self.length_bits) / 8) as usize
+    }
+
+    /// Ring buffer position of the first byte
+    fn ring_start(self) -> usize {
+        self.window_size() - self.max_match()
+    }
+
+    fn push_match(self, output: &mut Vec<u8>, ring_pos: usize, length: usize) {
+        output.push(ring_pos as u8);
+        let rest = (ring_pos >> 8) << self.length_bits | (length - self.min_match());
+        for i in 0..self.token_bytes() - 1 {
+            output.push((rest >> (8 * i)) as u8);
+        }
+    }
+
+    /// Ring buffer position and length of the match token at `input[pos..]`
+    fn read_match(self, input: &[u8], pos: usize) -> Option<(usize, usize)> {
+        let token = input.get(pos..pos + self.token_bytes())?;
+        let rest = token[1..].iter().rev().fold(0usize, |rest, &byte| rest << 8 | byte as usize);
+        let source = token[0] as usize | (rest >> self.length_bits) << 8;
+        let length = (rest & ((1 << self.length_bits) - 1)) + self.min_match();
+        Some((source, length))
+    }
+
+    /// Compress `input` with this layout
+    pub fn compress(self, input: &[u8]) -> Vec<u8> {
+        self.compress_at(input, self.ring_start())
+    }
+
+    /// Compress `input` for a decoder that writes the first byte at ring
+    /// buffer position `ring_start`
+    pub(crate) fn compress_at(self, input: &[u8], ring_start: usize) -> Vec<u8> {
+        // Leave the bytes the reference encoder keeps for look-ahead untouched
+        let max_distance = ring_start;
+        let min_match = self.min_match();
+
+        let mut output = Vec::with_capacity(input.len() + input.len().div_ceil(8));
+        let mut chains = HashChains::default();
+        chains.reset(max_distance, input.len(), min_match);
+
+        let mut flags_pos = 0;
+        let mut bit = 8;
+        let mut pos = 0;
+        while pos < input.len() {
+            if bit == 8 {
+                flags_pos = output.len();
+                output.push(0);
+                bit = 0;
+            }
+
+            let max_len = self.max_match().min(input.len() - pos);
+            let mut best_len = 0;
+            let mut best_pos = 0;
+            for candidate in chains.candidates(input, pos, pos.saturating_sub(max_distance)).take(SEARCH_DEPTH) {
+                let len = input[candidate..]
+                    .iter()
+                    .zip(&input[pos..pos + max_len])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    best_len = len;
+                    best_pos = candidate;
+                    if len == max_len {
+                        break;
+                    }
+                }
+            }
+
+            let advance = if best_len >= min_match {
+                self.push_match(&mut output, (ring_start + best_pos) % self.window_size(), best_len);
+                best_len
+            } else {
+                output[flags_pos] |= 1 << bit;
+                output.push(input[pos]);
+                1
+            };
+            bit += 1;
+
+            // Index every position, like the reference encoder
+            for indexed in pos..pos + advance {
+                chains.insert(input, indexed);
+            }
+            pos += advance;
+        }
+        output
+    }
+
+    /// Decompress a stream in this layout
+    ///
+    /// A match token cut off by the end of the input is a `TruncatedInput`
+    /// error; anything else decodes, since every position of the ring
+    /// buffer holds a byte.
+    pub fn decompress(self, input: &[u8]) -> Result<Vec<u8>, LzssError> {
+        self.decompress_at(input, self.ring_start())
+    }
+
+    /// Decompress a stream whose first byte goes to ring buffer position
+    /// `ring_start`
+    pub(crate) fn decompress_at(self, input: &[u8], ring_start: usize) -> Result<Vec<u8>, LzssError> {
+        let window_size = self.window_size();
+        let mut ring = vec![b' '; window_size];
+        let mut ring_pos = ring_start;
+        let mut output = Vec::with_capacity(input.len() * 2);
+
+        let mut pos = 0;
+        while let Some(&flags) = input.get(pos) {
+            pos += 1;
+            for bit in 0..8 {
+                let Some(&literal) = input.get(pos) else {
+                    return Ok(output);
+                };
+                let (source, length) = if flags & (1 << bit) != 0 {
+                    // A literal is copied through the ring like a 1-byte match
+                    ring[ring_pos] = literal;
+                    pos += 1;
+                    (ring_pos, 1)
+                } else {
+                    let token = self.read_match(input, pos).ok_or(LzssError::TruncatedInput)?;
+                    pos += self.token_bytes();
+                    token
+                };
+
+                // Byte by byte, so a match sees the bytes it has just
+                // written, as in the reference decoder
+                for k in 0..length {
+                    let byte = ring[(source + k) % window_size];
+                    ring[ring_pos] = byte;
+                    ring_pos = (ring_pos + 1) % window_size;
+                    output.push(byte);
+                }
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// Compress `input` into the classic 12-bit offset, 4-bit length layout
+pub fn compress(input: &[u8]) -> Vec<u8> {
+    BitLayout::CLASSIC.compress(input)
+}
+
+/// Decompress a stream in the classic layout (see `BitLayout::decompress`)
+pub fn decompress(input: &[u8]) -> Result<Vec<u8>, LzssError> {
+    BitLayout::CLASSIC.decompress(input)
+}
@@ -0,0 +1,558 @@
+//! Formats of other LZ77 tools
+//!
+//! Encoders and decoders for formats that ROM-hacking, homebrew and
+//! retro-development tools read and write.
+//!
+//! # Nintendo LZ10/LZ11
+//!
+//! The "LZ10" format read by the GBA and DS BIOS decompression functions,
+//! and the "LZ11" variant with longer matches used by many DS games.
+//!
+//! ```text
+//! type (0x10 or 0x11) | decompressed size (u24 LE; 0 if a u32 LE follows)
+//!     | flags (1 byte, MSB first, 1 = match) | up to 8 tokens | flags | ...
+//! ```
+//!
+//! Matches reach up to 4096 bytes back and are stored big-endian:
+//!
+//! ```text
+//! LZ10: (length - 3) << 12 | (distance - 1)                    3..=18
+//! LZ11: (length - 1) << 12 | (distance - 1)                    3..=16
+//!       (length - 0x11) << 12 | (distance - 1), 3 bytes        17..=272
+//!       1 << 28 | (length - 0x111) << 12 | (distance - 1)      273..=65808
+//! ```
+//!
+//! # Sega Kosinski
+//!
+//! The format of many Mega Drive games. Token types are bit codes in
+//! 16-bit little-endian descriptor fields, read LSB first and interleaved
+//! with the token bytes. The next descriptor is read as soon as the last
+//! bit of the current one is, so it comes before the bytes of the token
+//! that used that bit.
+//!
+//! ```text
+//! 1                      literal byte
+//! 0 0 c1 c0, d           length 2 + c (2..=5), distance 256 - d (1..=256)
+//! 0 1, lo, hi            length 2 + (hi & 7) (3..=9),
+//!                        distance 8192 - ((hi & 0xF8) << 5 | lo) (1..=8192)
+//! 0 1, lo, hi & 0xF8, n  same distance, length n + 1 (3..=256)
+//! 0 1, 00 F0 00          end of data (n = 1 is a no-op)
+//! ```
+//!
+//! There is no size header; the data ends at the end marker.
+//!
+//! # Microsoft SZDD
+//!
+//! The format of `COMPRESS.EXE` and `EXPAND.EXE`, found as `*.EX_`-style
+//! files on old installer disks:
+//!
+//! ```text
+//! "SZDD" 88 F0 27 33 | 'A' | missing last character of the file name
+//!     | decompressed size (u32 LE) | classic LZSS data
+//! ```
+//!
+//! The data is the classic 12-bit position, 4-bit length layout (see the
+//! `classic` module), with the first byte written at ring buffer position
+//! 4080 instead of 4078.
+
+use alloc::vec::Vec;
+
+use crate::chain::HashChains;
+use crate::classic::BitLayout;
+use crate::{copy, LzssError};
+
+/// Type byte of LZ10 data
+pub const LZ10_TAG: u8 = 0x10;
+
+/// Type byte of LZ11 data
+pub const LZ11_TAG: u8 = 0x11;
+
+/// Farthest a match can reach in both formats
+const WINDOW_SIZE: usize = 4096;
+
+/// Shortest match in both formats
+const MIN_MATCH: usize = 3;
+
+/// Longest LZ10 match
+const LZ10_MAX_MATCH: usize = 18;
+
+/// Longest LZ11 match
+const LZ11_MAX_MATCH: usize = 0x111 + 0xFFFF;
+
+/// Largest size the 3-byte size field holds; larger sizes use the 4-byte
+/// extension
+const MAX_SHORT_SIZE: usize = 0xFF_FFFF;
+
+/// Candidate positions examined per match search
+const SEARCH_DEPTH: usize = 128;
+
+/// Compress `input` into LZ10 data
+pub fn compress_lz10(input: &[u8]) -> Vec<u8> {
+    encode(input, LZ10_TAG, LZ10_MAX_MATCH, 1, push_lz10_match)
+}
+
+/// Compress `input` into LZ10 data that never references the previous
+/// byte
+///
+/// The GBA BIOS's VRAM variant writes 16 bits at a time, so a match one
+/// byte back would read a byte it hasn't written yet. Data decompressed
+/// straight into VRAM must be encoded this way.
+pub fn compress_lz10_vram_safe(input: &[u8]) -> Vec<u8> {
+    encode(input, LZ10_TAG, LZ10_MAX_MATCH, 2, push_lz10_match)
+}
+
+/// Compress `input` into LZ11 data
+pub fn compress_lz11(input: &[u8]) -> Vec<u8> {
+    encode(input, LZ11_TAG, LZ11_MAX_MATCH, 1, push_lz11_match)
+}
+
+/// Decompress LZ10 or LZ11 data, as told by its type byte
+///
+/// Any other type byte is an `InvalidHeader` error. Bytes after the
+/// decompressed size is reached (such as padding to a multiple of 4) are
+/// ignored.
+pub fn decompress_lz(input: &[u8]) -> Result<Vec<u8>, LzssError> {
+    let (tag, size, mut pos) = lz_header(input)?;
+    // A match token takes at least 4 bytes per `LZ11_MAX_MATCH` output
+    // bytes, so a forged size can't reserve more than the input can produce
+    let mut output = Vec::with_capacity(size.min((input.len() - pos).div_ceil(4) * LZ11_MAX_MATCH));
+    while output.len() < size {
+        let &flags = input.get(pos).ok_or(LzssError::TruncatedInput)?;
+        pos += 1;
+        for bit in 0..8 {
+            if output.len() >= size {
+                break;
+            }
+            let &first = input.get(pos).ok_or(LzssError::TruncatedInput)?;
+            if flags & (0x80 >> bit) == 0 {
+                output.push(first);
+                pos += 1;
+                continue;
+            }
+
+            let (length, distance, token_bytes) = lz_match(tag, &input[pos..])?;
+            pos += token_bytes;
+            if distance > output.len() {
+                return Err(LzssError::InvalidDistance { position: output.len(), distance });
+            }
+            let length = length.min(size - output.len());
+            copy::extend_match(&mut output, distance, length);
+        }
+    }
+    Ok(output)
+}
+
+/// Bytes of `input` taken by an LZ10 or LZ11 stream, found by walking its
+/// tokens without decoding them
+///
+/// Errors are the same as `decompress_lz` reports.
+#[cfg(feature = "std")]
+pub(crate) fn lz_stream_len(input: &[u8]) -> Result<usize, LzssError> {
+    let (tag, size, mut pos) = lz_header(input)?;
+    let mut produced = 0;
+    while produced < size {
+        let &flags = input.get(pos).ok_or(LzssError::TruncatedInput)?;
+        pos += 1;
+        for bit in 0..8 {
+            if produced >= size {
+                break;
+            }
+            if pos >= input.len() {
+                return Err(LzssError::TruncatedInput);
+            }
+            if flags & (0x80 >> bit) == 0 {
+                produced += 1;
+                pos += 1;
+                continue;
+            }
+
+            let (length, distance, token_bytes) = lz_match(tag, &input[pos..])?;
+            pos += token_bytes;
+            if distance > produced {
+                return Err(LzssError::InvalidDistance { position: produced, distance });
+            }
+            produced += length.min(size - produced);
+        }
+    }
+    Ok(pos)
+}
+
+/// Type byte, decompressed size and offset of the first flags byte of an
+/// LZ10 or LZ11 header
+pub(crate) fn lz_header(input: &[u8]) -> Result<(u8, usize, usize), LzssError> {
+    let &tag = input.first().ok_or(LzssError::TruncatedInput)?;
+    if tag != LZ10_TAG && tag != LZ11_TAG {
+        return Err(LzssError::InvalidHeader);
+    }
+    let header = input.get(..4).ok_or(LzssError::TruncatedInput)?;
+    let size = u32::from_le_bytes([header[1], header[2], header[3], 0]) as usize;
+    if size != 0 {
+        return Ok((tag, size, 4));
+    }
+    let extended = input.get(4..8).ok_or(LzssError::TruncatedInput)?;
+    let size = u32::from_le_bytes([extended[0], extended[1], extended[2], extended[3]]) as usize;
+    Ok((tag, size, 8))
+}
+
+/// Length, distance and size in bytes of the match token at the start of
+/// `input`
+fn lz_match(tag: u8, input: &[u8]) -> Result<(usize, usize, usize), LzssError> {
+    let token_bytes = match (tag, input[0] >> 4) {
+        (LZ11_TAG, 0) => 3,
+        (LZ11_TAG, 1) => 4,
+        _ => 2,
+    };
+    let token = input.get(..token_bytes).ok_or(LzssError::TruncatedInput)?;
+    let token = token.iter().fold(0u32, |token, &byte| token << 8 | u32::from(byte)) as usize;
+    let distance = (token & 0xFFF) + 1;
+    let length = match (tag, token_bytes) {
+        (LZ10_TAG, _) => (token >> 12) + 3,
+        (_, 2) => (token >> 12) + 1,
+        (_, 3) => (token >> 12) + 0x11,
+        _ => ((token >> 12) & 0xFFFF) + 0x111,
+    };
+    Ok((length, distance, token_bytes))
+}
+
+fn push_lz10_match(output: &mut Vec<u8>, length: usize, distance: usize) {
+    let token = (length - 3) << 12 | (distance - 1);
+    output.extend_from_slice(&(token as u16).to_be_bytes());
+}
+
+fn push_lz11_match(output: &mut Vec<u8>, length: usize, distance: usize) {
+    let distance = distance - 1;
+    if length <= 16 {
+        output.extend_from_slice(&((((length - 1) << 12) | distance) as u16).to_be_bytes());
+    } else if length <= 0x110 {
+        let token = (length - 0x11) << 12 | distance;
+        output.extend_from_slice(&(token as u32).to_be_bytes()[1..]);
+    } else {
+        let token = 1 << 28 | (length - 0x111) << 12 | distance;
+        output.extend_from_slice(&(token as u32).to_be_bytes());
+    }
+}
+
+/// Greedy encoder shared by LZ10 and LZ11: matches of up to `max_match`
+/// bytes at least `min_distance` back, written by `push_match`
+fn encode(
+    input: &[u8],
+    tag: u8,
+    max_match: usize,
+    min_distance: usize,
+    push_match: fn(&mut Vec<u8>, usize, usize),
+) -> Vec<u8> {
+    let mut output = Vec::with_capacity(8 + input.len() + input.len().div_ceil(8));
+    output.push(tag);
+    if input.len() <= MAX_SHORT_SIZE && !input.is_empty() {
+        output.extend_from_slice(&(input.len() as u32).to_le_bytes()[..3]);
+    } else {
+        // A zero size field announces the 4-byte size (empty input uses it
+        // too, since a zero 3-byte size can't mean zero)
+        output.extend_from_slice(&[0; 3]);
+        output.extend_from_slice(&(input.len() as u32).to_le_bytes());
+    }
+
+    let mut chains = HashChains::default();
+    chains.reset(WINDOW_SIZE, input.len(), MIN_MATCH);
+
+    let mut flags_pos = 0;
+    let mut bit = 8;
+    let mut pos = 0;
+    while pos < input.len() {
+        if bit == 8 {
+            flags_pos = output.len();
+            output.push(0);
+            bit = 0;
+        }
+
+        let (best_len, best_distance) = longest_match(&chains, input, pos, WINDOW_SIZE, max_match, min_distance);
+
+        let advance = if best_len >= MIN_MATCH {
+            output[flags_pos] |= 0x80 >> bit;
+            push_match(&mut output, best_len, best_distance);
+            best_len
+        } else {
+            output.push(input[pos]);
+            1
+        };
+        bit += 1;
+
+        for indexed in pos..pos + advance {
+            chains.insert(input, indexed);
+        }
+        pos += advance;
+    }
+    output
+}
+
+/// Length and distance of the longest match for `input[pos..]`, at most
+/// `max_match` bytes long and between `min_distance` and `window_size`
+/// bytes back (length 0 if there is none)
+fn longest_match(
+    chains: &HashChains,
+    input: &[u8],
+    pos: usize,
+    window_size: usize,
+    max_match: usize,
+    min_distance: usize,
+) -> (usize, usize) {
+    let max_len = max_match.min(input.len() - pos);
+    let mut best_len = 0;
+    let mut best_distance = 0;
+    for candidate in chains.candidates(input, pos, pos.saturating_sub(window_size)).take(SEARCH_DEPTH) {
+        if pos - candidate < min_distance {
+            continue;
+        }
+        let len = input[candidate..]
+            .iter()
+            .zip(&input[pos..pos + max_len])
+            .take_while(|(a, b)| a == b)
+            .count();
+        if len > best_len {
+            best_len = len;
+            best_distance = pos - candidate;
+            if len == max_len {
+                break;
+            }
+        }
+    }
+    (best_len, best_distance)
+}
+
+/// Farthest a Kosinski match can reach
+const KOSINSKI_WINDOW_SIZE: usize = 8192;
+
+/// Farthest a Kosinski inline match can reach
+const KOSINSKI_INLINE_DISTANCE: usize = 256;
+
+/// Longest Kosinski inline match
+const KOSINSKI_INLINE_MAX_MATCH: usize = 5;
+
+/// Longest Kosinski match with a 2-byte token
+const KOSINSKI_SHORT_MAX_MATCH: usize = 9;
+
+/// Longest Kosinski match
+const KOSINSKI_MAX_MATCH: usize = 256;
+
+/// Kosinski end marker, after the `0 1` descriptor bits
+const KOSINSKI_END: [u8; 3] = [0x00, 0xF0, 0x00];
+
+/// Compress `input` into Kosinski data
+pub fn compress_kosinski(input: &[u8]) -> Vec<u8> {
+    let mut writer = DescriptorWriter::new(input.len());
+    let mut chains = HashChains::default();
+    chains.reset(KOSINSKI_WINDOW_SIZE, input.len(), MIN_MATCH);
+
+    let mut pos = 0;
+    while pos < input.len() {
+        let (length, distance) = longest_match(&chains, input, pos, KOSINSKI_WINDOW_SIZE, KOSINSKI_MAX_MATCH, 1);
+
+        let inline = length <= KOSINSKI_INLINE_MAX_MATCH && distance <= KOSINSKI_INLINE_DISTANCE;
+        let advance = if length >= MIN_MATCH && !inline {
+            writer.bit(false);
+            writer.bit(true);
+            let field = KOSINSKI_WINDOW_SIZE - distance;
+            let high = (field >> 5) as u8 & 0xF8;
+            if length <= KOSINSKI_SHORT_MAX_MATCH {
+                writer.output.extend_from_slice(&[field as u8, high | (length - 2) as u8]);
+            } else {
+                writer.output.extend_from_slice(&[field as u8, high, (length - 1) as u8]);
+            }
+            length
+        } else if length >= MIN_MATCH {
+            writer.bit(false);
+            writer.bit(false);
+            writer.bit((length - 2) & 2 != 0);
+            writer.bit((length - 2) & 1 != 0);
+            writer.output.push((KOSINSKI_INLINE_DISTANCE - distance) as u8);
+            length
+        } else {
+            writer.bit(true);
+            writer.output.push(input[pos]);
+            1
+        };
+
+        for indexed in pos..pos + advance {
+            chains.insert(input, indexed);
+        }
+        pos += advance;
+    }
+
+    writer.bit(false);
+    writer.bit(true);
+    writer.output.extend_from_slice(&KOSINSKI_END);
+    writer.finish()
+}
+
+/// Decompress Kosinski data
+///
+/// Decoding stops at the end marker; data that runs out before it is a
+/// `TruncatedInput` error.
+pub fn decompress_kosinski(input: &[u8]) -> Result<Vec<u8>, LzssError> {
+    let mut reader = DescriptorReader::new(input)?;
+    let mut output = Vec::with_capacity(input.len() * 2);
+    loop {
+        if reader.bit()? {
+            output.push(reader.byte()?);
+            continue;
+        }
+
+        let (distance, length) = if reader.bit()? {
+            let low = reader.byte()? as usize;
+            let high = reader.byte()? as usize;
+            let distance = KOSINSKI_WINDOW_SIZE - ((high & 0xF8) << 5 | low);
+            let length = match high & 7 {
+                0 => match reader.byte()? {
+                    0 => return Ok(output),
+                    1 => continue,
+                    count => count as usize + 1,
+                },
+                count => count + 2,
+            };
+            (distance, length)
+        } else {
+            let count = (reader.bit()? as usize) << 1 | reader.bit()? as usize;
+            (KOSINSKI_INLINE_DISTANCE - reader.byte()? as usize, count + 2)
+        };
+
+        if distance > output.len() {
+            return Err(LzssError::InvalidDistance { position: output.len(), distance });
+        }
+        copy::extend_match(&mut output, distance, length);
+    }
+}
+
+/// Kosinski output with its pending descriptor field
+struct DescriptorWriter {
+    output: Vec<u8>,
+    /// Where the pending descriptor goes
+    descriptor_pos: usize,
+    descriptor: u16,
+    bits: u32,
+}
+
+impl DescriptorWriter {
+    fn new(input_len: usize) -> Self {
+        let mut output = Vec::with_capacity(input_len + input_len / 4 + 8);
+        output.extend_from_slice(&[0; 2]);
+        DescriptorWriter { output, descriptor_pos: 0, descriptor: 0, bits: 0 }
+    }
+
+    /// Add a descriptor bit, starting the next descriptor as soon as this
+    /// one is full, as the decoder reads it
+    fn bit(&mut self, bit: bool) {
+        self.descriptor |= u16::from(bit) << self.bits;
+        self.bits += 1;
+        if self.bits == 16 {
+            self.output[self.descriptor_pos..self.descriptor_pos + 2].copy_from_slice(&self.descriptor.to_le_bytes());
+            self.descriptor_pos = self.output.len();
+            self.output.extend_from_slice(&[0; 2]);
+            self.descriptor = 0;
+            self.bits = 0;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.output[self.descriptor_pos..self.descriptor_pos + 2].copy_from_slice(&self.descriptor.to_le_bytes());
+        self.output
+    }
+}
+
+/// Kosinski input with its current descriptor field
+struct DescriptorReader<'a> {
+    input: &'a [u8],
+    pos: usize,
+    descriptor: u16,
+    bits: u32,
+}
+
+impl<'a> DescriptorReader<'a> {
+    fn new(input: &'a [u8]) -> Result<Self, LzssError> {
+        let mut reader = DescriptorReader { input, pos: 0, descriptor: 0, bits: 0 };
+        reader.next_descriptor()?;
+        Ok(reader)
+    }
+
+    fn next_descriptor(&mut self) -> Result<(), LzssError> {
+        let field = self.input.get(self.pos..self.pos + 2).ok_or(LzssError::TruncatedInput)?;
+        self.descriptor = u16::from_le_bytes([field[0], field[1]]);
+        self.pos += 2;
+        self.bits = 16;
+        Ok(())
+    }
+
+    fn bit(&mut self) -> Result<bool, LzssError> {
+        let bit = self.descriptor & 1 != 0;
+        self.descriptor >>= 1;
+        self.bits -= 1;
+        if self.bits == 0 {
+            self.next_descriptor()?;
+        }
+        Ok(bit)
+    }
+
+    fn byte(&mut self) -> Result<u8, LzssError> {
+        let &byte = self.input.get(self.pos).ok_or(LzssError::TruncatedInput)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+}
+
+/// Magic bytes that start SZDD files
+pub const SZDD_MAGIC: [u8; 8] = *b"SZDD\x88\xF0\x27\x33";
+
+/// SZDD compression mode byte; the only mode there is
+const SZDD_MODE: u8 = b'A';
+
+/// Size of the SZDD header
+const SZDD_HEADER_SIZE: usize = 14;
+
+/// Ring buffer position of the first byte of SZDD data
+const SZDD_RING_START: usize = 4096 - 16;
+
+/// Compress `input` into an SZDD file
+///
+/// `missing_char` is the last character of the original file name, which
+/// the compressed file's name replaces with `_` (`b'E'` for `SETUP.EX_`),
+/// or 0 if unknown. Inputs of 4 GiB or more don't fit the size field.
+pub fn compress_szdd(input: &[u8], missing_char: u8) -> Vec<u8> {
+    let data = BitLayout::CLASSIC.compress_at(input, SZDD_RING_START);
+    let mut output = Vec::with_capacity(SZDD_HEADER_SIZE + data.len());
+    output.extend_from_slice(&SZDD_MAGIC);
+    output.push(SZDD_MODE);
+    output.push(missing_char);
+    output.extend_from_slice(&(input.len() as u32).to_le_bytes());
+    output.extend_from_slice(&data);
+    output
+}
+
+/// Decompress an SZDD file
+///
+/// Anything but the SZDD magic and mode `A` is an `InvalidHeader` error,
+/// and data that decodes to fewer bytes than the header records is a
+/// `SizeMismatch`.
+pub fn decompress_szdd(input: &[u8]) -> Result<Vec<u8>, LzssError> {
+    let size = szdd_header(input)?.1;
+    let mut output = BitLayout::CLASSIC.decompress_at(&input[SZDD_HEADER_SIZE..], SZDD_RING_START)?;
+    if output.len() < size {
+        return Err(LzssError::SizeMismatch { expected: size, actual: output.len() });
+    }
+    output.truncate(size);
+    Ok(output)
+}
+
+/// Last character of the original file name recorded in an SZDD header,
+/// or 0 if the compressor didn't know it
+pub fn szdd_missing_char(input: &[u8]) -> Result<u8, LzssError> {
+    Ok(szdd_header(input)?.0)
+}
+
+/// Missing character and decompressed size from an SZDD header
+pub(crate) fn szdd_header(input: &[u8]) -> Result<(u8, usize), LzssError> {
+    let header = input.get(..SZDD_HEADER_SIZE).ok_or(LzssError::TruncatedInput)?;
+    if header[..8] != SZDD_MAGIC || header[8] != SZDD_MODE {
+        return Err(LzssError::InvalidHeader);
+    }
+    let size = u32::from_le_bytes([header[10], header[11], header[12], header[13]]) as usize;
+    Ok((header[9], size))
+}
@@ -0,0 +1,309 @@
+//! Compression with a window that persists across calls
+//!
+//! `Compressor` keeps the tail of everything it has compressed and lets
+//! matches in the next `compress()` call reference it, so a sequence of
+//! related messages or files shares context without the full streaming
+//! API. Each output is decoded by a `Decompressor` that has seen the same
+//! sequence of buffers.
+//!
+//! For unreliable transports, independent-packet mode compresses every
+//! buffer against only the preset dictionary, so a lost packet never breaks
+//! the ones after it.
+//!
+//! With adaptive bypass, each output starts with a marker byte and payloads
+//! are passed through uncompressed while compression isn't paying for
+//! itself (e.g. already-compressed or encrypted data).
+//!
+//! A `Compressor` also keeps the match finder's tables between calls, so
+//! compressing many small payloads doesn't allocate them every time.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{LzssError, Scratch, LZSS};
+
+/// Marker byte of a payload passed through uncompressed
+const MARKER_STORED: u8 = 0;
+
+/// Marker byte of a compressed payload
+const MARKER_COMPRESSED: u8 = 1;
+
+/// Average compressed/original ratio above which compression is bypassed
+const BYPASS_RATIO: f64 = 0.95;
+
+/// Payloads passed through before compression is tried again
+const PROBE_INTERVAL: usize = 16;
+
+/// Recent compression gains of a `Compressor` in adaptive bypass mode
+#[derive(Debug, Clone, Copy, Default)]
+struct Bypass {
+    /// Moving average of the compressed/original size ratio
+    average_ratio: f64,
+    /// Payloads passed through since the last probe
+    skipped: usize,
+}
+
+impl Bypass {
+    fn is_active(&self) -> bool {
+        self.average_ratio > BYPASS_RATIO
+    }
+
+    fn record(&mut self, original: usize, compressed: usize) {
+        if original > 0 {
+            let ratio = compressed as f64 / original as f64;
+            self.average_ratio = 0.75 * self.average_ratio + 0.25 * ratio;
+        }
+        self.skipped = 0;
+    }
+}
+
+/// Shared history window, trimmed to the largest distance the codec can
+/// reference
+struct History {
+    data: Vec<u8>,
+    limit: usize,
+}
+
+impl History {
+    fn new(lzss: &LZSS) -> Self {
+        let limit = match lzss.long_distance_history {
+            Some(history) => history.max(lzss.window_size),
+            None => lzss.window_size,
+        };
+        History { data: Vec::new(), limit }
+    }
+
+    /// Restart the window from the preset dictionary
+    fn reset(&mut self, dictionary: &[u8]) {
+        self.data.clear();
+        self.data.extend_from_slice(dictionary);
+        self.trim();
+    }
+
+    /// Drop whatever has fallen out of the window
+    fn trim(&mut self) {
+        if self.data.len() > self.limit {
+            let excess = self.data.len() - self.limit;
+            self.data.drain(..excess);
+        }
+    }
+}
+
+/// Compressor that keeps its history window between `compress()` calls
+pub struct Compressor {
+    lzss: LZSS,
+    history: History,
+    dictionary: Vec<u8>,
+    independent: bool,
+    bypass: Option<Bypass>,
+    scratch: Scratch,
+}
+
+impl Compressor {
+    /// Create a compressor with an empty window
+    pub fn new(lzss: LZSS) -> Self {
+        let history = History::new(&lzss);
+        Compressor {
+            lzss,
+            history,
+            dictionary: Vec::new(),
+            independent: false,
+            bypass: None,
+            scratch: Scratch::default(),
+        }
+    }
+
+    /// Start the window (and every window reset) from a preset dictionary
+    pub fn with_dictionary(mut self, dictionary: &[u8]) -> Self {
+        self.dictionary = dictionary.to_vec();
+        self.history.reset(&self.dictionary);
+        self
+    }
+
+    /// Compress every buffer against only the preset dictionary, with no
+    /// window carried between calls
+    ///
+    /// Outputs can then be decompressed in any order, and losing one does
+    /// not affect the others, at the cost of ratio on related buffers.
+    pub fn with_independent_packets(mut self, independent: bool) -> Self {
+        self.independent = independent;
+        self
+    }
+
+    /// Prefix each output with a 1-byte marker and pass payloads through
+    /// uncompressed when recent compression gains are too small
+    ///
+    /// While bypassing, every 16th payload is compressed again to check
+    /// whether the data has become compressible. Payloads that don't shrink
+    /// are always passed through. The `Decompressor` must be configured the
+    /// same way.
+    pub fn with_adaptive_bypass(mut self, enabled: bool) -> Self {
+        self.bypass = enabled.then(Bypass::default);
+        self
+    }
+
+    /// Whether adaptive bypass is currently passing payloads through
+    pub fn is_bypassing(&self) -> bool {
+        self.bypass.is_some_and(|bypass| bypass.is_active())
+    }
+
+    /// Compress a buffer, referencing data from previous calls
+    ///
+    /// The output can only be decompressed by a `Decompressor` that has
+    /// decompressed all previous outputs of this compressor, in order,
+    /// since the last window reset (unless in independent-packet mode).
+    pub fn compress(&mut self, input: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+        self.compress_into_vec(input, &mut output);
+        output
+    }
+
+    /// Like `compress`, but replaces the contents of `output`, so a buffer
+    /// reused across calls only grows when a payload needs more room
+    pub fn compress_into_vec(&mut self, input: &[u8], output: &mut Vec<u8>) {
+        output.clear();
+        if self.independent {
+            self.history.reset(&self.dictionary);
+        }
+        let start = self.history.data.len();
+        self.history.data.extend_from_slice(input);
+
+        let Some(bypass) = &mut self.bypass else {
+            self.lzss.compress_to(&self.history.data, start, output, &mut self.scratch);
+            self.history.trim();
+            return;
+        };
+        output.push(MARKER_COMPRESSED);
+        let compressed = if bypass.is_active() && bypass.skipped < PROBE_INTERVAL {
+            bypass.skipped += 1;
+            false
+        } else {
+            self.lzss.compress_to(&self.history.data, start, output, &mut self.scratch);
+            let compressed_len = output.len() - 1;
+            bypass.record(input.len(), compressed_len);
+            compressed_len < input.len()
+        };
+        self.history.trim();
+
+        if !compressed {
+            output.clear();
+            output.push(MARKER_STORED);
+            output.extend_from_slice(input);
+        }
+    }
+
+    /// Forget all previous data, so the next output only depends on the
+    /// preset dictionary
+    pub fn reset_window(&mut self) {
+        self.history.reset(&self.dictionary);
+    }
+
+    /// Return to the state right after construction: the window restarts
+    /// from the preset dictionary and adaptive bypass forgets its
+    /// statistics
+    ///
+    /// The match finder's tables and the window's buffer are kept, so a
+    /// compressor can be pooled and reused for unrelated streams.
+    pub fn reset(&mut self) {
+        self.history.reset(&self.dictionary);
+        if let Some(bypass) = &mut self.bypass {
+            *bypass = Bypass::default();
+        }
+    }
+}
+
+/// Decompressor matching a `Compressor`'s persistent window
+pub struct Decompressor {
+    lzss: LZSS,
+    history: History,
+    dictionary: Vec<u8>,
+    independent: bool,
+    bypass: bool,
+}
+
+impl Decompressor {
+    /// Create a decompressor with an empty window
+    pub fn new(lzss: LZSS) -> Self {
+        let history = History::new(&lzss);
+        Decompressor { lzss, history, dictionary: Vec::new(), independent: false, bypass: false }
+    }
+
+    /// Use the same preset dictionary as the `Compressor`
+    pub fn with_dictionary(mut self, dictionary: &[u8]) -> Self {
+        self.dictionary = dictionary.to_vec();
+        self.history.reset(&self.dictionary);
+        self
+    }
+
+    /// Decode outputs of a `Compressor` in independent-packet mode
+    pub fn with_independent_packets(mut self, independent: bool) -> Self {
+        self.independent = independent;
+        self
+    }
+
+    /// Decode outputs of a `Compressor` in adaptive bypass mode
+    pub fn with_adaptive_bypass(mut self, enabled: bool) -> Self {
+        self.bypass = enabled;
+        self
+    }
+
+    /// Decompress the next output of the matching `Compressor`, patching
+    /// up corrupt input
+    ///
+    /// Invalid references are skipped and missing output is padded with
+    /// zeros, so corruption goes unnoticed.
+    #[deprecated(note = "hides corrupt input; use `try_decompress`")]
+    pub fn decompress(&mut self, input: &[u8]) -> Vec<u8> {
+        if self.independent {
+            self.history.reset(&self.dictionary);
+        }
+        let output = match input.split_first() {
+            Some((&MARKER_STORED, payload)) if self.bypass => payload.to_vec(),
+            Some((_, payload)) if self.bypass => {
+                self.lzss.decompress_with_history(payload, usize::MAX, &self.history.data)
+            }
+            _ => self.lzss.decompress_with_history(input, usize::MAX, &self.history.data),
+        };
+        self.history.data.extend_from_slice(&output);
+        self.history.trim();
+        output
+    }
+
+    /// Decompress the next output of the matching `Compressor`
+    ///
+    /// Corrupt input is an error, as in `LZSS::try_decompress`, and leaves
+    /// the window as it was.
+    pub fn try_decompress(&mut self, input: &[u8]) -> Result<Vec<u8>, LzssError> {
+        let output = match input.split_first() {
+            Some((&MARKER_STORED, payload)) if self.bypass => payload.to_vec(),
+            Some((_, payload)) if self.bypass => self.decode(payload)?,
+            None if self.bypass => return Err(LzssError::TruncatedInput),
+            _ => self.decode(input)?,
+        };
+        if self.independent {
+            self.history.reset(&self.dictionary);
+        }
+        self.history.data.extend_from_slice(&output);
+        self.history.trim();
+        Ok(output)
+    }
+
+    /// Strictly decode a stream whose matches may reference the window
+    fn decode(&self, stream: &[u8]) -> Result<Vec<u8>, LzssError> {
+        let history = if self.independent {
+            &self.dictionary[self.dictionary.len().saturating_sub(self.history.limit)..]
+        } else {
+            &self.history.data[..]
+        };
+        // Validate first, so a corrupt size header can't force a huge allocation
+        let stats = self.lzss.validate_stream(stream, history.len())?;
+        let mut output = vec![0; stats.content_size];
+        self.lzss.decompress_stream_into(stream, history, &mut output, &mut |_| true)?;
+        Ok(output)
+    }
+
+    /// Forget all previous data, mirroring `Compressor::reset_window`
+    pub fn reset_window(&mut self) {
+        self.history.reset(&self.dictionary);
+    }
+}
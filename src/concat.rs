@@ -0,0 +1,82 @@
+//! Concatenated streams
+//!
+//! Compressed outputs appended back to back (e.g. log chunks appended to a
+//! file) are decoded member by member, like gzip members. Each member is a
+//! frame, decoded with the parameters in its header, or a raw stream,
+//! decoded with the caller's `LZSS`. Frame padding is skipped.
+
+use alloc::vec::Vec;
+
+use crate::frame::FrameHeader;
+use crate::{LzssError, LZSS};
+
+/// Iterator over the decompressed members of concatenated streams
+///
+/// Created by `LZSS::decompress_members`. Yields each member's output and
+/// stops after the first error.
+pub struct Members<'a> {
+    lzss: &'a LZSS,
+    input: &'a [u8],
+    failed: bool,
+}
+
+impl<'a> Members<'a> {
+    /// Input bytes not decoded yet
+    pub fn remaining(&self) -> &'a [u8] {
+        self.input
+    }
+
+    /// Size of the member at the start of `input`
+    fn member_len(&self) -> Result<usize, LzssError> {
+        match FrameHeader::parse(self.input) {
+            Some(header) => {
+                let stream = &self.input[header.size()..];
+                let window = header.window_size.unwrap_or(usize::MAX);
+                let stats = header.decoder().validate_stream_within(stream, 0, window)?;
+                // Empty content compresses to an empty stream, so what
+                // follows the header is already padding or the next member
+                let stream_len = if stats.content_size == 0 { 0 } else { stats.compressed_size };
+                Ok(header.size() + stream_len + header.padding)
+            }
+            None => Ok(self.lzss.validate_stream(self.input, 0)?.compressed_size),
+        }
+    }
+
+    fn next_member(&mut self) -> Result<Vec<u8>, LzssError> {
+        let len = self.member_len()?;
+        let member = self.input.get(..len).ok_or(LzssError::TruncatedInput)?;
+        let output = self.lzss.try_decompress(member)?;
+        self.input = &self.input[len..];
+        Ok(output)
+    }
+}
+
+impl Iterator for Members<'_> {
+    type Item = Result<Vec<u8>, LzssError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.input.is_empty() {
+            return None;
+        }
+        let member = self.next_member();
+        self.failed = member.is_err();
+        Some(member)
+    }
+}
+
+impl LZSS {
+    /// Decompress back-to-back streams and frames, one member at a time
+    pub fn decompress_members<'a>(&'a self, input: &'a [u8]) -> Members<'a> {
+        Members { lzss: self, input, failed: false }
+    }
+
+    /// Decompress back-to-back streams and frames into their concatenated
+    /// output
+    pub fn decompress_concatenated(&self, input: &[u8]) -> Result<Vec<u8>, LzssError> {
+        let mut output = Vec::new();
+        for member in self.decompress_members(input) {
+            output.extend(member?);
+        }
+        Ok(output)
+    }
+}
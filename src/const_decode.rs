@@ -0,0 +1,189 @@
+//! Decompression in const contexts
+//!
+//! Small blobs (lookup tables, fonts, default configs) can be stored
+//! compressed in the source and expanded at compile time with
+//! `decompress_const`, at no runtime cost.
+
+use crate::frame::{
+    FLAG_CRC32, FLAG_DELTA, FLAG_DISTANCE_MINUS_ONE, FLAG_PADDED, FLAG_SHORT_OFFSETS, FLAG_SLOT_PADDED,
+    FLAG_WINDOW_SIZE, FLAG_XXH3, FRAME_HEADER_SIZE, FRAME_MAGIC,
+};
+
+/// Read a little-endian integer of `bytes` bytes at `pos`
+const fn read_le(input: &[u8], pos: usize, bytes: usize) -> usize {
+    let mut value = 0;
+    let mut i = 0;
+    while i < bytes {
+        value |= (input[pos + i] as usize) << (i * 8);
+        i += 1;
+    }
+    value
+}
+
+/// Decompress a frame or raw stream in a const context
+///
+/// Frames are decoded with the parameters in their header. Raw streams
+/// carry none and are decoded with the defaults: minimum match length 3,
+/// 2-byte distances and 1-byte length fields.
+///
+/// `N` must equal the decompressed size. Corrupt input or a size mismatch
+/// panics, which fails compilation when evaluated in a const.
+pub const fn decompress_const<const N: usize>(input: &[u8]) -> [u8; N] {
+    let mut min_match_length = 3;
+    let mut offset_bytes = 2;
+    let mut length_code = 0; // One-byte length fields
+    let mut distance_bias = 0;
+    let mut short_offsets = false;
+    let mut delta_order = 0;
+    let mut delta_stride = 1;
+    let mut pos = 0;
+
+    let is_frame = input.len() >= 7
+        && input[0] == FRAME_MAGIC[0] && input[1] == FRAME_MAGIC[1]
+        && input[2] == FRAME_MAGIC[2] && input[3] == FRAME_MAGIC[3];
+    if is_frame {
+        min_match_length = input[5] as usize;
+        offset_bytes = input[6] as usize;
+        pos = 7;
+        match input[4] {
+            1 => {}
+            2 => {
+                length_code = input[7];
+                let flags = input[8];
+                if flags & FLAG_DISTANCE_MINUS_ONE != 0 {
+                    distance_bias = 1;
+                }
+                short_offsets = flags & FLAG_SHORT_OFFSETS != 0;
+                pos = FRAME_HEADER_SIZE;
+                if flags & FLAG_PADDED != 0 {
+                    pos += 4;
+                }
+                if flags & FLAG_SLOT_PADDED != 0 {
+                    pos += 8;
+                }
+                // Checksums are not verified at compile time
+                if flags & FLAG_CRC32 != 0 {
+                    pos += 4;
+                }
+                if flags & FLAG_XXH3 != 0 {
+                    pos += 8;
+                }
+                if flags & FLAG_DELTA != 0 {
+                    if input.len() < pos + 3 {
+                        panic!("compressed input is truncated");
+                    }
+                    delta_order = input[pos];
+                    delta_stride = read_le(input, pos + 1, 2);
+                    if delta_order > 2 || delta_stride == 0 {
+                        panic!("invalid delta filter");
+                    }
+                    pos += 3;
+                }
+                if flags & FLAG_WINDOW_SIZE != 0 {
+                    pos += 4;
+                }
+            }
+            _ => panic!("unsupported frame version"),
+        }
+    }
+
+    let mut output = [0u8; N];
+    if input.len() == pos {
+        if N != 0 {
+            panic!("decompressed size doesn't match N");
+        }
+        return output; // Empty input compresses to nothing
+    }
+    if input.len() < pos + 4 || read_le(input, pos, 4) != N {
+        panic!("decompressed size doesn't match N");
+    }
+    pos += 4;
+
+    let mut produced = 0;
+    while produced < N {
+        if pos >= input.len() {
+            panic!("compressed input is truncated");
+        }
+        let control_byte = input[pos];
+        pos += 1;
+
+        let mut bit = 0;
+        while bit < 8 && produced < N {
+            if control_byte & (1 << bit) == 0 {
+                if pos >= input.len() {
+                    panic!("compressed input is truncated");
+                }
+                output[produced] = input[pos];
+                produced += 1;
+                pos += 1;
+            } else {
+                // Short offsets take one byte unless its lowest bit is set
+                let offset_len = if short_offsets && pos < input.len() && input[pos] & 1 == 0 { 1 } else { offset_bytes };
+                if pos + offset_len >= input.len() {
+                    panic!("compressed input is truncated");
+                }
+                let mut field = read_le(input, pos, offset_len);
+                pos += offset_len;
+                if short_offsets {
+                    field >>= 1;
+                }
+                let distance = field + distance_bias;
+
+                // Length field: one byte, two bytes or escaped
+                let mut length = match length_code {
+                    0 => {
+                        pos += 1;
+                        input[pos - 1] as usize
+                    }
+                    1 => {
+                        if pos + 1 >= input.len() {
+                            panic!("compressed input is truncated");
+                        }
+                        pos += 2;
+                        read_le(input, pos - 2, 2)
+                    }
+                    2 => {
+                        let mut length = 0;
+                        loop {
+                            if pos >= input.len() {
+                                panic!("compressed input is truncated");
+                            }
+                            let byte = input[pos];
+                            pos += 1;
+                            length += byte as usize;
+                            if byte != 255 {
+                                break;
+                            }
+                        }
+                        length
+                    }
+                    _ => panic!("unsupported length field"),
+                };
+                length += min_match_length;
+
+                if distance == 0 || distance > produced {
+                    panic!("match references data before the start of the output");
+                }
+                let mut i = 0;
+                while i < length && produced < N {
+                    output[produced] = output[produced - distance];
+                    produced += 1;
+                    i += 1;
+                }
+            }
+            bit += 1;
+        }
+    }
+
+    // Undo the delta filter, one order at a time
+    let mut order = 0;
+    while order < delta_order {
+        let mut i = delta_stride;
+        while i < N {
+            output[i] = output[i].wrapping_add(output[i - delta_stride]);
+            i += 1;
+        }
+        order += 1;
+    }
+    output
+}
@@ -0,0 +1,53 @@
+//! Match copies for the decoders
+//!
+//! A match that doesn't overlap the bytes it produces (`length <=
+//! distance`) is a single slice copy. An overlapping one repeats the last
+//! `distance` bytes, so it is copied in chunks that double in size: each
+//! chunk reads only bytes that were complete before it started, and a run
+//! of N bytes takes about log2(N / distance) copies instead of N.
+
+use alloc::vec::Vec;
+
+/// Append `length` bytes starting `distance` bytes before the end of
+/// `output` (`distance` must be in `1..=output.len()`)
+pub(crate) fn extend_match(output: &mut Vec<u8>, distance: usize, length: usize) {
+    let start = output.len() - distance;
+    if length <= distance {
+        output.extend_from_within(start..start + length);
+        return;
+    }
+
+    output.reserve(length);
+    let end = output.len() + length;
+    while output.len() < end {
+        let chunk = (end - output.len()).min(output.len() - start);
+        output.extend_from_within(start..start + chunk);
+    }
+}
+
+/// Write `length` bytes to `output[pos..]` from `distance` bytes back,
+/// where positions before `output` are the tail of `history`
+///
+/// `distance` must be in `1..=pos + history.len()` and the match must fit
+/// in `output`.
+pub(crate) fn copy_match(output: &mut [u8], mut pos: usize, distance: usize, mut length: usize, history: &[u8]) {
+    if distance > pos {
+        let back = distance - pos;
+        let from_history = length.min(back);
+        let start = history.len() - back;
+        output[pos..pos + from_history].copy_from_slice(&history[start..start + from_history]);
+        pos += from_history;
+        length -= from_history;
+        if length == 0 {
+            return;
+        }
+    }
+
+    let start = pos - distance;
+    let end = pos + length;
+    while pos < end {
+        let chunk = (end - pos).min(pos - start);
+        output.copy_within(start..start + chunk, pos);
+        pos += chunk;
+    }
+}
@@ -0,0 +1,34 @@
+//! C++ bindings generated with cxx
+//!
+//! Exposes `Compressor` and `Decompressor` to C++ through a cxx bridge.
+//! `include/rustzss_cxx.hpp` wraps the generated types in
+//! `rustzss::Compressor` and `rustzss::Decompressor` classes that take
+//! `std::span` and return `std::vector`; decoding errors surface as
+//! `rust::Error` exceptions carrying the `LzssError` message.
+
+use crate::compressor::{Compressor, Decompressor};
+use crate::LZSS;
+
+#[cxx::bridge(namespace = "rustzss::bridge")]
+mod bridge {
+    extern "Rust" {
+        type Compressor;
+        type Decompressor;
+
+        fn new_compressor(window_size: usize, min_match_length: usize, dictionary: &[u8]) -> Box<Compressor>;
+        fn compress(self: &mut Compressor, input: &[u8]) -> Vec<u8>;
+        fn reset_window(self: &mut Compressor);
+
+        fn new_decompressor(window_size: usize, min_match_length: usize, dictionary: &[u8]) -> Box<Decompressor>;
+        fn try_decompress(self: &mut Decompressor, input: &[u8]) -> Result<Vec<u8>>;
+        fn reset_window(self: &mut Decompressor);
+    }
+}
+
+fn new_compressor(window_size: usize, min_match_length: usize, dictionary: &[u8]) -> Box<Compressor> {
+    Box::new(Compressor::new(LZSS::new(window_size, min_match_length)).with_dictionary(dictionary))
+}
+
+fn new_decompressor(window_size: usize, min_match_length: usize, dictionary: &[u8]) -> Box<Decompressor> {
+    Box::new(Decompressor::new(LZSS::new(window_size, min_match_length)).with_dictionary(dictionary))
+}
@@ -0,0 +1,202 @@
+//! Format detection on decode
+//!
+//! Sniffs magic bytes and plausibility heuristics to tell the supported
+//! compressed formats apart, so callers holding data of unknown origin can
+//! decode it without knowing how it was produced.
+//!
+//! Of the `compat` formats, SZDD files and Nintendo LZ10/LZ11 data are
+//! recognized. Sega Kosinski data and `classic` streams start with token
+//! data instead of a header, so they can't be told apart from other bytes
+//! and are left out.
+
+use crate::frame::Checksum;
+use crate::{block, compat, frame, LengthField, LzssError, LZSS};
+
+/// Compressed formats recognized by `decode_any`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Self-describing frame (`RLZS` magic)
+    Framed,
+    /// Multi-block stream with per-block parameters (`RLZB` magic)
+    Blocks,
+    /// Legacy raw stream (size header + tokens) without parameters
+    Raw,
+    /// Microsoft SZDD file (`SZDD` magic)
+    Szdd,
+    /// Nintendo LZ10 data (type byte 0x10)
+    Lz10,
+    /// Nintendo LZ11 data (type byte 0x11)
+    Lz11,
+}
+
+impl Format {
+    /// Detect the format of `input` without decoding it
+    ///
+    /// Raw streams are checked against the longest match that
+    /// `min_match_length` and `length_field` allow. A single type byte is
+    /// weak evidence, since raw streams start with any byte, so LZ10/LZ11
+    /// data is only recognized if its tokens end within the last 4 bytes of
+    /// the input (the padding GBA and DS tools add). Returns `None` if the
+    /// input can't be a stream of any supported format.
+    pub fn detect(input: &[u8], min_match_length: usize, length_field: LengthField) -> Option<Self> {
+        if frame::is_frame(input) {
+            return Some(Format::Framed);
+        }
+        if block::is_block_stream(input) {
+            return Some(Format::Blocks);
+        }
+        if compat::szdd_header(input).is_ok() {
+            return Some(Format::Szdd);
+        }
+        if matches!(compat::lz_stream_len(input), Ok(len) if input.len() - len < 4) {
+            return Some(if input[0] == compat::LZ10_TAG { Format::Lz10 } else { Format::Lz11 });
+        }
+        if is_plausible_raw(input, min_match_length, length_field) {
+            return Some(Format::Raw);
+        }
+        None
+    }
+}
+
+/// Check that a raw stream's size header could have come from its payload
+fn is_plausible_raw(input: &[u8], min_match_length: usize, length_field: LengthField) -> bool {
+    if input.is_empty() {
+        return true; // Empty input compresses to nothing
+    }
+    if input.len() < 5 {
+        return false;
+    }
+
+    let original_size = u32::from_le_bytes([input[0], input[1], input[2], input[3]]) as usize;
+    if original_size == 0 {
+        return false; // Empty input never gets a header
+    }
+
+    // Every smallest match token (a one-byte short offset and the shortest
+    // length field) expands to at most one maximum-length match
+    let max_match_length = length_field.max_value().saturating_add(min_match_length);
+    let payload = input.len() - 4;
+    let max_matches = payload / (1 + length_field.min_bytes());
+    original_size <= max_matches.saturating_mul(max_match_length).saturating_add(payload)
+}
+
+/// Summary of a compressed buffer, gathered from headers only
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamInfo {
+    /// Detected format
+    pub format: Format,
+    /// Layout version (frames and multi-block streams only)
+    pub version: Option<u8>,
+    /// Minimum match length (frames only; blocks record it per block)
+    pub min_match_length: Option<usize>,
+    /// Bytes per match distance (frames only)
+    pub offset_bytes: Option<usize>,
+    /// Width of the match length field (frames only)
+    pub length_field: Option<LengthField>,
+    /// Uncompressed content size in bytes
+    pub content_size: usize,
+    /// Number of independently decodable blocks
+    pub block_count: usize,
+    /// Checksum of the content (frames only)
+    pub checksum: Checksum,
+    /// How far back matches can reach (frames that record it)
+    pub window_size: Option<usize>,
+    /// ID of the preset dictionary the content was compressed against
+    /// (frames that record it)
+    pub dictionary_id: Option<u32>,
+}
+
+/// Read the size header of a raw stream (empty streams have none)
+fn raw_content_size(stream: &[u8]) -> usize {
+    if stream.len() < 4 {
+        return 0;
+    }
+    u32::from_le_bytes([stream[0], stream[1], stream[2], stream[3]]) as usize
+}
+
+/// Inspect a compressed buffer without decompressing the payload
+///
+/// Raw streams carry no parameters, so `min_match_length` and
+/// `length_field` are only used to check their plausibility.
+pub fn inspect(input: &[u8], min_match_length: usize, length_field: LengthField) -> Option<StreamInfo> {
+    let format = Format::detect(input, min_match_length, length_field)?;
+    let info = match format {
+        Format::Framed => {
+            let header = frame::FrameHeader::parse(input)?;
+            StreamInfo {
+                format,
+                version: Some(header.version),
+                min_match_length: Some(header.min_match_length),
+                offset_bytes: Some(header.offset_bytes),
+                length_field: Some(header.length_field),
+                content_size: raw_content_size(header.stream(input)),
+                block_count: 1,
+                checksum: header.checksum_kind(),
+                window_size: header.window_size,
+                dictionary_id: header.dictionary_id,
+            }
+        }
+        Format::Blocks => {
+            let mut content_size = 0;
+            let mut block_count = 0;
+            for (params, payload) in block::BlockIter::new(input) {
+                content_size += match params.strategy {
+                    block::BlockStrategy::Stored => payload.len(),
+                    block::BlockStrategy::Lzss => raw_content_size(payload),
+                };
+                block_count += 1;
+            }
+            StreamInfo {
+                format,
+                version: Some(input[block::BLOCK_MAGIC.len()]),
+                min_match_length: None,
+                offset_bytes: None,
+                length_field: None,
+                content_size,
+                block_count,
+                checksum: Checksum::None,
+                window_size: None,
+                dictionary_id: None,
+            }
+        }
+        Format::Raw | Format::Szdd | Format::Lz10 | Format::Lz11 => StreamInfo {
+            format,
+            version: None,
+            min_match_length: None,
+            offset_bytes: None,
+            length_field: None,
+            content_size: match format {
+                Format::Szdd => compat::szdd_header(input).ok()?.1,
+                Format::Lz10 | Format::Lz11 => compat::lz_header(input).ok()?.1,
+                _ => raw_content_size(input),
+            },
+            block_count: 1,
+            checksum: Checksum::None,
+            window_size: None,
+            dictionary_id: None,
+        },
+    };
+    Some(info)
+}
+
+impl LZSS {
+    /// Decode input of any supported format
+    ///
+    /// Frames, multi-block streams and the `compat` formats carry their own
+    /// parameters; legacy raw streams are decoded with this instance's
+    /// parameters. Returns the
+    /// decoded data together with the detected format. Unrecognized input is
+    /// reported as `LzssError::InvalidHeader`, and corrupt input fails like
+    /// `try_decompress` instead of being decoded as far as possible.
+    pub fn decode_any(&self, input: &[u8]) -> Result<(Vec<u8>, Format), LzssError> {
+        let format = Format::detect(input, self.min_match_length, self.length_field)
+            .ok_or(LzssError::InvalidHeader)?;
+        let data = match format {
+            Format::Framed | Format::Raw => self.try_decompress(input)?,
+            Format::Blocks => LZSS::try_decompress_adaptive(input)?,
+            Format::Szdd => compat::decompress_szdd(input)?,
+            Format::Lz10 | Format::Lz11 => compat::decompress_lz(input)?,
+        };
+        Ok((data, format))
+    }
+}
@@ -0,0 +1,108 @@
+//! Preset dictionaries identified by ID
+//!
+//! A frame written by `LZSS::compress_framed_with_dictionary` records the
+//! ID of its dictionary rather than the dictionary itself. A `FrameDecoder`
+//! holds the dictionaries it knows, and a resolver callback lets it fetch
+//! the others lazily from the application's own storage (a database, an
+//! asset bundle, a network service) the first time a frame needs them.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::frame::FrameHeader;
+use crate::LzssError;
+
+/// Preset dictionary and the ID frames refer to it by
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dictionary {
+    id: u32,
+    data: Vec<u8>,
+}
+
+impl Dictionary {
+    /// Create a dictionary with the given ID
+    pub fn new(id: u32, data: impl Into<Vec<u8>>) -> Self {
+        Dictionary { id, data: data.into() }
+    }
+
+    /// ID recorded in frames compressed against this dictionary
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Content of the dictionary
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Callback fetching the dictionary with a given ID, or `None` if there is
+/// no such dictionary
+pub type DictionaryResolver = dyn Fn(u32) -> Option<Arc<Dictionary>> + Send + Sync;
+
+/// Frame decoder that finds the dictionaries frames were compressed against
+///
+/// Frames without a dictionary ID decode as with `decompress_auto`.
+#[derive(Default)]
+pub struct FrameDecoder {
+    dictionaries: BTreeMap<u32, Arc<Dictionary>>,
+    resolver: Option<Box<DictionaryResolver>>,
+}
+
+impl FrameDecoder {
+    /// Create a decoder that knows no dictionaries
+    pub fn new() -> Self {
+        FrameDecoder::default()
+    }
+
+    /// Make `dictionary` available to frames that refer to its ID
+    pub fn with_dictionary(mut self, dictionary: Arc<Dictionary>) -> Self {
+        self.dictionaries.insert(dictionary.id(), dictionary);
+        self
+    }
+
+    /// Call `resolver` for dictionary IDs the decoder doesn't know
+    ///
+    /// Each ID is resolved at most once: dictionaries it returns are kept
+    /// for later frames. A `None` is not remembered, so the next frame
+    /// asks again.
+    pub fn with_resolver(
+        mut self,
+        resolver: impl Fn(u32) -> Option<Arc<Dictionary>> + Send + Sync + 'static,
+    ) -> Self {
+        self.resolver = Some(Box::new(resolver));
+        self
+    }
+
+    /// Decompress a frame, resolving its dictionary if it has one
+    ///
+    /// Anything but a frame is an `InvalidHeader` error, and a dictionary
+    /// that is neither known nor resolved a `MissingDictionary` error.
+    /// Corrupt frames fail as in `LZSS::try_decompress`.
+    pub fn decompress(&mut self, input: &[u8]) -> Result<Vec<u8>, LzssError> {
+        let header = FrameHeader::parse(input).ok_or(LzssError::InvalidHeader)?;
+        match header.dictionary_id {
+            Some(id) => {
+                let dictionary = self.dictionary(id)?;
+                header.decoder().decompress_with_dictionary(dictionary.data(), input)
+            }
+            None => header.decoder().try_decompress(input),
+        }
+    }
+
+    /// The dictionary with ID `id`, asking the resolver if it isn't known
+    fn dictionary(&mut self, id: u32) -> Result<Arc<Dictionary>, LzssError> {
+        if let Some(dictionary) = self.dictionaries.get(&id) {
+            return Ok(dictionary.clone());
+        }
+        let dictionary = self
+            .resolver
+            .as_ref()
+            .and_then(|resolve| resolve(id))
+            .ok_or(LzssError::MissingDictionary { id })?;
+        self.dictionaries.insert(id, dictionary.clone());
+        Ok(dictionary)
+    }
+}
@@ -0,0 +1,362 @@
+//! embedded-io stream adapters (`embedded-io` feature)
+//!
+//! `LzssWriter` and `LzssReader` mirror the `std::io` adapters in `stream`
+//! over `embedded_io::Write`/`Read`, and `AsyncLzssWriter` and
+//! `AsyncLzssReader` over their `embedded_io_async` counterparts, so no_std
+//! firmware can stream compressed data through UART or flash drivers. The
+//! stream layout is the same as `stream`'s, so the other end can be a host
+//! using the `std::io` adapters.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use embedded_io::{ErrorKind, ErrorType, ReadExactError};
+
+use crate::compressor::Decompressor;
+use crate::stream::Encoder;
+use crate::{LzssError, LZSS};
+
+/// Compressed bytes read at a time while receiving a block, so a corrupt
+/// block length can't force a huge allocation
+const READ_CHUNK: usize = 4096;
+
+/// Errors from `LzssReader` and `AsyncLzssReader`
+#[derive(Debug)]
+pub enum StreamError<E> {
+    /// The underlying reader failed
+    Io(E),
+    /// The stream ended inside a block
+    UnexpectedEof,
+    /// A block is corrupt
+    Decompress(LzssError),
+}
+
+impl<E> From<ReadExactError<E>> for StreamError<E> {
+    fn from(error: ReadExactError<E>) -> Self {
+        match error {
+            ReadExactError::UnexpectedEof => StreamError::UnexpectedEof,
+            ReadExactError::Other(e) => StreamError::Io(e),
+        }
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for StreamError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Io(e) => write!(f, "read failed: {:?}", e),
+            StreamError::UnexpectedEof => write!(f, "stream ended inside a block"),
+            StreamError::Decompress(e) => write!(f, "decompression failed: {}", e),
+        }
+    }
+}
+
+impl<E: fmt::Debug> core::error::Error for StreamError<E> {}
+
+impl<E: embedded_io::Error> embedded_io::Error for StreamError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            StreamError::Io(e) => e.kind(),
+            StreamError::UnexpectedEof => ErrorKind::Other,
+            StreamError::Decompress(_) => ErrorKind::InvalidData,
+        }
+    }
+}
+
+/// Decoded block being handed out by a reader
+struct BlockReader {
+    decompressor: Decompressor,
+    block: Vec<u8>,
+    pos: usize,
+}
+
+impl BlockReader {
+    fn new(lzss: LZSS) -> Self {
+        BlockReader { decompressor: Decompressor::new(lzss), block: Vec::new(), pos: 0 }
+    }
+
+    fn is_drained(&self) -> bool {
+        self.pos == self.block.len()
+    }
+
+    fn decode<E>(&mut self, compressed: &[u8]) -> Result<(), StreamError<E>> {
+        self.block = self.decompressor.try_decompress(compressed).map_err(StreamError::Decompress)?;
+        self.pos = 0;
+        Ok(())
+    }
+
+    /// Copy as much of the block as fits into `buf`
+    fn take(&mut self, buf: &mut [u8]) -> usize {
+        let count = buf.len().min(self.block.len() - self.pos);
+        buf[..count].copy_from_slice(&self.block[self.pos..self.pos + count]);
+        self.pos += count;
+        count
+    }
+}
+
+/// Writer that compresses everything written to it into `inner`
+///
+/// Like `stream::LzssWriter`: data is buffered until a block is full, and
+/// `finish` (or `flush`) writes out the last partial block. Dropping the
+/// writer also does so but ignores errors.
+pub struct LzssWriter<W: embedded_io::Write> {
+    inner: Option<W>,
+    encoder: Encoder,
+}
+
+impl<W: embedded_io::Write> LzssWriter<W> {
+    /// Create a writer compressing into `inner` with the given codec
+    pub fn new(inner: W, lzss: LZSS) -> Self {
+        LzssWriter { inner: Some(inner), encoder: Encoder::new(lzss) }
+    }
+
+    /// Change the number of uncompressed bytes per block (see
+    /// `Encoder::with_block_size`)
+    pub fn with_block_size(mut self, block_size: usize) -> Self {
+        self.encoder.set_block_size(block_size);
+        self
+    }
+
+    /// The underlying writer
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().expect("writer is only taken by finish")
+    }
+
+    /// Compress the buffered data into a block and write it out
+    fn write_block(&mut self) -> Result<(), W::Error> {
+        let block = self.encoder.flush();
+        if block.is_empty() {
+            return Ok(());
+        }
+        self.inner.as_mut().expect("writer is only taken by finish").write_all(&block)
+    }
+
+    /// Write out the last block and return the underlying writer
+    pub fn finish(mut self) -> Result<W, W::Error> {
+        self.write_block()?;
+        let mut inner = self.inner.take().expect("writer is only taken by finish");
+        inner.flush()?;
+        Ok(inner)
+    }
+}
+
+impl<W: embedded_io::Write> ErrorType for LzssWriter<W> {
+    type Error = W::Error;
+}
+
+impl<W: embedded_io::Write> embedded_io::Write for LzssWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let count = self.encoder.buffer_some(buf);
+        if self.encoder.is_block_full() {
+            self.write_block()?;
+        }
+        Ok(count)
+    }
+
+    /// Write out the buffered data as a (possibly short) block and flush
+    /// the underlying writer
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.write_block()?;
+        self.inner.as_mut().expect("writer is only taken by finish").flush()
+    }
+}
+
+impl<W: embedded_io::Write> Drop for LzssWriter<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.write_block();
+        }
+    }
+}
+
+/// Reader that decompresses a stream written by an `LzssWriter`
+///
+/// Corrupt blocks are reported as `StreamError::Decompress` and a stream
+/// that ends inside a block as `StreamError::UnexpectedEof`.
+pub struct LzssReader<R: embedded_io::Read> {
+    inner: R,
+    blocks: BlockReader,
+}
+
+impl<R: embedded_io::Read> LzssReader<R> {
+    /// Create a reader decompressing `inner` with the codec the stream was
+    /// written with
+    pub fn new(inner: R, lzss: LZSS) -> Self {
+        LzssReader { inner, blocks: BlockReader::new(lzss) }
+    }
+
+    /// The underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Return the underlying reader, dropping any undelivered output
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Decode the next block, returning false at the end of the stream
+    fn next_block(&mut self) -> Result<bool, StreamError<R::Error>> {
+        let mut header = [0u8; 4];
+        let mut filled = 0;
+        while filled < header.len() {
+            match self.inner.read(&mut header[filled..]).map_err(StreamError::Io)? {
+                0 if filled == 0 => return Ok(false),
+                0 => return Err(StreamError::UnexpectedEof),
+                n => filled += n,
+            }
+        }
+
+        let len = u32::from_le_bytes(header) as usize;
+        let mut compressed = Vec::new();
+        while compressed.len() < len {
+            let start = compressed.len();
+            compressed.resize(start + (len - start).min(READ_CHUNK), 0);
+            self.inner.read_exact(&mut compressed[start..])?;
+        }
+        self.blocks.decode(&compressed)?;
+        Ok(true)
+    }
+}
+
+impl<R: embedded_io::Read> ErrorType for LzssReader<R> {
+    type Error = StreamError<R::Error>;
+}
+
+impl<R: embedded_io::Read> embedded_io::Read for LzssReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        // Skip over empty blocks, which a writer never produces but are valid
+        while self.blocks.is_drained() {
+            if buf.is_empty() || !self.next_block()? {
+                return Ok(0);
+            }
+        }
+        Ok(self.blocks.take(buf))
+    }
+}
+
+/// `LzssWriter` over an `embedded_io_async::Write`
+///
+/// Dropping it can't write anything, so call `finish` to write out the last
+/// partial block.
+pub struct AsyncLzssWriter<W: embedded_io_async::Write> {
+    inner: W,
+    encoder: Encoder,
+}
+
+impl<W: embedded_io_async::Write> AsyncLzssWriter<W> {
+    /// Create a writer compressing into `inner` with the given codec
+    pub fn new(inner: W, lzss: LZSS) -> Self {
+        AsyncLzssWriter { inner, encoder: Encoder::new(lzss) }
+    }
+
+    /// Change the number of uncompressed bytes per block (see
+    /// `Encoder::with_block_size`)
+    pub fn with_block_size(mut self, block_size: usize) -> Self {
+        self.encoder.set_block_size(block_size);
+        self
+    }
+
+    /// The underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Compress the buffered data into a block and write it out
+    async fn write_block(&mut self) -> Result<(), W::Error> {
+        let block = self.encoder.flush();
+        if block.is_empty() {
+            return Ok(());
+        }
+        self.inner.write_all(&block).await
+    }
+
+    /// Write out the last block and return the underlying writer
+    pub async fn finish(mut self) -> Result<W, W::Error> {
+        self.write_block().await?;
+        self.inner.flush().await?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: embedded_io_async::Write> ErrorType for AsyncLzssWriter<W> {
+    type Error = W::Error;
+}
+
+impl<W: embedded_io_async::Write> embedded_io_async::Write for AsyncLzssWriter<W> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let count = self.encoder.buffer_some(buf);
+        if self.encoder.is_block_full() {
+            self.write_block().await?;
+        }
+        Ok(count)
+    }
+
+    /// Write out the buffered data as a (possibly short) block and flush
+    /// the underlying writer
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.write_block().await?;
+        self.inner.flush().await
+    }
+}
+
+/// `LzssReader` over an `embedded_io_async::Read`
+pub struct AsyncLzssReader<R: embedded_io_async::Read> {
+    inner: R,
+    blocks: BlockReader,
+}
+
+impl<R: embedded_io_async::Read> AsyncLzssReader<R> {
+    /// Create a reader decompressing `inner` with the codec the stream was
+    /// written with
+    pub fn new(inner: R, lzss: LZSS) -> Self {
+        AsyncLzssReader { inner, blocks: BlockReader::new(lzss) }
+    }
+
+    /// The underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Return the underlying reader, dropping any undelivered output
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Decode the next block, returning false at the end of the stream
+    async fn next_block(&mut self) -> Result<bool, StreamError<R::Error>> {
+        let mut header = [0u8; 4];
+        let mut filled = 0;
+        while filled < header.len() {
+            match self.inner.read(&mut header[filled..]).await.map_err(StreamError::Io)? {
+                0 if filled == 0 => return Ok(false),
+                0 => return Err(StreamError::UnexpectedEof),
+                n => filled += n,
+            }
+        }
+
+        let len = u32::from_le_bytes(header) as usize;
+        let mut compressed = Vec::new();
+        while compressed.len() < len {
+            let start = compressed.len();
+            compressed.resize(start + (len - start).min(READ_CHUNK), 0);
+            self.inner.read_exact(&mut compressed[start..]).await?;
+        }
+        self.blocks.decode(&compressed)?;
+        Ok(true)
+    }
+}
+
+impl<R: embedded_io_async::Read> ErrorType for AsyncLzssReader<R> {
+    type Error = StreamError<R::Error>;
+}
+
+impl<R: embedded_io_async::Read> embedded_io_async::Read for AsyncLzssReader<R> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        while self.blocks.is_drained() {
+            if buf.is_empty() || !self.next_block().await? {
+                return Ok(0);
+            }
+        }
+        Ok(self.blocks.take(buf))
+    }
+}
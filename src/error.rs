@@ -0,0 +1,191 @@
+//! Error types for fallible decoding, buffer compression and configuration
+
+use core::fmt;
+
+/// Errors detected while decoding a compressed stream, or compressing into
+/// a caller's buffer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LzssError {
+    /// The input ends in the middle of the size header or a token
+    TruncatedInput,
+    /// A match references data before the start of the output
+    InvalidDistance {
+        /// Offset of the decoded data where the match starts
+        position: usize,
+        /// Distance stored in the match token
+        distance: usize,
+    },
+    /// The tokens decode to a different size than the header claims
+    SizeMismatch {
+        /// Size recorded in the stream header
+        expected: usize,
+        /// Size actually decoded
+        actual: usize,
+    },
+    /// The output buffer is not the size the stream decodes to
+    OutputSizeMismatch {
+        /// Size recorded in the stream header
+        expected: usize,
+        /// Size of the output buffer
+        actual: usize,
+    },
+    /// The output buffer is too small for the compressed data
+    OutputTooSmall {
+        /// Compressed size
+        required: usize,
+        /// Size of the output buffer
+        capacity: usize,
+    },
+    /// The decoded content doesn't match the checksum stored in its frame
+    ChecksumMismatch {
+        /// Checksum stored in the frame header
+        expected: u64,
+        /// Checksum of the decoded content
+        actual: u64,
+    },
+    /// The block index of a seekable archive is missing or inconsistent
+    InvalidIndex,
+    /// The stream claims to decode to more bytes than the caller allows
+    OutputLimitExceeded {
+        /// Size recorded in the stream header
+        size: usize,
+        /// Largest output the caller accepts
+        limit: usize,
+    },
+    /// The input doesn't start with the header its format requires
+    InvalidHeader,
+    /// Entropy-coded data holds bits that match no code of its tables
+    InvalidCode,
+    /// A progress callback stopped the operation
+    Cancelled,
+    /// The frame was compressed against a preset dictionary the decoder
+    /// doesn't have
+    MissingDictionary {
+        /// Dictionary ID recorded in the frame header
+        id: u32,
+    },
+}
+
+impl fmt::Display for LzssError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LzssError::TruncatedInput => write!(f, "compressed input is truncated"),
+            LzssError::InvalidDistance { position, distance } => write!(
+                f,
+                "match at output offset {} references {} bytes back, before the start of the data",
+                position, distance
+            ),
+            LzssError::SizeMismatch { expected, actual } => write!(
+                f,
+                "stream decodes to {} bytes but its header claims {}",
+                actual, expected
+            ),
+            LzssError::OutputSizeMismatch { expected, actual } => write!(
+                f,
+                "output buffer holds {} bytes but the stream decodes to {}",
+                actual, expected
+            ),
+            LzssError::OutputTooSmall { required, capacity } => write!(
+                f,
+                "output buffer holds {} bytes but the compressed data needs {}",
+                capacity, required
+            ),
+            LzssError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "content checksum {:#x} doesn't match the {:#x} stored in the frame",
+                actual, expected
+            ),
+            LzssError::InvalidIndex => write!(f, "seekable archive has a missing or corrupt block index"),
+            LzssError::InvalidHeader => write!(f, "input doesn't start with a valid header for its format"),
+            LzssError::InvalidCode => write!(f, "entropy-coded data holds an undefined code"),
+            LzssError::Cancelled => write!(f, "operation cancelled by its progress callback"),
+            LzssError::MissingDictionary { id } => write!(f, "frame needs dictionary {}, which isn't available", id),
+            LzssError::OutputLimitExceeded { size, limit } => write!(
+                f,
+                "stream claims {} bytes of output, more than the limit of {}",
+                size, limit
+            ),
+        }
+    }
+}
+
+impl core::error::Error for LzssError {}
+
+/// Invalid codec configuration rejected by `LzssBuilder::build`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The window is empty or larger than match distances can reach
+    InvalidWindowSize {
+        /// Requested window size
+        window_size: usize,
+        /// Largest window for the configuration
+        max: usize,
+    },
+    /// The minimum match length is below 2, where matches can't pay off,
+    /// or too large for a frame header
+    InvalidMinMatchLength {
+        /// Requested minimum match length
+        min_match_length: usize,
+        /// Largest minimum match length
+        max: usize,
+    },
+    /// The maximum match length is below the minimum or beyond what the
+    /// length field can represent
+    InvalidMaxMatchLength {
+        /// Requested maximum match length
+        max_match_length: usize,
+        /// Smallest allowed value (the minimum match length)
+        min: usize,
+        /// Largest value the length field can represent
+        max: usize,
+    },
+    /// The long-distance history is empty or beyond 3-byte distances
+    InvalidLongDistanceHistory {
+        /// Requested history size
+        history_size: usize,
+        /// Largest history size
+        max: usize,
+    },
+    /// The match search must examine at least one position
+    InvalidSearchDepth,
+    /// A bit-packed token layout that doesn't fill 2 or 3 bytes, has fewer
+    /// than 8 position bits or no length bits, or whose ring buffer isn't
+    /// larger than its longest match
+    InvalidBitLayout {
+        /// Requested position bits
+        offset_bits: u32,
+        /// Requested length bits
+        length_bits: u32,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::InvalidWindowSize { window_size, max } => {
+                write!(f, "window size {} is outside 1..={}", window_size, max)
+            }
+            ConfigError::InvalidMinMatchLength { min_match_length, max } => {
+                write!(f, "minimum match length {} is outside 2..={}", min_match_length, max)
+            }
+            ConfigError::InvalidMaxMatchLength { max_match_length, min, max } => write!(
+                f,
+                "maximum match length {} is outside {}..={}",
+                max_match_length, min, max
+            ),
+            ConfigError::InvalidLongDistanceHistory { history_size, max } => write!(
+                f,
+                "long-distance history of {} bytes is outside 1..={}",
+                history_size, max
+            ),
+            ConfigError::InvalidSearchDepth => write!(f, "search depth must be at least 1"),
+            ConfigError::InvalidBitLayout { offset_bits, length_bits } => write!(
+                f,
+                "{}-bit offsets with {}-bit lengths are not a usable token layout",
+                offset_bits, length_bits
+            ),
+        }
+    }
+}
+
+impl core::error::Error for ConfigError {}
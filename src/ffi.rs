@@ -1,31 +1,215 @@
-use crate::LZSS;
-use std::os::raw::{c_char, c_int, c_uchar, c_ulong};
+// The exported functions take raw pointers from C callers and validate them
+// before use, so they are intentionally not marked `unsafe`.
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+use crate::detect::{self, Format};
+use crate::frame::{self, Checksum};
+use crate::compressor::{Compressor, Decompressor};
+use crate::{LengthField, LzssError, LZSS};
+use std::os::raw::{c_char, c_int, c_uchar, c_uint, c_ulong, c_void};
 use std::slice;
 
+/// Status codes returned by the functions that report success or failure
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LzssStatus {
+    /// The call succeeded
+    Ok = 0,
+    /// A required pointer was null or a parameter was out of range
+    InvalidParameters = -1,
+    /// The output buffer is too small for the result
+    BufferTooSmall = -2,
+    /// The input is corrupt or does not decode to the expected size
+    CorruptInput = -3,
+    /// The progress callback cancelled the operation
+    Cancelled = -4,
+}
+
+/// Progress callback for long operations
+///
+/// Called with the caller's user data, the number of bytes processed so far
+/// and the total. Returning nonzero cancels the operation.
+pub type LzssProgress = Option<extern "C" fn(user_data: *mut c_void, done: u64, total: u64) -> c_int>;
+
+/// Capabilities reported by `lzss_features`, one bit each
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LzssFeature {
+    /// Self-describing frames are decoded with their own parameters
+    Frames = 1,
+    /// Frame checksums: CRC32
+    Crc32 = 2,
+    /// Frame checksums: xxHash3
+    Xxh3 = 4,
+    /// Preset dictionaries (`lzss_load_dictionary`)
+    Dictionaries = 8,
+    /// Huffman-coded streams (Rust API only)
+    Huffman = 16,
+    /// Built for small targets: windows are capped at 4096 bytes
+    SmallTarget = 32,
+}
+
+/// Layout of a compressed buffer, as reported by `lzss_get_frame_info`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LzssFormat {
+    /// Self-describing frame
+    Framed = 0,
+    /// Multi-block stream with per-block parameters
+    Blocks = 1,
+    /// Raw stream without parameters
+    Raw = 2,
+    /// Microsoft SZDD file
+    Szdd = 3,
+    /// Nintendo LZ10 data
+    Lz10 = 4,
+    /// Nintendo LZ11 data
+    Lz11 = 5,
+}
+
+/// Checksum stored in a frame header
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LzssChecksum {
+    /// No checksum
+    None = 0,
+    /// CRC32 (IEEE)
+    Crc32 = 1,
+    /// xxHash3, 64-bit
+    Xxh3 = 2,
+}
+
+/// Summary of a compressed buffer, filled in by `lzss_get_frame_info`
+///
+/// Fields a format doesn't record are 0.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LzssFrameInfo {
+    /// Detected layout
+    pub format: LzssFormat,
+    /// Layout version (frames and multi-block streams)
+    pub version: c_int,
+    /// Uncompressed content size in bytes
+    pub original_size: c_ulong,
+    /// Size of the compressed buffer in bytes
+    pub compressed_size: c_ulong,
+    /// Minimum match length used by the encoder (frames)
+    pub min_match_length: c_int,
+    /// Bytes per match distance (frames)
+    pub offset_bytes: c_int,
+    /// How far back matches can reach (frames that record it)
+    pub window_size: c_ulong,
+    /// Checksum of the content (frames)
+    pub checksum: LzssChecksum,
+    /// Number of independently decodable blocks
+    pub block_count: c_ulong,
+}
+
+/// Opaque struct holding the windows of a packet stream, such as one
+/// network connection
+///
+/// Each packet may reference the packets before it, so both ends must
+/// process every packet in order. Outgoing and incoming packets keep
+/// separate windows.
+pub struct LzssStream {
+    lzss: LZSS,
+    compressor: Compressor,
+    decompressor: Decompressor,
+    dictionary: Vec<u8>,
+    window: Option<ExternalWindow>,
+}
+
+/// Decompression window in memory provided by the caller
+/// (`lzss_stream_set_window`), holding the most recent decoded bytes
+struct ExternalWindow {
+    buffer: *mut u8,
+    capacity: usize,
+    len: usize,
+}
+
+impl ExternalWindow {
+    /// Bytes matches may reference
+    unsafe fn data(&self) -> &[u8] {
+        slice::from_raw_parts(self.buffer, self.len)
+    }
+
+    /// Restart from the end of `dictionary`
+    unsafe fn reset(&mut self, dictionary: &[u8]) {
+        self.len = 0;
+        self.push(dictionary);
+    }
+
+    /// Append decoded bytes, dropping the oldest ones that no longer fit
+    unsafe fn push(&mut self, data: &[u8]) {
+        let buffer = slice::from_raw_parts_mut(self.buffer, self.capacity);
+        let data = &data[data.len().saturating_sub(self.capacity)..];
+        let keep = self.len.min(self.capacity - data.len());
+        buffer.copy_within(self.len - keep..self.len, 0);
+        buffer[keep..keep + data.len()].copy_from_slice(data);
+        self.len = keep + data.len();
+    }
+}
+
 /// Opaque struct to hold the LZSS compressor instance
 pub struct LzssContext {
     lzss: LZSS,
+    dictionary: Vec<u8>,
+}
+
+/// Version of the library, e.g. "0.1.0"
+///
+/// Lets hosts that load the library dynamically check which version they
+/// got. The string is static and must not be freed.
+#[no_mangle]
+pub extern "C" fn lzss_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// Features the library was built with, as a bitmask of `LzssFeature`
+#[no_mangle]
+pub extern "C" fn lzss_features() -> c_uint {
+    let mut features = LzssFeature::Frames as c_uint
+        | LzssFeature::Crc32 as c_uint
+        | LzssFeature::Xxh3 as c_uint
+        | LzssFeature::Dictionaries as c_uint;
+    if cfg!(feature = "huffman") {
+        features |= LzssFeature::Huffman as c_uint;
+    }
+    if cfg!(feature = "small-target") {
+        features |= LzssFeature::SmallTarget as c_uint;
+    }
+    features
 }
 
 /// Create a new LZSS context with specified parameters
 ///
 /// # Parameters
-/// * `window_size` - Size of the sliding window (up to 65535)
+/// * `window_size` - Size of the sliding window (up to 65535, or 4096 in
+///   `small-target` builds)
 /// * `min_match_length` - Minimum match length for encoding
 ///
 /// # Returns
 /// Pointer to the LZSS context or null on failure
 #[no_mangle]
 pub extern "C" fn lzss_create(window_size: c_int, min_match_length: c_int) -> *mut LzssContext {
-    if window_size <= 0 || min_match_length <= 0 || window_size > 65535 {
+    let Some(lzss) = new_lzss(window_size, min_match_length) else {
         return std::ptr::null_mut();
-    }
+    };
 
-    let lzss = LZSS::new(window_size as usize, min_match_length as usize);
-    let context = Box::new(LzssContext { lzss });
+    let context = Box::new(LzssContext { lzss, dictionary: Vec::new() });
     Box::into_raw(context)
 }
 
+/// Codec for the parameters passed from C, if they are in range
+fn new_lzss(window_size: c_int, min_match_length: c_int) -> Option<LZSS> {
+    if window_size <= 0 || min_match_length <= 0 || window_size > 65535
+        || window_size as usize > crate::MAX_WINDOW_SIZE
+    {
+        return None;
+    }
+    Some(LZSS::new(window_size as usize, min_match_length as usize))
+}
+
 /// Free resources used by the LZSS context
 ///
 /// # Parameters
@@ -39,6 +223,39 @@ pub extern "C" fn lzss_destroy(context: *mut LzssContext) {
     }
 }
 
+/// Load a preset dictionary into the context
+///
+/// Subsequent `lzss_compress` and `lzss_decompress` calls on the context use
+/// the dictionary, so data compressed with it can only be decompressed by a
+/// context holding the same dictionary. The bytes are copied. Passing a
+/// null pointer or zero length removes the dictionary.
+///
+/// # Parameters
+/// * `context` - LZSS context created with lzss_create
+/// * `dictionary` - Pointer to the dictionary bytes
+/// * `dictionary_size` - Size of the dictionary in bytes
+///
+/// # Returns
+/// 0 on success, a negative `LzssStatus` on failure
+#[no_mangle]
+pub extern "C" fn lzss_load_dictionary(
+    context: *mut LzssContext,
+    dictionary: *const c_uchar,
+    dictionary_size: c_ulong,
+) -> c_int {
+    if context.is_null() {
+        return LzssStatus::InvalidParameters as c_int;
+    }
+
+    unsafe {
+        let context = &mut *context;
+        context.dictionary.clear();
+        context.dictionary.extend_from_slice(dictionary_slice(dictionary, dictionary_size));
+    }
+
+    LzssStatus::Ok as c_int
+}
+
 /// Compress data using LZSS algorithm
 ///
 /// # Parameters
@@ -47,10 +264,11 @@ pub extern "C" fn lzss_destroy(context: *mut LzssContext) {
 /// * `input_size` - Size of the input data in bytes
 /// * `output` - Pointer to output buffer (must be pre-allocated)
 /// * `output_size` - Size of the output buffer in bytes
-/// * `compressed_size` - Pointer to where the actual compressed size will be stored
+/// * `compressed_size` - Pointer to where the actual compressed size will be stored,
+///   or the size the output buffer needs if it is too small
 ///
 /// # Returns
-/// 0 on success, negative error code on failure
+/// 0 on success, a negative `LzssStatus` on failure
 #[no_mangle]
 pub extern "C" fn lzss_compress(
     context: *const LzssContext,
@@ -61,29 +279,38 @@ pub extern "C" fn lzss_compress(
     compressed_size: *mut c_ulong,
 ) -> c_int {
     if context.is_null() || input.is_null() || output.is_null() || compressed_size.is_null() {
-        return -1; // Invalid parameters
+        return LzssStatus::InvalidParameters as c_int;
     }
 
     unsafe {
-        let lzss = &(*context).lzss;
+        let context = &*context;
         let input_slice = slice::from_raw_parts(input, input_size as usize);
-        
-        // Compress the data
-        let compressed_data = lzss.compress(input_slice);
-        
-        // Ensure output buffer is large enough
-        if compressed_data.len() > output_size as usize {
-            return -2; // Output buffer too small
-        }
-        
-        // Copy compressed data to output buffer
         let output_slice = slice::from_raw_parts_mut(output, output_size as usize);
-        output_slice[..compressed_data.len()].copy_from_slice(&compressed_data);
-        
-        // Store the actual compressed size
-        *compressed_size = compressed_data.len() as c_ulong;
-        
-        0 // Success
+        compress_buffer(&context.lzss, &context.dictionary, input_slice, output_slice, &mut *compressed_size)
+    }
+}
+
+/// Compress straight into the caller's buffer against `dictionary`
+fn compress_buffer(lzss: &LZSS, dictionary: &[u8], input: &[u8], output: &mut [u8], compressed_size: &mut c_ulong) -> c_int {
+    let written = if dictionary.is_empty() {
+        lzss.compress_into(input, output)
+    } else {
+        lzss.compress_with_dictionary_into(dictionary, input, output)
+    };
+
+    match written {
+        Ok(size) => {
+            // Store the actual compressed size
+            *compressed_size = size as c_ulong;
+            LzssStatus::Ok as c_int
+        }
+        Err(error) => {
+            // Only a short buffer fails; report how large it must be
+            if let LzssError::OutputTooSmall { required, .. } = error {
+                *compressed_size = required as c_ulong;
+            }
+            LzssStatus::BufferTooSmall as c_int
+        }
     }
 }
 
@@ -95,10 +322,11 @@ pub extern "C" fn lzss_compress(
 /// * `input_size` - Size of the compressed data in bytes
 /// * `output` - Pointer to output buffer (must be pre-allocated)
 /// * `output_size` - Size of the output buffer in bytes
-/// * `decompressed_size` - Pointer to where the actual decompressed size will be stored
+/// * `decompressed_size` - Pointer to where the actual decompressed size will be stored,
+///   or the size the output buffer needs if it is too small
 ///
 /// # Returns
-/// 0 on success, negative error code on failure
+/// 0 on success, a negative `LzssStatus` on failure
 #[no_mangle]
 pub extern "C" fn lzss_decompress(
     context: *const LzssContext,
@@ -109,19 +337,23 @@ pub extern "C" fn lzss_decompress(
     decompressed_size: *mut c_ulong,
 ) -> c_int {
     if context.is_null() || input.is_null() || output.is_null() || decompressed_size.is_null() {
-        return -1; // Invalid parameters
+        return LzssStatus::InvalidParameters as c_int;
     }
 
     unsafe {
-        let lzss = &(*context).lzss;
+        let context = &*context;
         let input_slice = slice::from_raw_parts(input, input_size as usize);
         
         // Decompress the data
-        let decompressed_data = lzss.decompress(input_slice);
+        let decompressed_data = match context.lzss.decompress_with_dictionary(&context.dictionary, input_slice) {
+            Ok(data) => data,
+            Err(_) => return LzssStatus::CorruptInput as c_int,
+        };
         
         // Ensure output buffer is large enough
         if decompressed_data.len() > output_size as usize {
-            return -2; // Output buffer too small
+            *decompressed_size = decompressed_data.len() as c_ulong;
+            return LzssStatus::BufferTooSmall as c_int;
         }
         
         // Copy decompressed data to output buffer
@@ -131,7 +363,384 @@ pub extern "C" fn lzss_decompress(
         // Store the actual decompressed size
         *decompressed_size = decompressed_data.len() as c_ulong;
         
-        0 // Success
+        LzssStatus::Ok as c_int
+    }
+}
+
+/// Decompress data into a caller's buffer without trusting its size header
+///
+/// Unlike `lzss_decompress`, the size in the header is checked against
+/// `output_size` before anything is decoded or allocated, so a forged or
+/// corrupt header can't make the library reserve more memory than the
+/// caller's buffer.
+///
+/// # Parameters
+/// * `context` - LZSS context created with lzss_create
+/// * `input` - Pointer to compressed data buffer
+/// * `input_size` - Size of the compressed data in bytes
+/// * `output` - Pointer to output buffer
+/// * `output_size` - Size of the output buffer in bytes
+/// * `decompressed_size` - Pointer to where the actual decompressed size will be stored,
+///   or the size the output buffer needs if it is too small
+///
+/// # Returns
+/// 0 on success, -1 for invalid parameters, -2 if the output buffer is too
+/// small, -3 for corrupt input (the output may then have been partly
+/// written)
+#[no_mangle]
+pub extern "C" fn lzss_decompress_bounded(
+    context: *const LzssContext,
+    input: *const c_uchar,
+    input_size: c_ulong,
+    output: *mut c_uchar,
+    output_size: c_ulong,
+    decompressed_size: *mut c_ulong,
+) -> c_int {
+    if context.is_null() || input.is_null() || output.is_null() || decompressed_size.is_null() {
+        return LzssStatus::InvalidParameters as c_int;
+    }
+
+    unsafe {
+        let context = &*context;
+        let input_slice = slice::from_raw_parts(input, input_size as usize);
+        let output_slice = slice::from_raw_parts_mut(output, output_size as usize);
+        decompress_buffer(&context.lzss, &context.dictionary, input_slice, output_slice, &mut *decompressed_size)
+    }
+}
+
+/// Decompress into the caller's buffer against `dictionary`, checking the
+/// size header against the buffer first
+fn decompress_buffer(
+    lzss: &LZSS,
+    dictionary: &[u8],
+    input: &[u8],
+    output: &mut [u8],
+    decompressed_size: &mut c_ulong,
+) -> c_int {
+    let Some(size) = original_size(input) else {
+        return LzssStatus::CorruptInput as c_int;
+    };
+
+    *decompressed_size = size as c_ulong;
+    if size > output.len() {
+        return LzssStatus::BufferTooSmall as c_int;
+    }
+
+    match lzss.decompress_into_with_history(input, dictionary, &mut output[..size]) {
+        Ok(()) => LzssStatus::Ok as c_int,
+        Err(_) => LzssStatus::CorruptInput as c_int,
+    }
+}
+
+/// Dictionary passed from C (null for none)
+///
+/// # Safety
+/// `dictionary` must be null or point to `dictionary_size` readable bytes.
+unsafe fn dictionary_slice<'a>(dictionary: *const c_uchar, dictionary_size: c_ulong) -> &'a [u8] {
+    if dictionary.is_null() {
+        &[]
+    } else {
+        slice::from_raw_parts(dictionary, dictionary_size as usize)
+    }
+}
+
+/// Compress data against a dictionary given for this call only
+///
+/// Like `lzss_compress`, but uses `dictionary` instead of one loaded into
+/// the context, so one context can serve payloads with different
+/// dictionaries, from several threads at once. Decompress the output with
+/// `lzss_decompress_with_dict` and the same dictionary.
+///
+/// # Parameters
+/// * `context` - LZSS context created with lzss_create
+/// * `dictionary` - Pointer to the dictionary bytes (null for none)
+/// * `dictionary_size` - Size of the dictionary in bytes
+/// * `input` - Pointer to input data buffer
+/// * `input_size` - Size of the input data in bytes
+/// * `output` - Pointer to output buffer (must be pre-allocated)
+/// * `output_size` - Size of the output buffer in bytes
+/// * `compressed_size` - Pointer to where the actual compressed size will be stored,
+///   or the size the output buffer needs if it is too small
+///
+/// # Returns
+/// 0 on success, a negative `LzssStatus` on failure
+#[no_mangle]
+pub extern "C" fn lzss_compress_with_dict(
+    context: *const LzssContext,
+    dictionary: *const c_uchar,
+    dictionary_size: c_ulong,
+    input: *const c_uchar,
+    input_size: c_ulong,
+    output: *mut c_uchar,
+    output_size: c_ulong,
+    compressed_size: *mut c_ulong,
+) -> c_int {
+    if context.is_null() || input.is_null() || output.is_null() || compressed_size.is_null() {
+        return LzssStatus::InvalidParameters as c_int;
+    }
+
+    unsafe {
+        let dictionary = dictionary_slice(dictionary, dictionary_size);
+        let input_slice = slice::from_raw_parts(input, input_size as usize);
+        let output_slice = slice::from_raw_parts_mut(output, output_size as usize);
+        compress_buffer(&(*context).lzss, dictionary, input_slice, output_slice, &mut *compressed_size)
+    }
+}
+
+/// Decompress data compressed against a dictionary given for this call only
+///
+/// Like `lzss_decompress_bounded`, but uses `dictionary` instead of one
+/// loaded into the context.
+///
+/// # Parameters
+/// * `context` - LZSS context created with lzss_create
+/// * `dictionary` - Pointer to the dictionary bytes (null for none)
+/// * `dictionary_size` - Size of the dictionary in bytes
+/// * `input` - Pointer to compressed data buffer
+/// * `input_size` - Size of the compressed data in bytes
+/// * `output` - Pointer to output buffer
+/// * `output_size` - Size of the output buffer in bytes
+/// * `decompressed_size` - Pointer to where the actual decompressed size will be stored,
+///   or the size the output buffer needs if it is too small
+///
+/// # Returns
+/// 0 on success, a negative `LzssStatus` on failure
+#[no_mangle]
+pub extern "C" fn lzss_decompress_with_dict(
+    context: *const LzssContext,
+    dictionary: *const c_uchar,
+    dictionary_size: c_ulong,
+    input: *const c_uchar,
+    input_size: c_ulong,
+    output: *mut c_uchar,
+    output_size: c_ulong,
+    decompressed_size: *mut c_ulong,
+) -> c_int {
+    if context.is_null() || input.is_null() || output.is_null() || decompressed_size.is_null() {
+        return LzssStatus::InvalidParameters as c_int;
+    }
+
+    unsafe {
+        let dictionary = dictionary_slice(dictionary, dictionary_size);
+        let input_slice = slice::from_raw_parts(input, input_size as usize);
+        let output_slice = slice::from_raw_parts_mut(output, output_size as usize);
+        decompress_buffer(&(*context).lzss, dictionary, input_slice, output_slice, &mut *decompressed_size)
+    }
+}
+
+/// Compress data in one call, without a context
+///
+/// Compression keeps no state between calls, so this is the same as
+/// `lzss_compress` on a fresh context, with nothing to create or destroy.
+/// It is safe to call from any number of threads at once.
+///
+/// # Parameters
+/// * `window_size` - Size of the sliding window (as for lzss_create)
+/// * `min_match_length` - Minimum match length for encoding
+/// * `input` - Pointer to input data buffer
+/// * `input_size` - Size of the input data in bytes
+/// * `output` - Pointer to output buffer (must be pre-allocated)
+/// * `output_size` - Size of the output buffer in bytes
+/// * `compressed_size` - Pointer to where the actual compressed size will be stored,
+///   or the size the output buffer needs if it is too small
+///
+/// # Returns
+/// 0 on success, a negative `LzssStatus` on failure
+#[no_mangle]
+pub extern "C" fn lzss_compress_simple(
+    window_size: c_int,
+    min_match_length: c_int,
+    input: *const c_uchar,
+    input_size: c_ulong,
+    output: *mut c_uchar,
+    output_size: c_ulong,
+    compressed_size: *mut c_ulong,
+) -> c_int {
+    let Some(lzss) = new_lzss(window_size, min_match_length) else {
+        return LzssStatus::InvalidParameters as c_int;
+    };
+    let context = LzssContext { lzss, dictionary: Vec::new() };
+    lzss_compress(&context, input, input_size, output, output_size, compressed_size)
+}
+
+/// Decompress data in one call, without a context
+///
+/// The same as `lzss_decompress_bounded` on a fresh context: the size
+/// header is checked against `output_size` before decoding. It is safe to
+/// call from any number of threads at once.
+///
+/// # Parameters
+/// * `window_size` - Size of the sliding window used for compression
+/// * `min_match_length` - Minimum match length used for compression
+/// * `input` - Pointer to compressed data buffer
+/// * `input_size` - Size of the compressed data in bytes
+/// * `output` - Pointer to output buffer
+/// * `output_size` - Size of the output buffer in bytes
+/// * `decompressed_size` - Pointer to where the actual decompressed size will be stored,
+///   or the size the output buffer needs if it is too small
+///
+/// # Returns
+/// 0 on success, a negative `LzssStatus` on failure
+#[no_mangle]
+pub extern "C" fn lzss_decompress_simple(
+    window_size: c_int,
+    min_match_length: c_int,
+    input: *const c_uchar,
+    input_size: c_ulong,
+    output: *mut c_uchar,
+    output_size: c_ulong,
+    decompressed_size: *mut c_ulong,
+) -> c_int {
+    let Some(lzss) = new_lzss(window_size, min_match_length) else {
+        return LzssStatus::InvalidParameters as c_int;
+    };
+    let context = LzssContext { lzss, dictionary: Vec::new() };
+    lzss_decompress_bounded(&context, input, input_size, output, output_size, decompressed_size)
+}
+
+/// Hand a buffer over to the C caller, to be released with `lzss_free`
+fn into_raw_buffer(data: Vec<u8>, output: *mut *mut c_uchar, output_size: *mut c_ulong) {
+    let data = data.into_boxed_slice();
+    unsafe {
+        *output_size = data.len() as c_ulong;
+        *output = Box::into_raw(data) as *mut c_uchar;
+    }
+}
+
+/// Compress data into a buffer allocated by the library
+///
+/// Like `lzss_compress`, without sizing the output buffer up front. The
+/// buffer must be released with `lzss_free`.
+///
+/// # Parameters
+/// * `context` - LZSS context created with lzss_create
+/// * `input` - Pointer to input data buffer
+/// * `input_size` - Size of the input data in bytes
+/// * `output` - Pointer to where the address of the compressed data will be stored
+/// * `output_size` - Pointer to where the compressed size will be stored
+///
+/// # Returns
+/// 0 on success, a negative `LzssStatus` on failure
+#[no_mangle]
+pub extern "C" fn lzss_compress_alloc(
+    context: *const LzssContext,
+    input: *const c_uchar,
+    input_size: c_ulong,
+    output: *mut *mut c_uchar,
+    output_size: *mut c_ulong,
+) -> c_int {
+    if context.is_null() || input.is_null() || output.is_null() || output_size.is_null() {
+        return LzssStatus::InvalidParameters as c_int;
+    }
+
+    let context = unsafe { &*context };
+    let input_slice = unsafe { slice::from_raw_parts(input, input_size as usize) };
+    let compressed = if context.dictionary.is_empty() {
+        context.lzss.compress(input_slice)
+    } else {
+        context.lzss.compress_with_dictionary(&context.dictionary, input_slice)
+    };
+
+    into_raw_buffer(compressed, output, output_size);
+    LzssStatus::Ok as c_int
+}
+
+/// Decompress data into a buffer allocated by the library
+///
+/// Like `lzss_decompress`, without sizing the output buffer up front. The
+/// input is validated before anything is allocated, so a forged size header
+/// can't force a large allocation. The buffer must be released with
+/// `lzss_free`.
+///
+/// # Parameters
+/// * `context` - LZSS context created with lzss_create
+/// * `input` - Pointer to compressed data buffer
+/// * `input_size` - Size of the compressed data in bytes
+/// * `output` - Pointer to where the address of the decompressed data will be stored
+/// * `output_size` - Pointer to where the decompressed size will be stored
+///
+/// # Returns
+/// 0 on success, a negative `LzssStatus` on failure
+#[no_mangle]
+pub extern "C" fn lzss_decompress_alloc(
+    context: *const LzssContext,
+    input: *const c_uchar,
+    input_size: c_ulong,
+    output: *mut *mut c_uchar,
+    output_size: *mut c_ulong,
+) -> c_int {
+    if context.is_null() || input.is_null() || output.is_null() || output_size.is_null() {
+        return LzssStatus::InvalidParameters as c_int;
+    }
+
+    let context = unsafe { &*context };
+    let input_slice = unsafe { slice::from_raw_parts(input, input_size as usize) };
+    let Ok(stats) = context.lzss.validate_with_history(input_slice, context.dictionary.len()) else {
+        return LzssStatus::CorruptInput as c_int;
+    };
+    let mut decompressed = vec![0; stats.content_size];
+    if context.lzss.decompress_into_with_history(input_slice, &context.dictionary, &mut decompressed).is_err() {
+        return LzssStatus::CorruptInput as c_int;
+    }
+
+    into_raw_buffer(decompressed, output, output_size);
+    LzssStatus::Ok as c_int
+}
+
+/// Free a buffer returned by `lzss_compress_alloc` or `lzss_decompress_alloc`
+///
+/// # Parameters
+/// * `data` - Buffer returned by the library (null is ignored)
+/// * `size` - Size of the buffer, as returned alongside it
+#[no_mangle]
+pub extern "C" fn lzss_free(data: *mut c_uchar, size: c_ulong) {
+    if !data.is_null() {
+        unsafe {
+            drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(data, size as usize)));
+        }
+    }
+}
+
+/// Decompress into a buffer of exactly the decompressed size
+///
+/// Matches the memory-to-memory shape of engine compression plugins such as
+/// an Unreal `FCompressionFormat`'s `Uncompress`: nothing is allocated, and
+/// the call fails unless the data decodes cleanly to exactly `output_size`
+/// bytes. Neither buffer needs any particular alignment; frames written
+/// with an alignment option are passed whole, padding included.
+///
+/// # Parameters
+/// * `context` - LZSS context created with lzss_create
+/// * `input` - Pointer to compressed data buffer
+/// * `input_size` - Size of the compressed data in bytes
+/// * `output` - Pointer to output buffer
+/// * `output_size` - Expected decompressed size in bytes
+///
+/// # Returns
+/// 0 on success, -1 for invalid parameters, -3 for corrupt input or a size
+/// mismatch (the output may then have been partly written)
+#[no_mangle]
+pub extern "C" fn lzss_decompress_exact(
+    context: *const LzssContext,
+    input: *const c_uchar,
+    input_size: c_ulong,
+    output: *mut c_uchar,
+    output_size: c_ulong,
+) -> c_int {
+    if context.is_null() || (input.is_null() && input_size > 0) || (output.is_null() && output_size > 0) {
+        return LzssStatus::InvalidParameters as c_int;
+    }
+
+    unsafe {
+        let context = &*context;
+        let input_slice = if input_size == 0 { &[] } else { slice::from_raw_parts(input, input_size as usize) };
+        let output_slice =
+            if output_size == 0 { &mut [] } else { slice::from_raw_parts_mut(output, output_size as usize) };
+
+        match context.lzss.decompress_into_with_history(input_slice, &context.dictionary, output_slice) {
+            Ok(()) => LzssStatus::Ok as c_int,
+            Err(_) => LzssStatus::CorruptInput as c_int,
+        }
     }
 }
 
@@ -149,13 +758,14 @@ pub extern "C" fn lzss_decompress(
 #[no_mangle]
 pub extern "C" fn lzss_max_compressed_size(input_size: c_ulong) -> c_ulong {
     // 4 bytes for original size + control bytes (1 per 8 bytes worst case) + worst case of all literals
-    let control_bytes = (input_size + 7) / 8;
+    let control_bytes = input_size.div_ceil(8);
     (4 + control_bytes + input_size) as c_ulong
 }
 
 /// Get the original size of compressed data without decompressing it
 /// 
-/// This function extracts the original size from the header of the compressed data
+/// This function extracts the original size from the header of the compressed data,
+/// skipping the frame header of self-describing frames
 ///
 /// # Parameters
 /// * `compressed_data` - Pointer to compressed data buffer
@@ -172,14 +782,436 @@ pub extern "C" fn lzss_get_original_size(
         return 0; // Invalid parameters
     }
 
+    let data = unsafe { slice::from_raw_parts(compressed_data, compressed_size as usize) };
+    original_size(data).unwrap_or(0) as c_ulong
+}
+
+/// Size recorded in the header of a raw stream or frame, if there is one
+fn original_size(data: &[u8]) -> Option<usize> {
+    let start = frame::FrameHeader::parse(data).map_or(0, |header| header.size());
+    match data.get(start..)? {
+        [] => Some(0), // Empty input compresses to nothing
+        [a, b, c, d, ..] => Some(u32::from_le_bytes([*a, *b, *c, *d]) as usize),
+        _ => None,
+    }
+}
+
+/// Inspect a compressed buffer without decompressing it
+///
+/// Reads only headers, so asset packers can check the format, sizes and
+/// parameters of compressed data cheaply. Raw streams carry no parameters
+/// and are only recognized if their size header is plausible.
+///
+/// # Parameters
+/// * `data` - Pointer to compressed data buffer
+/// * `size` - Size of the compressed data in bytes
+/// * `info` - Pointer to where the summary will be stored
+///
+/// # Returns
+/// 0 on success, -1 for invalid parameters, -3 if the data isn't a
+/// recognized format
+#[no_mangle]
+pub extern "C" fn lzss_get_frame_info(data: *const c_uchar, size: c_ulong, info: *mut LzssFrameInfo) -> c_int {
+    if data.is_null() || info.is_null() {
+        return LzssStatus::InvalidParameters as c_int;
+    }
+
+    let data = unsafe { slice::from_raw_parts(data, size as usize) };
+    // The largest minimum match length and widest fixed length field accept
+    // raw streams of any parameters the C API can produce
+    let Some(stream) = detect::inspect(data, crate::MAX_MIN_MATCH_LENGTH, LengthField::TwoBytes) else {
+        return LzssStatus::CorruptInput as c_int;
+    };
+
+    let summary = LzssFrameInfo {
+        format: match stream.format {
+            Format::Framed => LzssFormat::Framed,
+            Format::Blocks => LzssFormat::Blocks,
+            Format::Raw => LzssFormat::Raw,
+            Format::Szdd => LzssFormat::Szdd,
+            Format::Lz10 => LzssFormat::Lz10,
+            Format::Lz11 => LzssFormat::Lz11,
+        },
+        version: stream.version.unwrap_or(0) as c_int,
+        original_size: stream.content_size as c_ulong,
+        compressed_size: size,
+        min_match_length: stream.min_match_length.unwrap_or(0) as c_int,
+        offset_bytes: stream.offset_bytes.unwrap_or(0) as c_int,
+        window_size: stream.window_size.unwrap_or(0) as c_ulong,
+        checksum: match stream.checksum {
+            Checksum::None => LzssChecksum::None,
+            Checksum::Crc32 => LzssChecksum::Crc32,
+            Checksum::Xxh3 => LzssChecksum::Xxh3,
+        },
+        block_count: stream.block_count as c_ulong,
+    };
+    unsafe { *info = summary };
+    LzssStatus::Ok as c_int
+}
+
+/// Adapt a C progress callback to the codec's, reporting against `total`
+fn progress_fn(progress: LzssProgress, user_data: *mut c_void, total: usize) -> impl FnMut(usize) -> bool {
+    move |done| progress.is_none_or(|callback| callback(user_data, done as u64, total as u64) == 0)
+}
+
+/// Compress data like `lzss_compress`, reporting progress
+///
+/// `progress` is called about every megabyte of input and once more when
+/// compression finishes, so GUIs can show progress on large inputs and
+/// let the user cancel. The return value of the final call is ignored.
+///
+/// # Parameters
+/// * `context` - LZSS context created with lzss_create
+/// * `input` - Pointer to input data buffer
+/// * `input_size` - Size of the input data in bytes
+/// * `output` - Pointer to output buffer (must be pre-allocated)
+/// * `output_size` - Size of the output buffer in bytes
+/// * `compressed_size` - Pointer to where the actual compressed size will be stored,
+///   or the size the output buffer needs if it is too small
+/// * `progress` - Callback receiving the input bytes compressed so far (may be null)
+/// * `user_data` - Pointer passed through to `progress`
+///
+/// # Returns
+/// 0 on success, a negative `LzssStatus` on failure (`LZSS_STATUS_CANCELLED`
+/// if the callback cancelled)
+#[no_mangle]
+pub extern "C" fn lzss_compress_progress(
+    context: *const LzssContext,
+    input: *const c_uchar,
+    input_size: c_ulong,
+    output: *mut c_uchar,
+    output_size: c_ulong,
+    compressed_size: *mut c_ulong,
+    progress: LzssProgress,
+    user_data: *mut c_void,
+) -> c_int {
+    if context.is_null() || input.is_null() || output.is_null() || compressed_size.is_null() {
+        return LzssStatus::InvalidParameters as c_int;
+    }
+
+    let context = unsafe { &*context };
+    let input_slice = unsafe { slice::from_raw_parts(input, input_size as usize) };
+    let output_slice = unsafe { slice::from_raw_parts_mut(output, output_size as usize) };
+    let mut report = progress_fn(progress, user_data, input_slice.len());
+
+    let prefixed;
+    let (data, start) = if context.dictionary.is_empty() {
+        (input_slice, 0)
+    } else {
+        prefixed = context.lzss.with_dictionary_prefix(&context.dictionary, input_slice);
+        (&prefixed.0[..], prefixed.1)
+    };
+
+    match context.lzss.compress_from_into_with_progress(data, start, output_slice, &mut report) {
+        Ok(size) => {
+            unsafe { *compressed_size = size as c_ulong };
+            report(input_slice.len());
+            LzssStatus::Ok as c_int
+        }
+        Err(LzssError::OutputTooSmall { required, .. }) => {
+            unsafe { *compressed_size = required as c_ulong };
+            LzssStatus::BufferTooSmall as c_int
+        }
+        Err(_) => LzssStatus::Cancelled as c_int,
+    }
+}
+
+/// Decompress data like `lzss_decompress_bounded`, reporting progress
+///
+/// `progress` is called about every megabyte of output and once more when
+/// decompression finishes.
+///
+/// # Parameters
+/// * `context` - LZSS context created with lzss_create
+/// * `input` - Pointer to compressed data buffer
+/// * `input_size` - Size of the compressed data in bytes
+/// * `output` - Pointer to output buffer
+/// * `output_size` - Size of the output buffer in bytes
+/// * `decompressed_size` - Pointer to where the actual decompressed size will be stored,
+///   or the size the output buffer needs if it is too small
+/// * `progress` - Callback receiving the bytes decompressed so far (may be null)
+/// * `user_data` - Pointer passed through to `progress`
+///
+/// # Returns
+/// 0 on success, a negative `LzssStatus` on failure (`LZSS_STATUS_CANCELLED`
+/// if the callback cancelled)
+#[no_mangle]
+pub extern "C" fn lzss_decompress_progress(
+    context: *const LzssContext,
+    input: *const c_uchar,
+    input_size: c_ulong,
+    output: *mut c_uchar,
+    output_size: c_ulong,
+    decompressed_size: *mut c_ulong,
+    progress: LzssProgress,
+    user_data: *mut c_void,
+) -> c_int {
+    if context.is_null() || input.is_null() || output.is_null() || decompressed_size.is_null() {
+        return LzssStatus::InvalidParameters as c_int;
+    }
+
+    let context = unsafe { &*context };
+    let input_slice = unsafe { slice::from_raw_parts(input, input_size as usize) };
+    let Some(size) = original_size(input_slice) else {
+        return LzssStatus::CorruptInput as c_int;
+    };
+
+    unsafe { *decompressed_size = size as c_ulong };
+    if size > output_size as usize {
+        return LzssStatus::BufferTooSmall as c_int;
+    }
+
+    let output_slice = unsafe { slice::from_raw_parts_mut(output, size) };
+    let mut report = progress_fn(progress, user_data, size);
+    match context.lzss.decompress_into_with_progress(input_slice, &context.dictionary, output_slice, &mut report) {
+        Ok(()) => {
+            report(size);
+            LzssStatus::Ok as c_int
+        }
+        Err(LzssError::Cancelled) => LzssStatus::Cancelled as c_int,
+        Err(_) => LzssStatus::CorruptInput as c_int,
+    }
+}
+
+/// Create a packet stream with empty windows
+///
+/// # Parameters
+/// * `window_size` - Size of the sliding window (as for lzss_create)
+/// * `min_match_length` - Minimum match length for encoding
+///
+/// # Returns
+/// Pointer to the stream or null on failure
+#[no_mangle]
+pub extern "C" fn lzss_stream_create(window_size: c_int, min_match_length: c_int) -> *mut LzssStream {
+    let Some(lzss) = new_lzss(window_size, min_match_length) else {
+        return std::ptr::null_mut();
+    };
+
+    let stream = LzssStream {
+        compressor: Compressor::new(LZSS { ..lzss }),
+        decompressor: Decompressor::new(LZSS { ..lzss }),
+        lzss,
+        dictionary: Vec::new(),
+        window: None,
+    };
+    Box::into_raw(Box::new(stream))
+}
+
+/// Free resources used by a packet stream
+///
+/// # Parameters
+/// * `stream` - Stream created with lzss_stream_create
+#[no_mangle]
+pub extern "C" fn lzss_stream_destroy(stream: *mut LzssStream) {
+    if !stream.is_null() {
+        unsafe {
+            drop(Box::from_raw(stream));
+        }
+    }
+}
+
+/// Start both windows of a stream from a preset dictionary
+///
+/// Both windows are reset, so call this before the first packet, on both
+/// ends. The bytes are copied. Passing a null pointer or zero length
+/// removes the dictionary.
+///
+/// # Parameters
+/// * `stream` - Stream created with lzss_stream_create
+/// * `dictionary` - Pointer to the dictionary bytes
+/// * `dictionary_size` - Size of the dictionary in bytes
+///
+/// # Returns
+/// 0 on success, a negative `LzssStatus` on failure
+#[no_mangle]
+pub extern "C" fn lzss_stream_set_dictionary(
+    stream: *mut LzssStream,
+    dictionary: *const c_uchar,
+    dictionary_size: c_ulong,
+) -> c_int {
+    if stream.is_null() {
+        return LzssStatus::InvalidParameters as c_int;
+    }
+
     unsafe {
-        let bytes = slice::from_raw_parts(compressed_data, 4);
-        let mut original_size = 0usize;
-        
-        for i in 0..4 {
-            original_size |= (bytes[i] as usize) << (i * 8);
+        let stream = &mut *stream;
+        let dictionary = dictionary_slice(dictionary, dictionary_size);
+        stream.compressor = Compressor::new(LZSS { ..stream.lzss }).with_dictionary(dictionary);
+        stream.dictionary = dictionary.to_vec();
+        match &mut stream.window {
+            Some(window) => window.reset(dictionary),
+            None => stream.decompressor = Decompressor::new(LZSS { ..stream.lzss }).with_dictionary(dictionary),
         }
-        
-        original_size as c_ulong
     }
-}
\ No newline at end of file
+    LzssStatus::Ok as c_int
+}
+
+/// Keep the decompression window of a stream in memory the caller provides
+///
+/// Lets embedded callers place the window (as large as the stream's
+/// window size, 4-64 KiB) in a specific memory region instead of the
+/// library's heap. The window is reset to the dictionary, so call this
+/// before the first incoming packet. `window` must stay valid, and must not
+/// be touched or overlap the buffers of `lzss_stream_decompress`, until the
+/// stream is destroyed or gets another window. Passing a null pointer or
+/// zero length goes back to a window allocated by the library.
+///
+/// # Parameters
+/// * `stream` - Stream created with lzss_stream_create
+/// * `window` - Pointer to the window memory
+/// * `window_size` - Size of the window memory in bytes; at least the
+///   stream's window size, of which only that much is used
+///
+/// # Returns
+/// 0 on success, -1 for invalid parameters (including memory smaller than
+/// the stream's window)
+#[no_mangle]
+pub extern "C" fn lzss_stream_set_window(stream: *mut LzssStream, window: *mut c_uchar, window_size: c_ulong) -> c_int {
+    if stream.is_null() {
+        return LzssStatus::InvalidParameters as c_int;
+    }
+
+    let stream = unsafe { &mut *stream };
+    if window.is_null() || window_size == 0 {
+        stream.window = None;
+        stream.decompressor = Decompressor::new(LZSS { ..stream.lzss }).with_dictionary(&stream.dictionary);
+        return LzssStatus::Ok as c_int;
+    }
+    // Packet streams don't use long-distance matching, so matches reach
+    // at most the window size back
+    if (window_size as usize) < stream.lzss.window_size {
+        return LzssStatus::InvalidParameters as c_int;
+    }
+
+    let mut external = ExternalWindow { buffer: window, capacity: stream.lzss.window_size, len: 0 };
+    unsafe { external.reset(&stream.dictionary) };
+    stream.window = Some(external);
+    // Drop the library's own window memory
+    stream.decompressor = Decompressor::new(LZSS { ..stream.lzss });
+    LzssStatus::Ok as c_int
+}
+
+/// Forget all previous packets, returning both windows to the dictionary
+///
+/// # Parameters
+/// * `stream` - Stream created with lzss_stream_create
+#[no_mangle]
+pub extern "C" fn lzss_stream_reset(stream: *mut LzssStream) {
+    if !stream.is_null() {
+        let stream = unsafe { &mut *stream };
+        stream.compressor.reset_window();
+        match &mut stream.window {
+            Some(window) => unsafe { window.reset(&stream.dictionary) },
+            None => stream.decompressor.reset_window(),
+        }
+    }
+}
+
+/// Compress the next outgoing packet of a stream
+///
+/// The output buffer must hold the worst case for the packet (see
+/// `lzss_max_compressed_size`); otherwise nothing is compressed, the
+/// window is unchanged and `compressed_size` receives the size needed.
+///
+/// # Parameters
+/// * `stream` - Stream created with lzss_stream_create
+/// * `input` - Pointer to input data buffer
+/// * `input_size` - Size of the input data in bytes
+/// * `output` - Pointer to output buffer
+/// * `output_size` - Size of the output buffer in bytes
+/// * `compressed_size` - Pointer to where the actual compressed size will be stored
+///
+/// # Returns
+/// 0 on success, a negative `LzssStatus` on failure
+#[no_mangle]
+pub extern "C" fn lzss_stream_compress(
+    stream: *mut LzssStream,
+    input: *const c_uchar,
+    input_size: c_ulong,
+    output: *mut c_uchar,
+    output_size: c_ulong,
+    compressed_size: *mut c_ulong,
+) -> c_int {
+    if stream.is_null() || input.is_null() || output.is_null() || compressed_size.is_null() {
+        return LzssStatus::InvalidParameters as c_int;
+    }
+
+    unsafe {
+        let stream = &mut *stream;
+        let required = stream.lzss.max_compressed_size(input_size as usize);
+        if required > output_size as usize {
+            *compressed_size = required as c_ulong;
+            return LzssStatus::BufferTooSmall as c_int;
+        }
+
+        let input_slice = slice::from_raw_parts(input, input_size as usize);
+        let compressed = stream.compressor.compress(input_slice);
+        slice::from_raw_parts_mut(output, compressed.len()).copy_from_slice(&compressed);
+        *compressed_size = compressed.len() as c_ulong;
+    }
+    LzssStatus::Ok as c_int
+}
+
+/// Decompress the next incoming packet of a stream
+///
+/// If the buffer is too small or the packet is corrupt, the window is
+/// unchanged, so the packet can be retried with a larger buffer.
+///
+/// # Parameters
+/// * `stream` - Stream created with lzss_stream_create
+/// * `input` - Pointer to compressed data buffer
+/// * `input_size` - Size of the compressed data in bytes
+/// * `output` - Pointer to output buffer
+/// * `output_size` - Size of the output buffer in bytes
+/// * `decompressed_size` - Pointer to where the actual decompressed size will be stored,
+///   or the size the output buffer needs if it is too small
+///
+/// # Returns
+/// 0 on success, a negative `LzssStatus` on failure
+#[no_mangle]
+pub extern "C" fn lzss_stream_decompress(
+    stream: *mut LzssStream,
+    input: *const c_uchar,
+    input_size: c_ulong,
+    output: *mut c_uchar,
+    output_size: c_ulong,
+    decompressed_size: *mut c_ulong,
+) -> c_int {
+    if stream.is_null() || input.is_null() || output.is_null() || decompressed_size.is_null() {
+        return LzssStatus::InvalidParameters as c_int;
+    }
+
+    unsafe {
+        let stream = &mut *stream;
+        let input_slice = slice::from_raw_parts(input, input_size as usize);
+        let Some(size) = original_size(input_slice) else {
+            return LzssStatus::CorruptInput as c_int;
+        };
+        *decompressed_size = size as c_ulong;
+        if size > output_size as usize {
+            return LzssStatus::BufferTooSmall as c_int;
+        }
+
+        if let Some(window) = &mut stream.window {
+            // Decode straight into the caller's buffer, then slide the window
+            let history = window.data();
+            if stream.lzss.validate_stream(input_slice, history.len()).is_err() {
+                return LzssStatus::CorruptInput as c_int;
+            }
+            let output_slice = slice::from_raw_parts_mut(output, size);
+            if stream.lzss.decompress_stream_into(input_slice, history, output_slice, &mut |_| true).is_err() {
+                return LzssStatus::CorruptInput as c_int;
+            }
+            window.push(output_slice);
+            return LzssStatus::Ok as c_int;
+        }
+
+        match stream.decompressor.try_decompress(input_slice) {
+            Ok(decompressed) => {
+                slice::from_raw_parts_mut(output, decompressed.len()).copy_from_slice(&decompressed);
+                LzssStatus::Ok as c_int
+            }
+            Err(_) => LzssStatus::CorruptInput as c_int,
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Pre-filters applied before compression
+//!
+//! A filter reversibly transforms the input into something LZSS compresses
+//! better, and is undone after decompression. `Rle` collapses long byte
+//! runs, `Delta` stores differences between neighbouring samples and
+//! `Transpose` splits interleaved records into planes; other transforms
+//! plug in by implementing `Filter`.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{LzssError, LZSS};
+
+/// A reversible transform applied to the input before compression
+pub trait Filter {
+    /// Transform `input` before compression
+    fn encode(&self, input: &[u8]) -> Vec<u8>;
+
+    /// Undo `encode` on decompressed data
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>, LzssError>;
+}
+
+/// Run-length pre-filter
+///
+/// After `min_run` equal bytes comes the number of further repeats (u16
+/// LE), so a run of any length costs a few bytes and longer runs take one
+/// such entry per 64 KiB. Matches are capped at 255 bytes with the default
+/// length field, so sparse data (heightmaps, zero-padded structs) compresses
+/// far better this way. Data without runs of `min_run` bytes passes through
+/// unchanged, and every run of exactly `min_run` bytes grows by 2 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rle {
+    min_run: usize,
+}
+
+impl Default for Rle {
+    fn default() -> Self {
+        Rle { min_run: 8 }
+    }
+}
+
+impl Rle {
+    /// Filter collapsing runs of at least `min_run` bytes (at least 2)
+    pub fn new(min_run: usize) -> Self {
+        Rle { min_run: min_run.max(2) }
+    }
+
+    /// Number of equal bytes that start a run
+    pub fn min_run(&self) -> usize {
+        self.min_run
+    }
+}
+
+impl Filter for Rle {
+    fn encode(&self, input: &[u8]) -> Vec<u8> {
+        let mut output = Vec::with_capacity(input.len());
+        let mut pos = 0;
+        while pos < input.len() {
+            let byte = input[pos];
+            let run = input[pos..].iter().take_while(|&&b| b == byte).count();
+            if run < self.min_run {
+                output.extend_from_slice(&input[pos..pos + run]);
+                pos += run;
+                continue;
+            }
+
+            let repeats = (run - self.min_run).min(u16::MAX as usize);
+            output.resize(output.len() + self.min_run, byte);
+            output.extend_from_slice(&(repeats as u16).to_le_bytes());
+            pos += self.min_run + repeats;
+        }
+        output
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>, LzssError> {
+        let mut output = Vec::with_capacity(input.len() * 2);
+        let mut run = 0;
+        let mut pos = 0;
+        while let Some(&byte) = input.get(pos) {
+            pos += 1;
+            run = if output.last() == Some(&byte) { run + 1 } else { 1 };
+            output.push(byte);
+            if run == self.min_run {
+                let count = input.get(pos..pos + 2).ok_or(LzssError::TruncatedInput)?;
+                pos += 2;
+                output.resize(output.len() + u16::from_le_bytes([count[0], count[1]]) as usize, byte);
+                run = 0;
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// Delta pre-filter
+///
+/// Replaces every byte with its difference (mod 256) from the byte
+/// `stride` bytes earlier, or with the difference of that difference for
+/// delta-of-delta. Smoothly varying samples turn into small, repetitive
+/// values: PCM audio (stride = bytes per sample frame, e.g. 4 for 16-bit
+/// stereo) and uncompressed texture rows (stride = bytes per pixel)
+/// compress far better. Frames can record it (see
+/// `FrameOptions::with_delta`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Delta {
+    stride: usize,
+    order: u8,
+}
+
+impl Delta {
+    /// Largest stride frames can record
+    pub const MAX_STRIDE: usize = u16::MAX as usize;
+
+    /// Differences between bytes `stride` apart (1 to `MAX_STRIDE`)
+    pub fn new(stride: usize) -> Self {
+        Delta { stride: stride.clamp(1, Self::MAX_STRIDE), order: 1 }
+    }
+
+    /// Differences of the differences between bytes `stride` apart, for
+    /// samples that change at a steady rate (ramps, gradients)
+    pub fn delta_of_delta(stride: usize) -> Self {
+        Delta { order: 2, ..Delta::new(stride) }
+    }
+
+    /// Filter of `order` 1 or 2 as recorded in a frame, if valid
+    pub(crate) fn from_parts(stride: usize, order: u8) -> Option<Self> {
+        ((1..=Self::MAX_STRIDE).contains(&stride) && (1..=2).contains(&order)).then_some(Delta { stride, order })
+    }
+
+    /// Distance between the bytes subtracted from each other
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// 1 for deltas, 2 for delta-of-delta
+    pub fn order(&self) -> u8 {
+        self.order
+    }
+}
+
+impl Filter for Delta {
+    fn encode(&self, input: &[u8]) -> Vec<u8> {
+        let mut output = input.to_vec();
+        for _ in 0..self.order {
+            // Back to front, so every byte is subtracted from its original
+            // predecessor
+            for i in (self.stride..output.len()).rev() {
+                output[i] = output[i].wrapping_sub(output[i - self.stride]);
+            }
+        }
+        output
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>, LzssError> {
+        let mut output = input.to_vec();
+        DeltaDecoder::new(*self).undo(&mut output);
+        Ok(output)
+    }
+}
+
+/// Undoes a `Delta` filter in place, one chunk at a time
+pub(crate) struct DeltaDecoder {
+    stride: usize,
+    /// Last `stride` bytes decoded at each order, indexed by position mod
+    /// `stride` (zeros before the start)
+    previous: Vec<Vec<u8>>,
+    pos: usize,
+}
+
+impl DeltaDecoder {
+    pub(crate) fn new(delta: Delta) -> Self {
+        DeltaDecoder { stride: delta.stride, previous: vec![vec![0; delta.stride]; delta.order as usize], pos: 0 }
+    }
+
+    /// Undo the filter on the next `chunk` of the data
+    pub(crate) fn undo(&mut self, chunk: &mut [u8]) {
+        for previous in &mut self.previous {
+            let mut slot = self.pos;
+            for byte in chunk.iter_mut() {
+                *byte = byte.wrapping_add(previous[slot]);
+                previous[slot] = *byte;
+                slot = if slot + 1 == self.stride { 0 } else { slot + 1 };
+            }
+        }
+        self.pos = (self.pos + chunk.len()) % self.stride;
+    }
+}
+
+/// Byte-stride transposition (array of structs to struct of arrays)
+///
+/// Splits a buffer of fixed-size records into one plane per byte offset
+/// within a record: the first byte of every record, then the second, and
+/// so on. Interleaved records such as vertex buffers (position, normal,
+/// UV) become runs of similar values that LZSS matches far better. Bytes
+/// after the last whole record stay at the end, unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Transpose {
+    record_size: usize,
+}
+
+impl Transpose {
+    /// Filter for records of `record_size` bytes (at least 1; 1 changes
+    /// nothing)
+    pub fn new(record_size: usize) -> Self {
+        Transpose { record_size: record_size.max(1) }
+    }
+
+    /// Size of the records in bytes
+    pub fn record_size(&self) -> usize {
+        self.record_size
+    }
+}
+
+impl Filter for Transpose {
+    fn encode(&self, input: &[u8]) -> Vec<u8> {
+        let records = input.len() / self.record_size;
+        let whole = records * self.record_size;
+        let mut output = Vec::with_capacity(input.len());
+        for offset in 0..self.record_size {
+            output.extend(input[..whole].iter().skip(offset).step_by(self.record_size));
+        }
+        output.extend_from_slice(&input[whole..]);
+        output
+    }
+
+    fn decode(&self, input: &[u8]) -> Result<Vec<u8>, LzssError> {
+        let records = input.len() / self.record_size;
+        let whole = records * self.record_size;
+        let mut output = vec![0; input.len()];
+        if records > 0 {
+            for (offset, plane) in input[..whole].chunks_exact(records).enumerate() {
+                for (record, &byte) in plane.iter().enumerate() {
+                    output[record * self.record_size + offset] = byte;
+                }
+            }
+        }
+        output[whole..].copy_from_slice(&input[whole..]);
+        Ok(output)
+    }
+}
+
+impl LZSS {
+    /// Compress `input` after passing it through `filter`
+    pub fn compress_filtered(&self, filter: &(impl Filter + ?Sized), input: &[u8]) -> Vec<u8> {
+        self.compress(&filter.encode(input))
+    }
+
+    /// Decompress data from `compress_filtered` and undo `filter`
+    pub fn decompress_filtered(&self, filter: &(impl Filter + ?Sized), input: &[u8]) -> Result<Vec<u8>, LzssError> {
+        filter.decode(&self.try_decompress(input)?)
+    }
+}
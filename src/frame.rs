@@ -0,0 +1,541 @@
+//! Self-describing framed format
+//!
+//! A frame prefixes the regular compressed stream with the parameters
+//! needed to decode it, so decompression no longer depends on constructing
+//! an `LZSS` with exactly matching settings.
+//!
+//! ```text
+//! magic "RLZS" | version (1 byte) | min_match (1 byte) | offset_bytes (1 byte)
+//!     | length_field (1 byte) | flags (1 byte) | [padding (u32 LE)]
+//!     | [slot_size (u32 LE) | content_padding (u32 LE)]
+//!     | [checksum (u32 LE for CRC32, u64 LE for xxHash3)]
+//!     | [delta order (1 byte) | delta stride (u16 LE)] | [window_size (u32 LE)]
+//!     | [dictionary_id (u32 LE)] | stream | [zero padding]
+//! ```
+//!
+//! Fields in brackets are only present when the matching flag is set,
+//! except the dictionary ID, which version 3 frames have and version 2
+//! frames don't. Frames are written as version 2 unless they were
+//! compressed against a dictionary, so decoders that predate dictionaries
+//! still read them.
+//!
+//! Version 1 frames end the header after `offset_bytes` and always use
+//! one-byte length fields; they are still decoded.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::dictionary::Dictionary;
+use crate::filter::{Delta, DeltaDecoder, Filter};
+use crate::{LengthField, LzssError, LZSS};
+
+/// Magic bytes identifying a frame
+pub const FRAME_MAGIC: [u8; 4] = *b"RLZS";
+
+/// Current version of the frame layout
+pub const FRAME_VERSION: u8 = 2;
+
+/// Version of frames that record the ID of their preset dictionary
+pub const FRAME_DICTIONARY_VERSION: u8 = 3;
+
+/// Size of the current frame header in bytes, without optional fields
+pub const FRAME_HEADER_SIZE: usize = 9;
+
+/// Size of a version 1 frame header in bytes
+const FRAME_V1_HEADER_SIZE: usize = 7;
+
+/// Flag: match tokens store `distance - 1`, so a zero field means a
+/// distance of one and no token can reference zero bytes back
+pub const FLAG_DISTANCE_MINUS_ONE: u8 = 0x01;
+
+/// Flag: the frame ends with zero padding, whose length follows the flags
+pub const FLAG_PADDED: u8 = 0x02;
+
+/// Flag: the content was padded with zeros to a multiple of a slot size
+/// before compression, described by the two fields after the padding field
+pub const FLAG_SLOT_PADDED: u8 = 0x04;
+
+/// Flag: the header ends with a CRC32 of the decompressed content (u32 LE)
+pub const FLAG_CRC32: u8 = 0x08;
+
+/// Flag: the header ends with an xxHash3-64 of the decompressed content
+/// (u64 LE)
+pub const FLAG_XXH3: u8 = 0x10;
+
+/// Flag: distances up to 128 are stored in one byte, and the lowest bit of
+/// an offset's first byte marks the longer form (see
+/// `LZSS::with_short_offsets`)
+pub const FLAG_SHORT_OFFSETS: u8 = 0x20;
+
+/// Flag: the content went through a delta filter before compression,
+/// described by the fields after the checksum (see `filter::Delta`)
+pub const FLAG_DELTA: u8 = 0x40;
+
+/// Flag: the header records how far back the encoder's matches can reach
+/// (u32 LE, after the delta filter fields), so decoders know how much
+/// history to keep
+pub const FLAG_WINDOW_SIZE: u8 = 0x80;
+
+/// Integrity check of the decompressed content stored in a frame header
+///
+/// Strict decoding (`decompress_iter`, `decompress_into`) verifies it;
+/// `decompress` and `validate` don't.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Checksum {
+    /// No checksum
+    #[default]
+    None,
+    /// CRC32 (IEEE), as used by zlib and zip
+    Crc32,
+    /// xxHash3, 64-bit
+    Xxh3,
+}
+
+impl Checksum {
+    /// Header flag selecting this checksum
+    fn flag(self) -> u8 {
+        match self {
+            Checksum::None => 0,
+            Checksum::Crc32 => FLAG_CRC32,
+            Checksum::Xxh3 => FLAG_XXH3,
+        }
+    }
+
+    /// Checksum selected by header flags, or `None` if both are set
+    fn from_flags(flags: u8) -> Option<Self> {
+        match (flags & FLAG_CRC32 != 0, flags & FLAG_XXH3 != 0) {
+            (false, false) => Some(Checksum::None),
+            (true, false) => Some(Checksum::Crc32),
+            (false, true) => Some(Checksum::Xxh3),
+            (true, true) => None,
+        }
+    }
+
+    /// Size of the checksum field in bytes
+    fn size(self) -> usize {
+        match self {
+            Checksum::None => 0,
+            Checksum::Crc32 => 4,
+            Checksum::Xxh3 => 8,
+        }
+    }
+
+    /// Checksum of `data` (0 for `Checksum::None`)
+    pub fn compute(self, data: &[u8]) -> u64 {
+        let mut hasher = ChecksumHasher::new(self);
+        hasher.update(data);
+        hasher.finish()
+    }
+}
+
+/// Incremental computation of a `Checksum`
+pub(crate) enum ChecksumHasher {
+    None,
+    Crc32(crc32fast::Hasher),
+    Xxh3(Box<Xxh3>),
+}
+
+impl ChecksumHasher {
+    pub(crate) fn new(checksum: Checksum) -> Self {
+        match checksum {
+            Checksum::None => ChecksumHasher::None,
+            Checksum::Crc32 => ChecksumHasher::Crc32(crc32fast::Hasher::new()),
+            Checksum::Xxh3 => ChecksumHasher::Xxh3(Box::new(Xxh3::new())),
+        }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match self {
+            ChecksumHasher::None => {}
+            ChecksumHasher::Crc32(hasher) => hasher.update(data),
+            ChecksumHasher::Xxh3(hasher) => hasher.update(data),
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        match self {
+            ChecksumHasher::None => 0,
+            ChecksumHasher::Crc32(hasher) => hasher.clone().finalize() as u64,
+            ChecksumHasher::Xxh3(hasher) => hasher.digest(),
+        }
+    }
+}
+
+/// Options for writing frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameOptions {
+    /// Pad the frame with zeros to a multiple of this many bytes (1 = no
+    /// padding), for platforms reading compressed data via aligned DMA or
+    /// whole sectors
+    pub alignment: usize,
+    /// Pad the decompressed content with zeros to a multiple of this many
+    /// bytes (1 = no padding), so loaders can place assets in fixed-size
+    /// flash/ROM slots directly; the header records the real size
+    pub slot_size: usize,
+    /// Checksum of the decompressed content to store in the header
+    pub checksum: Checksum,
+    /// Delta filter applied to the content before compression and undone
+    /// by decoders
+    pub delta: Option<Delta>,
+}
+
+impl Default for FrameOptions {
+    fn default() -> Self {
+        FrameOptions { alignment: 1, slot_size: 1, checksum: Checksum::None, delta: None }
+    }
+}
+
+impl FrameOptions {
+    /// Pad frames to a multiple of `alignment` bytes (e.g. 16, 64 or 4096)
+    pub fn with_alignment(mut self, alignment: usize) -> Self {
+        self.alignment = alignment.max(1);
+        self
+    }
+
+    /// Pad decompressed content to a multiple of `slot_size` bytes
+    pub fn with_slot_size(mut self, slot_size: usize) -> Self {
+        self.slot_size = slot_size.max(1);
+        self
+    }
+
+    /// Store a checksum of the decompressed content
+    pub fn with_checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Delta-filter the content before compression (e.g. for PCM audio or
+    /// texture rows)
+    pub fn with_delta(mut self, delta: Delta) -> Self {
+        self.delta = Some(delta);
+        self
+    }
+}
+
+/// Parameters recorded in a frame header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    /// Frame layout version
+    pub version: u8,
+    /// Minimum match length used by the encoder
+    pub min_match_length: usize,
+    /// Number of bytes used to store match distances (2, or 3 for LDM)
+    pub offset_bytes: usize,
+    /// Width of the match length field
+    pub length_field: LengthField,
+    /// Format flags (`FLAG_*`)
+    pub flags: u8,
+    /// Number of zero bytes padding the end of the frame (`FLAG_PADDED`)
+    pub padding: usize,
+    /// Slot size the content was padded to (`FLAG_SLOT_PADDED`, else 1)
+    pub slot_size: usize,
+    /// Number of zero bytes padding the end of the decompressed content
+    /// (`FLAG_SLOT_PADDED`)
+    pub content_padding: usize,
+    /// Checksum of the decompressed content, of the kind selected by the
+    /// flags (`FLAG_CRC32` or `FLAG_XXH3`, else 0)
+    pub checksum: u64,
+    /// Delta filter to undo after decompression (`FLAG_DELTA`)
+    pub delta: Option<Delta>,
+    /// Farthest back a match can reach (`FLAG_WINDOW_SIZE`); strict
+    /// decoding rejects matches reaching farther
+    pub window_size: Option<usize>,
+    /// ID of the preset dictionary the content was compressed against
+    /// (version 3 frames)
+    pub dictionary_id: Option<u32>,
+}
+
+impl FrameHeader {
+    /// Parse the header at the start of `input`
+    ///
+    /// Returns `None` if the input doesn't start with a valid frame header.
+    pub fn parse(input: &[u8]) -> Option<Self> {
+        if input.len() < FRAME_V1_HEADER_SIZE || input[..4] != FRAME_MAGIC {
+            return None;
+        }
+
+        let mut header = FrameHeader {
+            version: input[4],
+            min_match_length: input[5] as usize,
+            offset_bytes: input[6] as usize,
+            length_field: LengthField::OneByte,
+            flags: 0,
+            padding: 0,
+            slot_size: 1,
+            content_padding: 0,
+            checksum: 0,
+            delta: None,
+            window_size: None,
+            dictionary_id: None,
+        };
+
+        match header.version {
+            1 => {}
+            FRAME_VERSION | FRAME_DICTIONARY_VERSION => {
+                if input.len() < FRAME_HEADER_SIZE {
+                    return None;
+                }
+                header.length_field = LengthField::from_code(input[7])?;
+                header.flags = input[8];
+
+                // Optional fields
+                let mut pos = FRAME_HEADER_SIZE;
+                let mut read_le = |bytes: usize| {
+                    let field = input.get(pos..pos + bytes)?;
+                    pos += bytes;
+                    Some(field.iter().rev().fold(0u64, |value, &byte| value << 8 | byte as u64))
+                };
+                if header.flags & FLAG_PADDED != 0 {
+                    header.padding = read_le(4)? as usize;
+                }
+                if header.flags & FLAG_SLOT_PADDED != 0 {
+                    header.slot_size = read_le(4)? as usize;
+                    header.content_padding = read_le(4)? as usize;
+                }
+                let checksum = Checksum::from_flags(header.flags)?;
+                if checksum != Checksum::None {
+                    header.checksum = read_le(checksum.size())?;
+                }
+                if header.flags & FLAG_DELTA != 0 {
+                    let order = read_le(1)? as u8;
+                    header.delta = Some(Delta::from_parts(read_le(2)? as usize, order)?);
+                }
+                if header.flags & FLAG_WINDOW_SIZE != 0 {
+                    header.window_size = Some(read_le(4)? as usize);
+                }
+                if header.version == FRAME_DICTIONARY_VERSION {
+                    header.dictionary_id = Some(read_le(4)? as u32);
+                }
+            }
+            _ => return None,
+        }
+
+        // Every flag bit is defined; new flags need a new frame version
+        if header.min_match_length == 0 || !(2..=3).contains(&header.offset_bytes) {
+            return None;
+        }
+
+        Some(header)
+    }
+
+    /// Size of this header in bytes, which depends on its version and
+    /// optional fields
+    pub fn size(&self) -> usize {
+        if self.version == 1 {
+            return FRAME_V1_HEADER_SIZE;
+        }
+        let mut size = FRAME_HEADER_SIZE;
+        if self.flags & FLAG_PADDED != 0 {
+            size += 4;
+        }
+        if self.flags & FLAG_SLOT_PADDED != 0 {
+            size += 8;
+        }
+        if self.flags & FLAG_DELTA != 0 {
+            size += 3;
+        }
+        if self.flags & FLAG_WINDOW_SIZE != 0 {
+            size += 4;
+        }
+        if self.dictionary_id.is_some() {
+            size += 4;
+        }
+        size + self.checksum_kind().size()
+    }
+
+    /// Kind of checksum stored in the header
+    pub fn checksum_kind(&self) -> Checksum {
+        Checksum::from_flags(self.flags).unwrap_or_default()
+    }
+
+    /// Check decompressed content against the stored checksum (if any)
+    pub fn verify(&self, content: &[u8]) -> Result<(), LzssError> {
+        self.check_digest(self.checksum_kind().compute(content))
+    }
+
+    /// Compare a digest computed with `checksum_kind()` to the stored one
+    pub(crate) fn check_digest(&self, actual: u64) -> Result<(), LzssError> {
+        if self.checksum_kind() == Checksum::None || actual == self.checksum {
+            Ok(())
+        } else {
+            Err(LzssError::ChecksumMismatch { expected: self.checksum, actual })
+        }
+    }
+
+    /// The compressed stream of the frame starting at `input`, without the
+    /// header and padding
+    pub fn stream<'a>(&self, input: &'a [u8]) -> &'a [u8] {
+        let end = input.len().saturating_sub(self.padding).max(self.size());
+        &input[self.size()..end]
+    }
+
+    /// Serialize the header in the current layout (version 3 if it records
+    /// a dictionary ID, else version 2)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.size());
+        bytes.extend_from_slice(&FRAME_MAGIC);
+        bytes.push(if self.dictionary_id.is_some() { FRAME_DICTIONARY_VERSION } else { FRAME_VERSION });
+        bytes.push(self.min_match_length as u8);
+        bytes.push(self.offset_bytes as u8);
+        bytes.push(self.length_field.to_code());
+        bytes.push(self.flags);
+        if self.flags & FLAG_PADDED != 0 {
+            bytes.extend_from_slice(&(self.padding as u32).to_le_bytes());
+        }
+        if self.flags & FLAG_SLOT_PADDED != 0 {
+            bytes.extend_from_slice(&(self.slot_size as u32).to_le_bytes());
+            bytes.extend_from_slice(&(self.content_padding as u32).to_le_bytes());
+        }
+        let checksum = self.checksum.to_le_bytes();
+        bytes.extend_from_slice(&checksum[..self.checksum_kind().size()]);
+        if let Some(delta) = self.delta.filter(|_| self.flags & FLAG_DELTA != 0) {
+            bytes.push(delta.order());
+            bytes.extend_from_slice(&(delta.stride() as u16).to_le_bytes());
+        }
+        if self.flags & FLAG_WINDOW_SIZE != 0 {
+            bytes.extend_from_slice(&(self.window_size.unwrap_or(0) as u32).to_le_bytes());
+        }
+        if let Some(id) = self.dictionary_id {
+            bytes.extend_from_slice(&id.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Undo the content filters recorded in the header on the decoded
+    /// stream, in place
+    pub(crate) fn unfilter(&self, content: &mut [u8]) {
+        if let Some(delta) = self.delta {
+            DeltaDecoder::new(delta).undo(content);
+        }
+    }
+
+    /// Decoder undoing the delta filter chunk by chunk, if there is one
+    pub(crate) fn delta_decoder(&self) -> Option<DeltaDecoder> {
+        self.delta.map(DeltaDecoder::new)
+    }
+
+    /// Build a decoder configured from the header
+    pub fn decoder(&self) -> LZSS {
+        // The recorded window is checked by validation, not by the decoder
+        let lzss = LZSS::new(65535, self.min_match_length)
+            .with_length_field(self.length_field)
+            .with_distance_bias(self.flags & FLAG_DISTANCE_MINUS_ONE != 0)
+            .with_short_offsets(self.flags & FLAG_SHORT_OFFSETS != 0);
+        if self.offset_bytes == 3 {
+            lzss.with_long_distance_matching(crate::MAX_LONG_DISTANCE)
+        } else {
+            lzss
+        }
+    }
+}
+
+/// Check whether the input starts with a valid frame header
+pub fn is_frame(input: &[u8]) -> bool {
+    FrameHeader::parse(input).is_some()
+}
+
+/// Decompress a frame with the parameters in its header, without an
+/// `LZSS` instance
+///
+/// Anything but a frame is an `InvalidHeader` error: raw streams don't
+/// record their parameters, and decoding them with guessed ones could
+/// silently corrupt the output.
+pub fn decompress_auto(input: &[u8]) -> Result<Vec<u8>, LzssError> {
+    let header = FrameHeader::parse(input).ok_or(LzssError::InvalidHeader)?;
+    header.decoder().try_decompress(input)
+}
+
+impl LZSS {
+    /// Compress input into a self-describing frame
+    ///
+    /// The frame can be decompressed by `decompress` on any `LZSS`
+    /// instance, regardless of its configuration. Match distances are
+    /// stored minus one, so a 65536-byte window is fully reachable.
+    pub fn compress_framed(&self, input: &[u8]) -> Vec<u8> {
+        self.compress_framed_with(input, &FrameOptions::default())
+    }
+
+    /// Compress input into a self-describing frame with the given options
+    pub fn compress_framed_with(&self, input: &[u8], options: &FrameOptions) -> Vec<u8> {
+        self.compress_frame(input, options, None)
+    }
+
+    /// Compress input into a frame whose matches may reference `dictionary`
+    ///
+    /// The frame records the dictionary's ID rather than its content, so
+    /// it is decoded by a `dictionary::FrameDecoder` that has or can
+    /// resolve the dictionary; `try_decompress` reports it as a
+    /// `MissingDictionary` error.
+    pub fn compress_framed_with_dictionary(&self, dictionary: &Dictionary, input: &[u8]) -> Vec<u8> {
+        self.compress_frame(input, &FrameOptions::default(), Some(dictionary))
+    }
+
+    fn compress_frame(&self, input: &[u8], options: &FrameOptions, dictionary: Option<&Dictionary>) -> Vec<u8> {
+        let encoder = LZSS { ..*self }.with_distance_bias(true);
+        let mut header = FrameHeader {
+            version: FRAME_VERSION,
+            min_match_length: self.min_match_length,
+            offset_bytes: self.offset_bytes(),
+            length_field: self.length_field,
+            flags: FLAG_DISTANCE_MINUS_ONE,
+            padding: 0,
+            slot_size: 1,
+            content_padding: 0,
+            checksum: 0,
+            delta: None,
+            window_size: Some(encoder.match_reach()),
+            dictionary_id: dictionary.map(Dictionary::id),
+        };
+        header.flags |= FLAG_WINDOW_SIZE;
+        if self.short_offsets {
+            header.flags |= FLAG_SHORT_OFFSETS;
+        }
+
+        let mut padded = Vec::new();
+        let content = if options.slot_size > 1 {
+            header.flags |= FLAG_SLOT_PADDED;
+            header.slot_size = options.slot_size;
+            header.content_padding = input.len().next_multiple_of(options.slot_size) - input.len();
+
+            padded.reserve(input.len() + header.content_padding);
+            padded.extend_from_slice(input);
+            padded.resize(input.len() + header.content_padding, 0);
+            &padded[..]
+        } else {
+            input
+        };
+
+        // The checksum covers the content as decoders produce it, padding
+        // included
+        header.flags |= options.checksum.flag();
+        header.checksum = options.checksum.compute(content);
+        let filtered;
+        let content = match options.delta {
+            Some(delta) => {
+                header.flags |= FLAG_DELTA;
+                header.delta = Some(delta);
+                filtered = delta.encode(content);
+                &filtered[..]
+            }
+            None => content,
+        };
+        let stream = match dictionary {
+            Some(dictionary) => encoder.compress_with_dictionary(dictionary.data(), content),
+            None => encoder.compress(content),
+        };
+
+        if options.alignment > 1 {
+            header.flags |= FLAG_PADDED;
+            let unpadded = header.size() + stream.len();
+            header.padding = unpadded.next_multiple_of(options.alignment) - unpadded;
+        }
+
+        let mut output = Vec::with_capacity(header.size() + stream.len() + header.padding);
+        output.extend_from_slice(&header.to_bytes());
+        output.extend_from_slice(&stream);
+        output.resize(output.len() + header.padding, 0);
+        output
+    }
+}
@@ -0,0 +1,134 @@
+//! File helpers
+//!
+//! Outputs are written to a temporary file in the destination directory and
+//! renamed into place only once complete, so an interrupted run never leaves
+//! a truncated file that looks like a valid output.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::LZSS;
+
+/// Options for writing output files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Flush the file (and on Unix, its directory entry) to disk before
+    /// returning, so the output also survives a power loss
+    pub fsync: bool,
+}
+
+impl WriteOptions {
+    /// Set whether outputs are flushed to disk before returning
+    pub fn with_fsync(mut self, fsync: bool) -> Self {
+        self.fsync = fsync;
+        self
+    }
+}
+
+/// Write `data` to `path` atomically: readers see either the old file or
+/// the complete new one
+pub fn write_atomic(path: impl AsRef<Path>, data: &[u8], options: &WriteOptions) -> io::Result<()> {
+    write_atomic_with(path.as_ref(), options, |file| file.write_all(data))
+}
+
+/// Replace `path` with what `write` writes to a temporary file, as in
+/// `write_atomic`
+fn write_atomic_with(
+    path: &Path,
+    options: &WriteOptions,
+    write: impl FnOnce(&mut File) -> io::Result<()>,
+) -> io::Result<()> {
+    let (mut file, temp) = create_temp(path)?;
+
+    let result = (|| {
+        write(&mut file)?;
+        if options.fsync {
+            file.sync_all()?;
+        }
+        drop(file);
+        fs::rename(&temp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+        return result;
+    }
+
+    if options.fsync {
+        sync_parent(path)?;
+    }
+    Ok(())
+}
+
+/// Counter making temporary file names unique between calls in one process
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Create a new temporary file next to `path`, so the final rename stays on
+/// one filesystem
+///
+/// Names that are already taken, e.g. left behind by a crashed process that
+/// had the same ID, are skipped.
+fn create_temp(path: &Path) -> io::Result<(File, PathBuf)> {
+    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    loop {
+        let count = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let temp = path.with_file_name(format!(".{}.{}.{}.tmp", name, std::process::id(), count));
+        match OpenOptions::new().write(true).create_new(true).open(&temp) {
+            Ok(file) => return Ok((file, temp)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Flush the directory entry created by the rename
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => File::open(parent)?.sync_all(),
+        _ => File::open(".")?.sync_all(),
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+impl LZSS {
+    /// Compress the file at `input` into a frame written atomically to
+    /// `output`
+    pub fn compress_file(
+        &self,
+        input: impl AsRef<Path>,
+        output: impl AsRef<Path>,
+        options: &WriteOptions,
+    ) -> io::Result<()> {
+        let data = fs::read(input)?;
+        write_atomic(output, &self.compress_framed(&data), options)
+    }
+
+    /// Decompress the file at `input`, writing the result atomically to
+    /// `output`
+    ///
+    /// Corrupt input is reported as `InvalidData` and leaves `output`
+    /// untouched.
+    pub fn decompress_file(
+        &self,
+        input: impl AsRef<Path>,
+        output: impl AsRef<Path>,
+        options: &WriteOptions,
+    ) -> io::Result<()> {
+        let data = fs::read(input)?;
+        // Chunks go to the temporary file as they are decoded, so the
+        // output is never held in memory as a whole
+        write_atomic_with(output.as_ref(), options, |file| {
+            for chunk in self.decompress_iter(&data) {
+                let chunk = chunk.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                file.write_all(&chunk)?;
+            }
+            Ok(())
+        })
+    }
+}
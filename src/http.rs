@@ -0,0 +1,186 @@
+//! HTTP content-coding adapter
+//!
+//! `Encoder` and `Decoder` wrap an `http_body::Body` and compress or
+//! decompress it as one frame, so services that both use this crate can
+//! exchange bodies with `Content-Encoding: x-rlzs`. They work with tower's
+//! `MapRequestBody`/`MapResponseBody` layers like any other body.
+//!
+//! Frames are compressed in one piece, so the whole body is buffered before
+//! the single output data frame is produced. Trailers are passed through.
+
+use std::fmt;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use bytes::{Buf, Bytes};
+use http::HeaderMap;
+use http_body::{Body, Frame};
+
+use crate::{LzssError, LZSS};
+
+/// Content coding name for bodies produced by `Encoder`
+pub const CONTENT_CODING: &str = "x-rlzs";
+
+/// Errors from a body wrapped by `Decoder`
+#[derive(Debug)]
+pub enum BodyError<E> {
+    /// The wrapped body failed
+    Body(E),
+    /// The body is not a valid compressed frame
+    Decompress(LzssError),
+}
+
+impl<E: fmt::Display> fmt::Display for BodyError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BodyError::Body(e) => write!(f, "body failed: {}", e),
+            BodyError::Decompress(e) => write!(f, "decompression failed: {}", e),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for BodyError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BodyError::Body(e) => Some(e),
+            BodyError::Decompress(e) => Some(e),
+        }
+    }
+}
+
+/// Progress of a buffered body
+enum State {
+    /// Still collecting data from the wrapped body
+    Reading,
+    /// The coded data has been produced; trailers (if any) are next
+    Trailers,
+    /// Nothing left to produce
+    Done,
+}
+
+/// Wrapped body together with the data and trailers collected so far
+struct Buffered<B> {
+    body: Pin<Box<B>>,
+    buffer: Vec<u8>,
+    trailers: Option<HeaderMap>,
+    state: State,
+}
+
+impl<B: Body> Buffered<B> {
+    fn new(body: B) -> Self {
+        Buffered { body: Box::pin(body), buffer: Vec::new(), trailers: None, state: State::Reading }
+    }
+
+    /// Collect the wrapped body, then yield `code` applied to all of its
+    /// data followed by its trailers
+    fn poll_frame<E>(
+        &mut self,
+        cx: &mut Context<'_>,
+        code: impl FnOnce(&[u8]) -> Result<Vec<u8>, E>,
+        body_error: impl FnOnce(B::Error) -> E,
+    ) -> Poll<Option<Result<Frame<Bytes>, E>>> {
+        loop {
+            match self.state {
+                State::Reading => {}
+                State::Trailers => {
+                    self.state = State::Done;
+                    return Poll::Ready(self.trailers.take().map(|trailers| Ok(Frame::trailers(trailers))));
+                }
+                State::Done => return Poll::Ready(None),
+            }
+
+            match ready!(self.body.as_mut().poll_frame(cx)) {
+                Some(Ok(frame)) => match frame.into_data() {
+                    Ok(mut data) => {
+                        while data.has_remaining() {
+                            let chunk = data.chunk();
+                            let len = chunk.len();
+                            self.buffer.extend_from_slice(chunk);
+                            data.advance(len);
+                        }
+                    }
+                    Err(frame) => {
+                        if let Ok(trailers) = frame.into_trailers() {
+                            self.trailers = Some(trailers);
+                        }
+                    }
+                },
+                Some(Err(e)) => {
+                    self.state = State::Done;
+                    return Poll::Ready(Some(Err(body_error(e))));
+                }
+                None => {
+                    self.state = State::Trailers;
+                    let buffer = std::mem::take(&mut self.buffer);
+                    return Poll::Ready(Some(code(&buffer).map(|coded| Frame::data(Bytes::from(coded)))));
+                }
+            }
+        }
+    }
+}
+
+/// Body that compresses the wrapped body into a frame
+pub struct Encoder<B> {
+    inner: Buffered<B>,
+    lzss: LZSS,
+}
+
+impl<B: Body> Encoder<B> {
+    /// Compress `body` with the given parameters, which the frame header
+    /// records for the `Decoder`
+    pub fn new(body: B, lzss: LZSS) -> Self {
+        Encoder { inner: Buffered::new(body), lzss }
+    }
+}
+
+impl<B: Body> Body for Encoder<B> {
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, B::Error>>> {
+        let this = &mut *self;
+        let lzss = &this.lzss;
+        this.inner.poll_frame(cx, |data| Ok(lzss.compress_framed(data)), |e| e)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        matches!(self.inner.state, State::Done)
+    }
+}
+
+/// Body that decompresses a frame produced by `Encoder`
+pub struct Decoder<B> {
+    inner: Buffered<B>,
+}
+
+impl<B: Body> Decoder<B> {
+    /// Decompress `body`, reading the parameters from its frame header
+    pub fn new(body: B) -> Self {
+        Decoder { inner: Buffered::new(body) }
+    }
+}
+
+impl<B: Body> Body for Decoder<B> {
+    type Data = Bytes;
+    type Error = BodyError<B::Error>;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, BodyError<B::Error>>>> {
+        self.inner.poll_frame(cx, decode, BodyError::Body)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        matches!(self.inner.state, State::Done)
+    }
+}
+
+/// Strictly decode a frame, rejecting corrupt input
+fn decode<E>(input: &[u8]) -> Result<Vec<u8>, BodyError<E>> {
+    let mut output = Vec::new();
+    for chunk in LZSS::new(4096, 3).decompress_iter(input) {
+        output.extend_from_slice(&chunk.map_err(BodyError::Decompress)?);
+    }
+    Ok(output)
+}
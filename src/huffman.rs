@@ -0,0 +1,368 @@
+//! Huffman-coded streams (LZHUF-style)
+//!
+//! The LZSS tokens are entropy coded instead of stored as whole bytes:
+//! literals and match lengths share one static Huffman code, as in
+//! deflate, and distances have their own. Lengths and distances are
+//! coded by bucket (their top two bits), followed by the rest of their
+//! bits verbatim.
+//!
+//! ```text
+//! size (u32 LE) | min match | literal/length code count (u16 LE)
+//!     | distance code count | code lengths (4 bits each, high nibble first)
+//!     | Huffman bitstream (MSB first)
+//! ```
+//!
+//! The stream carries everything needed to decode it, so it decompresses
+//! with any `LZSS`.
+
+use alloc::collections::BinaryHeap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use crate::{copy, LengthField, LzssError, LZSS};
+
+/// Buckets of a 32-bit value: 0, 1, then two per bit position
+const BUCKETS: usize = 64;
+
+/// Literal/length symbols: 256 literals, then the length buckets
+const LITLEN_SYMBOLS: usize = 256 + BUCKETS;
+
+/// Longest code, so code lengths fit in 4 bits
+const MAX_CODE_BITS: usize = 15;
+
+/// Size of the fixed part of the header
+const HEADER_SIZE: usize = 8;
+
+enum Token {
+    Literal(u8),
+    Match { distance: usize, length: usize },
+}
+
+impl LZSS {
+    /// Compress `input` into a Huffman-coded stream
+    ///
+    /// Matches are found as with `compress` (with an extended length field,
+    /// so long ones aren't split), then coded with Huffman codes built for
+    /// this input. Closes part of the ratio gap to deflate on text and
+    /// other data with skewed byte frequencies, at the cost of slower
+    /// compression and decompression. The code tables take up to about 200
+    /// bytes, so tiny inputs grow.
+    pub fn compress_huffman(&self, input: &[u8]) -> Vec<u8> {
+        let lzss = LZSS { ..*self }.with_length_field(LengthField::Extended);
+        let stream = lzss.compress(input);
+        let tokens: Vec<Token> = lzss.tokens(&stream).collect();
+
+        let mut litlen_freqs = [0u32; LITLEN_SYMBOLS];
+        let mut distance_freqs = [0u32; BUCKETS];
+        for token in &tokens {
+            match *token {
+                Token::Literal(byte) => litlen_freqs[byte as usize] += 1,
+                Token::Match { distance, length } => {
+                    litlen_freqs[256 + bucket((length - self.min_match_length) as u32).0] += 1;
+                    distance_freqs[bucket((distance - 1) as u32).0] += 1;
+                }
+            }
+        }
+        let litlen_lengths = code_lengths(&litlen_freqs);
+        let distance_lengths = code_lengths(&distance_freqs);
+        let litlen_count = used_symbols(&litlen_lengths);
+        let distance_count = used_symbols(&distance_lengths);
+
+        let mut output = Vec::with_capacity(HEADER_SIZE + LITLEN_SYMBOLS / 2 + stream.len());
+        output.extend_from_slice(&(input.len() as u32).to_le_bytes());
+        output.push(self.min_match_length as u8);
+        output.extend_from_slice(&(litlen_count as u16).to_le_bytes());
+        output.push(distance_count as u8);
+        let lengths: Vec<u8> = litlen_lengths[..litlen_count]
+            .iter()
+            .chain(&distance_lengths[..distance_count])
+            .copied()
+            .collect();
+        for pair in lengths.chunks(2) {
+            output.push(pair[0] << 4 | pair.get(1).copied().unwrap_or(0));
+        }
+
+        let litlen_codes = canonical_codes(&litlen_lengths);
+        let distance_codes = canonical_codes(&distance_lengths);
+        let mut writer = BitWriter { output, acc: 0, bits: 0 };
+        for token in tokens {
+            match token {
+                Token::Literal(byte) => writer.write(litlen_codes[byte as usize], litlen_lengths[byte as usize]),
+                Token::Match { distance, length } => {
+                    let (symbol, extra_bits, extra) = bucket((length - self.min_match_length) as u32);
+                    writer.write(litlen_codes[256 + symbol], litlen_lengths[256 + symbol]);
+                    writer.write(extra, extra_bits as u8);
+                    let (symbol, extra_bits, extra) = bucket((distance - 1) as u32);
+                    writer.write(distance_codes[symbol], distance_lengths[symbol]);
+                    writer.write(extra, extra_bits as u8);
+                }
+            }
+        }
+        writer.finish()
+    }
+
+    /// Decompress a stream from `compress_huffman`
+    ///
+    /// Code tables that no Huffman code matches are an `InvalidHeader`
+    /// error, bits that match no code an `InvalidCode` error.
+    pub fn decompress_huffman(&self, input: &[u8]) -> Result<Vec<u8>, LzssError> {
+        let header = input.get(..HEADER_SIZE).ok_or(LzssError::TruncatedInput)?;
+        let size = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let min_match = header[4] as usize;
+        let litlen_count = u16::from_le_bytes([header[5], header[6]]) as usize;
+        let distance_count = header[7] as usize;
+        if litlen_count > LITLEN_SYMBOLS || distance_count > BUCKETS {
+            return Err(LzssError::InvalidHeader);
+        }
+
+        let table_bytes = (litlen_count + distance_count).div_ceil(2);
+        let table = input.get(HEADER_SIZE..HEADER_SIZE + table_bytes).ok_or(LzssError::TruncatedInput)?;
+        let lengths: Vec<u8> = table.iter().flat_map(|&pair| [pair >> 4, pair & 0xF]).collect();
+        let litlen = HuffmanDecoder::new(&lengths[..litlen_count])?;
+        let distances = HuffmanDecoder::new(&lengths[litlen_count..litlen_count + distance_count])?;
+
+        let mut reader = BitReader { input, pos: HEADER_SIZE + table_bytes, bit: 0 };
+        // Don't trust the header with a huge allocation up front
+        let mut output = Vec::with_capacity(size.min(input.len().saturating_mul(8)));
+        while output.len() < size {
+            let symbol = litlen.decode(&mut reader)?;
+            if symbol < 256 {
+                output.push(symbol as u8);
+                continue;
+            }
+            let length = read_bucket(&mut reader, symbol - 256)?.saturating_add(min_match);
+            let symbol = distances.decode(&mut reader)?;
+            let distance = read_bucket(&mut reader, symbol)?.saturating_add(1);
+
+            if distance > output.len() {
+                return Err(LzssError::InvalidDistance { position: output.len(), distance });
+            }
+            if length > size - output.len() {
+                return Err(LzssError::SizeMismatch { expected: size, actual: output.len().saturating_add(length) });
+            }
+            copy::extend_match(&mut output, distance, length);
+        }
+        Ok(output)
+    }
+
+    /// Tokens of `stream`, a stream produced by this encoder
+    fn tokens<'a>(&'a self, stream: &'a [u8]) -> impl Iterator<Item = Token> + 'a {
+        let mut pos = 4;
+        let mut control_byte = 0u8;
+        let mut bit = 8;
+        core::iter::from_fn(move || {
+            if bit == 8 {
+                control_byte = *stream.get(pos)?;
+                pos += 1;
+                bit = 0;
+            }
+            let is_match = control_byte & (1 << bit) != 0;
+            bit += 1;
+            if !is_match {
+                let &byte = stream.get(pos)?;
+                pos += 1;
+                return Some(Token::Literal(byte));
+            }
+            let (distance, distance_bytes) = self.read_distance(stream, pos)?;
+            let (length, length_bytes) = self.length_field.read(stream, pos + distance_bytes)?;
+            pos += distance_bytes + length_bytes;
+            Some(Token::Match { distance, length: length + self.min_match_length })
+        })
+    }
+}
+
+/// Bucket of `value`, with the number of bits and value of the rest
+fn bucket(value: u32) -> (usize, u32, u32) {
+    if value < 2 {
+        return (value as usize, 0, 0);
+    }
+    let top = 31 - value.leading_zeros();
+    let extra_bits = top - 1;
+    let symbol = 2 * top as usize + (value >> extra_bits & 1) as usize;
+    (symbol, extra_bits, value & ((1 << extra_bits) - 1))
+}
+
+/// Read the rest of a value in bucket `symbol`
+fn read_bucket(reader: &mut BitReader, symbol: usize) -> Result<usize, LzssError> {
+    if symbol < 2 {
+        return Ok(symbol);
+    }
+    let extra_bits = (symbol / 2 - 1) as u32;
+    let base = (2 | (symbol & 1)) << extra_bits;
+    Ok(base + reader.read(extra_bits)? as usize)
+}
+
+/// Number of symbols up to the last one with a code
+fn used_symbols(lengths: &[u8]) -> usize {
+    lengths.iter().rposition(|&length| length != 0).map_or(0, |last| last + 1)
+}
+
+/// Huffman code lengths for `freqs`, at most `MAX_CODE_BITS` long
+///
+/// Frequencies are halved until the tree is shallow enough; the codes get
+/// slightly worse, but only for inputs where some symbols are rarer than
+/// 1 in 2^15.
+fn code_lengths(freqs: &[u32]) -> Vec<u8> {
+    let mut lengths = vec![0u8; freqs.len()];
+    let used: Vec<usize> = (0..freqs.len()).filter(|&symbol| freqs[symbol] > 0).collect();
+    match used[..] {
+        [] => return lengths,
+        [only] => {
+            // A single symbol still needs a 1-bit code
+            lengths[only] = 1;
+            return lengths;
+        }
+        _ => {}
+    }
+
+    let mut weights: Vec<u64> = used.iter().map(|&symbol| u64::from(freqs[symbol])).collect();
+    loop {
+        // Leaves first, then the internal nodes as they are merged
+        let mut parent = vec![0usize; 2 * used.len()];
+        let mut heap: BinaryHeap<Reverse<(u64, usize)>> =
+            weights.iter().enumerate().map(|(node, &weight)| Reverse((weight, node))).collect();
+        let mut next = used.len();
+        while let (Some(Reverse((a, left))), Some(Reverse((b, right)))) = (heap.pop(), heap.pop()) {
+            parent[left] = next;
+            parent[right] = next;
+            heap.push(Reverse((a + b, next)));
+            next += 1;
+        }
+
+        let root = next - 1;
+        let mut depth = vec![0usize; next];
+        for node in (0..root).rev() {
+            depth[node] = depth[parent[node]] + 1;
+        }
+        if depth[..used.len()].iter().all(|&d| d <= MAX_CODE_BITS) {
+            for (&symbol, &d) in used.iter().zip(&depth) {
+                lengths[symbol] = d as u8;
+            }
+            return lengths;
+        }
+        for weight in &mut weights {
+            *weight = (*weight >> 1).max(1);
+        }
+    }
+}
+
+/// Canonical codes for `lengths`: shorter codes first, then by symbol
+fn canonical_codes(lengths: &[u8]) -> Vec<u32> {
+    let mut counts = [0u32; MAX_CODE_BITS + 1];
+    for &length in lengths {
+        counts[length as usize] += 1;
+    }
+    counts[0] = 0;
+    let mut next = [0u32; MAX_CODE_BITS + 1];
+    for bits in 1..=MAX_CODE_BITS {
+        next[bits] = (next[bits - 1] + counts[bits - 1]) << 1;
+    }
+    lengths
+        .iter()
+        .map(|&length| {
+            let code = next[length as usize];
+            next[length as usize] += 1;
+            code
+        })
+        .collect()
+}
+
+/// Canonical Huffman decoder, one bit at a time
+struct HuffmanDecoder {
+    /// Number of codes of each length
+    counts: [u32; MAX_CODE_BITS + 1],
+    /// Symbols in code order
+    symbols: Vec<usize>,
+}
+
+impl HuffmanDecoder {
+    fn new(lengths: &[u8]) -> Result<Self, LzssError> {
+        let mut counts = [0u32; MAX_CODE_BITS + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        // More codes of a length than the shorter ones leave room for
+        // can't form a prefix code
+        let mut left = 1i64;
+        for &count in &counts[1..] {
+            left = (left << 1) - i64::from(count);
+            if left < 0 {
+                return Err(LzssError::InvalidHeader);
+            }
+        }
+
+        let mut symbols = Vec::with_capacity(lengths.len());
+        for bits in 1..=MAX_CODE_BITS as u8 {
+            symbols.extend((0..lengths.len()).filter(|&symbol| lengths[symbol] == bits));
+        }
+        Ok(HuffmanDecoder { counts, symbols })
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<usize, LzssError> {
+        let mut code = 0;
+        let mut first = 0;
+        let mut index = 0;
+        for &count in &self.counts[1..] {
+            code |= reader.read(1)?;
+            if code < first + count {
+                return Ok(self.symbols[index + (code - first) as usize]);
+            }
+            index += count as usize;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(LzssError::InvalidCode)
+    }
+}
+
+struct BitWriter {
+    output: Vec<u8>,
+    acc: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    /// Append the low `bits` bits of `value` (at most 32), MSB first
+    fn write(&mut self, value: u32, bits: u8) {
+        self.acc = self.acc << bits | u64::from(value);
+        self.bits += u32::from(bits);
+        while self.bits >= 8 {
+            self.bits -= 8;
+            self.output.push((self.acc >> self.bits) as u8);
+        }
+        self.acc &= (1 << self.bits) - 1;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.output.push((self.acc << (8 - self.bits)) as u8);
+        }
+        self.output
+    }
+}
+
+struct BitReader<'a> {
+    input: &'a [u8],
+    pos: usize,
+    /// Bits of `input[pos]` already read
+    bit: u32,
+}
+
+impl BitReader<'_> {
+    /// Read `bits` bits (at most 32), MSB first
+    fn read(&mut self, bits: u32) -> Result<u32, LzssError> {
+        let mut value = 0u32;
+        for _ in 0..bits {
+            let &byte = self.input.get(self.pos).ok_or(LzssError::TruncatedInput)?;
+            value = value << 1 | u32::from(byte >> (7 - self.bit) & 1);
+            self.bit += 1;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.pos += 1;
+            }
+        }
+        Ok(value)
+    }
+}
@@ -0,0 +1,225 @@
+//! Lazy decoding in fixed-size chunks
+//!
+//! `LZSS::decompress_iter` decodes on demand, keeping only the part of the
+//! output that later matches can still reference, so large streams can be
+//! fed to parsers or hashers chunk by chunk without buffering everything.
+
+use alloc::vec::Vec;
+
+use crate::filter::DeltaDecoder;
+use crate::frame::{ChecksumHasher, FrameHeader};
+use crate::{LzssError, LZSS};
+
+/// Default size of the chunks yielded by `decompress_iter`
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Iterator over the decoded chunks of a compressed stream
+///
+/// Every chunk except the last holds exactly the configured chunk size. The
+/// first error ends the iteration.
+pub struct DecompressIter<'a> {
+    input: &'a [u8],
+    pos: usize,
+    /// Codec configuration of the stream
+    decoder: LZSS,
+    chunk_size: usize,
+    /// Size recorded in the stream header
+    original_size: usize,
+    /// Bytes decoded so far
+    produced: usize,
+    /// Most recent output, trimmed to the largest representable distance
+    history: Vec<u8>,
+    history_limit: usize,
+    control_byte: u8,
+    /// Next bit of the control byte (8 = read a new control byte)
+    bit: u32,
+    /// Distance and remaining length of a match spanning chunks
+    pending_match: Option<(usize, usize)>,
+    /// Frame header and running checksum of the output, for frames
+    checksum: Option<(FrameHeader, ChecksumHasher)>,
+    /// Undoes the frame's delta filter on each chunk
+    delta: Option<DeltaDecoder>,
+    done: bool,
+}
+
+impl<'a> DecompressIter<'a> {
+    fn new(lzss: &LZSS, input: &'a [u8]) -> Self {
+        // Frames carry their own parameters
+        let (stream, decoder, checksum, delta) = match FrameHeader::parse(input) {
+            Some(header) => {
+                let hasher = ChecksumHasher::new(header.checksum_kind());
+                (header.stream(input), header.decoder(), Some((header, hasher)), header.delta_decoder())
+            }
+            None => (input, LZSS { ..*lzss }, None, None),
+        };
+        // Frames that record their window need no more history than that
+        let window_size = checksum.as_ref().and_then(|(header, _)| header.window_size);
+
+        let mut iter = DecompressIter {
+            input: stream,
+            pos: 4,
+            history_limit: window_size.map_or(decoder.max_distance(), |window| window.min(decoder.max_distance())),
+            decoder,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            original_size: 0,
+            produced: 0,
+            history: Vec::new(),
+            control_byte: 0,
+            bit: 8,
+            pending_match: None,
+            checksum,
+            delta,
+            done: stream.is_empty(), // Empty input compresses to nothing
+        };
+
+        if stream.len() >= 4 {
+            iter.original_size = u32::from_le_bytes([stream[0], stream[1], stream[2], stream[3]]) as usize;
+        }
+        iter
+    }
+
+    /// Set the size of the chunks yielded from now on
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Uncompressed size recorded in the stream header
+    pub fn original_size(&self) -> usize {
+        self.original_size
+    }
+
+    /// Copy up to `limit` bytes of a match into `chunk`, returning how many
+    /// are left
+    fn copy_match(&mut self, chunk: &mut Vec<u8>, distance: usize, length: usize, limit: usize) -> usize {
+        let count = length.min(limit - chunk.len());
+        for _ in 0..count {
+            let byte = if distance <= chunk.len() {
+                chunk[chunk.len() - distance]
+            } else {
+                self.history[self.history.len() - (distance - chunk.len())]
+            };
+            chunk.push(byte);
+        }
+        length - count
+    }
+
+    /// Decode the next chunk of at most `chunk_size` bytes
+    fn decode_chunk(&mut self) -> Result<Vec<u8>, LzssError> {
+        if self.input.len() < 5 {
+            return Err(LzssError::TruncatedInput);
+        }
+
+        let limit = self.chunk_size.min(self.original_size - self.produced);
+        let mut chunk = Vec::with_capacity(limit);
+
+        if let Some((distance, length)) = self.pending_match.take() {
+            let left = self.copy_match(&mut chunk, distance, length, limit);
+            if left > 0 {
+                self.pending_match = Some((distance, left));
+            }
+        }
+
+        while chunk.len() < limit {
+            if self.bit == 8 {
+                if self.pos >= self.input.len() {
+                    return Err(LzssError::SizeMismatch {
+                        expected: self.original_size,
+                        actual: self.produced + chunk.len(),
+                    });
+                }
+                self.control_byte = self.input[self.pos];
+                self.pos += 1;
+                self.bit = 0;
+            }
+
+            let is_match = self.control_byte & (1 << self.bit) != 0;
+            self.bit += 1;
+
+            if is_match {
+                let (distance, distance_bytes) = self.decoder
+                    .read_distance(self.input, self.pos)
+                    .ok_or(LzssError::TruncatedInput)?;
+                let (length, length_bytes) = self.decoder.length_field
+                    .read(self.input, self.pos + distance_bytes)
+                    .ok_or(LzssError::TruncatedInput)?;
+
+                let length = length.saturating_add(self.decoder.min_match_length);
+                self.pos += distance_bytes + length_bytes;
+
+                let position = self.produced + chunk.len();
+                // History only goes back `history_limit` bytes, so matches
+                // reaching past it can't be copied
+                if distance == 0
+                    || distance > position
+                    || distance > self.history_limit
+                    || distance > self.history.len() + chunk.len()
+                {
+                    return Err(LzssError::InvalidDistance { position, distance });
+                }
+
+                // The encoder never emits matches past the end of the data
+                let length = length.min(self.original_size - position);
+                let left = self.copy_match(&mut chunk, distance, length, limit);
+                if left > 0 {
+                    self.pending_match = Some((distance, left));
+                }
+            } else {
+                if self.pos >= self.input.len() {
+                    return Err(LzssError::TruncatedInput);
+                }
+                chunk.push(self.input[self.pos]);
+                self.pos += 1;
+            }
+        }
+
+        self.produced += chunk.len();
+        // Matches refer to the data as compressed, before the filter is
+        // undone
+        self.history.extend_from_slice(&chunk);
+        if self.history.len() > self.history_limit {
+            let excess = self.history.len() - self.history_limit;
+            self.history.drain(..excess);
+        }
+        if let Some(delta) = &mut self.delta {
+            delta.undo(&mut chunk);
+        }
+        if let Some((header, hasher)) = &mut self.checksum {
+            hasher.update(&chunk);
+            if self.produced == self.original_size {
+                header.check_digest(hasher.finish())?;
+            }
+        }
+
+        Ok(chunk)
+    }
+}
+
+impl Iterator for DecompressIter<'_> {
+    type Item = Result<Vec<u8>, LzssError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.decode_chunk();
+        if result.is_err() || self.produced == self.original_size {
+            self.done = true;
+        }
+        Some(result)
+    }
+}
+
+impl LZSS {
+    /// Decode lazily, yielding the output in chunks of `DEFAULT_CHUNK_SIZE`
+    /// bytes (see `DecompressIter::with_chunk_size`)
+    ///
+    /// Unlike `decompress`, corruption is reported: the iterator yields an
+    /// error and stops instead of skipping bad references or padding the
+    /// output. Frames are decoded with the parameters in their header, and
+    /// a checksum mismatch is reported in place of the last chunk.
+    pub fn decompress_iter<'a>(&self, input: &'a [u8]) -> DecompressIter<'a> {
+        DecompressIter::new(self, input)
+    }
+}
@@ -0,0 +1,100 @@
+//! Long-distance match finder
+//!
+//! Keeps a coarse hash table of 32-byte sequences sampled every
+//! `LDM_STRIDE` bytes across a large history (1 MiB and up). Each bucket
+//! only remembers the most recent position, so memory stays fixed no
+//! matter how far back the history reaches.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Length of the hashed sequences, and the shortest match the LDM reports
+pub(crate) const LDM_MIN_MATCH: usize = 32;
+
+/// Distance between sampled positions. Any duplicated region at least
+/// `LDM_MIN_MATCH + LDM_STRIDE` bytes long is guaranteed to be found.
+const LDM_STRIDE: usize = 16;
+
+/// Number of hash table bits (64K buckets, or 1K in `small-target` builds)
+const LDM_HASH_BITS: u32 = if cfg!(feature = "small-target") { 10 } else { 16 };
+
+pub(crate) struct LongDistanceMatcher {
+    history_size: usize,
+    table: Vec<usize>,
+    // Next sampled position that hasn't been inserted yet
+    next_insert: usize,
+}
+
+impl LongDistanceMatcher {
+    pub(crate) fn new(history_size: usize) -> Self {
+        LongDistanceMatcher {
+            history_size,
+            // usize::MAX marks an empty bucket
+            table: vec![usize::MAX; 1 << LDM_HASH_BITS],
+            next_insert: 0,
+        }
+    }
+
+    /// Empty the table for new input, reusing its memory
+    pub(crate) fn reset(&mut self, history_size: usize) {
+        self.history_size = history_size;
+        self.table.fill(usize::MAX);
+        self.next_insert = 0;
+    }
+
+    /// Hash the `LDM_MIN_MATCH` bytes starting at `pos`
+    #[cfg(not(feature = "small-target"))]
+    fn hash(input: &[u8], pos: usize) -> usize {
+        let mut h = 0u64;
+        for chunk in input[pos..pos + LDM_MIN_MATCH].chunks_exact(8) {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(chunk);
+            h = (h ^ u64::from_le_bytes(word)).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        }
+        (h >> (64 - LDM_HASH_BITS)) as usize
+    }
+
+    /// Hash the `LDM_MIN_MATCH` bytes starting at `pos` with 32-bit words
+    #[cfg(feature = "small-target")]
+    fn hash(input: &[u8], pos: usize) -> usize {
+        let mut h = 0u32;
+        for chunk in input[pos..pos + LDM_MIN_MATCH].chunks_exact(4) {
+            let mut word = [0u8; 4];
+            word.copy_from_slice(chunk);
+            h = (h ^ u32::from_le_bytes(word)).wrapping_mul(0x9E37_79B1);
+        }
+        (h >> (32 - LDM_HASH_BITS)) as usize
+    }
+
+    /// Record all sampled positions before `pos`
+    pub(crate) fn insert_up_to(&mut self, input: &[u8], pos: usize) {
+        while self.next_insert < pos && self.next_insert + LDM_MIN_MATCH <= input.len() {
+            let h = Self::hash(input, self.next_insert);
+            self.table[h] = self.next_insert;
+            self.next_insert += LDM_STRIDE;
+        }
+    }
+
+    /// Look for a long match at `pos`, returning `(length, distance)`
+    pub(crate) fn find(&self, input: &[u8], pos: usize, max_len: usize) -> Option<(usize, usize)> {
+        if max_len < LDM_MIN_MATCH || pos + LDM_MIN_MATCH > input.len() {
+            return None;
+        }
+
+        let candidate = self.table[Self::hash(input, pos)];
+        if candidate == usize::MAX || candidate >= pos || pos - candidate > self.history_size {
+            return None;
+        }
+
+        let mut len = 0;
+        while len < max_len && input[candidate + len] == input[pos + len] {
+            len += 1;
+        }
+
+        if len >= LDM_MIN_MATCH {
+            Some((len, pos - candidate))
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Compression level presets
+//!
+//! Levels trade speed for ratio without having to pick window sizes and
+//! match finder settings by hand, like the levels of flate2 or zlib.
+
+/// Compression level from 1 (fastest) to 9 (best ratio)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CompressionLevel(u8);
+
+/// Encoder settings for one level
+pub(crate) struct LevelSettings {
+    pub(crate) window_size: usize,
+    pub(crate) search_depth: usize,
+    pub(crate) nice_length: usize,
+    pub(crate) lazy_matching: bool,
+    pub(crate) skip_incompressible: bool,
+}
+
+impl CompressionLevel {
+    /// Fastest compression (level 1)
+    pub const FAST: CompressionLevel = CompressionLevel(1);
+    /// Balance of speed and ratio (level 6)
+    pub const DEFAULT: CompressionLevel = CompressionLevel(6);
+    /// Best ratio (level 9)
+    pub const BEST: CompressionLevel = CompressionLevel(9);
+
+    /// Level `level`, clamped to 1..=9
+    pub const fn new(level: u32) -> Self {
+        CompressionLevel(if level < 1 { 1 } else if level > 9 { 9 } else { level as u8 })
+    }
+
+    /// The level as a number from 1 to 9
+    pub const fn level(self) -> u32 {
+        self.0 as u32
+    }
+
+    /// Window size, match finder depth and parse strategy of this level
+    pub(crate) fn settings(self) -> LevelSettings {
+        let (window_size, search_depth, nice_length, lazy_matching) = match self.0 {
+            1 => (4096, 4, 8, false),
+            2 => (4096, 8, 16, false),
+            3 => (8192, 16, 16, false),
+            4 => (16384, 16, 32, false),
+            5 => (32768, 32, 32, true),
+            6 => (65535, 64, 64, true),
+            7 => (65535, 128, 128, true),
+            8 => (65535, 512, 255, true),
+            _ => (65535, usize::MAX, 255, true),
+        };
+        // Only the best level searches every position of incompressible data
+        let skip_incompressible = self.0 < 9;
+        LevelSettings { window_size, search_depth, nice_length, lazy_matching, skip_incompressible }
+    }
+}
+
+impl Default for CompressionLevel {
+    fn default() -> Self {
+        CompressionLevel::DEFAULT
+    }
+}
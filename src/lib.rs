@@ -1,8 +1,129 @@
-use std::collections::HashMap;
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
 
 // Make the FFI module public
+#[cfg(feature = "std")]
 pub mod ffi;
 
+// Multi-block streams with per-block adaptive parameters
+#[cfg(feature = "std")]
+pub mod block;
+
+// Compressor/decompressor pairs that keep their window between calls
+pub mod compressor;
+
+// Self-describing framed format
+pub mod frame;
+
+// Preset dictionaries referenced by ID from frames
+pub mod dictionary;
+
+// Frames whose length field is chosen by a first compression pass
+mod two_pass;
+
+// Huffman coding of the tokens
+#[cfg(feature = "huffman")]
+mod huffman;
+
+// Reversible transforms applied before compression
+pub mod filter;
+
+// Decoding of back-to-back streams and frames
+pub mod concat;
+
+// Textbook 12-bit offset, 4-bit length token layout
+pub mod classic;
+
+// Nintendo LZ10/LZ11, Sega Kosinski and Microsoft SZDD formats
+pub mod compat;
+
+// Block-indexed archives with random access
+pub mod seekable;
+
+// Format detection for data of unknown origin
+#[cfg(feature = "std")]
+pub mod detect;
+
+// File helpers with atomic output writes
+#[cfg(feature = "std")]
+pub mod fs;
+
+// Chunked lazy decoding
+pub mod iter;
+
+// Streaming encoder/decoder and std::io adapters
+pub mod stream;
+#[cfg(feature = "std")]
+pub use stream::{copy_compress, copy_decompress};
+
+// embedded-io stream adapters for no_std firmware
+#[cfg(feature = "embedded-io")]
+pub mod embedded;
+
+// Decompression in const contexts
+mod const_decode;
+pub use const_decode::decompress_const;
+pub use frame::decompress_auto;
+
+// Standalone C decoder generation
+pub mod c_decoder;
+
+// Stream validation and conformance checks
+pub mod validate;
+
+// Error types for fallible decoding and configuration
+mod error;
+pub use error::{ConfigError, LzssError};
+
+// Validated configuration builder
+mod builder;
+pub use builder::LzssBuilder;
+
+// Match length field widths
+mod token;
+pub use token::LengthField;
+
+// Hash-chain match finder
+mod chain;
+
+// Long-distance match finder used by the optional LDM mode
+mod ldm;
+
+// Match copies for the decoders
+mod copy;
+
+// Compression level presets
+mod level;
+pub use level::CompressionLevel;
+
+// Compressed postcard telemetry records
+#[cfg(feature = "postcard")]
+pub mod telemetry;
+
+// HTTP content-coding adapter
+#[cfg(feature = "http")]
+pub mod http;
+
+// C++ bindings generated with cxx
+#[cfg(feature = "cxx")]
+mod cxx_bridge;
+
+// WebAssembly bindings generated with wasm-bindgen
+#[cfg(feature = "wasm")]
+mod wasm;
+
+// Python bindings generated with PyO3
+#[cfg(feature = "python")]
+mod python;
+
+// Compressed serde values
+#[cfg(feature = "serde")]
+pub mod serialize;
+
 // Add autotuning support
 #[cfg(feature = "autotune")]
 pub mod autotune;
@@ -14,106 +135,617 @@ pub mod autotune;
 pub struct LZSS {
     window_size: usize,
     min_match_length: usize,
+    long_distance_history: Option<usize>,
+    length_field: LengthField,
+    /// Store `distance - 1` in match tokens (used by frames)
+    distance_bias: bool,
+    /// Store distances up to 128 in one byte (see `with_short_offsets`)
+    short_offsets: bool,
+    /// Whether matches may be longer than their distance
+    overlapping_matches: bool,
+    /// Match length that ends the search for a longer match
+    nice_length: usize,
+    /// Whether a match may be deferred for a longer one at the next byte
+    lazy_matching: bool,
+    /// Most candidate positions examined per match search
+    search_depth: usize,
+    /// Longest match the encoder emits, if below what the format allows
+    max_match_length: Option<usize>,
+    /// Whether the encoder skips ahead through regions without matches
+    skip_incompressible: bool,
+}
+
+/// Largest distance representable with the 2-byte offsets of the default
+/// mode, and so the largest useful window without long-distance matching
+pub const MAX_SHORT_DISTANCE: usize = 65535;
+
+/// Largest distance representable with the 3-byte offsets used in
+/// long-distance matching mode (16 MiB - 1)
+pub const MAX_LONG_DISTANCE: usize = 0xFF_FFFF;
+
+/// Largest minimum match length, which frame headers store in one byte
+pub const MAX_MIN_MATCH_LENGTH: usize = 255;
+
+/// Distance fields below this are stored in one byte with short offsets
+const SHORT_OFFSET_LIMIT: usize = 128;
+
+/// Match length at which the match search stops by default
+pub const DEFAULT_NICE_LENGTH: usize = 16;
+
+/// Failed match searches before the encoder starts skipping positions in
+/// incompressible data (as a power of two, like LZ4's skip strength)
+const SKIP_STRENGTH: u32 = 6;
+
+/// Most literals the encoder emits between searches in incompressible data,
+/// which bounds how much of a following compressible region is missed
+const MAX_SKIP: usize = 32;
+
+/// Bytes processed between calls to a progress callback
+pub(crate) const PROGRESS_INTERVAL: usize = 1 << 20;
+
+/// Largest sliding window; bigger windows passed to `LZSS::new` are capped
+///
+/// Only limited with the `small-target` feature, which keeps the window (and
+/// the history the compressor and decompressor hold) small enough for
+/// microcontrollers.
+pub const MAX_WINDOW_SIZE: usize = if cfg!(feature = "small-target") { 4096 } else { usize::MAX };
+
+/// Largest possible compressed size of `input_len` bytes for the default
+/// token layout (2-byte distances, 1-byte lengths, minimum match length of
+/// 3 or more)
+///
+/// All-literal output costs a control byte per 8 literals on top of the
+/// 4-byte size header, and no match token is longer than the bytes it
+/// covers. Use `LZSS::max_compressed_size` for other parameters.
+pub fn max_compressed_size(input_len: usize) -> usize {
+    if input_len == 0 {
+        return 0;
+    }
+    4 + input_len.div_ceil(8) + input_len
+}
+
+/// Destination of encoded bytes
+pub(crate) trait Sink {
+    /// Append a byte
+    fn push(&mut self, byte: u8);
+
+    /// Append several bytes
+    fn extend_from_slice(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.push(byte);
+        }
+    }
+
+    /// Number of bytes appended so far
+    fn len(&self) -> usize;
+
+    /// Overwrite the byte at `pos`, which has already been appended
+    fn set(&mut self, pos: usize, byte: u8);
+}
+
+impl Sink for Vec<u8> {
+    fn push(&mut self, byte: u8) {
+        Vec::push(self, byte);
+    }
+
+    fn extend_from_slice(&mut self, bytes: &[u8]) {
+        Vec::extend_from_slice(self, bytes);
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn set(&mut self, pos: usize, byte: u8) {
+        self[pos] = byte;
+    }
+}
+
+/// Caller-provided buffer; bytes past its end are counted but dropped, so
+/// the size it would have needed is still known
+struct SliceSink<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl Sink for SliceSink<'_> {
+    fn push(&mut self, byte: u8) {
+        if let Some(slot) = self.buffer.get_mut(self.len) {
+            *slot = byte;
+        }
+        self.len += 1;
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn set(&mut self, pos: usize, byte: u8) {
+        if let Some(slot) = self.buffer.get_mut(pos) {
+            *slot = byte;
+        }
+    }
+}
+
+/// Match finder tables, kept by `compressor::Compressor` so that repeated
+/// calls reuse their memory
+#[derive(Default)]
+pub(crate) struct Scratch {
+    chains: chain::HashChains,
+    long_distance: Option<ldm::LongDistanceMatcher>,
 }
 
 impl LZSS {
     /// Create a new LZSS compressor/decompressor with given parameters
+    ///
+    /// Out-of-range parameters are clamped rather than rejected: the
+    /// minimum match length to `1..=MAX_MIN_MATCH_LENGTH`, and the window
+    /// in effect to `MAX_SHORT_DISTANCE` (or `MAX_LONG_DISTANCE` with
+    /// long-distance matching), since matches never reach further than
+    /// their distance field. Use `try_new` or `LZSS::builder()` to have
+    /// invalid parameters reported instead.
     pub fn new(window_size: usize, min_match_length: usize) -> Self {
+        #[cfg(feature = "small-target")]
+        let window_size = window_size.min(MAX_WINDOW_SIZE);
+        let min_match_length = min_match_length.clamp(1, MAX_MIN_MATCH_LENGTH);
         LZSS {
             window_size,
             min_match_length,
+            long_distance_history: None,
+            length_field: LengthField::OneByte,
+            distance_bias: false,
+            short_offsets: false,
+            overlapping_matches: true,
+            nice_length: DEFAULT_NICE_LENGTH,
+            lazy_matching: false,
+            search_depth: usize::MAX,
+            max_match_length: None,
+            skip_incompressible: true,
         }
     }
 
+    /// Create a codec, rejecting parameters the token format can't honour
+    ///
+    /// The window must be in `1..=MAX_SHORT_DISTANCE` (capped further by
+    /// `MAX_WINDOW_SIZE`) and the minimum match length in
+    /// `2..=MAX_MIN_MATCH_LENGTH`. Larger windows need long-distance
+    /// matching, configured through `LZSS::builder()`.
+    pub fn try_new(window_size: usize, min_match_length: usize) -> Result<Self, ConfigError> {
+        LzssBuilder::new()
+            .window_size(window_size)
+            .min_match_length(min_match_length)
+            .build()
+    }
+
+    /// Enable long-distance matching (LDM) over `history_size` bytes
+    ///
+    /// A coarse hash of sampled 32-byte sequences is kept for the whole
+    /// history, layered over the normal window search, so duplicated regions
+    /// that are megabytes apart still turn into matches. The history is
+    /// capped at `MAX_LONG_DISTANCE` (and at `MAX_WINDOW_SIZE` in
+    /// `small-target` builds).
+    ///
+    /// In this mode match distances are stored in 3 bytes instead of 2, so
+    /// the decompressor must be configured the same way.
+    pub fn with_long_distance_matching(mut self, history_size: usize) -> Self {
+        #[cfg(feature = "small-target")]
+        let history_size = history_size.min(MAX_WINDOW_SIZE);
+        self.long_distance_history = Some(history_size.min(MAX_LONG_DISTANCE));
+        self
+    }
+
+    /// Choose the width of the match length field (one byte by default)
+    ///
+    /// Wider fields allow longer matches, which pays off on long runs and
+    /// highly repetitive data. The decompressor must use the same setting
+    /// for raw streams; frames record it.
+    pub fn with_length_field(mut self, length_field: LengthField) -> Self {
+        self.length_field = length_field;
+        self
+    }
+
+    /// Longest match the encoder emits: the configured cap, or else the
+    /// longest the length field can represent
+    fn max_match_length(&self) -> usize {
+        let representable = self.length_field.max_value().saturating_add(self.min_match_length);
+        self.max_match_length.map_or(representable, |max| max.min(representable))
+    }
+
+    /// Number of bytes used to store a match distance (the longest form,
+    /// with short offsets)
+    pub(crate) fn offset_bytes(&self) -> usize {
+        if self.long_distance_history.is_some() { 3 } else { 2 }
+    }
+
+    /// Store near match distances in one byte (off by default)
+    ///
+    /// The lowest bit of the first offset byte then tells the two forms
+    /// apart: distances up to 128 take a single byte, farther ones the
+    /// usual 2 bytes (3 with long-distance matching) with one bit less of
+    /// reach, so the farthest distance is 32767 (about 8 MiB with LDM).
+    /// This pays off with the small windows of embedded profiles, where
+    /// most matches are near. The decompressor must use the same setting
+    /// for raw streams; frames record it.
+    pub fn with_short_offsets(mut self, enabled: bool) -> Self {
+        self.short_offsets = enabled;
+        self
+    }
+
+    /// Allow or forbid matches that overlap the data they produce
+    ///
+    /// By default a match may be longer than its distance (e.g. distance 1,
+    /// length 50 for a run), so the decoder copies bytes it has just
+    /// written. Some DMA and hardware LZ decoders can't do that; with
+    /// overlapping matches disallowed, every match copies from data that was
+    /// complete before it started. `validate` checks existing streams.
+    pub fn with_overlapping_matches(mut self, allowed: bool) -> Self {
+        self.overlapping_matches = allowed;
+        self
+    }
+
+    /// Stop searching for a longer match once one of `nice_length` bytes is
+    /// found (16 by default)
+    ///
+    /// Lower values compress faster; higher values find longer matches,
+    /// which pays off on repetitive data such as text. The output format is
+    /// unchanged, so the decompressor doesn't need the same setting.
+    pub fn with_nice_length(mut self, nice_length: usize) -> Self {
+        self.nice_length = nice_length;
+        self
+    }
+
+    /// Enable lazy matching (off by default)
+    ///
+    /// Before taking a match, the encoder checks whether the match starting
+    /// at the next byte is longer, and if so emits a literal and takes that
+    /// one instead, like deflate's lazy evaluation. This improves the ratio
+    /// on text at some cost in speed; matches of at least the nice length
+    /// are taken right away. The output format is unchanged.
+    pub fn with_lazy_matching(mut self, enabled: bool) -> Self {
+        self.lazy_matching = enabled;
+        self
+    }
+
+    /// Examine at most `depth` earlier positions per match search (no
+    /// limit by default)
+    ///
+    /// The most recent positions are tried first. A shallow search is much
+    /// faster on repetitive data, where the candidate lists grow long, at
+    /// some cost in ratio.
+    pub fn with_search_depth(mut self, depth: usize) -> Self {
+        self.search_depth = depth.max(1);
+        self
+    }
+
+    /// Skip ahead through data without matches (on by default)
+    ///
+    /// After 64 consecutive positions without a match, such as in encrypted
+    /// or already-compressed data, the encoder copies more and more bytes
+    /// (up to 32) as literals between searches and leaves them out of the
+    /// match finder, like LZ4's acceleration. The first match found returns to
+    /// searching every position. This makes incompressible input much
+    /// cheaper to get through, at the cost of occasionally missing a match
+    /// right after such a region. The output format is unchanged.
+    pub fn with_skip_incompressible(mut self, enabled: bool) -> Self {
+        self.skip_incompressible = enabled;
+        self
+    }
+
+    /// Apply a compression level preset
+    ///
+    /// Sets the window size, search depth, nice length, parse strategy and
+    /// incompressible-data skipping; the minimum match length and other
+    /// settings are kept. Level 1 is fastest, level 9 gives the best ratio.
+    pub fn with_level(mut self, level: CompressionLevel) -> Self {
+        let settings = level.settings();
+        let window_size = settings.window_size;
+        #[cfg(feature = "small-target")]
+        let window_size = window_size.min(MAX_WINDOW_SIZE);
+        self.window_size = window_size;
+        self.search_depth = settings.search_depth;
+        self.nice_length = settings.nice_length;
+        self.lazy_matching = settings.lazy_matching;
+        self.skip_incompressible = settings.skip_incompressible;
+        self
+    }
+
+    /// Store match distances minus one, so a zero distance can't be encoded
+    /// and the reach grows by one byte
+    pub(crate) fn with_distance_bias(mut self, distance_bias: bool) -> Self {
+        self.distance_bias = distance_bias;
+        self
+    }
+
+    /// Value subtracted from distances when storing them
+    fn distance_bias(&self) -> usize {
+        self.distance_bias as usize
+    }
+
+    /// Largest match distance the token format can represent
+    pub(crate) fn max_distance(&self) -> usize {
+        let max_field = if self.long_distance_history.is_some() { MAX_LONG_DISTANCE } else { MAX_SHORT_DISTANCE };
+        // Short offsets spend a bit of the long form on the form flag
+        let max_field = if self.short_offsets { max_field >> 1 } else { max_field };
+        max_field + self.distance_bias()
+    }
+
+    /// Farthest back a match can reach: the window, or the long-distance
+    /// history if that is larger, within what the offset field can hold
+    pub(crate) fn match_reach(&self) -> usize {
+        let reach = self.long_distance_history.map_or(self.window_size, |history| history.max(self.window_size));
+        reach.min(self.max_distance())
+    }
+
+    /// Append a match distance using the configured offset width (little-endian)
+    fn push_distance(&self, output: &mut impl Sink, distance: usize) {
+        let mut field = distance - self.distance_bias();
+        if self.short_offsets {
+            if field < SHORT_OFFSET_LIMIT {
+                output.push((field << 1) as u8);
+                return;
+            }
+            field = field << 1 | 1;
+        }
+        for i in 0..self.offset_bytes() {
+            output.push(((field >> (i * 8)) & 0xFF) as u8);
+        }
+    }
+
+    /// Read a match distance stored by `push_distance`, returning it with
+    /// the number of bytes it occupies, or `None` if the input ends first
+    pub(crate) fn read_distance(&self, input: &[u8], pos: usize) -> Option<(usize, usize)> {
+        let first = *input.get(pos)? as usize;
+        if self.short_offsets && first & 1 == 0 {
+            return Some(((first >> 1) + self.distance_bias(), 1));
+        }
+        let bytes = self.offset_bytes();
+        let field = input
+            .get(pos..pos + bytes)?
+            .iter()
+            .rev()
+            .fold(0usize, |field, &byte| field << 8 | byte as usize);
+        let field = if self.short_offsets { field >> 1 } else { field };
+        Some((field + self.distance_bias(), bytes))
+    }
+
     /// Compress input data using LZSS algorithm
     /// 
     /// Returns compressed byte vector
     pub fn compress(&self, input: &[u8]) -> Vec<u8> {
-        let input_len = input.len();
-        
-        // Handle empty input
+        self.compress_from(input, 0)
+    }
+
+    /// Compress `input` without the 4-byte size header
+    ///
+    /// For container formats that carry the uncompressed size out of band.
+    /// The output is the normal stream minus its first 4 bytes, so it must
+    /// be decompressed with `decompress_raw` and the original length.
+    pub fn compress_raw(&self, input: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+        self.compress_tokens(input, 0, &mut output, &mut Scratch::default(), &mut |_| true);
+        output
+    }
+
+    /// Compress `input[start..]`, allowing matches to reference the bytes
+    /// before `start` as already-known history
+    pub(crate) fn compress_from(&self, input: &[u8], start: usize) -> Vec<u8> {
+        let mut output = Vec::new();
+        self.compress_to(input, start, &mut output, &mut Scratch::default());
+        output
+    }
+
+    /// Compress `input` into `output`, which must hold at least
+    /// `max_compressed_size(input.len())` bytes to be sure it fits
+    ///
+    /// Returns the compressed size. Nothing is allocated for the output,
+    /// which suits embedded and FFI callers with their own buffers. If the
+    /// output is too small the error reports the size it needed, and the
+    /// buffer's contents are unspecified.
+    pub fn compress_into(&self, input: &[u8], output: &mut [u8]) -> Result<usize, LzssError> {
+        self.compress_from_into(input, 0, output)
+    }
+
+    /// Compress `input[start..]` into `output` (see `compress_from`)
+    pub(crate) fn compress_from_into(&self, input: &[u8], start: usize, output: &mut [u8]) -> Result<usize, LzssError> {
+        self.compress_from_into_with_progress(input, start, output, &mut |_| true)
+    }
+
+    /// Compress `input[start..]` into `output`, calling `progress` with the
+    /// number of bytes encoded every `PROGRESS_INTERVAL` bytes
+    ///
+    /// Returns `Cancelled` if `progress` returns false.
+    pub(crate) fn compress_from_into_with_progress(
+        &self,
+        input: &[u8],
+        start: usize,
+        output: &mut [u8],
+        progress: &mut dyn FnMut(usize) -> bool,
+    ) -> Result<usize, LzssError> {
+        let mut sink = SliceSink { buffer: output, len: 0 };
+        if !self.compress_to_with_progress(input, start, &mut sink, &mut Scratch::default(), progress) {
+            return Err(LzssError::Cancelled);
+        }
+        if sink.len > sink.buffer.len() {
+            return Err(LzssError::OutputTooSmall { required: sink.len, capacity: sink.buffer.len() });
+        }
+        Ok(sink.len)
+    }
+
+    /// Largest possible compressed size of `input_len` bytes with these
+    /// parameters
+    ///
+    /// Incompressible data costs a control byte per 8 literals on top of
+    /// the 4-byte size header. With short minimum match lengths (or 3-byte
+    /// LDM distances) a match token can be longer than the bytes it covers,
+    /// which is accounted for too. For minimum match lengths of 3 and up
+    /// without LDM this equals the C API's `lzss_max_compressed_size`.
+    pub fn max_compressed_size(&self, input_len: usize) -> usize {
         if input_len == 0 {
-            return Vec::new();
+            return 0;
+        }
+        let token = self.offset_bytes() + self.length_field.min_bytes();
+        let min_match = self.min_match_length.max(1);
+        if token <= min_match {
+            return max_compressed_size(input_len);
+        }
+        4 + input_len.div_ceil(8) + (input_len * token).div_ceil(min_match)
+    }
+
+    /// Rough estimate of the compressed size of `input`, for reserving
+    /// capacity before compressing
+    ///
+    /// Based on the entropy of the byte histogram, so it is quick but
+    /// ignores repeats: it tends to overestimate repetitive input and never
+    /// exceeds `max_compressed_size`.
+    #[cfg(feature = "std")]
+    pub fn compressed_size_hint(&self, input: &[u8]) -> usize {
+        if input.is_empty() {
+            return 0;
+        }
+        let entropy = block::BlockStats::analyze(input).entropy;
+        let literals = (input.len() as f64 * entropy / 8.0).ceil() as usize;
+        (4 + input.len().div_ceil(8) + literals).min(self.max_compressed_size(input.len()))
+    }
+
+    /// Encode `input[start..]` into `output`, using `scratch` for the match
+    /// finder
+    pub(crate) fn compress_to(&self, input: &[u8], start: usize, output: &mut impl Sink, scratch: &mut Scratch) {
+        self.compress_to_with_progress(input, start, output, scratch, &mut |_| true);
+    }
+
+    /// Encode like `compress_to`, calling `progress` as `compress_tokens`
+    /// does; returns false if it stopped the encoding
+    fn compress_to_with_progress(
+        &self,
+        input: &[u8],
+        start: usize,
+        output: &mut impl Sink,
+        scratch: &mut Scratch,
+        progress: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        // Handle empty input
+        if input.len() == start {
+            return true;
         }
-        
-        let mut output = Vec::new();
-        let mut pos = 0;
         
         // Store original size for exact decompression
+        let content_len = input.len() - start;
         for i in 0..4 {
-            output.push(((input_len >> (i * 8)) & 0xFF) as u8);
+            output.push(((content_len >> (i * 8)) & 0xFF) as u8);
         }
         
+        self.compress_tokens(input, start, output, scratch, progress)
+    }
+
+    /// Encode `input[start..]` into `output` as control bytes and tokens,
+    /// without the size header
+    ///
+    /// `progress` is called with the number of bytes encoded every
+    /// `PROGRESS_INTERVAL` bytes; if it returns false, encoding stops and
+    /// false is returned.
+    fn compress_tokens(
+        &self,
+        input: &[u8],
+        start: usize,
+        output: &mut impl Sink,
+        scratch: &mut Scratch,
+        progress: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        let input_len = input.len();
+        if input_len == start {
+            return true;
+        }
+        
+        let mut pos = start;
+        let mut next_report = start + PROGRESS_INTERVAL;
+        
         // Control byte and its bit position
         let mut control_byte = 0u8;
         let mut bit_pos = 0;
         let mut control_byte_pos = output.len();
         output.push(0); // Reserve space for first control byte
         
-        // Dictionary for finding matches
-        let mut dictionary: HashMap<&[u8], Vec<usize>> = HashMap::new();
+        // Hash chains for finding matches
+        let chains = &mut scratch.chains;
+        chains.reset(self.window_size, input_len, self.min_match_length);
+        
+        // Seed the chains with the part of the history inside the window
+        for prev_pos in start.saturating_sub(self.window_size)..start {
+            chains.insert(input, prev_pos);
+        }
         
-        // Calculate the maximum representable match length
-        let max_match_code = 255; // One byte to encode the match length adjustment
-        let max_match_length = max_match_code + self.min_match_length;
+        // Optional long-distance matcher layered over the normal window
+        let mut long_distance = self.long_distance_history.map(|history_size| {
+            let matcher = scratch.long_distance.get_or_insert_with(|| ldm::LongDistanceMatcher::new(history_size));
+            matcher.reset(history_size);
+            matcher
+        });
+        let max_distance = self.max_distance();
+        
+        let max_match_length = self.max_match_length();
+        
+        // Searches without a match since the last one, and literals left to
+        // emit without searching (see `with_skip_incompressible`)
+        let mut misses = 0usize;
+        let mut skip = 0usize;
         
         while pos < input_len {
-            // Find the longest match in the sliding window
-            let max_look_ahead = std::cmp::min(input_len - pos, max_match_length);
-            let window_begin = if pos > self.window_size { pos - self.window_size } else { 0 };
-            
-            // Try to find the longest match
-            let mut best_match_len = 0;
-            let mut best_match_dist = 0;
+            if pos >= next_report {
+                if !progress(pos - start) {
+                    return false;
+                }
+                next_report = pos + PROGRESS_INTERVAL;
+            }
+            let max_look_ahead = core::cmp::min(input_len - pos, max_match_length);
             
-            // Only look for matches if we have enough bytes ahead
-            if max_look_ahead >= self.min_match_length {
-                let key_size = std::cmp::min(3, max_look_ahead);
-                let search_key = &input[pos..pos + key_size];
+            let (mut best_match_len, mut best_match_dist) = if skip > 0 {
+                skip -= 1;
+                (0, 0)
+            } else {
+                // Index the current position (only whole 3- or 4-byte keys are stored)
+                if max_look_ahead >= self.min_match_length {
+                    chains.insert(input, pos);
+                }
+                if let Some(matcher) = long_distance.as_mut() {
+                    matcher.insert_up_to(input, pos);
+                }
                 
-                if let Some(positions) = dictionary.get(search_key) {
-                    for &prev_pos in positions.iter().rev() {
-                        if prev_pos < window_begin {
-                            continue;
-                        }
-                        
-                        let mut match_len = 0;
-                        let max_possible = std::cmp::min(input_len - pos, input_len - prev_pos);
-                        
-                        while match_len < max_possible && match_len < max_look_ahead && 
-                              input[prev_pos + match_len] == input[pos + match_len] {
-                            match_len += 1;
-                        }
-                        
-                        if match_len >= self.min_match_length && match_len > best_match_len {
-                            best_match_len = match_len;
-                            best_match_dist = pos - prev_pos;
-                            
-                            if match_len >= 16 { // Early termination if we find a good match
-                                break;
-                            }
-                        }
+                let (mut best_match_len, best_match_dist) =
+                    self.longest_match(input, pos, chains, long_distance.as_deref(), max_match_length);
+                
+                // Lazy matching: emit a literal instead if the match starting at
+                // the next byte is longer
+                if self.lazy_matching && best_match_len >= self.min_match_length && best_match_len < self.nice_length {
+                    let (next_len, _) =
+                        self.longest_match(input, pos + 1, chains, long_distance.as_deref(), max_match_length);
+                    if next_len > best_match_len {
+                        best_match_len = 0;
                     }
                 }
                 
-                // Add current position to dictionary
-                if key_size == 3 { // Only add 3-byte keys
-                    dictionary.entry(search_key).or_insert_with(Vec::new).push(pos);
+                if best_match_len >= self.min_match_length {
+                    misses = 0;
+                } else if self.skip_incompressible {
+                    misses += 1;
+                    skip = (misses >> SKIP_STRENGTH).min(MAX_SKIP);
                 }
-            }
+                (best_match_len, best_match_dist)
+            };
             
             // Encode literal or match
             if best_match_len >= self.min_match_length {
                 // Encode a match
                 control_byte |= 1 << bit_pos;
                 
-                // Use 2 bytes for offset to support larger window sizes (up to 65535)
-                if best_match_dist > 65535 {
-                    best_match_dist = 65535; // Limit to max representable value with 2 bytes
+                // Use 2 bytes for offset to support larger window sizes (up to 65535),
+                // or 3 bytes in long-distance matching mode
+                if best_match_dist > max_distance {
+                    best_match_dist = max_distance; // Limit to max representable value
                     // Recalculate match length with this constrained distance
                     let back_pos = pos - best_match_dist;
                     let mut adjusted_len = 0;
                     while adjusted_len < max_look_ahead && 
+                          (self.overlapping_matches || adjusted_len < best_match_dist) &&
                           input[back_pos + adjusted_len] == input[pos + adjusted_len] {
                         adjusted_len += 1;
                     }
@@ -125,17 +757,13 @@ impl LZSS {
                         output.push(input[pos]);
                         pos += 1;
                     } else {
-                        // Store the distance using 2 bytes (little-endian)
-                        output.push((best_match_dist & 0xFF) as u8);            // Low byte
-                        output.push(((best_match_dist >> 8) & 0xFF) as u8);     // High byte
-                        output.push((best_match_len - self.min_match_length) as u8);
+                        self.push_distance(output, best_match_dist);
+                        self.length_field.push(output, best_match_len - self.min_match_length);
                         pos += best_match_len;
                     }
                 } else {
-                    // Store the distance using 2 bytes (little-endian)
-                    output.push((best_match_dist & 0xFF) as u8);           // Low byte
-                    output.push(((best_match_dist >> 8) & 0xFF) as u8);    // High byte
-                    output.push((best_match_len - self.min_match_length) as u8);
+                    self.push_distance(output, best_match_dist);
+                    self.length_field.push(output, best_match_len - self.min_match_length);
                     pos += best_match_len;
                 }
             } else {
@@ -148,7 +776,7 @@ impl LZSS {
             
             // If control byte is full, start a new one
             if bit_pos == 8 {
-                output[control_byte_pos] = control_byte;
+                output.set(control_byte_pos, control_byte);
                 
                 if pos < input_len {
                     control_byte = 0;
@@ -161,27 +789,334 @@ impl LZSS {
         
         // Update the last control byte if not full
         if bit_pos > 0 && bit_pos < 8 {
-            output[control_byte_pos] = control_byte;
+            output.set(control_byte_pos, control_byte);
         }
+        true
+    }
+
+    /// Longest match for `pos` among the indexed positions before it,
+    /// returning `(length, distance)` (length 0 if there is none)
+    fn longest_match(
+        &self,
+        input: &[u8],
+        pos: usize,
+        chains: &chain::HashChains,
+        long_distance: Option<&ldm::LongDistanceMatcher>,
+        max_match_length: usize,
+    ) -> (usize, usize) {
+        let input_len = input.len();
+        let max_look_ahead = core::cmp::min(input_len - pos, max_match_length);
+        let window_begin = pos.saturating_sub(self.window_size);
         
-        output
+        let mut best_match_len = 0;
+        let mut best_match_dist = 0;
+        
+        // Only look for matches if we have enough bytes ahead
+        if max_look_ahead >= self.min_match_length {
+            for prev_pos in chains.candidates(input, pos, window_begin).take(self.search_depth) {
+                let mut match_len = 0;
+                let mut max_possible = core::cmp::min(input_len - pos, input_len - prev_pos);
+                if !self.overlapping_matches {
+                    max_possible = max_possible.min(pos - prev_pos);
+                }
+                
+                while match_len < max_possible && match_len < max_look_ahead && 
+                      input[prev_pos + match_len] == input[pos + match_len] {
+                    match_len += 1;
+                }
+                
+                if match_len >= self.min_match_length && match_len > best_match_len {
+                    best_match_len = match_len;
+                    best_match_dist = pos - prev_pos;
+                    
+                    if match_len >= self.nice_length { // Early termination if we find a good match
+                        break;
+                    }
+                }
+            }
+        }
+        
+        // Fall back to the long-distance matcher when the window search
+        // didn't produce a long match
+        if let Some(matcher) = long_distance {
+            if best_match_len < ldm::LDM_MIN_MATCH {
+                if let Some((len, dist)) = matcher.find(input, pos, max_look_ahead) {
+                    let len = if self.overlapping_matches { len } else { len.min(dist) };
+                    if len > best_match_len {
+                        best_match_len = len;
+                        best_match_dist = dist;
+                    }
+                }
+            }
+        }
+        
+        (best_match_len, best_match_dist)
+    }
+
+    /// Compress `input` with a preset dictionary
+    ///
+    /// Matches may reference the end of `dictionary` (as much as fits in the
+    /// window) as if it preceded the input, which helps small payloads that
+    /// share content with known data. The output must be decompressed with
+    /// `decompress_with_dictionary` and the same dictionary.
+    pub fn compress_with_dictionary(&self, dictionary: &[u8], input: &[u8]) -> Vec<u8> {
+        let (data, start) = self.with_dictionary_prefix(dictionary, input);
+        self.compress_from(&data, start)
+    }
+
+    /// Compress `input` with a preset dictionary into `output` (see
+    /// `compress_into`)
+    #[cfg(feature = "std")]
+    pub(crate) fn compress_with_dictionary_into(
+        &self,
+        dictionary: &[u8],
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<usize, LzssError> {
+        let (data, start) = self.with_dictionary_prefix(dictionary, input);
+        self.compress_from_into(&data, start, output)
+    }
+
+    /// The reachable end of `dictionary` followed by `input`, and where the
+    /// input starts
+    fn with_dictionary_prefix(&self, dictionary: &[u8], input: &[u8]) -> (Vec<u8>, usize) {
+        let dictionary = &dictionary[dictionary.len().saturating_sub(self.max_distance())..];
+        let mut data = Vec::with_capacity(dictionary.len() + input.len());
+        data.extend_from_slice(dictionary);
+        data.extend_from_slice(input);
+        (data, dictionary.len())
+    }
+
+    /// Decompress data produced by `compress_with_dictionary`
+    ///
+    /// Corrupt input is an error, as in `try_decompress`; matches may reach
+    /// back into `dictionary`.
+    pub fn decompress_with_dictionary(&self, dictionary: &[u8], input: &[u8]) -> Result<Vec<u8>, LzssError> {
+        // Validate first, so a corrupt size header can't force a huge allocation
+        let stats = self.validate_with_history(input, dictionary.len())?;
+        let mut output = vec![0; stats.content_size];
+        self.decompress_into_with_history(input, dictionary, &mut output)?;
+        Ok(output)
     }
 
     /// Decompress data compressed with the LZSS algorithm
-    /// 
-    /// Returns the decompressed byte vector
+    ///
+    /// Self-describing frames (see `compress_framed`) are decoded with the
+    /// parameters stored in their header, and their checksum is verified;
+    /// raw streams use this instance's parameters, which must match the
+    /// encoder's.
+    ///
+    /// Corrupt input is an error: a stream that ends early
+    /// (`TruncatedInput`), a match reaching before the start of the data
+    /// (`InvalidDistance`), or tokens that don't add up to the size in the
+    /// header (`SizeMismatch`).
+    pub fn try_decompress(&self, input: &[u8]) -> Result<Vec<u8>, LzssError> {
+        // Validate first, so a corrupt size header can't force a huge allocation
+        let stats = self.validate(input)?;
+        let mut output = vec![0; stats.content_size];
+        self.decompress_into(input, &mut output)?;
+        Ok(output)
+    }
+
+    /// Decompress like `try_decompress`, but refuse input whose header
+    /// claims more than `max_output` bytes
+    ///
+    /// The claimed size is checked before anything is allocated or decoded,
+    /// so untrusted input can't make the decoder reserve gigabytes (e.g. a
+    /// forged `0xFFFFFFFF` size, or a stream built from maximal matches).
+    pub fn decompress_with_limit(&self, input: &[u8], max_output: usize) -> Result<Vec<u8>, LzssError> {
+        let stream = match frame::FrameHeader::parse(input) {
+            Some(header) => header.stream(input),
+            None => input,
+        };
+        if let Some(header) = stream.get(..4) {
+            let size = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+            if size > max_output {
+                return Err(LzssError::OutputLimitExceeded { size, limit: max_output });
+            }
+        }
+        self.try_decompress(input)
+    }
+
+    /// Decompress data compressed with the LZSS algorithm, patching up
+    /// corrupt input
+    ///
+    /// Invalid match references are skipped and missing output is padded
+    /// with zeros, so corruption goes unnoticed.
+    #[deprecated(note = "hides corrupt input; use `try_decompress`")]
     pub fn decompress(&self, input: &[u8]) -> Vec<u8> {
+        self.decompress_lossy(input)
+    }
+
+    /// Lossy decoding behind the deprecated `decompress`
+    pub(crate) fn decompress_lossy(&self, input: &[u8]) -> Vec<u8> {
+        if let Some(header) = frame::FrameHeader::parse(input) {
+            let mut output = header.decoder().decompress_with_window(header.stream(input), usize::MAX);
+            header.unfilter(&mut output);
+            return output;
+        }
+        self.decompress_with_window(input, usize::MAX)
+    }
+
+    /// Decompress into `output`, which must be exactly the decompressed
+    /// size
+    ///
+    /// Nothing is allocated, which suits engine and firmware loaders that
+    /// know the size up front. Corrupt input or a buffer of the wrong size
+    /// is an error, and `output` may then have been partly written. Frames
+    /// are decoded with the parameters in their header, and their checksum
+    /// and window size are verified.
+    pub fn decompress_into(&self, input: &[u8], output: &mut [u8]) -> Result<(), LzssError> {
+        self.decompress_into_with_history(input, &[], output)
+    }
+
+    /// Decompress into `output` a stream whose matches may reference
+    /// `history`, the data that preceded it
+    pub(crate) fn decompress_into_with_history(
+        &self,
+        input: &[u8],
+        history: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), LzssError> {
+        self.decompress_into_with_progress(input, history, output, &mut |_| true)
+    }
+
+    /// Decompress like `decompress_into_with_history`, calling `progress`
+    /// with the number of bytes decoded every `PROGRESS_INTERVAL` bytes
+    ///
+    /// Returns `Cancelled` if `progress` returns false.
+    pub(crate) fn decompress_into_with_progress(
+        &self,
+        input: &[u8],
+        history: &[u8],
+        output: &mut [u8],
+        progress: &mut dyn FnMut(usize) -> bool,
+    ) -> Result<(), LzssError> {
+        if let Some(header) = frame::FrameHeader::parse(input) {
+            let decoder = header.decoder();
+            // Decoding checks distances against the data, not the window
+            // the frame records, so check that first
+            if let Some(window) = header.window_size {
+                decoder.validate_stream_within(header.stream(input), history.len(), window)?;
+            }
+            decoder.decompress_stream_into(header.stream(input), history, output, progress)?;
+            header.unfilter(output);
+            return header.verify(output);
+        }
+        self.decompress_stream_into(input, history, output, progress)
+    }
+
+    fn decompress_stream_into(
+        &self,
+        input: &[u8],
+        history: &[u8],
+        output: &mut [u8],
+        progress: &mut dyn FnMut(usize) -> bool,
+    ) -> Result<(), LzssError> {
+        let size = match input.len() {
+            0 => 0, // Empty input compresses to nothing
+            1..=4 => return Err(LzssError::TruncatedInput),
+            _ => u32::from_le_bytes([input[0], input[1], input[2], input[3]]) as usize,
+        };
+        if size != output.len() {
+            return Err(LzssError::OutputSizeMismatch { expected: size, actual: output.len() });
+        }
+        self.decode_tokens(input.get(4..).unwrap_or_default(), history, output, progress)
+    }
+
+    /// Decompress output of `compress_raw` whose original length is `size`
+    ///
+    /// The stream has no header to check the length against, so a wrong
+    /// `size` shows up as truncated input or, if it is too small, as
+    /// silently shortened output.
+    pub fn decompress_raw(&self, input: &[u8], size: usize) -> Result<Vec<u8>, LzssError> {
+        let mut output = vec![0; size];
+        self.decompress_raw_into(input, &mut output)?;
+        Ok(output)
+    }
+
+    /// Decompress output of `compress_raw` into `output`, which must be
+    /// exactly the original length (see `decompress_into`)
+    pub fn decompress_raw_into(&self, input: &[u8], output: &mut [u8]) -> Result<(), LzssError> {
+        self.decode_tokens(input, &[], output, &mut |_| true)
+    }
+
+    /// Decode control bytes and tokens until `output` is full, calling
+    /// `progress` every `PROGRESS_INTERVAL` bytes
+    fn decode_tokens(
+        &self,
+        input: &[u8],
+        history: &[u8],
+        output: &mut [u8],
+        progress: &mut dyn FnMut(usize) -> bool,
+    ) -> Result<(), LzssError> {
+        let size = output.len();
+        let mut produced = 0;
+        let mut pos = 0;
+        let mut next_report = PROGRESS_INTERVAL;
+
+        while produced < size {
+            if produced >= next_report {
+                if !progress(produced) {
+                    return Err(LzssError::Cancelled);
+                }
+                next_report = produced + PROGRESS_INTERVAL;
+            }
+            let &control_byte = input.get(pos).ok_or(LzssError::SizeMismatch { expected: size, actual: produced })?;
+            pos += 1;
+
+            for bit in 0..8 {
+                if produced >= size {
+                    break;
+                }
+
+                if control_byte & (1 << bit) == 0 {
+                    output[produced] = *input.get(pos).ok_or(LzssError::TruncatedInput)?;
+                    produced += 1;
+                    pos += 1;
+                    continue;
+                }
+
+                let (distance, distance_bytes) = self.read_distance(input, pos).ok_or(LzssError::TruncatedInput)?;
+                let (length, length_bytes) = self.length_field
+                    .read(input, pos + distance_bytes)
+                    .ok_or(LzssError::TruncatedInput)?;
+                let length = length.saturating_add(self.min_match_length).min(size - produced);
+                pos += distance_bytes + length_bytes;
+
+                if distance == 0 || distance > produced + history.len() {
+                    return Err(LzssError::InvalidDistance { position: produced, distance });
+                }
+                copy::copy_match(output, produced, distance, length, history);
+                produced += length;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Decompress, treating references further back than `max_distance`
+    /// as invalid
+    pub(crate) fn decompress_with_window(&self, input: &[u8], max_distance: usize) -> Vec<u8> {
+        self.decompress_with_history(input, max_distance, &[])
+    }
+
+    /// Decompress a stream whose matches may reference `history`, the data
+    /// that preceded it
+    pub(crate) fn decompress_with_history(&self, input: &[u8], max_distance: usize, history: &[u8]) -> Vec<u8> {
         if input.len() < 5 { // Need at least 4 bytes for size + 1 for control
             return Vec::new();
         }
         
         // Extract original size from header
         let mut original_size = 0usize;
-        for i in 0..4 {
-            original_size |= (input[i] as usize) << (i * 8);
+        for (i, &byte) in input[..4].iter().enumerate() {
+            original_size |= (byte as usize) << (i * 8);
         }
         
-        let mut output = Vec::with_capacity(original_size);
+        let mut output = Vec::with_capacity(history.len() + original_size);
+        output.extend_from_slice(history);
+        let original_size = history.len() + original_size;
         let mut pos = 4; // Start after size header
         
         while pos < input.len() && output.len() < original_size {
@@ -196,38 +1131,26 @@ impl LZSS {
                 
                 if (control_byte & (1 << bit)) != 0 {
                     // This is a match reference
-                    if pos + 2 >= input.len() { // Need 2 bytes for distance + 1 for length
+                    let Some((distance, distance_bytes)) = self.read_distance(input, pos) else {
                         break; // Not enough data
-                    }
+                    };
+                    let (length, length_bytes) = match self.length_field.read(input, pos + distance_bytes) {
+                        Some(field) => field,
+                        None => break, // Not enough data
+                    };
                     
-                    // Read distance from 2 bytes (little-endian)
-                    let distance = (input[pos] as usize) | ((input[pos + 1] as usize) << 8);
-                    let length = (input[pos + 2] as usize) + self.min_match_length;
-                    pos += 3;
+                    let length = length + self.min_match_length;
+                    pos += distance_bytes + length_bytes;
                     
                     // Sanity check
-                    if distance == 0 || distance > output.len() {
+                    if distance == 0 || distance > output.len() || distance > max_distance {
                         continue; // Skip invalid reference
                     }
                     
-                    // Copy from the already decompressed output
-                    let start_pos = output.len() - distance;
-                    
-                    for i in 0..length {
-                        if start_pos + i < output.len() {
-                            // Regular copy from earlier in output
-                            output.push(output[start_pos + i]);
-                        } else {
-                            // Handle self-referential copies (like ABABAB pattern)
-                            // Calculate correct offset based on what we've copied so far
-                            let offset = i % distance;
-                            output.push(output[start_pos + offset]);
-                        }
-                        
-                        if output.len() >= original_size {
-                            break;
-                        }
-                    }
+                    // Copy from the already decompressed output, stopping at
+                    // the original size
+                    let length = length.min(original_size - output.len());
+                    copy::extend_match(&mut output, distance, length);
                 } else {
                     // This is a literal byte
                     output.push(input[pos]);
@@ -245,7 +1168,11 @@ impl LZSS {
             output.resize(original_size, 0);
         }
         
-        output
+        if history.is_empty() {
+            output
+        } else {
+            output.split_off(history.len())
+        }
     }
 }
 
@@ -1,8 +1,31 @@
-use rustzss::LZSS;
+use rustzss::{LengthField, LZSS};
+use rustzss::block::BlockIter;
+use rustzss::detect::{self, Format};
+use rustzss::frame::Checksum;
+use rustzss::validate::CONFORMANCE_VECTORS;
 use std::time::Instant;
 use rand::prelude::*;
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 && args[1] == "info" {
+        if args.len() < 3 {
+            eprintln!("Usage: {} info <file>", args[0]);
+            std::process::exit(1);
+        }
+        if let Err(message) = print_info(&args[2]) {
+            eprintln!("{}: {}", args[2], message);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.len() > 1 && args[1] == "selftest" {
+        if !run_selftest() {
+            std::process::exit(1);
+        }
+        return;
+    }
+
     println!("LZSS Test Suite");
     println!("===============\n");
     
@@ -24,7 +47,6 @@ fn main() {
     test_compression_decompression("Large pattern (1MB)", &large_pattern);
     
     // Test with 10MB data if user wants to run it
-    let args: Vec<String> = std::env::args().collect();
     if args.len() > 1 && args[1] == "--huge" {
         println!("Generating 10MB test data...");
         let huge_pattern = generate_random_data(10_000_000);
@@ -34,6 +56,116 @@ fn main() {
     println!("All tests completed!");
 }
 
+/// Print the headers of a compressed file without decompressing the payload
+fn print_info(path: &str) -> Result<(), String> {
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    // Raw streams may use either fixed length field
+    let info = detect::inspect(&data, 3, LengthField::TwoBytes).ok_or("not a recognized compressed file")?;
+    
+    let format = match info.format {
+        Format::Framed => "framed",
+        Format::Blocks => "multi-block",
+        Format::Raw => "raw (legacy, parameters not recorded)",
+        Format::Szdd => "SZDD",
+        Format::Lz10 => "Nintendo LZ10",
+        Format::Lz11 => "Nintendo LZ11",
+    };
+    println!("File: {}", path);
+    println!("Format: {}", format);
+    if let Some(version) = info.version {
+        println!("Format version: {}", version);
+    }
+    if let Some(min_match) = info.min_match_length {
+        println!("Min match length: {}", min_match);
+    }
+    if let Some(offset_bytes) = info.offset_bytes {
+        println!("Offset width: {} bytes", offset_bytes);
+    }
+    if let Some(length_field) = info.length_field {
+        println!("Length field: {:?}", length_field);
+    }
+    if info.format == Format::Blocks {
+        for (i, (params, payload)) in BlockIter::new(&data).enumerate() {
+            println!("  Block {}: {:?}, window={}, min_match={}, {} bytes",
+                     i, params.strategy, params.window_size, params.min_match_length, payload.len());
+        }
+    }
+    println!("Compressed size: {} bytes", data.len());
+    println!("Content size: {} bytes", info.content_size);
+    println!("Blocks: {}", info.block_count);
+    let checksum = match info.checksum {
+        Checksum::None => "none",
+        Checksum::Crc32 => "crc32",
+        Checksum::Xxh3 => "xxh3",
+    };
+    println!("Checksum: {}", checksum);
+    if let Some(id) = info.dictionary_id {
+        println!("Dictionary ID: {}", id);
+    }
+    Ok(())
+}
+
+/// Run the conformance vectors and a randomized roundtrip on this build,
+/// returning whether everything passed
+fn run_selftest() -> bool {
+    let mut failures = 0;
+    
+    println!("Conformance vectors:");
+    for vector in CONFORMANCE_VECTORS {
+        match vector.check() {
+            Ok(()) => println!("  ok    {}", vector.name),
+            Err(message) => {
+                println!("  FAIL  {}: {}", vector.name, message);
+                failures += 1;
+            }
+        }
+    }
+    
+    println!("Randomized roundtrip:");
+    let mut rng = rand::thread_rng();
+    let configs = [
+        ("window 4096, min match 3", LZSS::new(4096, 3)),
+        ("window 256, min match 4", LZSS::new(256, 4)),
+        ("extended length field", LZSS::new(4096, 3).with_length_field(LengthField::Extended)),
+        ("long-distance matching", LZSS::new(4096, 3).with_long_distance_matching(1 << 20)),
+    ];
+    for (name, lzss) in &configs {
+        let mut passed = true;
+        for _ in 0..20 {
+            // Mix random bytes with repeats so both literals and matches occur
+            let mut data = generate_random_data(rng.gen_range(0..20_000));
+            for _ in 0..rng.gen_range(0..50) {
+                if data.is_empty() {
+                    break;
+                }
+                let start = rng.gen_range(0..data.len());
+                let end = (start + rng.gen_range(1..600)).min(data.len());
+                data.extend_from_within(start..end);
+            }
+            
+            let compressed = lzss.compress(&data);
+            let framed = lzss.compress_framed(&data);
+            if lzss.try_decompress(&compressed).as_deref() != Ok(&data[..]) || lzss.try_decompress(&framed).as_deref() != Ok(&data[..]) {
+                passed = false;
+                break;
+            }
+        }
+        if passed {
+            println!("  ok    {}", name);
+        } else {
+            println!("  FAIL  {}", name);
+            failures += 1;
+        }
+    }
+    
+    if failures == 0 {
+        println!("Self-test passed");
+    } else {
+        println!("Self-test failed: {} failure(s)", failures);
+    }
+    failures == 0
+}
+
 fn generate_random_data(size: usize) -> Vec<u8> {
     let mut rng = rand::thread_rng();
     let mut data = Vec::with_capacity(size);
@@ -62,7 +194,14 @@ fn test_compression_decompression(test_name: &str, data: &[u8]) {
     
     // Decompress
     let start = Instant::now();
-    let decompressed = lzss.decompress(&compressed);
+    let decompressed = match lzss.try_decompress(&compressed) {
+        Ok(decompressed) => decompressed,
+        Err(e) => {
+            println!("FAILED: {}", e);
+            println!();
+            return;
+        }
+    };
     let decompress_time = start.elapsed();
     println!("Decompression time: {:?}", decompress_time);
     
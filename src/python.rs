@@ -0,0 +1,139 @@
+//! Python bindings generated with PyO3
+//!
+//! The `rustzss` extension module gives pipeline scripts `compress`,
+//! `decompress`, `quick_tune` and `tune` directly, instead of going through
+//! the C API with ctypes. Build it with maturin (see `pyproject.toml`).
+//! Input may be any contiguous object supporting the buffer protocol
+//! (`bytes`, `bytearray`, `memoryview`, numpy arrays of any dtype), read in
+//! place, and the GIL is released while the codec runs.
+//! Decoding errors are raised as `ValueError` carrying the `LzssError`
+//! message.
+
+use std::mem::MaybeUninit;
+use std::slice;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::{ffi, PyErr};
+use pyo3::types::{PyBytes, PyDict};
+
+use crate::autotune::{CompressionParameters, Tuner, TunerConfig};
+use crate::LZSS;
+
+/// Bytes of a Python object, borrowed through the buffer protocol
+///
+/// The view is requested with `PyBUF_SIMPLE`, so the exporter hands out its
+/// memory as plain contiguous bytes whatever its element type, and refuses
+/// non-contiguous objects.
+struct BytesView(Box<ffi::Py_buffer>);
+
+impl BytesView {
+    fn as_slice(&self) -> &[u8] {
+        if self.0.len == 0 {
+            return &[];
+        }
+        // The exporter keeps the memory alive and in place until the view
+        // is released
+        unsafe { slice::from_raw_parts(self.0.buf as *const u8, self.0.len as usize) }
+    }
+}
+
+impl FromPyObject<'_> for BytesView {
+    fn extract_bound(object: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let mut view = Box::new(MaybeUninit::<ffi::Py_buffer>::uninit());
+        if unsafe { ffi::PyObject_GetBuffer(object.as_ptr(), view.as_mut_ptr(), ffi::PyBUF_SIMPLE) } == -1 {
+            return Err(PyErr::fetch(object.py()));
+        }
+        // Initialized by `PyObject_GetBuffer`
+        Ok(BytesView(unsafe { Box::from_raw(Box::into_raw(view).cast()) }))
+    }
+}
+
+impl Drop for BytesView {
+    fn drop(&mut self) {
+        Python::with_gil(|_| unsafe { ffi::PyBuffer_Release(&mut *self.0) });
+    }
+}
+
+/// Compress `data` into a raw stream
+#[pyfunction]
+#[pyo3(signature = (data, window=4096, min_match=3))]
+fn compress<'py>(py: Python<'py>, data: BytesView, window: usize, min_match: usize) -> Bound<'py, PyBytes> {
+    let data = data.as_slice();
+    let compressed = py.allow_threads(|| LZSS::new(window, min_match).compress(data));
+    PyBytes::new(py, &compressed)
+}
+
+/// Compress `data` into a self-describing frame, which `decompress` reads
+/// without being told the parameters
+#[pyfunction]
+#[pyo3(signature = (data, window=4096, min_match=3))]
+fn compress_framed<'py>(py: Python<'py>, data: BytesView, window: usize, min_match: usize) -> Bound<'py, PyBytes> {
+    let data = data.as_slice();
+    let compressed = py.allow_threads(|| LZSS::new(window, min_match).compress_framed(data));
+    PyBytes::new(py, &compressed)
+}
+
+/// Decompress a frame, or a raw stream compressed with the given parameters
+#[pyfunction]
+#[pyo3(signature = (data, window=4096, min_match=3))]
+fn decompress<'py>(py: Python<'py>, data: BytesView, window: usize, min_match: usize) -> PyResult<Bound<'py, PyBytes>> {
+    let data = data.as_slice();
+    let decompressed = py
+        .allow_threads(|| LZSS::new(window, min_match).try_decompress(data))
+        .map_err(|error| PyValueError::new_err(error.to_string()))?;
+    Ok(PyBytes::new(py, &decompressed))
+}
+
+/// `{"window": ..., "min_match": ..., "nice_length": ...}` for `params`
+fn parameters_dict(py: Python<'_>, params: CompressionParameters) -> PyResult<Bound<'_, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("window", params.window_size)?;
+    dict.set_item("min_match", params.min_match_length)?;
+    dict.set_item("nice_length", params.nice_length)?;
+    Ok(dict)
+}
+
+/// Recommend parameters for `data` from match statistics, without
+/// benchmarking (well under a second)
+#[pyfunction]
+fn quick_tune<'py>(py: Python<'py>, data: BytesView) -> PyResult<Bound<'py, PyDict>> {
+    let data = data.as_slice();
+    let result = py.allow_threads(|| Tuner::new(TunerConfig::default()).quick_tune(data));
+    let dict = parameters_dict(py, result.parameters)?;
+    dict.set_item("entropy", result.entropy)?;
+    dict.set_item("match_coverage", result.match_coverage)?;
+    Ok(dict)
+}
+
+/// Benchmark parameter sets on `data` and return the best one
+///
+/// `ratio_priority` weighs compression ratio against speed (1.0 = only
+/// ratio, 0.0 = only speed).
+#[pyfunction]
+#[pyo3(signature = (data, ratio_priority=0.5, max_iterations=30, seed=None))]
+fn tune<'py>(
+    py: Python<'py>,
+    data: BytesView,
+    ratio_priority: f64,
+    max_iterations: usize,
+    seed: Option<u64>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let config = TunerConfig { ratio_priority, max_iterations, random_seed: seed, ..TunerConfig::default() };
+    let data = data.as_slice();
+    let result = py.allow_threads(|| Tuner::new(config).tune_for_data(data, None));
+    let dict = parameters_dict(py, result.best_parameters)?;
+    dict.set_item("compression_ratio", result.best_result.compression_ratio())?;
+    dict.set_item("iterations", result.iterations)?;
+    Ok(dict)
+}
+
+#[pymodule]
+pub(crate) fn rustzss(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(compress, module)?)?;
+    module.add_function(wrap_pyfunction!(compress_framed, module)?)?;
+    module.add_function(wrap_pyfunction!(decompress, module)?)?;
+    module.add_function(wrap_pyfunction!(quick_tune, module)?)?;
+    module.add_function(wrap_pyfunction!(tune, module)?)?;
+    Ok(())
+}
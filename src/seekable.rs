@@ -0,0 +1,216 @@
+//! Seekable block-indexed archives
+//!
+//! The input is split into blocks that are compressed independently as
+//! frames, and a footer indexes where each block starts in the archive and
+//! in the content. A reader can then decode only the blocks covering a
+//! byte range, e.g. one asset inside a large game archive.
+//!
+//! ```text
+//! frame 0 | frame 1 | ... | index | content_size (u64 LE)
+//!     | block_count (u32 LE) | magic "RLZI"
+//! ```
+//!
+//! Each index entry is the offset of the block's frame in the archive
+//! followed by the offset of its first byte in the content (both u64 LE).
+//! Blocks are frames, so the archive carries its own decoding parameters.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{LzssError, LZSS};
+
+/// Magic bytes ending a seekable archive
+pub const SEEKABLE_MAGIC: [u8; 4] = *b"RLZI";
+
+/// Uncompressed bytes per block by default
+pub const DEFAULT_SEEKABLE_BLOCK_SIZE: usize = 64 * 1024;
+
+/// Size of one index entry in bytes
+const ENTRY_SIZE: usize = 16;
+
+/// Size of the footer after the index in bytes
+const TRAILER_SIZE: usize = 16;
+
+/// Location of one block, as recorded in the index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockEntry {
+    /// Offset of the block's frame in the archive
+    pub compressed_offset: usize,
+    /// Offset of the block's first byte in the decompressed content
+    pub content_offset: usize,
+}
+
+/// Read access to a seekable archive held in memory
+pub struct SeekableArchive<'a> {
+    archive: &'a [u8],
+    blocks: Vec<BlockEntry>,
+    index_offset: usize,
+    content_size: usize,
+}
+
+impl<'a> SeekableArchive<'a> {
+    /// Read the index of `archive`
+    ///
+    /// Only the footer is parsed; blocks are decoded when read. A missing
+    /// footer or an index that doesn't describe consecutive blocks inside
+    /// the archive is an `InvalidIndex` error.
+    pub fn open(archive: &'a [u8]) -> Result<Self, LzssError> {
+        let trailer_start = archive.len().checked_sub(TRAILER_SIZE).ok_or(LzssError::InvalidIndex)?;
+        let trailer = &archive[trailer_start..];
+        if trailer[12..] != SEEKABLE_MAGIC {
+            return Err(LzssError::InvalidIndex);
+        }
+        let content_size = read_u64(&trailer[..8])?;
+        let block_count = u32::from_le_bytes([trailer[8], trailer[9], trailer[10], trailer[11]]) as usize;
+
+        let index_offset = block_count
+            .checked_mul(ENTRY_SIZE)
+            .and_then(|index_size| trailer_start.checked_sub(index_size))
+            .ok_or(LzssError::InvalidIndex)?;
+        let blocks = archive[index_offset..trailer_start]
+            .chunks_exact(ENTRY_SIZE)
+            .map(|entry| {
+                Ok(BlockEntry {
+                    compressed_offset: read_u64(&entry[..8])?,
+                    content_offset: read_u64(&entry[8..])?,
+                })
+            })
+            .collect::<Result<Vec<_>, LzssError>>()?;
+
+        // Blocks must be non-empty and follow each other from offset 0
+        let mut next = BlockEntry { compressed_offset: 0, content_offset: 0 };
+        for (i, block) in blocks.iter().enumerate() {
+            let in_order = if i == 0 {
+                *block == next
+            } else {
+                block.compressed_offset > next.compressed_offset && block.content_offset > next.content_offset
+            };
+            if !in_order {
+                return Err(LzssError::InvalidIndex);
+            }
+            next = *block;
+        }
+        if !blocks.is_empty() && (next.compressed_offset >= index_offset || next.content_offset >= content_size) {
+            return Err(LzssError::InvalidIndex);
+        }
+        if blocks.is_empty() && (index_offset != 0 || content_size != 0) {
+            return Err(LzssError::InvalidIndex);
+        }
+
+        Ok(SeekableArchive { archive, blocks, index_offset, content_size })
+    }
+
+    /// Size of the decompressed content
+    pub fn content_size(&self) -> usize {
+        self.content_size
+    }
+
+    /// The blocks in content order
+    pub fn blocks(&self) -> &[BlockEntry] {
+        &self.blocks
+    }
+
+    /// Compressed and content ranges of block `index`
+    fn block_ranges(&self, index: usize) -> (Range<usize>, Range<usize>) {
+        let block = self.blocks[index];
+        let (compressed_end, content_end) = match self.blocks.get(index + 1) {
+            Some(next) => (next.compressed_offset, next.content_offset),
+            None => (self.index_offset, self.content_size),
+        };
+        (block.compressed_offset..compressed_end, block.content_offset..content_end)
+    }
+
+    /// Decompress block `index`
+    ///
+    /// The block is validated before anything is allocated, and a block
+    /// whose frame doesn't decode to the size the index gives it is an
+    /// `InvalidIndex` error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not below the number of blocks.
+    pub fn read_block(&self, index: usize) -> Result<Vec<u8>, LzssError> {
+        let (compressed, content) = self.block_ranges(index);
+        // Blocks are frames, which decode with the parameters in their header
+        let lzss = LZSS::new(4096, 3);
+        let frame = &self.archive[compressed];
+        if lzss.validate(frame)?.content_size != content.len() {
+            return Err(LzssError::InvalidIndex);
+        }
+        let mut output = vec![0; content.len()];
+        lzss.decompress_into(frame, &mut output)?;
+        Ok(output)
+    }
+
+    /// Decompress the bytes in `range` of the content, decoding only the
+    /// blocks that overlap it
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range extends past the end of the content.
+    pub fn read_range(&self, range: Range<usize>) -> Result<Vec<u8>, LzssError> {
+        assert!(
+            range.start <= range.end && range.end <= self.content_size,
+            "range {:?} out of bounds for content of {} bytes",
+            range,
+            self.content_size
+        );
+        // The range comes from the footer, so the output grows with the
+        // decoded blocks rather than being reserved up front
+        let mut output = Vec::new();
+        if range.is_empty() {
+            return Ok(output);
+        }
+
+        let first = self.blocks.partition_point(|block| block.content_offset <= range.start) - 1;
+        for index in first..self.blocks.len() {
+            let (_, content) = self.block_ranges(index);
+            if content.start >= range.end {
+                break;
+            }
+            let block = self.read_block(index)?;
+            let start = range.start.max(content.start) - content.start;
+            let end = range.end.min(content.end) - content.start;
+            output.extend_from_slice(&block[start..end]);
+        }
+        Ok(output)
+    }
+
+    /// Decompress the whole content
+    pub fn decompress_all(&self) -> Result<Vec<u8>, LzssError> {
+        self.read_range(0..self.content_size)
+    }
+}
+
+/// Read a u64 LE offset that must fit in `usize`
+fn read_u64(bytes: &[u8]) -> Result<usize, LzssError> {
+    let mut field = [0u8; 8];
+    field.copy_from_slice(bytes);
+    usize::try_from(u64::from_le_bytes(field)).map_err(|_| LzssError::InvalidIndex)
+}
+
+impl LZSS {
+    /// Compress `input` into a seekable archive of independently
+    /// compressed blocks of `block_size` bytes (see `SeekableArchive`)
+    ///
+    /// Matches can't cross block boundaries, so smaller blocks make reads
+    /// cheaper but compress worse.
+    pub fn compress_seekable(&self, input: &[u8], block_size: usize) -> Vec<u8> {
+        let block_size = block_size.max(1);
+        let mut archive = Vec::new();
+        let mut index = Vec::new();
+        for (i, block) in input.chunks(block_size).enumerate() {
+            index.extend_from_slice(&(archive.len() as u64).to_le_bytes());
+            index.extend_from_slice(&((i * block_size) as u64).to_le_bytes());
+            archive.extend(self.compress_framed(block));
+        }
+
+        let block_count = index.len() / ENTRY_SIZE;
+        archive.extend(index);
+        archive.extend_from_slice(&(input.len() as u64).to_le_bytes());
+        archive.extend_from_slice(&(block_count as u32).to_le_bytes());
+        archive.extend_from_slice(&SEEKABLE_MAGIC);
+        archive
+    }
+}
@@ -0,0 +1,60 @@
+//! Compressed serde values
+//!
+//! Serializes values with bincode and wraps the result in a self-describing
+//! frame, for compact save games and cache files that can be read back
+//! without knowing the compression parameters.
+
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{LzssError, LZSS};
+
+/// Errors from the compressed serde helpers
+#[derive(Debug)]
+pub enum SerdeError {
+    /// The value couldn't be serialized, or the decompressed bytes couldn't
+    /// be deserialized into the requested type
+    Bincode(bincode::Error),
+    /// The compressed data is corrupt
+    Decompress(LzssError),
+}
+
+impl fmt::Display for SerdeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SerdeError::Bincode(e) => write!(f, "serialization failed: {}", e),
+            SerdeError::Decompress(e) => write!(f, "decompression failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SerdeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SerdeError::Bincode(e) => Some(e),
+            SerdeError::Decompress(e) => Some(e),
+        }
+    }
+}
+
+/// Parameters used to compress values
+fn codec() -> LZSS {
+    LZSS::new(4096, 3)
+}
+
+/// Serialize `value` with bincode and compress it into a frame
+pub fn to_compressed_vec<T: Serialize>(value: &T) -> Result<Vec<u8>, SerdeError> {
+    let bytes = bincode::serialize(value).map_err(SerdeError::Bincode)?;
+    Ok(codec().compress_framed(&bytes))
+}
+
+/// Decompress a buffer written by `to_compressed_vec` and deserialize it
+pub fn from_compressed_slice<T: DeserializeOwned>(input: &[u8]) -> Result<T, SerdeError> {
+    let mut bytes = Vec::new();
+    for chunk in codec().decompress_iter(input) {
+        bytes.extend_from_slice(&chunk.map_err(SerdeError::Decompress)?);
+    }
+    bincode::deserialize(&bytes).map_err(SerdeError::Bincode)
+}
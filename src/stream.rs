@@ -0,0 +1,366 @@
+//! Streaming compression
+//!
+//! `Encoder` compresses input fed to it in chunks and `LzssWriter` the
+//! bytes written to it, in blocks, so the input never has to be in memory
+//! at once. Blocks are compressed with a `Compressor`, so matches still
+//! reach back across block boundaries. `LzssReader` decodes such a stream
+//! on the fly, holding only the window and the current block, and
+//! `Decoder` decodes it from bytes pushed as they arrive. `copy_compress`
+//! and `copy_decompress` pipe a reader into a writer through them.
+//!
+//! The stream is a sequence of blocks, each a 4-byte little-endian length
+//! followed by that many bytes of `Compressor` output. It carries no codec
+//! parameters: the reader must be configured like the writer.
+//!
+//! `Encoder` and `Decoder` only need `alloc`; the `std::io` adapters need
+//! the `std` feature (see `embedded` for `embedded-io` ones).
+
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+use crate::compressor::{Compressor, Decompressor};
+use crate::{LzssError, LZSS};
+
+/// Uncompressed bytes per block by default
+pub const DEFAULT_BLOCK_SIZE: usize = 64 * 1024;
+
+/// Push-based encoder for input of unknown length
+///
+/// Input is fed in chunks of any size; whenever a block fills up it is
+/// compressed and returned, so output is produced progressively with the
+/// window carried across blocks. `flush` emits a short block on demand
+/// (e.g. when a sensor goes idle) and `finish` emits the last one.
+pub struct Encoder {
+    compressor: Compressor,
+    buffer: Vec<u8>,
+    block_size: usize,
+}
+
+impl Encoder {
+    /// Create an encoder with the given codec
+    pub fn new(lzss: LZSS) -> Self {
+        Encoder {
+            compressor: Compressor::new(lzss),
+            buffer: Vec::new(),
+            block_size: DEFAULT_BLOCK_SIZE,
+        }
+    }
+
+    /// Change the number of uncompressed bytes per block
+    ///
+    /// Larger blocks compress slightly better; smaller ones bound the
+    /// memory of both ends and produce output sooner. The decoder doesn't
+    /// need the same setting.
+    pub fn with_block_size(mut self, block_size: usize) -> Self {
+        self.set_block_size(block_size);
+        self
+    }
+
+    pub(crate) fn set_block_size(&mut self, block_size: usize) {
+        self.block_size = block_size.clamp(1, u32::MAX as usize / 2);
+    }
+
+    /// Number of fed bytes waiting for their block to fill up
+    pub fn pending(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Feed more input, returning the compressed blocks it completed
+    pub fn feed(&mut self, mut input: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+        while !input.is_empty() {
+            let count = self.buffer_some(input);
+            input = &input[count..];
+            if self.is_block_full() {
+                self.take_block(&mut output);
+            }
+        }
+        output
+    }
+
+    /// Compress the pending input into a (possibly short) block now
+    pub fn flush(&mut self) -> Vec<u8> {
+        let mut output = Vec::new();
+        self.take_block(&mut output);
+        output
+    }
+
+    /// Compress the pending input, ending the stream
+    pub fn finish(mut self) -> Vec<u8> {
+        self.flush()
+    }
+
+    /// Buffer as much of `input` as fits in the current block, returning
+    /// how much was taken
+    pub(crate) fn buffer_some(&mut self, input: &[u8]) -> usize {
+        let count = input.len().min(self.block_size - self.buffer.len());
+        self.buffer.extend_from_slice(&input[..count]);
+        count
+    }
+
+    pub(crate) fn is_block_full(&self) -> bool {
+        self.buffer.len() == self.block_size
+    }
+
+    /// Compress the buffered input into a length-prefixed block appended
+    /// to `output`
+    fn take_block(&mut self, output: &mut Vec<u8>) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let block = self.compressor.compress(&self.buffer);
+        self.buffer.clear();
+
+        output.extend_from_slice(&(block.len() as u32).to_le_bytes());
+        output.extend_from_slice(&block);
+    }
+}
+
+/// Writer that compresses everything written to it into `inner`
+///
+/// Data is buffered until a block is full. Call `finish` (or `flush`) to
+/// write out the last partial block; dropping the writer also does so but
+/// ignores errors.
+#[cfg(feature = "std")]
+pub struct LzssWriter<W: Write> {
+    inner: Option<W>,
+    encoder: Encoder,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> LzssWriter<W> {
+    /// Create a writer compressing into `inner` with the given codec
+    pub fn new(inner: W, lzss: LZSS) -> Self {
+        LzssWriter {
+            inner: Some(inner),
+            encoder: Encoder::new(lzss),
+        }
+    }
+
+    /// Change the number of uncompressed bytes per block (see
+    /// `Encoder::with_block_size`)
+    pub fn with_block_size(mut self, block_size: usize) -> Self {
+        self.encoder.set_block_size(block_size);
+        self
+    }
+
+    /// The underlying writer
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().expect("writer is only taken by finish")
+    }
+
+    /// Compress the buffered data into a block and write it out
+    fn write_block(&mut self) -> io::Result<()> {
+        let block = self.encoder.flush();
+        if block.is_empty() {
+            return Ok(());
+        }
+        self.inner.as_mut().expect("writer is only taken by finish").write_all(&block)
+    }
+
+    /// Write out the last block and return the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_block()?;
+        let mut inner = self.inner.take().expect("writer is only taken by finish");
+        inner.flush()?;
+        Ok(inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> Write for LzssWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let count = self.encoder.buffer_some(buf);
+        if self.encoder.is_block_full() {
+            self.write_block()?;
+        }
+        Ok(count)
+    }
+
+    /// Write out the buffered data as a (possibly short) block and flush
+    /// the underlying writer
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_block()?;
+        self.inner.as_mut().expect("writer is only taken by finish").flush()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> Drop for LzssWriter<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.write_block();
+        }
+    }
+}
+
+/// Output of one `Decoder::decode` call
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecodeProgress {
+    /// Data decoded from the complete blocks in the input
+    pub output: Vec<u8>,
+    /// Input bytes used; the rest must be passed again with more data
+    pub consumed: usize,
+    /// Whether the unconsumed input is the start of a block that needs
+    /// more bytes to decode
+    pub needs_input: bool,
+}
+
+/// Push-based decoder for streams written by `Encoder` or `LzssWriter`
+///
+/// Compressed bytes can be passed as they arrive (e.g. from a socket).
+/// Each call decodes the complete blocks at the start of the input and
+/// reports how much it used; the caller keeps the unconsumed tail and
+/// passes it again with the next bytes. A stream that ends while
+/// `needs_input` is set was truncated.
+pub struct Decoder {
+    decompressor: Decompressor,
+}
+
+impl Decoder {
+    /// Create a decoder for a stream written with the given codec
+    pub fn new(lzss: LZSS) -> Self {
+        Decoder { decompressor: Decompressor::new(lzss) }
+    }
+
+    /// Decode the complete blocks at the start of `input`
+    ///
+    /// A corrupt block stops decoding there, after the output of the blocks
+    /// before it has been returned: the error is reported once the corrupt
+    /// block is at the start of the input.
+    pub fn decode(&mut self, input: &[u8]) -> Result<DecodeProgress, LzssError> {
+        let mut progress = DecodeProgress::default();
+        loop {
+            let rest = &input[progress.consumed..];
+            let Some(header) = rest.get(..4) else {
+                progress.needs_input = !rest.is_empty();
+                return Ok(progress);
+            };
+            let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+            let Some(block) = rest.get(4..4 + len) else {
+                progress.needs_input = true;
+                return Ok(progress);
+            };
+
+            match self.decompressor.try_decompress(block) {
+                Ok(output) => progress.output.extend(output),
+                // Hand out what was decoded; the next call reports the error
+                Err(_) if progress.consumed > 0 => return Ok(progress),
+                Err(e) => return Err(e),
+            }
+            progress.consumed += 4 + len;
+        }
+    }
+}
+
+/// Reader that decompresses a stream written by `LzssWriter`
+///
+/// Corrupt blocks are reported as `InvalidData` errors and a stream that
+/// ends inside a block as `UnexpectedEof`.
+#[cfg(feature = "std")]
+pub struct LzssReader<R: Read> {
+    inner: R,
+    decompressor: Decompressor,
+    block: Vec<u8>,
+    pos: usize,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> LzssReader<R> {
+    /// Create a reader decompressing `inner` with the codec the stream was
+    /// written with
+    pub fn new(inner: R, lzss: LZSS) -> Self {
+        LzssReader {
+            inner,
+            decompressor: Decompressor::new(lzss),
+            block: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// The underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Return the underlying reader, dropping any undelivered output
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Decode the next block, returning false at the end of the stream
+    fn next_block(&mut self) -> io::Result<bool> {
+        let mut header = [0u8; 4];
+        let mut filled = 0;
+        while filled < header.len() {
+            match self.inner.read(&mut header[filled..]) {
+                Ok(0) if filled == 0 => return Ok(false),
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        // Read through `take` so a corrupt length can't force a huge allocation
+        let len = u32::from_le_bytes(header) as u64;
+        let mut compressed = Vec::new();
+        (&mut self.inner).take(len).read_to_end(&mut compressed)?;
+        if (compressed.len() as u64) < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        self.block = self.decompressor
+            .try_decompress(&compressed)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.pos = 0;
+        Ok(true)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> Read for LzssReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Skip over empty blocks, which a writer never produces but are valid
+        while self.pos == self.block.len() {
+            if buf.is_empty() || !self.next_block()? {
+                return Ok(0);
+            }
+        }
+
+        let count = buf.len().min(self.block.len() - self.pos);
+        buf[..count].copy_from_slice(&self.block[self.pos..self.pos + count]);
+        self.pos += count;
+        Ok(count)
+    }
+}
+
+/// Compress everything `reader` yields into `writer`, like `io::copy`
+///
+/// Memory use is bounded by the block size and the window, whatever the
+/// input size. Returns the number of uncompressed bytes read.
+#[cfg(feature = "std")]
+pub fn copy_compress<R, W>(reader: &mut R, writer: &mut W, lzss: LZSS) -> io::Result<u64>
+where
+    R: Read + ?Sized,
+    W: Write + ?Sized,
+{
+    let mut encoder = LzssWriter::new(writer, lzss);
+    let copied = io::copy(reader, &mut encoder)?;
+    encoder.finish()?;
+    Ok(copied)
+}
+
+/// Decompress a stream written by `copy_compress` (or `LzssWriter`) from
+/// `reader` into `writer`
+///
+/// Returns the number of decompressed bytes written.
+#[cfg(feature = "std")]
+pub fn copy_decompress<R, W>(reader: &mut R, writer: &mut W, lzss: LZSS) -> io::Result<u64>
+where
+    R: Read + ?Sized,
+    W: Write + ?Sized,
+{
+    io::copy(&mut LzssReader::new(reader, lzss), writer)
+}
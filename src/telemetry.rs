@@ -0,0 +1,62 @@
+//! Compressed telemetry records
+//!
+//! Serializes small records with postcard and compresses them as raw
+//! streams against a preset dictionary, for devices that send many short,
+//! repetitive messages over constrained links. There is no frame header:
+//! both sides agree on the dictionary (typically a few representative
+//! records) and the parameters up front. Only `alloc` is needed, so the
+//! helpers build for `no_std` devices.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{LzssError, LZSS};
+
+/// Errors from the telemetry helpers
+#[derive(Debug)]
+pub enum TelemetryError {
+    /// The record couldn't be serialized, or the decompressed bytes
+    /// couldn't be deserialized into the requested type
+    Postcard(postcard::Error),
+    /// The compressed record is corrupt
+    Decompress(LzssError),
+}
+
+impl fmt::Display for TelemetryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TelemetryError::Postcard(e) => write!(f, "serialization failed: {}", e),
+            TelemetryError::Decompress(e) => write!(f, "decompression failed: {}", e),
+        }
+    }
+}
+
+impl core::error::Error for TelemetryError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            TelemetryError::Postcard(e) => Some(e),
+            TelemetryError::Decompress(e) => Some(e),
+        }
+    }
+}
+
+/// Parameters used to compress records
+fn codec() -> LZSS {
+    LZSS::new(4096, 3)
+}
+
+/// Serialize `record` with postcard and compress it against `dictionary`
+pub fn to_compressed_record<T: Serialize>(record: &T, dictionary: &[u8]) -> Result<Vec<u8>, TelemetryError> {
+    let bytes = postcard::to_allocvec(record).map_err(TelemetryError::Postcard)?;
+    Ok(codec().compress_with_dictionary(dictionary, &bytes))
+}
+
+/// Decompress a record written by `to_compressed_record` with the same
+/// dictionary and deserialize it
+pub fn from_compressed_record<T: DeserializeOwned>(input: &[u8], dictionary: &[u8]) -> Result<T, TelemetryError> {
+    let bytes = codec().decompress_with_dictionary(dictionary, input).map_err(TelemetryError::Decompress)?;
+    postcard::from_bytes(&bytes).map_err(TelemetryError::Postcard)
+}
@@ -1,9 +1,15 @@
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
+#[allow(clippy::module_inception)]
 mod tests {
     use crate::LZSS;
     use rand::prelude::*;
     use std::time::Instant;
 
+    // Lets the benchmark tests see allocation counts
+    #[cfg(feature = "alloc-count")]
+    #[global_allocator]
+    static ALLOCATOR: crate::autotune::alloc::CountingAllocator = crate::autotune::alloc::CountingAllocator;
+
     // Generate random data of specified size
     fn generate_random_data(size: usize) -> Vec<u8> {
         let mut rng = rand::thread_rng();
@@ -36,7 +42,7 @@ mod tests {
         let compress_time = start.elapsed();
 
         let start = Instant::now();
-        let decompressed = lzss.decompress(&compressed);
+        let decompressed = lzss.try_decompress(&compressed).unwrap();
         let decompress_time = start.elapsed();
 
         let ratio = (compressed.len() as f64) / (data.len() as f64) * 100.0;
@@ -79,7 +85,7 @@ mod tests {
         let lzss = LZSS::new(4096, 3);
         let data = Vec::new();
         let compressed = lzss.compress(&data);
-        let decompressed = lzss.decompress(&compressed);
+        let decompressed = lzss.try_decompress(&compressed).unwrap();
         assert_eq!(data, decompressed);
     }
 
@@ -88,7 +94,7 @@ mod tests {
         let lzss = LZSS::new(4096, 3);
         let data = vec![42];  // Just a single byte
         let compressed = lzss.compress(&data);
-        let decompressed = lzss.decompress(&compressed);
+        let decompressed = lzss.try_decompress(&compressed).unwrap();
         assert_eq!(data, decompressed);
     }
 
@@ -177,4 +183,2765 @@ mod tests {
             assert!(test_compression_cycle(&lzss, &data, &name, true));
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    #[cfg(not(feature = "small-target"))]
+    fn test_long_distance_matching() {
+        // A block repeated 300KB later, far beyond the 64KB window
+        let block = generate_random_data(50_000);
+        let mut data = block.clone();
+        data.extend_from_slice(&generate_random_data(300_000));
+        data.extend_from_slice(&block);
+
+        let plain = LZSS::new(65535, 3);
+        let ldm = LZSS::new(65535, 3).with_long_distance_matching(1 << 20);
+        assert!(test_compression_cycle(&ldm, &data, "Long-distance duplication", true));
+
+        let plain_size = plain.compress(&data).len();
+        let ldm_size = ldm.compress(&data).len();
+        assert!(ldm_size + 40_000 < plain_size,
+                "LDM should find the far match: {} vs {}", ldm_size, plain_size);
+    }
+
+    #[test]
+    fn test_adaptive_blocks() {
+        // Text, a binary table and random data in one file
+        let mut data = generate_pattern_data(100_000);
+        for i in 0..20_000u32 {
+            data.extend_from_slice(&(i % 700).to_le_bytes());
+        }
+        data.extend_from_slice(&generate_random_data(70_000));
+
+        let lzss = LZSS::new(4096, 3);
+        let compressed = lzss.compress_adaptive(&data);
+        assert!(compressed.len() < lzss.compress(&data).len());
+        assert_eq!(LZSS::try_decompress_adaptive(&compressed).unwrap(), data);
+
+        // Empty input still produces a valid stream
+        let empty = lzss.compress_adaptive(&[]);
+        assert!(LZSS::try_decompress_adaptive(&empty).unwrap().is_empty());
+
+        // The deprecated decoder patches up a truncated stream instead
+        let truncated = &compressed[..compressed.len() - 1];
+        assert_eq!(LZSS::try_decompress_adaptive(truncated), Err(crate::LzssError::TruncatedInput));
+        #[allow(deprecated)]
+        let patched = LZSS::decompress_adaptive(truncated);
+        assert!(patched.len() < data.len());
+    }
+
+    #[test]
+    fn test_per_block_parameters() {
+        use crate::block::{BlockIter, BlockParameters};
+
+        let text = generate_pattern_data(50_000);
+        let table: Vec<u8> = (0..50_000u32).map(|i| (i % 251) as u8).collect();
+        let compressed = LZSS::compress_blocks(vec![
+            (&text[..], BlockParameters::new(1024, 3)),
+            (&table[..], BlockParameters::new(8192, 5)),
+        ]);
+
+        let params: Vec<_> = BlockIter::new(&compressed).map(|(params, _)| params).collect();
+        assert_eq!(params, vec![BlockParameters::new(1024, 3), BlockParameters::new(8192, 5)]);
+
+        let mut expected = text.clone();
+        expected.extend_from_slice(&table);
+        assert_eq!(LZSS::try_decompress_adaptive(&compressed).unwrap(), expected);
+
+        // The decoder honors the declared window: shrinking it in the header
+        // makes the (now out-of-window) references invalid
+        let mut data = generate_random_data(3000);
+        data.extend_from_within(..3000);
+        let mut compressed = LZSS::compress_blocks(vec![(&data[..], BlockParameters::new(4096, 3))]);
+        compressed[7..9].copy_from_slice(&1024u16.to_le_bytes());
+        assert!(matches!(
+            LZSS::try_decompress_adaptive(&compressed),
+            Err(crate::LzssError::InvalidDistance { .. })
+        ));
+
+        // Out-of-range minimum match lengths are clamped before they are
+        // recorded, even when set directly on the fields
+        let mut params = BlockParameters::new(4096, 3);
+        params.min_match_length = 300;
+        let compressed = LZSS::compress_blocks(vec![(&text[..], params)]);
+        let (recorded, _) = BlockIter::new(&compressed).next().unwrap();
+        assert_eq!(recorded.min_match_length, crate::MAX_MIN_MATCH_LENGTH);
+        assert_eq!(LZSS::try_decompress_adaptive(&compressed).unwrap(), text);
+        assert_eq!(BlockParameters::new(4096, 0).min_match_length, 1);
+    }
+
+    #[test]
+    fn test_framed_self_describing() {
+        let data = generate_pattern_data(100_000);
+
+        // Frames decode with any instance, whatever its configuration
+        for lzss in [
+            LZSS::new(4096, 5),
+            LZSS::new(65535, 2).with_long_distance_matching(1 << 20),
+        ] {
+            let framed = lzss.compress_framed(&data);
+            assert_eq!(LZSS::new(256, 3).try_decompress(&framed).unwrap(), data);
+        }
+
+        let empty = LZSS::new(4096, 4).compress_framed(&[]);
+        assert!(LZSS::new(4096, 3).try_decompress(&empty).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_decode_any() {
+        use crate::compat;
+        use crate::detect::Format;
+        use crate::{LengthField, LzssError};
+
+        let data = generate_pattern_data(10_000);
+        let lzss = LZSS::new(4096, 3);
+
+        let mut padded = compat::compress_lz10(&data);
+        padded.resize(padded.len().next_multiple_of(4), 0);
+        let cases = [
+            (lzss.compress_framed(&data), Format::Framed),
+            (lzss.compress_adaptive(&data), Format::Blocks),
+            (lzss.compress(&data), Format::Raw),
+            (compat::compress_szdd(&data, b'T'), Format::Szdd),
+            (padded, Format::Lz10),
+            (compat::compress_lz11(&data), Format::Lz11),
+        ];
+        for (compressed, format) in cases {
+            assert_eq!(lzss.decode_any(&compressed), Ok((data.clone(), format)));
+        }
+
+        // The raw stream above starts with the LZ10 type byte (10,000 is
+        // 0x2710), but its tokens don't make a stream of the size that
+        // follows
+        assert_eq!(lzss.compress(&data)[0], compat::LZ10_TAG);
+
+        // A size header the payload can't possibly produce
+        assert_eq!(lzss.decode_any(&[0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x41]), Err(LzssError::InvalidHeader));
+
+        // Corrupt input is detected but reported instead of patched up
+        let mut framed = lzss.compress_framed(&data);
+        framed.truncate(framed.len() - 10);
+        assert!(lzss.decode_any(&framed).is_err());
+        let blocks = lzss.compress_adaptive(&data);
+        assert_eq!(lzss.decode_any(&blocks[..blocks.len() - 10]), Err(LzssError::TruncatedInput));
+
+        // Wider length fields make far smaller raw streams plausible
+        let zeros = vec![0; 100_000];
+        let wide = LZSS::new(4096, 3).with_length_field(LengthField::TwoBytes);
+        let compressed = wide.compress(&zeros);
+        assert!(compressed.len() < 20);
+        assert_eq!(wide.decode_any(&compressed), Ok((zeros, Format::Raw)));
+    }
+
+    #[test]
+    fn test_inspect_headers() {
+        use crate::detect::{inspect, Format};
+        use crate::LengthField;
+
+        let data = generate_pattern_data(150_000);
+        let lzss = LZSS::new(4096, 4);
+
+        let info = inspect(&lzss.compress_framed(&data), 3, LengthField::OneByte).unwrap();
+        assert_eq!(info.format, Format::Framed);
+        assert_eq!(info.min_match_length, Some(4));
+        assert_eq!(info.offset_bytes, Some(2));
+        assert_eq!(info.content_size, data.len());
+
+        let info = inspect(&lzss.compress_adaptive(&data), 3, LengthField::OneByte).unwrap();
+        assert_eq!(info.format, Format::Blocks);
+        assert_eq!(info.block_count, 3);
+        assert_eq!(info.content_size, data.len());
+    }
+
+    #[test]
+    fn test_persistent_window() {
+        use crate::compressor::{Compressor, Decompressor};
+
+        let messages: Vec<Vec<u8>> = (0..20)
+            .map(|i| format!("{{\"id\": {}, \"status\": \"ok\", \"payload\": \"sensor reading\"}}", i).into_bytes())
+            .collect();
+
+        let mut compressor = Compressor::new(LZSS::new(4096, 3));
+        let mut decompressor = Decompressor::new(LZSS::new(4096, 3));
+        let mut shared_total = 0;
+        for message in &messages {
+            let compressed = compressor.compress(message);
+            shared_total += compressed.len();
+            assert_eq!(&decompressor.try_decompress(&compressed).unwrap(), message);
+        }
+
+        // Sharing context beats compressing each message on its own
+        let lzss = LZSS::new(4096, 3);
+        let independent_total: usize = messages.iter().map(|m| lzss.compress(m).len()).sum();
+        assert!(shared_total < independent_total / 2);
+
+        // After a reset the output is self-contained again
+        compressor.reset_window();
+        let compressed = compressor.compress(&messages[0]);
+        assert_eq!(lzss.try_decompress(&compressed).unwrap(), messages[0]);
+    }
+
+    #[cfg(feature = "autotune")]
+    #[test]
+    fn test_command_executor() {
+        use crate::autotune::{BenchmarkExecutor, CommandExecutor, CompressionParameters};
+
+        // A stand-in for a device harness reporting fixed timings
+        let executor = CommandExecutor::new("sh", vec![
+            "-c".to_string(),
+            "test -f {input} && echo compressed_size={window_size} && echo compression_ns={min_match}000 && echo decompression_ns=500".to_string(),
+        ]);
+        let result = executor.execute(&[1, 2, 3], CompressionParameters::new(4096, 3), None, 1).unwrap();
+        assert_eq!(result.original_size, 3);
+        assert_eq!(result.compressed_size, 4096);
+        assert_eq!(result.compression_time.as_nanos(), 3000);
+        assert_eq!(result.decompression_time.as_nanos(), 500);
+    }
+
+    #[cfg(feature = "autotune")]
+    #[test]
+    fn test_stratified_sample() {
+        use crate::autotune::tuner::stratified_sample;
+
+        // 30 small files and 10 large ones
+        let assets: Vec<(usize, usize)> = (0..40)
+            .map(|i| (i, if i < 30 { 1_000 } else { 1_000_000 }))
+            .collect();
+        let mut rng = rand::thread_rng();
+
+        // Four strata over the sorted sizes always include a large file
+        let sample = stratified_sample(&assets, 4, 0, &mut rng);
+        assert_eq!(sample.len(), 4);
+        assert!(sample.iter().any(|&i| i >= 30));
+
+        // The byte minimum pulls in more assets
+        let sample = stratified_sample(&assets, 4, 3_500_000, &mut rng);
+        let total: usize = sample.iter().map(|&i| assets[i].1).sum();
+        assert!(total >= 3_500_000);
+    }
+
+    #[cfg(feature = "autotune")]
+    #[test]
+    fn test_quick_tune() {
+        use crate::autotune::{Tuner, TunerConfig};
+
+        let tuner = Tuner::new(TunerConfig::default());
+
+        // Random data: no repeats, pick the fastest settings
+        let result = tuner.quick_tune(&generate_random_data(200_000));
+        assert!(result.match_coverage < 0.01);
+        assert_eq!(result.parameters.min_match_length, 8);
+
+        // A pattern repeating every 36 bytes only needs a tiny window
+        let result = tuner.quick_tune(&generate_pattern_data(200_000));
+        assert!(result.match_coverage > 0.9);
+        assert_eq!(result.parameters.window_size, 256);
+        assert_eq!(result.parameters.min_match_length, 3);
+    }
+
+    #[cfg(feature = "autotune")]
+    #[test]
+    fn test_tuning_noise() {
+        use crate::autotune::{Tuner, TunerConfig};
+
+        let data = generate_pattern_data(20_000);
+        let config = TunerConfig {
+            max_iterations: 4,
+            random_seed: Some(7),
+            parallel: false,
+            ..TunerConfig::default()
+        };
+
+        // Every difference is significant only if at least 100% of the score
+        let mut tuner = Tuner::new(TunerConfig { min_significant_difference: 1.0, ..config.clone() });
+        let result = tuner.tune_for_data(&data, None);
+        assert_eq!(result.within_noise.len(), result.iterations - 1);
+        assert!(!result.within_noise.contains(&result.best_parameters));
+        assert!(!result.is_significant());
+        assert!(result.explanations.last().unwrap().contains("not a clear winner"));
+
+        // Timings vary between runs, and the variation is recorded
+        for r in &result.all_results {
+            assert!(r.throughput_noise() >= 0.0);
+        }
+        assert!(result.all_results.iter().any(|r| r.compression_time_stddev > std::time::Duration::ZERO));
+    }
+
+    #[cfg(feature = "autotune")]
+    #[test]
+    fn test_device_profiles() {
+        use crate::autotune::{DeviceProfile, Tuner, TunerConfig};
+
+        let data = generate_pattern_data(20_000);
+        let config = TunerConfig {
+            max_iterations: 12,
+            random_seed: Some(3),
+            parallel: false,
+            device_profiles: vec![
+                DeviceProfile::new("PC", 1.0, 3000.0),
+                DeviceProfile::new("mobile", 4.0, 50.0).with_memory_cap(1024),
+                DeviceProfile::new("tiny", 8.0, 10.0).with_memory_cap(16),
+            ],
+            ..TunerConfig::default()
+        };
+        let result = Tuner::new(config).tune_for_data(&data, None);
+
+        let devices: Vec<&str> = result.device_recommendations.iter().map(|r| r.device.as_str()).collect();
+        assert_eq!(devices, ["PC", "mobile", "tiny"]);
+
+        // The PC pick is the fastest load of everything tested
+        let pc = &result.device_recommendations[0];
+        let fastest = result.all_results.iter()
+            .map(|r| DeviceProfile::new("PC", 1.0, 3000.0).estimated_load_time(r).total_time)
+            .min()
+            .unwrap();
+        assert_eq!(pc.estimate.as_ref().unwrap().total_time, fastest);
+
+        // The memory cap limits the window, and a slower CPU scales decoding
+        let mobile = &result.device_recommendations[1];
+        if let Some(params) = mobile.parameters() {
+            assert!(params.window_size <= 1024);
+            let tested = result.all_results.iter().find(|r| r.parameters == params).unwrap();
+            assert_eq!(mobile.estimate.as_ref().unwrap().decompression_time, tested.decompression_time.mul_f64(4.0));
+        }
+
+        // Nothing fits a 16 byte window
+        assert!(result.device_recommendations[2].parameters().is_none());
+        assert!(result.device_recommendations[2].to_string().contains("no tested parameters"));
+    }
+
+    #[cfg(feature = "autotune")]
+    #[test]
+    fn test_benchmark_allocations() {
+        use crate::autotune::{run_benchmark, CompressionParameters};
+
+        let data = generate_pattern_data(20_000);
+        let result = run_benchmark(&data, CompressionParameters::new(4096, 3), None, 2);
+        if cfg!(feature = "alloc-count") {
+            let compression = result.compression_allocations.unwrap();
+            let decompression = result.decompression_allocations.unwrap();
+            // The match finder's hash table dominates compression
+            assert!(compression.allocations > decompression.allocations);
+            assert!(compression.bytes > 0);
+            assert!(decompression.bytes >= data.len());
+        } else {
+            assert!(result.compression_allocations.is_none());
+            assert!(result.decompression_allocations.is_none());
+        }
+    }
+
+    #[test]
+    fn test_nice_length() {
+        let mut data = Vec::new();
+        for i in 0..400 {
+            data.extend_from_slice(format!("entry {} of the long repeated record header; ", i % 23).as_bytes());
+        }
+
+        let quick = LZSS::new(4096, 3).with_nice_length(3).compress(&data);
+        let default = LZSS::new(4096, 3).compress(&data);
+        let thorough = LZSS::new(4096, 3).with_nice_length(255).compress(&data);
+        assert!(thorough.len() <= default.len());
+        assert!(default.len() < quick.len());
+
+        // Decoding doesn't depend on the setting
+        for compressed in [&quick, &default, &thorough] {
+            assert_eq!(LZSS::new(4096, 3).try_decompress(compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_lzss_writer() {
+        use crate::compressor::Decompressor;
+        use crate::stream::LzssWriter;
+        use std::io::Write;
+
+        let data = generate_pattern_data(10_000);
+        let mut writer = LzssWriter::new(Vec::new(), LZSS::new(4096, 3)).with_block_size(3000);
+        // Odd-sized writes straddle block boundaries
+        for piece in data.chunks(777) {
+            writer.write_all(piece).unwrap();
+        }
+        let compressed = writer.finish().unwrap();
+        assert!(compressed.len() < data.len() / 4);
+
+        // Four length-prefixed blocks of Compressor output
+        let mut decompressor = Decompressor::new(LZSS::new(4096, 3));
+        let mut decoded = Vec::new();
+        let mut blocks = 0;
+        let mut rest = &compressed[..];
+        while !rest.is_empty() {
+            let len = u32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+            decoded.extend(decompressor.try_decompress(&rest[4..4 + len]).unwrap());
+            rest = &rest[4 + len..];
+            blocks += 1;
+        }
+        assert_eq!(blocks, 4);
+        assert_eq!(decoded, data);
+
+        // Dropping the writer writes out the partial block
+        let mut output = Vec::new();
+        {
+            let mut writer = LzssWriter::new(&mut output, LZSS::new(4096, 3));
+            writer.write_all(b"abcabcabcabc").unwrap();
+        }
+        assert!(!output.is_empty());
+    }
+
+    #[test]
+    fn test_lzss_reader() {
+        use crate::stream::{LzssReader, LzssWriter};
+        use std::io::{ErrorKind, Read, Write};
+
+        let data = generate_pattern_data(50_000);
+        let mut writer = LzssWriter::new(Vec::new(), LZSS::new(4096, 3)).with_block_size(8192);
+        writer.write_all(&data).unwrap();
+        let compressed = writer.finish().unwrap();
+
+        // Small reads straddle block boundaries
+        let mut reader = LzssReader::new(&compressed[..], LZSS::new(4096, 3));
+        let mut decoded = Vec::new();
+        let mut buf = [0u8; 1000];
+        loop {
+            let n = reader.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            decoded.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(decoded, data);
+
+        // A stream cut inside a block is truncated, not just shorter
+        let mut reader = LzssReader::new(&compressed[..compressed.len() - 3], LZSS::new(4096, 3));
+        let error = reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+
+        // A corrupt block length makes the block undecodable
+        let mut corrupt = compressed.clone();
+        corrupt[0] ^= 0x40;
+        let error = LzssReader::new(&corrupt[..], LZSS::new(4096, 3)).read_to_end(&mut Vec::new()).unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::InvalidData | ErrorKind::UnexpectedEof));
+    }
+
+    #[test]
+    fn test_try_decompress_errors() {
+        use crate::LzssError;
+
+        let lzss = LZSS::new(4096, 3);
+        let data = generate_pattern_data(5_000);
+        let compressed = lzss.compress(&data);
+        assert_eq!(lzss.try_decompress(&compressed), Ok(data));
+
+        // Cut inside the tokens
+        assert!(matches!(
+            lzss.try_decompress(&compressed[..compressed.len() / 2]),
+            Err(LzssError::TruncatedInput) | Err(LzssError::SizeMismatch { .. })
+        ));
+        assert_eq!(lzss.try_decompress(&compressed[..3]), Err(LzssError::TruncatedInput));
+
+        // A match before any output
+        let bad_reference = [4, 0, 0, 0, 0x01, 9, 0, 1];
+        assert_eq!(
+            lzss.try_decompress(&bad_reference),
+            Err(LzssError::InvalidDistance { position: 0, distance: 9 })
+        );
+
+        // The header claims more than the tokens produce
+        let short = [9, 0, 0, 0, 0x00, b'a', b'b'];
+        assert!(matches!(
+            lzss.try_decompress(&short),
+            Err(LzssError::TruncatedInput) | Err(LzssError::SizeMismatch { .. })
+        ));
+
+        // The deprecated shim patches the corruption up instead
+        #[allow(deprecated)]
+        let patched = lzss.decompress(&bad_reference);
+        assert_eq!(patched, vec![0; 4]);
+    }
+
+    #[test]
+    fn test_compress_into() {
+        use crate::ffi::lzss_max_compressed_size;
+        use crate::{LengthField, LzssError};
+
+        let data = generate_pattern_data(10_000);
+        let lzss = LZSS::new(4096, 3);
+        let mut output = vec![0u8; lzss.max_compressed_size(data.len())];
+        let size = lzss.compress_into(&data, &mut output).unwrap();
+        assert_eq!(&output[..size], &lzss.compress(&data)[..]);
+
+        // Too small: the error reports the size needed
+        let mut small = vec![0u8; size - 1];
+        assert_eq!(
+            lzss.compress_into(&data, &mut small),
+            Err(LzssError::OutputTooSmall { required: size, capacity: size - 1 })
+        );
+        assert_eq!(lzss.compress_into(&[], &mut []), Ok(0));
+
+        // The bound matches the C API and holds for incompressible data,
+        // including parameters where a match token outgrows its bytes
+        let random = generate_random_data(5_000);
+        assert_eq!(lzss.max_compressed_size(random.len()), lzss_max_compressed_size(random.len() as _) as usize);
+        let mut noisy = random.clone();
+        for i in (0..noisy.len()).step_by(7) {
+            noisy[i] = b'x';
+        }
+        for lzss in [
+            LZSS::new(4096, 3),
+            LZSS::new(4096, 2).with_length_field(LengthField::TwoBytes),
+            LZSS::new(4096, 3).with_long_distance_matching(1 << 20),
+        ] {
+            for input in [&random, &noisy] {
+                let mut output = vec![0u8; lzss.max_compressed_size(input.len())];
+                let size = lzss.compress_into(input, &mut output).unwrap();
+                assert_eq!(lzss.try_decompress(&output[..size]).unwrap(), *input);
+            }
+        }
+    }
+
+    #[test]
+    fn test_incremental_encoder() {
+        use crate::stream::{Encoder, LzssReader};
+        use std::io::Read;
+
+        let data = generate_pattern_data(20_000);
+        let mut encoder = Encoder::new(LZSS::new(4096, 3)).with_block_size(4096);
+        let mut compressed = Vec::new();
+
+        // Output appears as soon as a block fills up
+        compressed.extend(encoder.feed(&data[..4000]));
+        assert!(compressed.is_empty());
+        assert_eq!(encoder.pending(), 4000);
+        compressed.extend(encoder.feed(&data[4000..9000]));
+        assert!(!compressed.is_empty());
+        assert_eq!(encoder.pending(), 9000 - 8192);
+
+        // Flushing emits the partial block
+        compressed.extend(encoder.flush());
+        assert_eq!(encoder.pending(), 0);
+        for chunk in data[9000..].chunks(333) {
+            compressed.extend(encoder.feed(chunk));
+        }
+        compressed.extend(encoder.finish());
+
+        let mut decoded = Vec::new();
+        LzssReader::new(&compressed[..], LZSS::new(4096, 3)).read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_incremental_decoder() {
+        use crate::stream::{Decoder, Encoder};
+
+        let data = generate_pattern_data(30_000);
+        let mut encoder = Encoder::new(LZSS::new(4096, 3)).with_block_size(5000);
+        let mut compressed = encoder.feed(&data);
+        compressed.extend(encoder.finish());
+
+        // Bytes arrive in small packets; unconsumed bytes are kept
+        let mut decoder = Decoder::new(LZSS::new(4096, 3));
+        let mut pending = Vec::new();
+        let mut decoded = Vec::new();
+        let mut needs_input = false;
+        for packet in compressed.chunks(100) {
+            pending.extend_from_slice(packet);
+            let progress = decoder.decode(&pending).unwrap();
+            decoded.extend(progress.output);
+            pending.drain(..progress.consumed);
+            needs_input = progress.needs_input;
+            assert_eq!(needs_input, !pending.is_empty());
+        }
+        assert!(!needs_input);
+        assert_eq!(decoded, data);
+
+        // A partial header or block is left alone
+        let mut decoder = Decoder::new(LZSS::new(4096, 3));
+        let progress = decoder.decode(&compressed[..3]).unwrap();
+        assert_eq!((progress.consumed, progress.needs_input), (0, true));
+        assert!(progress.output.is_empty());
+        assert_eq!(decoder.decode(&[]).unwrap(), Default::default());
+
+        // Corrupt blocks are errors
+        let mut corrupt = compressed.clone();
+        corrupt[8] ^= 0xFF;
+        let mut decoder = Decoder::new(LZSS::new(4096, 3));
+        assert!(decoder.decode(&corrupt).is_err());
+
+        // Blocks before a corrupt one are still delivered
+        let first_block = 4 + u32::from_le_bytes(compressed[..4].try_into().unwrap()) as usize;
+        let mut corrupt = compressed.clone();
+        corrupt[first_block + 8] ^= 0xFF;
+        let mut decoder = Decoder::new(LZSS::new(4096, 3));
+        let progress = decoder.decode(&corrupt).unwrap();
+        assert_eq!(progress.consumed, first_block);
+        assert_eq!(progress.output, data[..5000]);
+        assert!(decoder.decode(&corrupt[first_block..]).is_err());
+    }
+
+    #[test]
+    fn test_lazy_matching() {
+        // Greedy parsing takes "abcd" at "abcdefgh" and misses the longer
+        // "bcdefgh..." that starts one byte later
+        let mut data = Vec::new();
+        for i in 0..200 {
+            data.extend_from_slice(b"abcd-");
+            data.extend_from_slice(b"xbcdefghijklm ");
+            data.extend_from_slice(format!("{} ", i).as_bytes());
+            data.extend_from_slice(b"abcdefghijklm ");
+        }
+
+        let greedy = LZSS::new(4096, 3).compress(&data);
+        let lazy = LZSS::new(4096, 3).with_lazy_matching(true).compress(&data);
+        assert!(lazy.len() < greedy.len(), "lazy {} vs greedy {}", lazy.len(), greedy.len());
+
+        // Same format, decoded by any LZSS with the same parameters
+        assert_eq!(LZSS::new(4096, 3).try_decompress(&lazy).unwrap(), data);
+        for lzss in [
+            LZSS::new(256, 4).with_lazy_matching(true),
+            LZSS::new(4096, 3).with_lazy_matching(true).with_overlapping_matches(false),
+            LZSS::new(4096, 3).with_lazy_matching(true).with_long_distance_matching(1 << 20),
+        ] {
+            let text = generate_pattern_data(30_000);
+            assert_eq!(lzss.try_decompress(&lzss.compress(&text)).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn test_compression_levels() {
+        use crate::CompressionLevel;
+
+        assert_eq!(CompressionLevel::new(0), CompressionLevel::FAST);
+        assert_eq!(CompressionLevel::new(42), CompressionLevel::BEST);
+        assert_eq!(CompressionLevel::default().level(), 6);
+
+        let mut data = Vec::new();
+        for i in 0..3_000 {
+            data.extend_from_slice(format!("record {} of {}: status=ok, value={}\n", i, i % 97, i * 7 % 1013).as_bytes());
+        }
+
+        let sizes: Vec<usize> = (1..=9)
+            .map(|level| {
+                let lzss = LZSS::new(4096, 3).with_level(CompressionLevel::new(level));
+                let compressed = lzss.compress(&data);
+                assert_eq!(LZSS::new(4096, 3).try_decompress(&compressed).unwrap(), data);
+                compressed.len()
+            })
+            .collect();
+        assert!(sizes[8] < sizes[0], "{:?}", sizes);
+        assert!(sizes[5] <= sizes[0], "{:?}", sizes);
+
+        // A shallower search never finds longer matches
+        let deep = LZSS::new(4096, 3).compress(&data);
+        let shallow = LZSS::new(4096, 3).with_search_depth(1).compress(&data);
+        assert!(shallow.len() >= deep.len());
+        assert_eq!(LZSS::new(4096, 3).try_decompress(&shallow).unwrap(), data);
+    }
+
+    #[test]
+    fn test_builder() {
+        use crate::{CompressionLevel, ConfigError, LengthField};
+
+        let data = b"abcabcabcabcabcabcabcabcabcabcabcabcabcabcabcabc".repeat(200);
+
+        let lzss = LZSS::builder().window_size(2048).min_match_length(4).build().unwrap();
+        assert_eq!(lzss.try_decompress(&lzss.compress(&data)).unwrap(), data);
+
+        let leveled = LZSS::builder().level(CompressionLevel::FAST).build().unwrap();
+        assert_eq!(leveled.try_decompress(&leveled.compress(&data)).unwrap(), data);
+
+        // A match length cap shortens the tokens but keeps the stream decodable
+        let capped = LZSS::builder().max_match_length(10).build().unwrap();
+        let compressed = capped.compress(&data);
+        let stats = capped.validate(&compressed).unwrap();
+        assert!(stats.max_match_length <= 10, "{}", stats.max_match_length);
+        assert_eq!(LZSS::new(4096, 3).try_decompress(&compressed).unwrap(), data);
+
+        assert!(matches!(LZSS::builder().window_size(0).build(), Err(ConfigError::InvalidWindowSize { .. })));
+        assert!(matches!(LZSS::builder().window_size(100_000).build(), Err(ConfigError::InvalidWindowSize { .. })));
+        assert!(matches!(LZSS::builder().min_match_length(1).build(), Err(ConfigError::InvalidMinMatchLength { .. })));
+        assert!(matches!(LZSS::builder().max_match_length(2).build(), Err(ConfigError::InvalidMaxMatchLength { .. })));
+        assert!(matches!(LZSS::builder().max_match_length(259).build(), Err(ConfigError::InvalidMaxMatchLength { .. })));
+        assert!(LZSS::builder().length_field(LengthField::TwoBytes).max_match_length(259).build().is_ok());
+        assert!(matches!(LZSS::builder().long_distance_matching(0).build(), Err(ConfigError::InvalidLongDistanceHistory { .. })));
+        assert!(matches!(LZSS::builder().search_depth(0).build(), Err(ConfigError::InvalidSearchDepth)));
+    }
+
+    #[test]
+    fn test_try_new() {
+        use crate::{ConfigError, MAX_MIN_MATCH_LENGTH};
+
+        let data = b"constructor parameters, constructor parameters, constructor parameters".repeat(50);
+        let lzss = LZSS::try_new(1024, 3).unwrap();
+        assert_eq!(lzss.try_decompress(&lzss.compress(&data)).unwrap(), data);
+
+        assert!(matches!(LZSS::try_new(0, 3), Err(ConfigError::InvalidWindowSize { .. })));
+        let max_window = if cfg!(feature = "small-target") { crate::MAX_WINDOW_SIZE } else { 65535 };
+        assert!(matches!(LZSS::try_new(65536, 3), Err(ConfigError::InvalidWindowSize { max, .. }) if max == max_window));
+        assert!(matches!(LZSS::try_new(1024, 0), Err(ConfigError::InvalidMinMatchLength { .. })));
+        assert!(matches!(LZSS::try_new(1024, 1), Err(ConfigError::InvalidMinMatchLength { .. })));
+        assert!(matches!(
+            LZSS::try_new(1024, MAX_MIN_MATCH_LENGTH + 1),
+            Err(ConfigError::InvalidMinMatchLength { .. })
+        ));
+
+        // `new` clamps instead, so even a zero minimum match length round-trips
+        for lzss in [LZSS::new(0, 0), LZSS::new(1 << 20, 3), LZSS::new(1024, 1000)] {
+            assert_eq!(lzss.try_decompress(&lzss.compress(&data)).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_size_bounds() {
+        let random = generate_random_data(50_000);
+        let mut text = Vec::new();
+        for i in 0..1_000 {
+            text.extend_from_slice(format!("line {} of the size estimate test\n", i).as_bytes());
+        }
+
+        let lzss = LZSS::new(4096, 3);
+        assert_eq!(crate::max_compressed_size(0), 0);
+        for data in [&random, &text] {
+            assert_eq!(lzss.max_compressed_size(data.len()), crate::max_compressed_size(data.len()));
+            assert!(lzss.compress(data).len() <= crate::max_compressed_size(data.len()));
+
+            let hint = lzss.compressed_size_hint(data);
+            assert!(hint <= lzss.max_compressed_size(data.len()));
+            assert!(hint >= lzss.compress(data).len() / 2, "{} vs {}", hint, lzss.compress(data).len());
+        }
+
+        // Text's narrower histogram gives a smaller hint than random bytes
+        assert!(lzss.compressed_size_hint(&text) < lzss.compressed_size_hint(&random[..text.len()]));
+        assert_eq!(lzss.compressed_size_hint(&[]), 0);
+    }
+
+    #[test]
+    fn test_raw_streams() {
+        use crate::{LengthField, LzssError};
+
+        let mut data = b"size carried out of band, size carried out of band. ".repeat(100);
+        data.extend(generate_random_data(2_000));
+
+        for lzss in [LZSS::new(4096, 3), LZSS::new(4096, 2).with_length_field(LengthField::TwoBytes)] {
+            let raw = lzss.compress_raw(&data);
+            assert_eq!(raw, lzss.compress(&data)[4..]);
+            assert_eq!(lzss.decompress_raw(&raw, data.len()).unwrap(), data);
+
+            let mut output = vec![0u8; data.len()];
+            lzss.decompress_raw_into(&raw, &mut output).unwrap();
+            assert_eq!(output, data);
+
+            // Too large a size runs out of input
+            assert!(matches!(
+                lzss.decompress_raw(&raw, data.len() + 1),
+                Err(LzssError::SizeMismatch { .. }) | Err(LzssError::TruncatedInput)
+            ));
+        }
+
+        let lzss = LZSS::new(4096, 3);
+        assert!(lzss.compress_raw(&[]).is_empty());
+        assert!(lzss.decompress_raw(&[], 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_concatenated_members() {
+        use crate::frame::{Checksum, FrameOptions};
+
+        let lzss = LZSS::new(4096, 3);
+        let chunks = [
+            b"first log chunk, first log chunk\n".repeat(20),
+            generate_random_data(3_000),
+            Vec::new(),
+            generate_pattern_data(10_000),
+        ];
+
+        let mut input = lzss.compress(&chunks[0]);
+        input.extend(lzss.compress_framed_with(&chunks[1], &FrameOptions::default().with_checksum(Checksum::Crc32)));
+        input.extend(lzss.compress_framed_with(&chunks[2], &FrameOptions::default().with_alignment(64)));
+        input.extend(LZSS::new(1024, 5).compress_framed(&chunks[3]));
+
+        let members: Vec<Vec<u8>> = lzss.decompress_members(&input).collect::<Result<_, _>>().unwrap();
+        assert_eq!(members, chunks);
+        assert_eq!(lzss.decompress_concatenated(&input).unwrap(), chunks.concat());
+
+        // A truncated last member fails after the complete ones
+        let mut members = lzss.decompress_members(&input[..input.len() - 10]);
+        assert_eq!(members.next().unwrap().unwrap(), chunks[0]);
+        assert_eq!(members.next().unwrap().unwrap(), chunks[1]);
+        assert_eq!(members.next().unwrap().unwrap(), chunks[2]);
+        assert!(matches!(members.next(), Some(Err(_))));
+        assert!(members.next().is_none());
+        assert!(lzss.decompress_concatenated(&input[..input.len() - 10]).is_err());
+    }
+
+    #[test]
+    fn test_seekable_archive() {
+        use crate::seekable::SeekableArchive;
+        use crate::LzssError;
+
+        let lzss = LZSS::new(4096, 3);
+        let mut data = generate_pattern_data(100_000);
+        data.extend(generate_random_data(20_000));
+
+        let archive = lzss.compress_seekable(&data, 16 * 1024);
+        let reader = SeekableArchive::open(&archive).unwrap();
+        assert_eq!(reader.content_size(), data.len());
+        assert_eq!(reader.blocks().len(), data.len().div_ceil(16 * 1024));
+        assert_eq!(reader.decompress_all().unwrap(), data);
+        assert_eq!(reader.read_block(2).unwrap(), data[32 * 1024..48 * 1024]);
+
+        for range in [0..0, 0..1, 10..20, 16_383..16_385, 50_000..110_000, 119_000..120_000] {
+            assert_eq!(reader.read_range(range.clone()).unwrap(), data[range]);
+        }
+
+        // Empty input still has a footer
+        let empty = lzss.compress_seekable(&[], 1024);
+        assert_eq!(SeekableArchive::open(&empty).unwrap().decompress_all().unwrap(), b"");
+
+        // A damaged footer or block is reported
+        assert_eq!(SeekableArchive::open(&archive[..archive.len() - 1]).err(), Some(LzssError::InvalidIndex));
+        let mut corrupt = archive.clone();
+        let count_pos = corrupt.len() - 8;
+        corrupt[count_pos] += 1;
+        assert_eq!(SeekableArchive::open(&corrupt).err(), Some(LzssError::InvalidIndex));
+        let mut corrupt = archive.clone();
+        corrupt[reader.blocks()[1].compressed_offset + 20] ^= 0xFF;
+        let reader = SeekableArchive::open(&corrupt).unwrap();
+        assert!(reader.read_range(0..100).is_ok());
+        assert_ne!(reader.read_block(1).ok(), Some(data[16 * 1024..32 * 1024].to_vec()));
+
+        // A forged content size is caught by the last block's frame before
+        // anything that large is allocated
+        let mut forged = archive.clone();
+        let size_pos = forged.len() - 16;
+        forged[size_pos..size_pos + 8].copy_from_slice(&(1u64 << 50).to_le_bytes());
+        let reader = SeekableArchive::open(&forged).unwrap();
+        assert_eq!(reader.decompress_all(), Err(LzssError::InvalidIndex));
+        assert_eq!(reader.read_range(0..100).unwrap(), data[..100]);
+    }
+
+    #[test]
+    fn test_decompress_with_limit() {
+        use crate::LzssError;
+
+        let lzss = LZSS::new(4096, 3);
+        let data = generate_pattern_data(10_000);
+        let compressed = lzss.compress(&data);
+
+        assert_eq!(lzss.decompress_with_limit(&compressed, 10_000).unwrap(), data);
+        assert_eq!(
+            lzss.decompress_with_limit(&compressed, 9_999),
+            Err(LzssError::OutputLimitExceeded { size: 10_000, limit: 9_999 })
+        );
+        assert_eq!(lzss.decompress_with_limit(&lzss.compress_framed(&data), 10_000).unwrap(), data);
+        assert!(matches!(
+            lzss.decompress_with_limit(&lzss.compress_framed(&data), 1_000),
+            Err(LzssError::OutputLimitExceeded { .. })
+        ));
+
+        // A forged header is rejected without allocating what it claims
+        let mut forged = compressed.clone();
+        forged[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            lzss.decompress_with_limit(&forged, 1 << 20),
+            Err(LzssError::OutputLimitExceeded { size, .. }) if size == u32::MAX as usize
+        ));
+        assert_eq!(lzss.decompress_with_limit(&[], 0).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_copy_compress() {
+        use std::io::Cursor;
+
+        let mut data = generate_pattern_data(200_000);
+        data.extend(generate_random_data(30_000));
+
+        let mut compressed = Vec::new();
+        let read = crate::copy_compress(&mut Cursor::new(&data), &mut compressed, LZSS::new(4096, 3)).unwrap();
+        assert_eq!(read, data.len() as u64);
+        assert!(compressed.len() < data.len());
+
+        let mut restored = Vec::new();
+        let written = crate::copy_decompress(&mut compressed.as_slice(), &mut restored, LZSS::new(4096, 3)).unwrap();
+        assert_eq!(written, data.len() as u64);
+        assert_eq!(restored, data);
+
+        // Truncated input is an error, not short output
+        let mut sink = Vec::new();
+        let truncated = &compressed[..compressed.len() - 1];
+        assert!(crate::copy_decompress(&mut &truncated[..], &mut sink, LZSS::new(4096, 3)).is_err());
+    }
+
+    #[test]
+    fn test_hash_chain_window() {
+        // Repeats both inside and beyond a small window; the chains only
+        // remember the window, so no match may reach further back
+        let mut data = generate_random_data(3_000);
+        data.extend(data[..1_000].to_vec());
+        data.extend(generate_pattern_data(5_000));
+        data.extend(data[2_000..2_500].to_vec());
+
+        for window in [1, 256, 1024, 4096] {
+            let lzss = LZSS::new(window, 3);
+            let compressed = lzss.compress(&data);
+            let stats = lzss.validate(&compressed).unwrap();
+            assert!(stats.max_distance <= window, "{} > {}", stats.max_distance, window);
+            assert_eq!(lzss.try_decompress(&compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_four_byte_hash() {
+        // Records sharing 3-byte prefixes but rarely 4, where 3-byte keys
+        // would fill the chains with candidates too short to use
+        let mut data = Vec::new();
+        for i in 0u32..20_000 {
+            data.extend_from_slice(&[0xAB, 0xCD, (i % 16) as u8, (i % 7) as u8, 0x55, (i % 5) as u8]);
+        }
+
+        for min_match in [4, 5, 8] {
+            let lzss = LZSS::new(4096, min_match).with_search_depth(4);
+            let compressed = lzss.compress(&data);
+            assert!(compressed.len() < data.len());
+            let stats = lzss.validate(&compressed).unwrap();
+            assert!(stats.matches > 0);
+            assert_eq!(lzss.try_decompress(&compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_skip_incompressible() {
+        let mut rng = rand::thread_rng();
+        let mut input: Vec<u8> = (0..100_000).map(|_| rng.gen()).collect();
+        let text = b"Incompressible regions are skipped, text after them still matches. ".repeat(200);
+        input.extend_from_slice(&text);
+
+        let skipping = LZSS::new(4096, 3);
+        let searching = LZSS::new(4096, 3).with_skip_incompressible(false);
+        let fast = skipping.compress(&input);
+        let thorough = searching.compress(&input);
+        assert_eq!(skipping.try_decompress(&fast).unwrap(), input);
+        assert_eq!(searching.try_decompress(&thorough).unwrap(), input);
+
+        // The first match ends the skipping, so the text still compresses
+        let random_only = skipping.compress(&input[..100_000]).len();
+        assert!(fast.len() < random_only + text.len() / 4);
+
+        // Only the best level searches every position
+        let best = LZSS::new(4096, 3).with_level(crate::CompressionLevel::BEST);
+        let best_searching = LZSS::new(4096, 3)
+            .with_level(crate::CompressionLevel::BEST)
+            .with_skip_incompressible(false);
+        assert_eq!(best.compress(&input), best_searching.compress(&input));
+    }
+
+    #[test]
+    fn test_compressor_reuse() {
+        use crate::compressor::{Compressor, Decompressor};
+
+        // Independent packets without a dictionary are plain streams, and
+        // reused tables must not leak matches from one payload into the next
+        let payloads: Vec<Vec<u8>> = (0..50)
+            .map(|i| format!("payload {} {}", i, "abcabcabc".repeat(i % 7 + 1)).into_bytes())
+            .chain([vec![7u8; 10_000], b"short".to_vec(), Vec::new()])
+            .collect();
+        let configs: [fn() -> LZSS; 2] =
+            [|| LZSS::new(4096, 3), || LZSS::new(4096, 4).with_long_distance_matching(1 << 20)];
+        for config in configs {
+            let expected: Vec<Vec<u8>> = payloads.iter().map(|payload| config().compress(payload)).collect();
+            let mut compressor = Compressor::new(config()).with_independent_packets(true);
+            let mut output = Vec::new();
+            for (payload, expected) in payloads.iter().zip(&expected) {
+                compressor.compress_into_vec(payload, &mut output);
+                assert_eq!(&output, expected);
+            }
+        }
+
+        // reset() restarts the window and the bypass statistics
+        let mut compressor = Compressor::new(LZSS::new(4096, 3)).with_adaptive_bypass(true);
+        let mut rng = rand::thread_rng();
+        for _ in 0..10 {
+            let noise: Vec<u8> = (0..1000).map(|_| rng.gen()).collect();
+            compressor.compress(&noise);
+        }
+        assert!(compressor.is_bypassing());
+        compressor.reset();
+        assert!(!compressor.is_bypassing());
+
+        let message = b"the same message, compressed twice".to_vec();
+        let first = compressor.compress(&message);
+        compressor.reset();
+        assert_eq!(compressor.compress(&message), first);
+        let mut decompressor = Decompressor::new(LZSS::new(4096, 3)).with_adaptive_bypass(true);
+        assert_eq!(decompressor.try_decompress(&first).unwrap(), message);
+    }
+
+    #[test]
+    fn test_match_copies() {
+        use crate::copy::{copy_match, extend_match};
+
+        // Compare with a byte-at-a-time copy, overlapping or not, with and
+        // without the source starting in the history
+        let mut rng = rand::thread_rng();
+        let history: Vec<u8> = (0..64).map(|_| rng.gen()).collect();
+        for _ in 0..500 {
+            let produced = rng.gen_range(0..40);
+            let distance = rng.gen_range(1..=produced + history.len());
+            let length = rng.gen_range(0..300);
+
+            let mut naive: Vec<u8> = history.iter().copied().chain((0..produced).map(|_| rng.gen())).collect();
+            let mut output = vec![0; produced + length];
+            output[..produced].copy_from_slice(&naive[history.len()..]);
+            for _ in 0..length {
+                naive.push(naive[naive.len() - distance]);
+            }
+            copy_match(&mut output, produced, distance, length, &history);
+            assert_eq!(output, naive[history.len()..]);
+
+            let mut extended = naive[..history.len() + produced].to_vec();
+            extend_match(&mut extended, distance, length);
+            assert_eq!(extended, naive);
+        }
+
+        // Runs with short periods decode through both decoders
+        let mut input = Vec::new();
+        for period in [1, 2, 3, 7, 64] {
+            let pattern: Vec<u8> = (0..period).map(|_| rng.gen()).collect();
+            input.extend(pattern.iter().cycle().take(1000));
+        }
+        let lzss = LZSS::new(4096, 3).with_length_field(crate::LengthField::TwoBytes);
+        let compressed = lzss.compress(&input);
+        assert_eq!(lzss.try_decompress(&compressed).unwrap(), input);
+        #[allow(deprecated)]
+        let lenient = lzss.decompress(&compressed);
+        assert_eq!(lenient, input);
+    }
+
+    #[test]
+    fn test_two_pass() {
+        use crate::frame::FrameHeader;
+        use crate::LengthField;
+
+        let lzss = LZSS::new(4096, 3);
+
+        // Short matches only: one-byte lengths are as good as any
+        let words = ["two", "passes", "pick", "the", "length", "field", "one", "byte", "suits", "short", "matches"];
+        let mut rng = rand::thread_rng();
+        let text: Vec<u8> = (0..400).flat_map(|_| format!("{} ", words[rng.gen_range(0..words.len())]).into_bytes()).collect();
+        assert_eq!(lzss.best_length_field(&text), LengthField::OneByte);
+
+        // A few long runs among short matches favour the extended field
+        let mut runs = text.clone();
+        runs.extend(vec![b'x'; 5000]);
+        runs.extend_from_slice(&text);
+        assert_eq!(lzss.best_length_field(&runs), LengthField::Extended);
+
+        // Nothing but very long runs favours two-byte lengths
+        let mut long_runs = Vec::new();
+        for byte in 0..20u8 {
+            long_runs.extend(vec![byte; 3000]);
+        }
+        assert_eq!(lzss.best_length_field(&long_runs), LengthField::TwoBytes);
+
+        for input in [&text[..], &runs, &long_runs, b""] {
+            let framed = lzss.compress_two_pass(input);
+            if !input.is_empty() {
+                let header = FrameHeader::parse(&framed).unwrap();
+                assert_eq!(header.length_field, lzss.best_length_field(input));
+                assert!(framed.len() <= lzss.compress_framed(input).len());
+            }
+            assert_eq!(LZSS::new(256, 5).try_decompress(&framed).unwrap(), input);
+        }
+    }
+
+    #[test]
+    fn test_short_offsets() {
+        use crate::frame::{FrameHeader, FLAG_SHORT_OFFSETS};
+        use crate::{decompress_const, ConfigError};
+
+        // Near repeats in a small window, as in embedded profiles
+        let mut rng = rand::thread_rng();
+        let words = ["led", "on", "off", "pwm", "adc", "tick", "irq", "gpio"];
+        let data: Vec<u8> = (0..2000).flat_map(|_| format!("{}={} ", words[rng.gen_range(0..8)], rng.gen_range(0..4)).into_bytes()).collect();
+
+        let full = LZSS::new(256, 3);
+        let short = LZSS::new(256, 3).with_short_offsets(true);
+        let compressed = short.compress(&data);
+        assert!(compressed.len() < full.compress(&data).len() * 9 / 10);
+        assert_eq!(short.try_decompress(&compressed).unwrap(), data);
+        assert_eq!(short.decompress_iter(&compressed).collect::<Result<Vec<_>, _>>().unwrap().concat(), data);
+        assert!(short.validate(&compressed).is_ok());
+        #[allow(deprecated)]
+        let lenient = short.decompress(&compressed);
+        assert_eq!(lenient, data);
+
+        // Far matches use the long form, which reaches half as far
+        let mut far = data.clone();
+        far.extend_from_within(..3000);
+        for lzss in [LZSS::new(32767, 3), LZSS::new(4096, 4).with_long_distance_matching(1 << 20)] {
+            let lzss = lzss.with_short_offsets(true);
+            assert_eq!(lzss.try_decompress(&lzss.compress(&far)).unwrap(), far);
+        }
+
+        // Frames record the setting, also for const decoding
+        let framed = short.compress_framed(&data[..4000]);
+        assert_ne!(FrameHeader::parse(&framed).unwrap().flags & FLAG_SHORT_OFFSETS, 0);
+        assert_eq!(LZSS::new(4096, 3).try_decompress(&framed).unwrap(), &data[..4000]);
+        assert_eq!(decompress_const::<4000>(&framed).to_vec(), &data[..4000]);
+
+        let builder = LZSS::builder().short_offsets(true);
+        let max = if cfg!(feature = "small-target") { 4096 } else { 32767 };
+        assert!(builder.clone().window_size(max).build().is_ok());
+        assert!(matches!(
+            builder.window_size(65535).build(),
+            Err(ConfigError::InvalidWindowSize { window_size: 65535, max: m }) if m == max
+        ));
+    }
+
+    #[test]
+    fn test_classic_layout() {
+        use crate::{classic, LzssError};
+
+        // Two literals, then 8 bytes from ring position 0xFEE (the first
+        // byte), as LZSS.C writes them
+        assert_eq!(classic::compress(b"ABABABABAB"), [0x03, b'A', b'B', 0xEE, 0xF5]);
+        assert_eq!(classic::decompress(&[0x03, b'A', b'B', 0xEE, 0xF5]).unwrap(), b"ABABABABAB");
+
+        // Other encoders may match the spaces the ring starts with
+        assert_eq!(classic::decompress(&[0x02, 0x00, 0x02, b'x']).unwrap(), b"     x");
+        assert!(matches!(classic::decompress(&[0x00, 0x00]), Err(LzssError::TruncatedInput)));
+        assert!(classic::compress(b"").is_empty());
+        assert!(classic::decompress(b"").unwrap().is_empty());
+
+        let mut rng = rand::thread_rng();
+        let words = ["classic", "layout", "ring", "buffer", "okumura", "token", "flag", "byte"];
+        let text: Vec<u8> = (0..3000).flat_map(|_| format!("{} ", words[rng.gen_range(0..8)]).into_bytes()).collect();
+        let random: Vec<u8> = (0..10_000).map(|_| rng.gen()).collect();
+        for input in [&text, &random] {
+            assert_eq!(classic::decompress(&classic::compress(input)).unwrap(), *input);
+        }
+
+        // 2-byte matches beat the default 3-byte ones at the same window
+        assert!(classic::compress(&text).len() < LZSS::new(4096, 3).compress(&text).len());
+    }
+
+    #[test]
+    fn test_bit_layouts() {
+        use crate::classic::BitLayout;
+        use crate::ConfigError;
+
+        assert_eq!(BitLayout::default(), BitLayout::CLASSIC);
+        let layout = BitLayout::new(10, 6).unwrap();
+        assert_eq!((layout.window_size(), layout.min_match(), layout.max_match()), (1024, 3, 66));
+        // Position 958 (the first byte) is 0xBE in the first byte and 0b11
+        // above the 6 length bits in the second
+        assert_eq!(layout.compress(b"ABABABABAB"), [0x03, b'A', b'B', 0xBE, 0xC0 | 5]);
+
+        let wide = BitLayout::new(20, 4).unwrap();
+        assert_eq!((wide.min_match(), wide.max_match()), (4, 19));
+
+        for (offset_bits, length_bits) in [(12, 5), (7, 9), (16, 0), (8, 8), (30, 10)] {
+            assert!(matches!(
+                BitLayout::new(offset_bits, length_bits),
+                Err(ConfigError::InvalidBitLayout { .. })
+            ));
+        }
+
+        let mut rng = rand::thread_rng();
+        let words = ["sprite", "tile", "palette", "bank", "vram", "oam", "dma", "map"];
+        let data: Vec<u8> = (0..5000).flat_map(|_| format!("{} ", words[rng.gen_range(0..8)]).into_bytes()).collect();
+        for (offset_bits, length_bits) in [(10, 6), (13, 3), (15, 1), (14, 10), (16, 8), (20, 4)] {
+            let layout = BitLayout::new(offset_bits, length_bits).unwrap();
+            let packed = layout.compress(&data);
+            assert!(packed.len() < data.len());
+            assert_eq!(layout.decompress(&packed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_nintendo_lz() {
+        use crate::{compat, LzssError};
+
+        // "ABCABCABC": three literals, then a 6-byte match 3 bytes back
+        let lz10 = [0x10, 9, 0, 0, 0x10, b'A', b'B', b'C', 0x30, 0x02];
+        assert_eq!(compat::decompress_lz(&lz10).unwrap(), b"ABCABCABC");
+        assert_eq!(compat::compress_lz10(b"ABCABCABC"), lz10);
+        let lz11 = [0x11, 9, 0, 0, 0x10, b'A', b'B', b'C', 0x50, 0x02];
+        assert_eq!(compat::decompress_lz(&lz11).unwrap(), b"ABCABCABC");
+        assert_eq!(compat::compress_lz11(b"ABCABCABC"), lz11);
+
+        // LZ11 needs all three token sizes for runs this long
+        let mut data = b"Nintendo LZ round trip ".repeat(200);
+        data.extend(core::iter::repeat_n(0u8, 300));
+        data.extend(core::iter::repeat_n(7u8, 20));
+        data.extend((0..5000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8));
+        for compress in [compat::compress_lz10, compat::compress_lz10_vram_safe, compat::compress_lz11] {
+            let packed = compress(&data);
+            assert!(packed.len() < data.len());
+            assert_eq!(compat::decompress_lz(&packed).unwrap(), data);
+            let mut padded = packed.clone();
+            padded.resize(packed.len().next_multiple_of(4), 0);
+            assert_eq!(compat::decompress_lz(&padded).unwrap(), data);
+        }
+        assert!(compat::compress_lz11(&data).len() < compat::compress_lz10(&data).len());
+
+        // The VRAM-safe encoder never copies from the previous byte
+        let run = [0u8; 64];
+        let safe = compat::compress_lz10_vram_safe(&run);
+        assert_eq!(compat::decompress_lz(&safe).unwrap(), run);
+        let mut pos = 4;
+        while pos < safe.len() {
+            let flags = safe[pos];
+            pos += 1;
+            for bit in 0..8 {
+                if pos >= safe.len() {
+                    break;
+                }
+                if flags & (0x80 >> bit) != 0 {
+                    assert_ne!(safe[pos] & 0x0F | safe[pos + 1], 0);
+                    pos += 2;
+                } else {
+                    pos += 1;
+                }
+            }
+        }
+
+        for empty in [compat::compress_lz10(&[]), compat::compress_lz11(&[])] {
+            assert!(compat::decompress_lz(&empty).unwrap().is_empty());
+        }
+        assert_eq!(compat::decompress_lz(&[0x40, 1, 0, 0, 0]), Err(LzssError::InvalidHeader));
+        assert_eq!(compat::decompress_lz(&lz10[..8]), Err(LzssError::TruncatedInput));
+        // A forged 4 GB extended size doesn't get reserved up front
+        assert_eq!(compat::decompress_lz(&[0x11, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xF0]), Err(LzssError::TruncatedInput));
+        assert_eq!(
+            compat::decompress_lz(&[0x10, 4, 0, 0, 0x40, b'A', 0x00, 0x01]),
+            Err(LzssError::InvalidDistance { position: 1, distance: 2 })
+        );
+    }
+
+    #[test]
+    fn test_kosinski() {
+        use crate::{compat, LzssError};
+
+        // Literals, then a 2-byte-token match of 6 bytes 3 back
+        let separate = [0x57, 0x00, b'A', b'B', b'C', 0xFD, 0xFC, 0x00, 0xF0, 0x00];
+        assert_eq!(compat::compress_kosinski(b"ABCABCABC"), separate);
+        assert_eq!(compat::decompress_kosinski(&separate).unwrap(), b"ABCABCABC");
+        // An inline match of 3 bytes 2 back
+        let inline = [0xA3, 0x00, b'A', b'B', 0xFE, 0x00, 0xF0, 0x00];
+        assert_eq!(compat::compress_kosinski(b"ABABA"), inline);
+        assert_eq!(compat::decompress_kosinski(&inline).unwrap(), b"ABABA");
+
+        // The next descriptor comes right after the 16th bit is read, before
+        // the byte of the literal that used it
+        let literals = b"0123456789abcdef";
+        let mut expected = vec![0xFF, 0xFF];
+        expected.extend_from_slice(&literals[..15]);
+        expected.extend_from_slice(&[0x02, 0x00, b'f', 0x00, 0xF0, 0x00]);
+        assert_eq!(compat::compress_kosinski(literals), expected);
+        assert_eq!(compat::decompress_kosinski(&expected).unwrap(), literals);
+
+        let mut data = b"Kosinski descriptor fields ".repeat(300);
+        data.extend(core::iter::repeat_n(0xAAu8, 1000));
+        data.extend((0..9000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8));
+        data.extend_from_within(..200);
+        let packed = compat::compress_kosinski(&data);
+        assert!(packed.len() < data.len());
+        assert_eq!(compat::decompress_kosinski(&packed).unwrap(), data);
+        assert!(compat::decompress_kosinski(&compat::compress_kosinski(&[])).unwrap().is_empty());
+
+        // A no-op marker (count byte 1) decodes to nothing
+        let with_noop = [0x15, 0x00, b'A', 0x00, 0xF0, 0x01, 0x00, 0xF0, 0x00];
+        assert_eq!(compat::decompress_kosinski(&with_noop).unwrap(), b"A");
+        assert_eq!(compat::decompress_kosinski(&separate[..8]), Err(LzssError::TruncatedInput));
+        assert_eq!(
+            compat::decompress_kosinski(&[0x02, 0x00, 0xFF, 0xFC]),
+            Err(LzssError::InvalidDistance { position: 0, distance: 1 })
+        );
+    }
+
+    #[test]
+    fn test_szdd() {
+        use crate::{compat, LzssError};
+
+        // Three literals, then a match of 6 bytes from ring position 4080
+        let mut file = b"SZDD\x88\xF0\x27\x33A_\x09\x00\x00\x00".to_vec();
+        file.extend_from_slice(&[0x07, b'A', b'B', b'C', 0xF0, 0xF3]);
+        assert_eq!(compat::compress_szdd(b"ABCABCABC", b'_'), file);
+        assert_eq!(compat::decompress_szdd(&file).unwrap(), b"ABCABCABC");
+        assert_eq!(compat::szdd_missing_char(&file), Ok(b'_'));
+
+        let data = b"MS-DOS installer disk payload. ".repeat(500);
+        let packed = compat::compress_szdd(&data, b'E');
+        assert!(packed.len() < data.len() / 4);
+        assert_eq!(compat::decompress_szdd(&packed).unwrap(), data);
+        assert_eq!(compat::szdd_missing_char(&packed), Ok(b'E'));
+        assert!(compat::decompress_szdd(&compat::compress_szdd(&[], 0)).unwrap().is_empty());
+
+        let mut bad_magic = file.clone();
+        bad_magic[3] = b'Q';
+        assert_eq!(compat::decompress_szdd(&bad_magic), Err(LzssError::InvalidHeader));
+        assert_eq!(compat::decompress_szdd(&file[..10]), Err(LzssError::TruncatedInput));
+        assert_eq!(
+            compat::decompress_szdd(&file[..file.len() - 2]),
+            Err(LzssError::SizeMismatch { expected: 9, actual: 3 })
+        );
+    }
+
+    #[cfg(feature = "huffman")]
+    #[test]
+    fn test_huffman_streams() {
+        use crate::LzssError;
+
+        let words = ["the", "quick", "brown", "fox", "jumps", "over", "lazy", "dog", "and", "cat"];
+        let mut seed = 12345u32;
+        let mut text = Vec::new();
+        while text.len() < 50_000 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            text.extend_from_slice(words[(seed >> 16) as usize % words.len()].as_bytes());
+            text.push(b' ');
+        }
+
+        let lzss = LZSS::new(4096, 3);
+        let huffman = lzss.compress_huffman(&text);
+        assert!(huffman.len() < lzss.compress(&text).len() * 3 / 4);
+        // The stream carries its own tables and minimum match length
+        assert_eq!(LZSS::new(256, 5).decompress_huffman(&huffman).unwrap(), text);
+
+        let mut mixed = vec![0u8; 100_000];
+        mixed.extend((0..5000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8));
+        mixed.extend_from_slice(&text[..1000]);
+        for lzss in [LZSS::new(32767, 4), LZSS::new(256, 2).with_short_offsets(true)] {
+            for data in [&mixed[..], &text[..], b"a", b""] {
+                let packed = lzss.compress_huffman(data);
+                assert_eq!(lzss.decompress_huffman(&packed).unwrap(), data);
+            }
+        }
+
+        assert_eq!(lzss.decompress_huffman(&huffman[..7]), Err(LzssError::TruncatedInput));
+        assert_eq!(lzss.decompress_huffman(&huffman[..huffman.len() / 2]), Err(LzssError::TruncatedInput));
+        // Three 1-bit literal codes can't form a prefix code
+        let mut oversubscribed = huffman[..8].to_vec();
+        oversubscribed[5..8].copy_from_slice(&[3, 0, 0]);
+        oversubscribed.extend_from_slice(&[0x11, 0x10]);
+        assert_eq!(lzss.decompress_huffman(&oversubscribed), Err(LzssError::InvalidHeader));
+        // A lone 1-bit code leaves the code starting with 1 undefined
+        let undefined = [1, 0, 0, 0, 3, 1, 0, 0, 0x10, 0x80, 0x00];
+        assert_eq!(lzss.decompress_huffman(&undefined), Err(LzssError::InvalidCode));
+    }
+
+    #[test]
+    fn test_rle_filter() {
+        use crate::filter::{Filter, Rle};
+        use crate::LzssError;
+
+        let rle = Rle::new(4);
+        assert_eq!(rle.encode(b"abcaaab"), b"abcaaab");
+        assert_eq!(rle.encode(b"xaaaaaaay"), b"xaaaa\x03\x00y");
+        assert_eq!(rle.decode(b"xaaaa\x03\x00y").unwrap(), b"xaaaaaaay");
+        assert_eq!(rle.decode(b"xaaaa\x03"), Err(LzssError::TruncatedInput));
+
+        // Sparse data: long zero runs between short records
+        let mut sparse = Vec::new();
+        for i in 0..200u32 {
+            sparse.extend_from_slice(&i.to_le_bytes());
+            sparse.resize(sparse.len() + 2000 + i as usize * 7, 0);
+        }
+        // Runs longer than a u16 count split into several entries
+        sparse.resize(sparse.len() + 200_000, 0xFF);
+        sparse.extend_from_slice(b"aaaaaaaa");
+
+        let lzss = LZSS::new(4096, 3);
+        for filter in [Rle::default(), Rle::new(2), Rle::new(1)] {
+            let encoded = filter.encode(&sparse);
+            assert_eq!(filter.decode(&encoded).unwrap(), sparse);
+
+            let packed = lzss.compress_filtered(&filter, &sparse);
+            assert_eq!(lzss.decompress_filtered(&filter, &packed).unwrap(), sparse);
+            assert!(packed.len() < lzss.compress(&sparse).len() / 5);
+        }
+        assert_eq!(Rle::new(1).min_run(), 2);
+
+        // Filters plug in as trait objects too
+        let filter: &dyn Filter = &Rle::default();
+        let packed = lzss.compress_filtered(filter, b"");
+        assert!(lzss.decompress_filtered(filter, &packed).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_delta_filter() {
+        use crate::filter::{Delta, Filter};
+        use crate::frame::{Checksum, FrameHeader, FrameOptions, FLAG_DELTA, FRAME_HEADER_SIZE};
+
+        assert_eq!(Delta::new(1).encode(&[10, 12, 15, 15, 5]), [10, 2, 3, 0, 246]);
+        assert_eq!(Delta::new(2).encode(&[1, 2, 4, 6, 7, 10]), [1, 2, 3, 4, 3, 4]);
+        // A ramp turns into a constant with delta-of-delta
+        let ramp: Vec<u8> = (0..50u32).map(|i| (i * i) as u8).collect();
+        let encoded = Delta::delta_of_delta(1).encode(&ramp);
+        assert!(encoded[2..].iter().all(|&b| b == 2));
+        assert_eq!(Delta::delta_of_delta(1).decode(&encoded).unwrap(), ramp);
+        assert_eq!(Delta::new(0).stride(), 1);
+        assert_eq!(Delta::delta_of_delta(3).order(), 2);
+
+        // 16-bit stereo PCM: a slow sine on the left, a ramp on the right
+        let mut pcm = Vec::new();
+        for i in 0..20_000 {
+            let left = ((i as f64 / 40.0).sin() * 12_000.0) as i16;
+            let right = (i * 3 % 30_000) as i16;
+            pcm.extend_from_slice(&left.to_le_bytes());
+            pcm.extend_from_slice(&right.to_le_bytes());
+        }
+
+        let lzss = LZSS::new(4096, 3);
+        let plain = lzss.compress_framed(&pcm);
+        for delta in [Delta::new(4), Delta::delta_of_delta(4)] {
+            let options = FrameOptions::default().with_delta(delta).with_checksum(Checksum::Crc32);
+            let framed = lzss.compress_framed_with(&pcm, &options);
+            assert!(framed.len() < plain.len() * 3 / 4);
+
+            let header = FrameHeader::parse(&framed).unwrap();
+            assert_ne!(header.flags & FLAG_DELTA, 0);
+            assert_eq!(header.delta, Some(delta));
+            // The checksum covers the content as decoded, after the filter
+            // is undone
+            assert_eq!(header.checksum, Checksum::Crc32.compute(&pcm));
+
+            assert_eq!(LZSS::new(256, 5).try_decompress(&framed).unwrap(), pcm);
+            let mut output = vec![0; pcm.len()];
+            lzss.decompress_into(&framed, &mut output).unwrap();
+            assert_eq!(output, pcm);
+            let chunks: Vec<u8> = lzss
+                .decompress_iter(&framed)
+                .with_chunk_size(1001)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+                .concat();
+            assert_eq!(chunks, pcm);
+            assert_eq!(crate::decompress_const::<80_000>(&framed).to_vec(), pcm);
+        }
+
+        // Only orders 1 and 2 are defined
+        let mut framed = lzss.compress_framed_with(&pcm, &FrameOptions::default().with_delta(Delta::new(4)));
+        framed[FRAME_HEADER_SIZE] = 3;
+        assert!(FrameHeader::parse(&framed).is_none());
+    }
+
+    #[test]
+    fn test_transpose_filter() {
+        use crate::filter::{Filter, Transpose};
+
+        let transpose = Transpose::new(3);
+        assert_eq!(transpose.encode(b"xyzXYZ123ab"), b"xX1yY2zZ3ab");
+        assert_eq!(transpose.decode(b"xX1yY2zZ3ab").unwrap(), b"xyzXYZ123ab");
+        assert_eq!(transpose.encode(b"ab"), b"ab");
+        assert_eq!(Transpose::new(0).record_size(), 1);
+
+        // Grid mesh vertices: position (3 x f32), packed normal, UV (2 x u16)
+        let mut mesh = Vec::new();
+        for y in 0..64u32 {
+            for x in 0..64u32 {
+                let height = (x as f32 / 9.0).sin() * (y as f32 / 7.0).cos() * 4.0;
+                for v in [x as f32 * 0.5, height, y as f32 * 0.5] {
+                    mesh.extend_from_slice(&v.to_le_bytes());
+                }
+                mesh.extend_from_slice(&[0, 127, (height * 8.0) as i8 as u8, 0]);
+                for uv in [x * 1024, y * 1024] {
+                    mesh.extend_from_slice(&(uv as u16).to_le_bytes());
+                }
+            }
+        }
+        mesh.extend_from_slice(b"tail");
+
+        let lzss = LZSS::new(4096, 3);
+        let filter = Transpose::new(20);
+        let packed = lzss.compress_filtered(&filter, &mesh);
+        assert!(packed.len() < lzss.compress(&mesh).len() * 9 / 10);
+        assert_eq!(lzss.decompress_filtered(&filter, &packed).unwrap(), mesh);
+        for record_size in [1, 7, 20, 1000, 100_000] {
+            let filter = Transpose::new(record_size);
+            assert_eq!(filter.decode(&filter.encode(&mesh)).unwrap(), mesh);
+        }
+    }
+
+    #[test]
+    fn test_decompress_auto() {
+        use crate::frame::{FrameHeader, FLAG_WINDOW_SIZE};
+        use crate::{decompress_auto, LengthField, LzssError};
+
+        let data = b"Frames carry their own decoding parameters. ".repeat(300);
+        let configs: [fn() -> LZSS; 4] = [
+            || LZSS::new(4096, 3),
+            || LZSS::new(1000, 6).with_length_field(LengthField::Extended),
+            || LZSS::new(256, 2).with_short_offsets(true),
+            || LZSS::new(4096, 4).with_long_distance_matching(1 << 20),
+        ];
+        for make in configs {
+            let lzss = make();
+            let framed = lzss.compress_framed(&data);
+            assert_eq!(decompress_auto(&framed).unwrap(), data);
+
+            let header = FrameHeader::parse(&framed).unwrap();
+            assert_ne!(header.flags & FLAG_WINDOW_SIZE, 0);
+            assert_eq!(header.window_size, Some(lzss.match_reach()));
+        }
+        assert_eq!(FrameHeader::parse(&LZSS::new(1000, 3).compress_framed(&data)).unwrap().window_size, Some(1000));
+
+        // Raw streams don't say how to decode them
+        let raw = LZSS::new(4096, 5).compress(&data);
+        assert_eq!(decompress_auto(&raw), Err(LzssError::InvalidHeader));
+        let framed = LZSS::new(4096, 5).compress_framed(&data);
+        assert!(decompress_auto(&framed[..framed.len() - 3]).is_err());
+    }
+
+    #[test]
+    fn test_ffi_alloc() {
+        use crate::ffi::*;
+
+        let data = generate_pattern_data(10_000);
+        let context = lzss_create(4096, 3);
+
+        let mut compressed = std::ptr::null_mut();
+        let mut compressed_size = 0;
+        assert_eq!(lzss_compress_alloc(context, data.as_ptr(), data.len() as _, &mut compressed, &mut compressed_size), 0);
+        let expected = LZSS::new(4096, 3).compress(&data);
+        assert_eq!(unsafe { std::slice::from_raw_parts(compressed, compressed_size as usize) }, &expected[..]);
+
+        let mut decompressed = std::ptr::null_mut();
+        let mut decompressed_size = 0;
+        assert_eq!(lzss_decompress_alloc(context, compressed, compressed_size, &mut decompressed, &mut decompressed_size), 0);
+        assert_eq!(unsafe { std::slice::from_raw_parts(decompressed, decompressed_size as usize) }, &data[..]);
+
+        // Corrupt input and forged size headers are rejected before allocating
+        let mut forged = expected.clone();
+        forged[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let truncated = &expected[..expected.len() / 2];
+        for corrupt in [&forged[..], truncated, &[9, 0, 0, 0, 0b0000_0001, 9, 0, 0]] {
+            assert_eq!(
+                lzss_decompress_alloc(context, corrupt.as_ptr(), corrupt.len() as _, &mut decompressed, &mut decompressed_size),
+                LzssStatus::CorruptInput as i32
+            );
+        }
+
+        lzss_free(compressed, compressed_size);
+        lzss_free(decompressed, decompressed_size);
+        lzss_free(std::ptr::null_mut(), 0);
+        assert_eq!(lzss_compress_alloc(context, data.as_ptr(), data.len() as _, std::ptr::null_mut(), &mut compressed_size), LzssStatus::InvalidParameters as i32);
+        lzss_destroy(context);
+    }
+
+    #[test]
+    fn test_ffi_decompress_bounded() {
+        use crate::ffi::*;
+
+        let data = generate_pattern_data(10_000);
+        let context = lzss_create(4096, 3);
+        let compressed = LZSS::new(4096, 3).compress(&data);
+
+        let mut output = vec![0u8; data.len() + 10];
+        let mut size = 0;
+        assert_eq!(lzss_decompress_bounded(context, compressed.as_ptr(), compressed.len() as _, output.as_mut_ptr(), output.len() as _, &mut size), 0);
+        assert_eq!(&output[..size as usize], &data[..]);
+
+        // Too small a buffer fails before decoding and reports the size needed
+        size = 0;
+        assert_eq!(
+            lzss_decompress_bounded(context, compressed.as_ptr(), compressed.len() as _, output.as_mut_ptr(), 100, &mut size),
+            LzssStatus::BufferTooSmall as i32
+        );
+        assert_eq!(size as usize, data.len());
+
+        // A forged size header can't force an allocation, and corruption is reported
+        let mut forged = compressed.clone();
+        forged[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            lzss_decompress_bounded(context, forged.as_ptr(), forged.len() as _, output.as_mut_ptr(), output.len() as _, &mut size),
+            LzssStatus::BufferTooSmall as i32
+        );
+        assert_eq!(size as usize, u32::MAX as usize);
+        let truncated = &compressed[..compressed.len() / 2];
+        assert_eq!(
+            lzss_decompress_bounded(context, truncated.as_ptr(), truncated.len() as _, output.as_mut_ptr(), output.len() as _, &mut size),
+            LzssStatus::CorruptInput as i32
+        );
+
+        // The caller-allocated variants also report the size they need
+        size = 0;
+        assert_eq!(
+            lzss_decompress(context, compressed.as_ptr(), compressed.len() as _, output.as_mut_ptr(), 100, &mut size),
+            LzssStatus::BufferTooSmall as i32
+        );
+        assert_eq!(size as usize, data.len());
+        assert_eq!(
+            lzss_compress(context, data.as_ptr(), data.len() as _, output.as_mut_ptr(), 10, &mut size),
+            LzssStatus::BufferTooSmall as i32
+        );
+        assert_eq!(size as usize, compressed.len());
+        lzss_destroy(context);
+    }
+
+    #[test]
+    fn test_ffi_frame_info() {
+        use crate::ffi::*;
+        use crate::frame::{Checksum, FrameOptions};
+
+        let frame_info = |data: &[u8]| {
+            let mut info = std::mem::MaybeUninit::<LzssFrameInfo>::uninit();
+            match lzss_get_frame_info(data.as_ptr(), data.len() as _, info.as_mut_ptr()) {
+                0 => Ok(unsafe { info.assume_init() }),
+                status => Err(status),
+            }
+        };
+
+        let data = generate_pattern_data(200_000);
+        let lzss = LZSS::new(4096, 4);
+        let framed = lzss.compress_framed_with(&data, &FrameOptions::default().with_checksum(Checksum::Xxh3));
+        let info = frame_info(&framed).unwrap();
+        assert_eq!(info.format, LzssFormat::Framed);
+        assert_eq!(info.original_size as usize, data.len());
+        assert_eq!(info.compressed_size as usize, framed.len());
+        assert_eq!((info.min_match_length, info.offset_bytes, info.window_size), (4, 2, 4096));
+        assert_eq!(info.checksum, LzssChecksum::Xxh3);
+        assert_eq!(info.block_count, 1);
+
+        // Multi-block streams report their block count, raw streams no parameters
+        let info = frame_info(&lzss.compress_adaptive(&data)).unwrap();
+        assert_eq!((info.format, info.original_size as usize), (LzssFormat::Blocks, data.len()));
+        assert!(info.block_count > 1);
+        let info = frame_info(&lzss.compress(&data)).unwrap();
+        assert_eq!((info.format, info.min_match_length, info.checksum), (LzssFormat::Raw, 0, LzssChecksum::None));
+
+        let wide = LZSS::new(4096, 3).with_length_field(crate::LengthField::TwoBytes).compress(&[0; 100_000]);
+        assert_eq!(frame_info(&wide).unwrap().original_size, 100_000);
+        assert_eq!(frame_info(&[0xFF; 3]), Err(LzssStatus::CorruptInput as i32));
+        assert_eq!(lzss_get_frame_info(framed.as_ptr(), 3, std::ptr::null_mut()), LzssStatus::InvalidParameters as i32);
+    }
+
+    #[test]
+    fn test_ffi_simple() {
+        use crate::ffi::*;
+
+        let data = generate_pattern_data(10_000);
+        let expected = LZSS::new(2048, 4).compress(&data);
+
+        // Each thread compresses and decompresses without a shared context
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    let mut compressed = vec![0u8; lzss_max_compressed_size(data.len() as _) as usize];
+                    let mut size = 0;
+                    assert_eq!(lzss_compress_simple(2048, 4, data.as_ptr(), data.len() as _, compressed.as_mut_ptr(), compressed.len() as _, &mut size), 0);
+                    assert_eq!(&compressed[..size as usize], &expected[..]);
+
+                    let mut output = vec![0u8; data.len()];
+                    assert_eq!(lzss_decompress_simple(2048, 4, compressed.as_ptr(), size, output.as_mut_ptr(), output.len() as _, &mut size), 0);
+                    assert_eq!(output, data);
+                });
+            }
+        });
+
+        let mut size = 0;
+        let mut output = vec![0u8; 16];
+        assert_eq!(
+            lzss_compress_simple(0, 3, data.as_ptr(), data.len() as _, output.as_mut_ptr(), 16, &mut size),
+            LzssStatus::InvalidParameters as i32
+        );
+        assert_eq!(
+            lzss_decompress_simple(2048, 4, expected.as_ptr(), expected.len() as _, output.as_mut_ptr(), 16, &mut size),
+            LzssStatus::BufferTooSmall as i32
+        );
+        assert_eq!(size as usize, data.len());
+    }
+
+    #[test]
+    fn test_ffi_version_and_features() {
+        use crate::ffi::*;
+
+        let version = unsafe { std::ffi::CStr::from_ptr(lzss_version()) };
+        assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+
+        let features = lzss_features();
+        for feature in [LzssFeature::Frames, LzssFeature::Crc32, LzssFeature::Xxh3, LzssFeature::Dictionaries] {
+            assert_ne!(features & feature as u32, 0, "{:?}", feature);
+        }
+        assert_eq!(features & LzssFeature::Huffman as u32 != 0, cfg!(feature = "huffman"));
+        assert_eq!(features & LzssFeature::SmallTarget as u32 != 0, cfg!(feature = "small-target"));
+    }
+
+    #[test]
+    fn test_ffi_progress() {
+        use crate::ffi::*;
+        use std::os::raw::{c_int, c_void};
+
+        extern "C" fn record(user_data: *mut c_void, done: u64, total: u64) -> c_int {
+            let calls = unsafe { &mut *(user_data as *mut Vec<(u64, u64)>) };
+            calls.push((done, total));
+            0
+        }
+        extern "C" fn cancel(_: *mut c_void, _: u64, _: u64) -> c_int {
+            1
+        }
+
+        let data = generate_pattern_data(5_000_000);
+        let context = lzss_create(4096, 3);
+        let mut compressed = vec![0u8; lzss_max_compressed_size(data.len() as _) as usize];
+        let mut size = 0;
+        let mut calls = Vec::<(u64, u64)>::new();
+        let user_data = &mut calls as *mut Vec<(u64, u64)> as *mut c_void;
+        assert_eq!(
+            lzss_compress_progress(context, data.as_ptr(), data.len() as _, compressed.as_mut_ptr(), compressed.len() as _, &mut size, Some(record), user_data),
+            0
+        );
+        compressed.truncate(size as usize);
+        assert_eq!(compressed, LZSS::new(4096, 3).compress(&data));
+        // Roughly every megabyte, ending with the total
+        assert!(calls.len() >= 5);
+        assert!(calls.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(calls.iter().all(|&(_, total)| total == data.len() as u64));
+        assert_eq!(calls.last(), Some(&(data.len() as u64, data.len() as u64)));
+
+        calls.clear();
+        let mut output = vec![0u8; data.len()];
+        assert_eq!(
+            lzss_decompress_progress(context, compressed.as_ptr(), size, output.as_mut_ptr(), output.len() as _, &mut size, Some(record), user_data),
+            0
+        );
+        assert_eq!(output, data);
+        assert!(calls.len() >= 5);
+        assert_eq!(calls.last(), Some(&(data.len() as u64, data.len() as u64)));
+
+        // A nonzero return cancels; no callback behaves like the plain calls
+        let cancelled = LzssStatus::Cancelled as i32;
+        assert_eq!(
+            lzss_compress_progress(context, data.as_ptr(), data.len() as _, output.as_mut_ptr(), output.len() as _, &mut size, Some(cancel), std::ptr::null_mut()),
+            cancelled
+        );
+        assert_eq!(
+            lzss_decompress_progress(context, compressed.as_ptr(), compressed.len() as _, output.as_mut_ptr(), output.len() as _, &mut size, Some(cancel), std::ptr::null_mut()),
+            cancelled
+        );
+        assert_eq!(
+            lzss_decompress_progress(context, compressed.as_ptr(), compressed.len() as _, output.as_mut_ptr(), output.len() as _, &mut size, None, std::ptr::null_mut()),
+            0
+        );
+        assert_eq!(output, data);
+        lzss_destroy(context);
+    }
+
+    #[test]
+    fn test_ffi_dictionaries() {
+        use crate::ffi::*;
+
+        let dictionary = b"{\"player\": \"\", \"position\": [0, 0, 0], \"health\": 100, \"inventory\": []}".to_vec();
+        let message = b"{\"player\": \"ana\", \"position\": [3, 1, 0], \"health\": 100, \"inventory\": []}".to_vec();
+        let lzss = LZSS::new(4096, 3);
+
+        // Per-call dictionaries leave the context untouched
+        let context = lzss_create(4096, 3);
+        let mut compressed = vec![0u8; 256];
+        let mut size = 0;
+        assert_eq!(
+            lzss_compress_with_dict(context, dictionary.as_ptr(), dictionary.len() as _, message.as_ptr(), message.len() as _, compressed.as_mut_ptr(), compressed.len() as _, &mut size),
+            0
+        );
+        compressed.truncate(size as usize);
+        assert_eq!(compressed, lzss.compress_with_dictionary(&dictionary, &message));
+
+        let mut output = vec![0u8; message.len()];
+        assert_eq!(
+            lzss_decompress_with_dict(context, dictionary.as_ptr(), dictionary.len() as _, compressed.as_ptr(), size, output.as_mut_ptr(), output.len() as _, &mut size),
+            0
+        );
+        assert_eq!(output, message);
+        assert_eq!(
+            lzss_decompress_with_dict(context, std::ptr::null(), 0, compressed.as_ptr(), compressed.len() as _, output.as_mut_ptr(), output.len() as _, &mut size),
+            LzssStatus::CorruptInput as i32
+        );
+        lzss_destroy(context);
+
+        // Streams keep their windows between packets, starting from the dictionary
+        let sender = lzss_stream_create(4096, 3);
+        let receiver = lzss_stream_create(4096, 3);
+        for stream in [sender, receiver] {
+            assert_eq!(lzss_stream_set_dictionary(stream, dictionary.as_ptr(), dictionary.len() as _), 0);
+        }
+        let mut sizes = Vec::new();
+        for _ in 0..3 {
+            let mut packet = vec![0u8; lzss_max_compressed_size(message.len() as _) as usize];
+            assert_eq!(
+                lzss_stream_compress(sender, message.as_ptr(), message.len() as _, packet.as_mut_ptr(), packet.len() as _, &mut size),
+                0
+            );
+            packet.truncate(size as usize);
+            sizes.push(packet.len());
+
+            // A short buffer leaves the window as it was
+            assert_eq!(
+                lzss_stream_decompress(receiver, packet.as_ptr(), packet.len() as _, output.as_mut_ptr(), 4, &mut size),
+                LzssStatus::BufferTooSmall as i32
+            );
+            assert_eq!(size as usize, message.len());
+            assert_eq!(
+                lzss_stream_decompress(receiver, packet.as_ptr(), packet.len() as _, output.as_mut_ptr(), output.len() as _, &mut size),
+                0
+            );
+            assert_eq!(output, message);
+        }
+        assert!(sizes[0] < message.len() / 2);
+        assert!(sizes[1] < sizes[0]);
+
+        let mut packet = [0u8; 4];
+        assert_eq!(
+            lzss_stream_compress(sender, message.as_ptr(), message.len() as _, packet.as_mut_ptr(), 4, &mut size),
+            LzssStatus::BufferTooSmall as i32
+        );
+        assert_eq!(size as usize, lzss.max_compressed_size(message.len()));
+        lzss_stream_reset(sender);
+        lzss_stream_destroy(sender);
+        lzss_stream_destroy(receiver);
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn test_wasm_bindings() {
+        use crate::wasm;
+
+        let data = generate_pattern_data(20_000);
+
+        // Defaults match the engine's usual parameters
+        let compressed = wasm::compress(&data, None, None);
+        assert_eq!(compressed, LZSS::new(4096, 3).compress(&data));
+        assert_eq!(wasm::decompress(&compressed, None, None).unwrap(), data);
+
+        let framed = wasm::compress_framed(&data, Some(1024), Some(4));
+        assert_eq!(wasm::decompress(&framed, None, None).unwrap(), data);
+
+        let dictionary = data[..1000].to_vec();
+        let mut compressor = wasm::Compressor::new(None, None, Some(dictionary.clone()));
+        let mut decompressor = wasm::Decompressor::new(None, None, Some(dictionary));
+        for packet in data.chunks(5000) {
+            let compressed = compressor.compress(packet);
+            assert_eq!(decompressor.decompress(&compressed).unwrap(), packet);
+        }
+        compressor.reset_window();
+        decompressor.reset_window();
+        assert_eq!(decompressor.decompress(&compressor.compress(&data)).unwrap(), data);
+
+        // The stream transformers use the `stream` block format, fed in
+        // chunks that split blocks anywhere
+        let mut encoder = crate::stream::Encoder::new(LZSS::new(4096, 3)).with_block_size(3000);
+        let mut compressed = encoder.feed(&data);
+        compressed.extend(encoder.finish());
+        let mut stream = wasm::DecompressorStream::new(None, None);
+        let mut restored = Vec::new();
+        for chunk in compressed.chunks(777) {
+            restored.extend(stream.decode(chunk).unwrap());
+        }
+        assert!(stream.finish().is_ok());
+        assert_eq!(restored, data);
+
+        let mut stream = wasm::DecompressorStream::new(None, None);
+        assert!(stream.decode(&compressed[..compressed.len() - 1]).is_ok());
+        assert_eq!(stream.finish(), Err(crate::LzssError::TruncatedInput));
+
+        // A corrupt block after good ones fails the chunk it completes
+        let mut stream = wasm::DecompressorStream::new(None, None);
+        let first_block = 4 + u32::from_le_bytes(compressed[..4].try_into().unwrap()) as usize;
+        let mut corrupt = compressed[..first_block].to_vec();
+        corrupt.extend_from_slice(&[2, 0, 0, 0, 0x00, 0xff]);
+        assert!(stream.decode(&corrupt).is_err());
+    }
+
+    #[cfg(feature = "python")]
+    #[test]
+    fn test_python_bindings() {
+        use pyo3::prelude::*;
+        use pyo3::types::PyDict;
+
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::new(py, "rustzss").unwrap();
+            crate::python::rustzss(&module).unwrap();
+            let locals = PyDict::new(py);
+            locals.set_item("rustzss", module).unwrap();
+            py.run(
+                c"
+data = b'{\"position\": [0, 0, 0], \"health\": 100} ' * 500
+compressed = rustzss.compress(data)
+assert isinstance(compressed, bytes) and len(compressed) < len(data) // 10
+assert rustzss.decompress(compressed) == data
+assert rustzss.decompress(rustzss.compress(data, window=1024, min_match=4), window=1024, min_match=4) == data
+assert rustzss.decompress(rustzss.compress_framed(data, min_match=5)) == data
+try:
+    rustzss.decompress(compressed[:-5])
+    raise AssertionError('truncated input was accepted')
+except ValueError:
+    pass
+assert rustzss.quick_tune(data)['min_match'] >= 2
+best = rustzss.tune(data, max_iterations=3, seed=7)
+assert best['compression_ratio'] < 0.2
+
+# Any contiguous buffer is read in place, whatever its element type
+import array
+assert rustzss.decompress(rustzss.compress(memoryview(data)[100:])) == data[100:]
+assert rustzss.decompress(bytearray(compressed)) == data
+samples = array.array('d', range(1000))
+assert rustzss.decompress(rustzss.compress(samples)) == samples.tobytes()
+for bad in ['text', memoryview(data)[::2]]:
+    try:
+        rustzss.compress(bad)
+        raise AssertionError('unsupported input was accepted')
+    except (TypeError, BufferError):
+        pass
+",
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+        });
+    }
+
+    #[test]
+    fn test_frame_window_limits_distances() {
+        use crate::frame::FrameHeader;
+        use crate::LzssError;
+
+        // Random bytes leave no matches of 8, except a repeat 100 bytes
+        // back that reaches into the first chunk
+        let mut data = generate_random_data(70_000);
+        data.copy_within(65_486..65_506, 65_586);
+        let framed = LZSS::new(4096, 8).with_skip_incompressible(false).compress_framed(&data);
+
+        // A forged header claiming a 16-byte window
+        let mut header = FrameHeader::parse(&framed).unwrap();
+        header.window_size = Some(16);
+        let mut forged = header.to_bytes();
+        forged.extend_from_slice(header.stream(&framed));
+
+        let lzss = LZSS::new(4096, 3);
+        let last = lzss.decompress_iter(&forged).last().unwrap();
+        assert_eq!(last, Err(LzssError::InvalidDistance { position: 65_586, distance: 100 }));
+        assert_eq!(lzss.try_decompress(&forged), Err(LzssError::InvalidDistance { position: 65_586, distance: 100 }));
+        assert_eq!(lzss.try_decompress(&framed).unwrap(), data);
+
+        // Buffer decoding, including the C API's, checks the window too
+        let mut output = vec![0; data.len()];
+        assert_eq!(
+            lzss.decompress_into(&forged, &mut output),
+            Err(LzssError::InvalidDistance { position: 65_586, distance: 100 })
+        );
+        let context = crate::ffi::lzss_create(4096, 3);
+        let mut size = 0;
+        let status = crate::ffi::lzss_decompress_bounded(
+            context,
+            forged.as_ptr(),
+            forged.len() as _,
+            output.as_mut_ptr(),
+            output.len() as _,
+            &mut size,
+        );
+        assert_eq!(status, crate::ffi::LzssStatus::CorruptInput as i32);
+        crate::ffi::lzss_destroy(context);
+    }
+
+    #[cfg(feature = "embedded-io")]
+    #[test]
+    fn test_embedded_io_streams() {
+        use crate::embedded::{AsyncLzssReader, AsyncLzssWriter, LzssReader, LzssWriter, StreamError};
+        use std::io::Read as _;
+
+        // Futures over slices never wait, so one poll completes them
+        fn block_on<F: std::future::Future>(future: F) -> F::Output {
+            let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+            match std::pin::pin!(future).poll(&mut context) {
+                std::task::Poll::Ready(output) => output,
+                std::task::Poll::Pending => panic!("slice IO never waits"),
+            }
+        }
+
+        let data = generate_pattern_data(20_000);
+        let lzss = || LZSS::new(4096, 3);
+
+        // Blocking: written into a flash-like buffer, read back in small pieces
+        let mut flash = vec![0u8; data.len()];
+        let mut writer = LzssWriter::new(&mut flash[..], lzss()).with_block_size(3000);
+        for piece in data.chunks(777) {
+            embedded_io::Write::write_all(&mut writer, piece).unwrap();
+        }
+        let free = writer.finish().unwrap().len();
+        let compressed = flash[..flash.len() - free].to_vec();
+        assert!(compressed.len() < data.len() / 4);
+
+        let mut reader = LzssReader::new(&compressed[..], lzss());
+        let mut decoded = vec![0u8; data.len()];
+        for piece in decoded.chunks_mut(100) {
+            embedded_io::Read::read_exact(&mut reader, piece).unwrap();
+        }
+        assert_eq!(decoded, data);
+        assert_eq!(embedded_io::Read::read(&mut reader, &mut [0; 8]).unwrap(), 0);
+
+        // Same stream layout as the std::io adapters
+        let mut host = Vec::new();
+        crate::stream::LzssReader::new(&compressed[..], lzss()).read_to_end(&mut host).unwrap();
+        assert_eq!(host, data);
+
+        // Truncation and corruption are reported
+        let mut truncated = LzssReader::new(&compressed[..compressed.len() - 10], lzss());
+        let error = embedded_io::Read::read_exact(&mut truncated, &mut decoded).unwrap_err();
+        assert!(matches!(error, embedded_io::ReadExactError::Other(StreamError::UnexpectedEof)));
+        let mut corrupt = compressed.clone();
+        corrupt[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        let error = embedded_io::Read::read(&mut LzssReader::new(&corrupt[..], lzss()), &mut decoded).unwrap_err();
+        assert!(matches!(error, StreamError::Decompress(_)));
+        assert_eq!(embedded_io::Error::kind(&error), embedded_io::ErrorKind::InvalidData);
+
+        // Async variants produce and read the same stream
+        let mut flash = vec![0u8; data.len()];
+        let free = block_on(async {
+            let mut writer = AsyncLzssWriter::new(&mut flash[..], lzss()).with_block_size(3000);
+            for piece in data.chunks(777) {
+                embedded_io_async::Write::write_all(&mut writer, piece).await.unwrap();
+            }
+            writer.finish().await.unwrap().len()
+        });
+        assert_eq!(&flash[..flash.len() - free], &compressed[..]);
+
+        let mut decoded = vec![0u8; data.len()];
+        block_on(async {
+            let mut reader = AsyncLzssReader::new(&compressed[..], lzss());
+            embedded_io_async::Read::read_exact(&mut reader, &mut decoded).await.unwrap();
+            assert_eq!(embedded_io_async::Read::read(&mut reader, &mut [0; 8]).await.unwrap(), 0);
+        });
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn test_dictionary_resolver() {
+        use crate::detect::inspect;
+        use crate::dictionary::{Dictionary, FrameDecoder};
+        use crate::frame::{FrameHeader, FRAME_DICTIONARY_VERSION};
+        use crate::{LengthField, LzssError};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let template = b"{\"player\": \"\", \"position\": [0, 0, 0], \"health\": 100, \"inventory\": []}".to_vec();
+        let message = b"{\"player\": \"ana\", \"position\": [3, 1, 0], \"health\": 100, \"inventory\": []}".to_vec();
+        let dictionary = Arc::new(Dictionary::new(7, template));
+
+        let lzss = LZSS::new(4096, 3);
+        let framed = lzss.compress_framed_with_dictionary(&dictionary, &message);
+        assert!(framed.len() < lzss.compress_framed(&message).len());
+        let header = FrameHeader::parse(&framed).unwrap();
+        assert_eq!((header.version, header.dictionary_id), (FRAME_DICTIONARY_VERSION, Some(7)));
+        assert_eq!(inspect(&framed, 3, LengthField::OneByte).unwrap().dictionary_id, Some(7));
+
+        // Without the dictionary the frame is reported, not misdecoded
+        assert_eq!(lzss.try_decompress(&framed), Err(LzssError::MissingDictionary { id: 7 }));
+        assert_eq!(FrameDecoder::new().decompress(&framed), Err(LzssError::MissingDictionary { id: 7 }));
+
+        // The resolver is asked once per ID and its answer kept
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut decoder = FrameDecoder::new().with_resolver({
+            let (calls, dictionary) = (calls.clone(), dictionary.clone());
+            move |id| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                (id == 7).then(|| dictionary.clone())
+            }
+        });
+        assert_eq!(decoder.decompress(&framed).unwrap(), message);
+        assert_eq!(decoder.decompress(&framed).unwrap(), message);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        let other = lzss.compress_framed_with_dictionary(&Dictionary::new(8, b"other".to_vec()), &message);
+        assert_eq!(decoder.decompress(&other), Err(LzssError::MissingDictionary { id: 8 }));
+        assert_eq!(decoder.decompress(&lzss.compress_framed(&message)).unwrap(), message);
+
+        let mut known = FrameDecoder::new().with_dictionary(dictionary);
+        assert_eq!(known.decompress(&framed).unwrap(), message);
+        assert_eq!(known.decompress(&lzss.compress(&message)), Err(LzssError::InvalidHeader));
+    }
+
+    #[test]
+    fn test_stream_external_window() {
+        use crate::ffi::*;
+
+        let dictionary = generate_pattern_data(1000);
+        let packets: Vec<Vec<u8>> = generate_pattern_data(3000).chunks(300).map(<[u8]>::to_vec).collect();
+        let sender = lzss_stream_create(256, 3);
+        let receiver = lzss_stream_create(256, 3);
+        for stream in [sender, receiver] {
+            assert_eq!(lzss_stream_set_dictionary(stream, dictionary.as_ptr(), dictionary.len() as _), 0);
+        }
+
+        // The memory must hold the whole window
+        let mut window = vec![0u8; 300];
+        assert_eq!(lzss_stream_set_window(receiver, window.as_mut_ptr(), 255), LzssStatus::InvalidParameters as i32);
+        assert_eq!(lzss_stream_set_window(receiver, window.as_mut_ptr(), window.len() as _), 0);
+
+        let mut size = 0;
+        let mut output = vec![0u8; 300];
+        for (i, message) in packets.iter().enumerate() {
+            let mut packet = vec![0u8; lzss_max_compressed_size(message.len() as _) as usize];
+            assert_eq!(
+                lzss_stream_compress(sender, message.as_ptr(), message.len() as _, packet.as_mut_ptr(), packet.len() as _, &mut size),
+                0
+            );
+            packet.truncate(size as usize);
+
+            // A corrupt packet leaves the window as it was
+            if i == 3 {
+                let mut corrupt = packet.clone();
+                corrupt.truncate(corrupt.len() - 3);
+                assert_eq!(
+                    lzss_stream_decompress(receiver, corrupt.as_ptr(), corrupt.len() as _, output.as_mut_ptr(), output.len() as _, &mut size),
+                    LzssStatus::CorruptInput as i32
+                );
+            }
+            assert_eq!(
+                lzss_stream_decompress(receiver, packet.as_ptr(), packet.len() as _, output.as_mut_ptr(), output.len() as _, &mut size),
+                0
+            );
+            assert_eq!(&output[..size as usize], &message[..]);
+
+            // The caller's memory holds the most recent bytes
+            assert_eq!(window[..256], message[message.len() - 256..]);
+        }
+
+        // Going back to the library's window keeps decoding in step after a
+        // reset on both ends
+        assert_eq!(lzss_stream_set_window(receiver, std::ptr::null_mut(), 0), 0);
+        lzss_stream_reset(sender);
+        let mut packet = vec![0u8; lzss_max_compressed_size(300) as usize];
+        assert_eq!(
+            lzss_stream_compress(sender, packets[0].as_ptr(), 300, packet.as_mut_ptr(), packet.len() as _, &mut size),
+            0
+        );
+        assert_eq!(lzss_stream_decompress(receiver, packet.as_ptr(), size, output.as_mut_ptr(), 300, &mut size), 0);
+        assert_eq!(output, packets[0]);
+        lzss_stream_destroy(sender);
+        lzss_stream_destroy(receiver);
+    }
+
+    #[test]
+    fn test_decompress_iter() {
+        use crate::LzssError;
+
+        let mut data = b"the quick brown fox jumps over the lazy dog. ".repeat(2_000);
+        data.extend(generate_random_data(50_000));
+        data.extend(generate_pattern_data(50_000));
+
+        let lzss = LZSS::new(4096, 3);
+        let compressed = lzss.compress(&data);
+
+        // Default chunks and tiny chunks splitting matches
+        for chunk_size in [crate::iter::DEFAULT_CHUNK_SIZE, 7] {
+            let chunks: Vec<Vec<u8>> = lzss.decompress_iter(&compressed)
+                .with_chunk_size(chunk_size)
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert!(chunks[..chunks.len() - 1].iter().all(|chunk| chunk.len() == chunk_size));
+            assert_eq!(chunks.concat(), data);
+        }
+
+        // Frames carry their own parameters, including LDM offsets
+        let ldm = LZSS::new(4096, 4).with_long_distance_matching(1 << 20);
+        let framed = ldm.compress_framed(&data);
+        let decoded: Result<Vec<Vec<u8>>, _> = lzss.decompress_iter(&framed).collect();
+        assert_eq!(decoded.unwrap().concat(), data);
+
+        // Empty input yields nothing
+        assert_eq!(lzss.decompress_iter(&[]).count(), 0);
+
+        // Corruption is reported instead of padded over
+        let truncated = &compressed[..compressed.len() / 2];
+        let last = lzss.decompress_iter(truncated).last().unwrap();
+        assert!(matches!(last, Err(LzssError::SizeMismatch { .. }) | Err(LzssError::TruncatedInput)));
+
+        let bad_reference = [4, 0, 0, 0, 0b0000_0001, 9, 0, 0];
+        let mut iter = lzss.decompress_iter(&bad_reference);
+        assert_eq!(iter.next(), Some(Err(LzssError::InvalidDistance { position: 0, distance: 9 })));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_compressed_serde_values() {
+        use crate::serialize::{from_compressed_slice, to_compressed_vec, SerdeError};
+        use std::collections::HashMap;
+
+        let mut save: HashMap<String, Vec<u32>> = HashMap::new();
+        save.insert("inventory".into(), vec![7; 500]);
+        save.insert("visited".into(), (0..200).collect());
+
+        let compressed = to_compressed_vec(&save).unwrap();
+        assert!(compressed.len() < bincode::serialize(&save).unwrap().len());
+        assert_eq!(from_compressed_slice::<HashMap<String, Vec<u32>>>(&compressed).unwrap(), save);
+
+        let truncated = &compressed[..compressed.len() - 10];
+        assert!(matches!(
+            from_compressed_slice::<HashMap<String, Vec<u32>>>(truncated),
+            Err(SerdeError::Decompress(_))
+        ));
+    }
+
+    #[test]
+    fn test_preset_dictionary() {
+        use crate::ffi::*;
+
+        let dictionary = b"{\"player\": \"\", \"position\": [0, 0, 0], \"health\": 100, \"inventory\": []}".to_vec();
+        let message = b"{\"player\": \"ana\", \"position\": [3, 1, 0], \"health\": 100, \"inventory\": []}".to_vec();
+
+        let lzss = LZSS::new(4096, 3);
+        let compressed = lzss.compress_with_dictionary(&dictionary, &message);
+        assert!(compressed.len() < lzss.compress(&message).len());
+        assert_eq!(lzss.decompress_with_dictionary(&dictionary, &compressed).unwrap(), message);
+        // Corrupt input is reported instead of patched up
+        let mut corrupt = compressed.clone();
+        corrupt[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(lzss.decompress_with_dictionary(&dictionary, &corrupt).is_err());
+        assert!(lzss.decompress_with_dictionary(&[], &compressed).is_err());
+
+        // The C API applies a loaded dictionary to both directions
+        let context = lzss_create(4096, 3);
+        assert_eq!(lzss_load_dictionary(context, dictionary.as_ptr(), dictionary.len() as _), 0);
+
+        let mut output = vec![0u8; lzss_max_compressed_size(message.len() as _) as usize];
+        let mut size = 0;
+        assert_eq!(lzss_compress(context, message.as_ptr(), message.len() as _, output.as_mut_ptr(), output.len() as _, &mut size), 0);
+        assert_eq!(&output[..size as usize], &compressed[..]);
+
+        let mut decoded = vec![0u8; message.len()];
+        assert_eq!(lzss_decompress(context, output.as_ptr(), size, decoded.as_mut_ptr(), decoded.len() as _, &mut size), 0);
+        assert_eq!(decoded, message);
+
+        lzss_destroy(context);
+    }
+
+    #[test]
+    fn test_length_field_widths() {
+        use crate::frame::{FrameHeader, FRAME_MAGIC};
+        use crate::LengthField;
+
+        // Long runs are where wider length fields pay off
+        let mut data = vec![0u8; 100_000];
+        data.extend(generate_pattern_data(50_000));
+        data.extend(generate_random_data(10_000));
+
+        let one_byte = LZSS::new(4096, 3).compress(&data);
+        for field in [LengthField::OneByte, LengthField::TwoBytes, LengthField::Extended] {
+            let lzss = LZSS::new(4096, 3).with_length_field(field);
+            let compressed = lzss.compress(&data);
+            assert_eq!(lzss.try_decompress(&compressed).unwrap(), data, "{:?}", field);
+            if field != LengthField::OneByte {
+                assert!(compressed.len() < one_byte.len(), "{:?}", field);
+            }
+
+            // Frames record the field width
+            let framed = lzss.compress_framed(&data);
+            assert_eq!(FrameHeader::parse(&framed).unwrap().length_field, field);
+            assert_eq!(LZSS::new(256, 5).try_decompress(&framed).unwrap(), data);
+            let chunks: Vec<Vec<u8>> = LZSS::new(256, 5).decompress_iter(&framed).collect::<Result<_, _>>().unwrap();
+            assert_eq!(chunks.concat(), data);
+        }
+
+        // Version 1 frames (no length field byte) still decode
+        let mut v1 = FRAME_MAGIC.to_vec();
+        v1.extend_from_slice(&[1, 3, 2]);
+        v1.extend_from_slice(&one_byte);
+        assert_eq!(FrameHeader::parse(&v1).unwrap().size(), 7);
+        assert_eq!(LZSS::new(256, 5).try_decompress(&v1).unwrap(), data);
+    }
+
+    #[test]
+    #[cfg(not(feature = "small-target"))]
+    fn test_frame_distance_minus_one() {
+        use crate::frame::{FrameHeader, FLAG_DISTANCE_MINUS_ONE};
+        use crate::LzssError;
+
+        // A repeat exactly 65536 bytes back is only reachable in frames
+        let mut data = generate_random_data(65_536);
+        data.extend_from_within(..1000);
+
+        let lzss = LZSS::new(65_536, 3);
+        let framed = lzss.compress_framed(&data);
+        assert_ne!(FrameHeader::parse(&framed).unwrap().flags & FLAG_DISTANCE_MINUS_ONE, 0);
+        assert_eq!(lzss.try_decompress(&framed).unwrap(), data);
+        assert!(framed.len() < lzss.compress(&data).len());
+
+        // In raw streams a zero distance is invalid, and strict decoding says so
+        let zero_distance = [4, 0, 0, 0, 0b0000_0010, b'a', 0, 0, 0];
+        assert_eq!(
+            lzss.decompress_iter(&zero_distance).next(),
+            Some(Err(LzssError::InvalidDistance { position: 1, distance: 0 }))
+        );
+    }
+
+    #[test]
+    fn test_no_overlapping_matches() {
+        let mut data = vec![b'x'; 5000];
+        data.extend(generate_pattern_data(20_000));
+        data.extend(generate_random_data(5000));
+
+        // Runs compress to overlapping matches by default
+        let lzss = LZSS::new(4096, 3);
+        let stats = lzss.validate(&lzss.compress(&data)).unwrap();
+        assert!(!stats.is_overlap_free());
+        assert_eq!(stats.content_size, data.len());
+
+        for lzss in [LZSS::new(4096, 3), LZSS::new(4096, 4).with_long_distance_matching(1 << 20)] {
+            let lzss = lzss.with_overlapping_matches(false);
+            let compressed = lzss.compress(&data);
+            let stats = lzss.validate(&compressed).unwrap();
+            assert!(stats.is_overlap_free());
+            assert!(stats.matches > 0);
+            assert_eq!(lzss.try_decompress(&compressed).unwrap(), data);
+
+            let framed = lzss.compress_framed(&data);
+            assert!(LZSS::new(256, 3).validate(&framed).unwrap().is_overlap_free());
+        }
+
+        // Validation reports corruption like strict decoding
+        let compressed = lzss.compress(&data);
+        assert!(lzss.validate(&compressed[..compressed.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn test_frame_alignment() {
+        use crate::frame::{FrameHeader, FrameOptions};
+
+        let lzss = LZSS::new(4096, 3);
+        for len in [0, 1, 1000, 100_000] {
+            let data = generate_pattern_data(len);
+            for alignment in [16, 64, 4096] {
+                let options = FrameOptions::default().with_alignment(alignment);
+                let framed = lzss.compress_framed_with(&data, &options);
+                assert_eq!(framed.len() % alignment, 0);
+
+                let header = FrameHeader::parse(&framed).unwrap();
+                assert!(header.padding < alignment);
+                assert!(framed[framed.len() - header.padding..].iter().all(|&b| b == 0));
+                assert_eq!(LZSS::new(256, 5).try_decompress(&framed).unwrap(), data);
+                assert_eq!(lzss.validate(&framed).unwrap().content_size, len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_frame_slot_padding() {
+        use crate::frame::{FrameHeader, FrameOptions};
+
+        let lzss = LZSS::new(4096, 3);
+        let data = generate_pattern_data(10_000);
+        let options = FrameOptions::default().with_slot_size(4096).with_alignment(64);
+        let framed = lzss.compress_framed_with(&data, &options);
+        assert_eq!(framed.len() % 64, 0);
+
+        let header = FrameHeader::parse(&framed).unwrap();
+        assert_eq!(header.slot_size, 4096);
+        assert_eq!(header.content_padding, 12_288 - 10_000);
+
+        // The decoded content fills whole slots, with the real data first
+        let decoded = lzss.try_decompress(&framed).unwrap();
+        assert_eq!(decoded.len(), 12_288);
+        assert_eq!(&decoded[..data.len()], &data[..]);
+        assert!(decoded[data.len()..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_decompress_const() {
+        use crate::frame::FrameOptions;
+        use crate::{decompress_const, LengthField};
+
+        const TABLE: [u8; 64] = decompress_const(&[64, 0, 0, 0, 0b0000_0010, 7, 1, 0, 60]);
+        assert_eq!(LZSS::new(4096, 3).compress(&[7; 64]), [64, 0, 0, 0, 0b0000_0010, 7, 1, 0, 60]);
+        assert_eq!(TABLE, [7; 64]);
+
+        // Frames of any length field width and padding, evaluated at runtime
+        let data: Vec<u8> = (0..3000u32).map(|i| (i * i % 251) as u8).chain([9; 1000]).collect();
+        for field in [LengthField::OneByte, LengthField::TwoBytes, LengthField::Extended] {
+            let lzss = LZSS::new(1024, 4).with_length_field(field);
+            let framed = lzss.compress_framed_with(&data, &FrameOptions::default().with_alignment(16));
+            assert_eq!(decompress_const::<4000>(&framed).to_vec(), data);
+        }
+        assert_eq!(decompress_const::<4000>(&LZSS::new(4096, 3).compress(&data)).to_vec(), data);
+    }
+
+    #[test]
+    fn test_c_decoder_source() {
+        use crate::c_decoder::CDecoderOptions;
+        use crate::LengthField;
+        use std::process::Command;
+
+        let data: Vec<u8> = (0..3000u32).map(|i| (i * i % 251) as u8).chain([9; 1000]).collect();
+        let options = CDecoderOptions { function_name: "asset_decode".to_string(), frames: true };
+        let source = LZSS::new(4096, 3).c_decoder_source(&options);
+        assert!(source.contains("int asset_decode(const uint8_t *in, size_t in_len"));
+        assert!(!source.contains("#include <stdlib.h>"));
+
+        // Round-trip through the generated code when a C compiler is available
+        if Command::new("cc").arg("--version").output().is_err() {
+            return;
+        }
+        let dir = std::env::temp_dir().join(format!("rustzss_c_decoder_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let fields = [LengthField::OneByte, LengthField::TwoBytes, LengthField::Extended];
+        for (i, (field, short_offsets)) in fields.into_iter().flat_map(|f| [(f, false), (f, true)]).enumerate() {
+            for frames in [false, true] {
+                let lzss = LZSS::new(1024, 4).with_length_field(field).with_short_offsets(short_offsets);
+                let compressed = if frames { lzss.compress_framed(&data) } else { lzss.compress(&data) };
+                let options = CDecoderOptions { frames, ..CDecoderOptions::default() };
+                let bytes: Vec<String> = compressed.iter().map(u8::to_string).collect();
+                let program = format!(
+                    "{}\n#include <stdio.h>\nstatic const uint8_t input[] = {{{}}};\nstatic uint8_t out[8192];\n\
+                     int main(void) {{ size_t n; if (rlzss_decode(input, sizeof input, out, sizeof out, &n)) return 1; \
+                     fwrite(out, 1, n, stdout); return 0; }}\n",
+                    lzss.c_decoder_source(&options), bytes.join(",")
+                );
+                let name = format!("decode_{}_{}", i, frames);
+                std::fs::write(dir.join(format!("{}.c", name)), program).unwrap();
+                let status = Command::new("cc")
+                    .current_dir(&dir)
+                    .args(["-std=c99", "-Wall", "-Werror", "-o", &name])
+                    .arg(format!("{}.c", name))
+                    .status()
+                    .unwrap();
+                assert!(status.success(), "generated decoder failed to compile");
+                let output = Command::new(dir.join(&name)).output().unwrap();
+                assert!(output.status.success());
+                assert_eq!(output.stdout, data);
+            }
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_conformance_vectors() {
+        use crate::validate::CONFORMANCE_VECTORS;
+
+        for vector in CONFORMANCE_VECTORS {
+            assert_eq!(vector.check(), Ok(()), "{}", vector.name);
+        }
+    }
+
+    #[test]
+    fn test_file_helpers() {
+        use crate::fs::WriteOptions;
+
+        let dir = std::env::temp_dir().join(format!("rustzss_fs_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let data = generate_pattern_data(50_000);
+        std::fs::write(dir.join("input"), &data).unwrap();
+
+        let lzss = LZSS::new(4096, 3);
+        let options = WriteOptions::default().with_fsync(true);
+        lzss.compress_file(dir.join("input"), dir.join("input.lzs"), &options).unwrap();
+        lzss.decompress_file(dir.join("input.lzs"), dir.join("output"), &options).unwrap();
+        assert_eq!(std::fs::read(dir.join("output")).unwrap(), data);
+
+        // A failed decompression leaves the previous output in place and no
+        // temporary files behind
+        std::fs::write(dir.join("corrupt.lzs"), [200, 0, 0, 0, 0xFF, 9, 9]).unwrap();
+        let err = lzss.decompress_file(dir.join("corrupt.lzs"), dir.join("output"), &options).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(std::fs::read(dir.join("output")).unwrap(), data);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 4);
+
+        // Threads writing the same path don't share a temporary file, so the
+        // result is always one complete write
+        let writes: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; 100_000]).collect();
+        std::thread::scope(|scope| {
+            for write in &writes {
+                let path = dir.join("shared");
+                scope.spawn(move || crate::fs::write_atomic(path, write, &WriteOptions::default()).unwrap());
+            }
+        });
+        assert!(writes.contains(&std::fs::read(dir.join("shared")).unwrap()));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 5);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_independent_packets() {
+        use crate::compressor::{Compressor, Decompressor};
+
+        let dictionary = b"{\"id\": 0, \"status\": \"ok\", \"payload\": \"sensor reading\"}";
+        let packets: Vec<Vec<u8>> = (0..10)
+            .map(|i| format!("{{\"id\": {}, \"status\": \"ok\", \"payload\": \"sensor reading\"}}", i).into_bytes())
+            .collect();
+
+        let mut compressor = Compressor::new(LZSS::new(4096, 3))
+            .with_dictionary(dictionary)
+            .with_independent_packets(true);
+        let compressed: Vec<Vec<u8>> = packets.iter().map(|p| compressor.compress(p)).collect();
+        assert!(compressed.iter().zip(&packets).all(|(c, p)| c.len() < p.len() / 2));
+
+        // Packets decode in any order, with some of them lost
+        let mut decompressor = Decompressor::new(LZSS::new(4096, 3))
+            .with_dictionary(dictionary)
+            .with_independent_packets(true);
+        for i in [7, 2, 9, 0] {
+            assert_eq!(decompressor.try_decompress(&compressed[i]).unwrap(), packets[i]);
+        }
+    }
+
+    #[test]
+    fn test_adaptive_bypass() {
+        use crate::compressor::{Compressor, Decompressor};
+
+        let mut compressor = Compressor::new(LZSS::new(4096, 3)).with_adaptive_bypass(true);
+        let mut decompressor = Decompressor::new(LZSS::new(4096, 3)).with_adaptive_bypass(true);
+
+        // Random payloads soon stop being compressed, costing one byte each
+        let random: Vec<Vec<u8>> = (0..40).map(|_| generate_random_data(500)).collect();
+        for payload in &random {
+            let output = compressor.compress(payload);
+            assert_eq!(output.len(), payload.len() + 1);
+            assert_eq!(&decompressor.try_decompress(&output).unwrap(), payload);
+        }
+        assert!(compressor.is_bypassing());
+
+        // A periodic probe notices when the data becomes compressible again
+        let text = generate_pattern_data(500);
+        let mut total = 0;
+        for _ in 0..40 {
+            let output = compressor.compress(&text);
+            total += output.len();
+            assert_eq!(decompressor.try_decompress(&output).unwrap(), text);
+        }
+        assert!(!compressor.is_bypassing());
+        assert!(total < 40 * text.len() / 2);
+    }
+
+    #[test]
+    fn test_small_target_window() {
+        let lzss = LZSS::new(65535, 3);
+        let expected = if cfg!(feature = "small-target") { crate::MAX_WINDOW_SIZE } else { 65535 };
+        assert_eq!(lzss.window_size, expected);
+
+        // Inputs well past 64 KiB exercise the wrapped chain positions
+        let mut data = generate_pattern_data(100_000);
+        data.extend(generate_random_data(100_000));
+        data.extend(generate_pattern_data(100_000));
+        assert_eq!(lzss.try_decompress(&lzss.compress(&data)).unwrap(), data);
+    }
+
+    #[test]
+    fn test_try_decompress_keeps_window() {
+        use crate::compressor::{Compressor, Decompressor};
+        use crate::LzssError;
+
+        let mut compressor = Compressor::new(LZSS::new(4096, 3));
+        let mut decompressor = Decompressor::new(LZSS::new(4096, 3));
+        let first = generate_pattern_data(1000);
+        assert_eq!(decompressor.try_decompress(&compressor.compress(&first)), Ok(first.clone()));
+
+        // Matches into the previous call's window are valid
+        let second = compressor.compress(&first);
+        assert!(LZSS::new(4096, 3).validate(&second).is_err());
+
+        // A corrupt packet is rejected without disturbing the window
+        assert!(matches!(
+            decompressor.try_decompress(&[9, 0, 0, 0, 1, 0xFF, 0xFF, 0]),
+            Err(LzssError::InvalidDistance { .. })
+        ));
+        assert_eq!(decompressor.try_decompress(&second), Ok(first));
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_http_body_round_trip() {
+        use crate::http::{BodyError, Decoder, Encoder};
+        use bytes::Bytes;
+        use http_body_util::{BodyExt, Full};
+        use std::future::Future;
+        use std::task::{Context, Poll, Waker};
+
+        // Full bodies are always ready, so polling once completes them
+        fn ready<F: Future>(future: F) -> F::Output {
+            match std::pin::pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+                Poll::Ready(output) => output,
+                Poll::Pending => panic!("body was not ready"),
+            }
+        }
+
+        let data = generate_pattern_data(10_000);
+        let encoded = ready(Encoder::new(Full::new(Bytes::from(data.clone())), LZSS::new(4096, 3)).collect())
+            .unwrap()
+            .to_bytes();
+        assert!(encoded.len() < data.len() / 4);
+
+        let decoded = ready(Decoder::new(Full::new(encoded.clone())).collect()).unwrap().to_bytes();
+        assert_eq!(decoded, data);
+
+        // A truncated body is reported instead of being padded out
+        let truncated = Full::new(encoded.slice(..encoded.len() - 1));
+        assert!(matches!(ready(Decoder::new(truncated).collect()), Err(BodyError::Decompress(_))));
+    }
+
+    #[cfg(feature = "postcard")]
+    #[test]
+    fn test_telemetry_records() {
+        use crate::telemetry::{from_compressed_record, to_compressed_record, TelemetryError};
+
+        #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+        struct Reading {
+            sensor: String,
+            status: String,
+            values: Vec<u16>,
+        }
+
+        let record = |i: u16| Reading {
+            sensor: "greenhouse/north/temperature".to_string(),
+            status: "nominal".to_string(),
+            values: vec![210 + i, 211, 209, 210],
+        };
+        let dictionary = postcard::to_allocvec(&record(0)).unwrap();
+        let plain = postcard::to_allocvec(&record(7)).unwrap();
+
+        let compressed = to_compressed_record(&record(7), &dictionary).unwrap();
+        assert!(compressed.len() < plain.len() / 2);
+        assert_eq!(from_compressed_record::<Reading>(&compressed, &dictionary).unwrap(), record(7));
+
+        // Without the dictionary the matches point before the start
+        assert!(matches!(
+            from_compressed_record::<Reading>(&compressed, &[]),
+            Err(TelemetryError::Decompress(_))
+        ));
+    }
+
+    #[test]
+    fn test_decompress_exact() {
+        use crate::ffi::*;
+        use crate::frame::FrameOptions;
+        use crate::LzssError;
+
+        let lzss = LZSS::new(4096, 3);
+        let data = generate_pattern_data(5000);
+        let compressed = lzss.compress(&data);
+
+        let mut output = vec![0u8; data.len()];
+        assert_eq!(lzss.decompress_into(&compressed, &mut output), Ok(()));
+        assert_eq!(output, data);
+
+        // The buffer must be exactly the decompressed size
+        let mut short = vec![0u8; data.len() - 1];
+        assert_eq!(
+            lzss.decompress_into(&compressed, &mut short),
+            Err(LzssError::OutputSizeMismatch { expected: data.len(), actual: data.len() - 1 })
+        );
+        assert_eq!(lzss.decompress_into(&compressed[..compressed.len() - 1], &mut output), Err(LzssError::TruncatedInput));
+
+        // Frames are read with their own parameters, padding included
+        let framed = lzss.compress_framed_with(&data, &FrameOptions::default().with_alignment(64));
+        let mut output = vec![0u8; data.len()];
+        assert_eq!(LZSS::new(100, 5).decompress_into(&framed, &mut output), Ok(()));
+        assert_eq!(output, data);
+
+        // The C entry point reports any mismatch as a failure
+        let context = lzss_create(4096, 3);
+        let mut output = vec![0u8; data.len()];
+        assert_eq!(lzss_decompress_exact(context, compressed.as_ptr(), compressed.len() as _, output.as_mut_ptr(), output.len() as _), 0);
+        assert_eq!(output, data);
+        assert_eq!(lzss_decompress_exact(context, compressed.as_ptr(), compressed.len() as _, output.as_mut_ptr(), 100), -3);
+        lzss_destroy(context);
+    }
+
+    #[test]
+    fn test_frame_checksums() {
+        use crate::frame::{Checksum, FrameHeader, FrameOptions};
+        use crate::LzssError;
+
+        let lzss = LZSS::new(4096, 3);
+        let data = generate_pattern_data(100_000);
+
+        for checksum in [Checksum::None, Checksum::Crc32, Checksum::Xxh3] {
+            let options = FrameOptions::default().with_checksum(checksum).with_slot_size(512);
+            let framed = lzss.compress_framed_with(&data, &options);
+            let header = FrameHeader::parse(&framed).unwrap();
+            assert_eq!(header.checksum_kind(), checksum);
+
+            let decoded = lzss.try_decompress(&framed).unwrap();
+            assert_eq!(&decoded[..data.len()], &data[..]);
+            assert_eq!(header.verify(&decoded), Ok(()));
+            let chunks = lzss.decompress_iter(&framed).with_chunk_size(4096).collect::<Result<Vec<_>, _>>();
+            assert_eq!(chunks.unwrap().concat(), decoded);
+        }
+        assert_eq!(
+            FrameHeader::parse(&lzss.compress_framed_with(&data, &FrameOptions::default().with_checksum(Checksum::Crc32)))
+                .unwrap()
+                .checksum,
+            crc32fast::hash(&data) as u64
+        );
+
+        // Flipping a literal changes the content but not the structure
+        let options = FrameOptions::default().with_checksum(Checksum::Xxh3);
+        let mut framed = lzss.compress_framed_with(b"checksums catch silent corruption", &options);
+        let last = framed.len() - 1;
+        framed[last] ^= 0x20;
+        let mut output = vec![0u8; 33];
+        assert!(matches!(lzss.decompress_into(&framed, &mut output), Err(LzssError::ChecksumMismatch { .. })));
+        assert!(matches!(lzss.decompress_iter(&framed).last(), Some(Err(LzssError::ChecksumMismatch { .. }))));
+
+        // Headers selecting two checksums at once are rejected
+        let mut framed = lzss.compress_framed_with(&data, &FrameOptions::default().with_checksum(Checksum::Crc32));
+        framed[8] |= crate::frame::FLAG_XXH3;
+        assert!(FrameHeader::parse(&framed).is_none());
+    }
+
+    #[test]
+    fn test_parallel_decompress_and_verify() {
+        use crate::block::{VerifyError, ADAPTIVE_BLOCK_SIZE};
+        use crate::LzssError;
+
+        let mut data = generate_pattern_data(5 * ADAPTIVE_BLOCK_SIZE);
+        data.extend(generate_random_data(ADAPTIVE_BLOCK_SIZE));
+        data.extend(generate_pattern_data(ADAPTIVE_BLOCK_SIZE / 2));
+        let compressed = LZSS::new(4096, 3).compress_adaptive(&data);
+
+        // Each block is checked against a digest recorded at install time
+        let digests: Vec<u32> = data.chunks(ADAPTIVE_BLOCK_SIZE).map(crc32fast::hash).collect();
+        let verify = |index: usize, block: &[u8]| {
+            if crc32fast::hash(block) == digests[index] { Ok(()) } else { Err("bad digest") }
+        };
+        assert_eq!(LZSS::decompress_adaptive_verified(&compressed, 3, 2, verify), Ok(data.clone()));
+
+        let reject_third = |index: usize, _: &[u8]| if index == 2 { Err("rejected") } else { Ok(()) };
+        assert_eq!(
+            LZSS::decompress_adaptive_verified(&compressed, 2, 2, reject_third),
+            Err(VerifyError::Verify { block: 2, error: "rejected" })
+        );
+
+        // Corrupt the size header of the first block
+        let mut corrupt = compressed.clone();
+        corrupt[13] ^= 0x01;
+        assert!(matches!(
+            LZSS::decompress_adaptive_verified(&corrupt, 2, 1, verify),
+            Err(VerifyError::Decode { block: 0, error: LzssError::TruncatedInput })
+        ));
+
+        // A forged size is rejected before anything is allocated
+        let mut forged = compressed.clone();
+        forged[13..17].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            LZSS::decompress_adaptive_verified(&forged, 2, 1, verify),
+            Err(VerifyError::Decode { block: 0, .. })
+        ));
+
+        // References beyond the declared window are corrupt, as in
+        // `try_decompress_adaptive`
+        let mut narrowed = compressed.clone();
+        narrowed[7..9].copy_from_slice(&16u16.to_le_bytes());
+        assert!(matches!(
+            LZSS::decompress_adaptive_verified(&narrowed, 2, 1, verify),
+            Err(VerifyError::Decode { block: 0, error: LzssError::InvalidDistance { .. } })
+        ));
+
+        // Input that isn't a block stream, or ends inside a block, is not
+        // mistaken for fewer blocks
+        assert_eq!(
+            LZSS::decompress_adaptive_verified(b"not a block stream", 2, 1, verify),
+            Err(VerifyError::Decode { block: 0, error: LzssError::InvalidHeader })
+        );
+        assert_eq!(
+            LZSS::decompress_adaptive_verified(&compressed[..compressed.len() - 1], 2, 1, verify),
+            Err(VerifyError::Decode { block: 6, error: LzssError::TruncatedInput })
+        );
+    }
+}
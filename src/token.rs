@@ -0,0 +1,100 @@
+//! Encoding of the match length field in match tokens
+
+use crate::Sink;
+
+/// Width of the match length field in match tokens
+///
+/// The field stores `length - min_match_length`, so the longest match is
+/// set by the field width rather than by the minimum match length alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LengthField {
+    /// One byte, for matches up to `min_match_length + 255` bytes
+    #[default]
+    OneByte,
+    /// Two bytes (little-endian), for matches up to
+    /// `min_match_length + 65535` bytes
+    TwoBytes,
+    /// One byte where 255 is an escape: further bytes follow and are added
+    /// on, each 255 continuing the sequence (as in LZ4). Short matches cost
+    /// one byte and long runs need no extra tokens.
+    Extended,
+}
+
+impl LengthField {
+    /// Largest value the field can store
+    pub fn max_value(self) -> usize {
+        match self {
+            LengthField::OneByte => 255,
+            LengthField::TwoBytes => 65535,
+            // Content sizes are stored in 32 bits
+            LengthField::Extended => u32::MAX as usize,
+        }
+    }
+
+    /// Code identifying the field width in frame headers
+    pub(crate) fn to_code(self) -> u8 {
+        match self {
+            LengthField::OneByte => 0,
+            LengthField::TwoBytes => 1,
+            LengthField::Extended => 2,
+        }
+    }
+
+    /// Field width for a frame header code
+    pub(crate) fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(LengthField::OneByte),
+            1 => Some(LengthField::TwoBytes),
+            2 => Some(LengthField::Extended),
+            _ => None,
+        }
+    }
+
+    /// Bytes taken by the smallest value
+    pub(crate) fn min_bytes(self) -> usize {
+        match self {
+            LengthField::TwoBytes => 2,
+            LengthField::OneByte | LengthField::Extended => 1,
+        }
+    }
+
+    /// Append `value` (at most `max_value()`)
+    pub(crate) fn push(self, output: &mut impl Sink, value: usize) {
+        match self {
+            LengthField::OneByte => output.push(value as u8),
+            LengthField::TwoBytes => output.extend_from_slice(&(value as u16).to_le_bytes()),
+            LengthField::Extended => {
+                let mut rest = value;
+                while rest >= 255 {
+                    output.push(255);
+                    rest -= 255;
+                }
+                output.push(rest as u8);
+            }
+        }
+    }
+
+    /// Read the value at `input[pos..]`, returning it with the number of
+    /// bytes it occupies, or `None` if the input ends first
+    pub(crate) fn read(self, input: &[u8], pos: usize) -> Option<(usize, usize)> {
+        match self {
+            LengthField::OneByte => input.get(pos).map(|&byte| (byte as usize, 1)),
+            LengthField::TwoBytes => {
+                let bytes = input.get(pos..pos + 2)?;
+                Some((u16::from_le_bytes([bytes[0], bytes[1]]) as usize, 2))
+            }
+            LengthField::Extended => {
+                let mut value = 0usize;
+                let mut used = 0;
+                loop {
+                    let byte = *input.get(pos + used)?;
+                    used += 1;
+                    value = value.saturating_add(byte as usize);
+                    if byte != 255 {
+                        return Some((value, used));
+                    }
+                }
+            }
+        }
+    }
+}
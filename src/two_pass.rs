@@ -0,0 +1,108 @@
+//! Two-pass compression
+//!
+//! The first pass compresses with the extended length field, which never
+//! splits a match, and records the length of every match. The second pass
+//! compresses into a frame with the length field that would have encoded
+//! those matches in the fewest bytes: one byte when matches are short, the
+//! extended field for occasional long runs, two bytes when most matches are
+//! very long. Frames record the field, so the output decodes with any
+//! `LZSS`.
+
+use alloc::vec::Vec;
+
+use crate::{LengthField, LZSS};
+
+/// Length fields the second pass chooses from, in order of preference on
+/// ties
+const LENGTH_FIELDS: [LengthField; 3] = [LengthField::OneByte, LengthField::Extended, LengthField::TwoBytes];
+
+impl LZSS {
+    /// Compress `input` into a frame, choosing the match length field from
+    /// a first pass over the input (see `best_length_field`)
+    ///
+    /// Compresses about half as fast as `compress_framed`; the other
+    /// settings are kept.
+    pub fn compress_two_pass(&self, input: &[u8]) -> Vec<u8> {
+        let length_field = self.best_length_field(input);
+        LZSS { ..*self }.with_length_field(length_field).compress_framed(input)
+    }
+
+    /// Length field that encodes the matches found in `input` in the fewest
+    /// bytes
+    ///
+    /// Runs a full compression pass to collect the match lengths, then
+    /// compares the token bytes each field would spend on them, including
+    /// the extra tokens (or literals) needed where a field can't hold a
+    /// whole match. The result applies to raw streams too, as long as the
+    /// decoder is configured with it.
+    pub fn best_length_field(&self, input: &[u8]) -> LengthField {
+        let first_pass = LZSS { ..*self }.with_length_field(LengthField::Extended);
+        let stream = first_pass.compress(input);
+
+        // Token bits each field would spend, literals being the same for all
+        let mut costs = [0usize; LENGTH_FIELDS.len()];
+        for length in first_pass.match_lengths(&stream) {
+            for (cost, &field) in costs.iter_mut().zip(&LENGTH_FIELDS) {
+                *cost += self.match_cost(field, length);
+            }
+        }
+
+        let mut best = 0;
+        for (i, &cost) in costs.iter().enumerate() {
+            if cost < costs[best] {
+                best = i;
+            }
+        }
+        LENGTH_FIELDS[best]
+    }
+
+    /// Bits spent on a match of `length` bytes with `field`, split into as
+    /// many tokens as the field needs
+    fn match_cost(&self, field: LengthField, length: usize) -> usize {
+        let encoder = LZSS { ..*self }.with_length_field(field);
+        let token_bits = |value: usize| {
+            let length_bytes = match field {
+                LengthField::Extended => 1 + value / 255,
+                _ => field.min_bytes(),
+            };
+            1 + 8 * (encoder.offset_bytes() + length_bytes)
+        };
+
+        let max_length = encoder.max_match_length();
+        let full_tokens = length / max_length;
+        let rest = length % max_length;
+        let mut bits = full_tokens * token_bits(max_length - self.min_match_length);
+        if rest >= self.min_match_length {
+            bits += token_bits(rest - self.min_match_length);
+        } else {
+            // Too short for a token: the bytes become literals
+            bits += rest * 9;
+        }
+        bits
+    }
+
+    /// Lengths of the matches in `stream`, a stream produced by this
+    /// encoder
+    fn match_lengths<'a>(&'a self, stream: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        let mut pos = 4;
+        let mut control_byte = 0u8;
+        let mut bit = 8;
+        core::iter::from_fn(move || loop {
+            if bit == 8 {
+                control_byte = *stream.get(pos)?;
+                pos += 1;
+                bit = 0;
+            }
+            let is_match = control_byte & (1 << bit) != 0;
+            bit += 1;
+            if !is_match {
+                pos += 1;
+                continue;
+            }
+            let (_, distance_bytes) = self.read_distance(stream, pos)?;
+            let (length, length_bytes) = self.length_field.read(stream, pos + distance_bytes)?;
+            pos += distance_bytes + length_bytes;
+            return Some(length + self.min_match_length);
+        })
+    }
+}
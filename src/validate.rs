@@ -0,0 +1,223 @@
+//! Structural validation of compressed streams
+//!
+//! Walks the tokens of a stream without producing output, to check that it
+//! decodes cleanly and whether it conforms to target-specific constraints
+//! such as the no-overlap rule of hardware decoders.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{frame, LzssError, LZSS};
+
+/// Token statistics of a valid stream
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamStats {
+    /// Uncompressed size recorded in the stream header
+    pub content_size: usize,
+    /// Number of literal tokens
+    pub literals: usize,
+    /// Number of match tokens
+    pub matches: usize,
+    /// Matches longer than their distance, which copy bytes written by the
+    /// match itself (unsupported by some hardware decoders)
+    pub overlapping_matches: usize,
+    /// Longest match distance used
+    pub max_distance: usize,
+    /// Longest match length used
+    pub max_match_length: usize,
+    /// Bytes of input the stream occupies; anything after them is not part
+    /// of it
+    pub compressed_size: usize,
+}
+
+impl StreamStats {
+    /// Whether the stream can be decoded by decoders that can't copy from
+    /// the bytes they are writing
+    pub fn is_overlap_free(&self) -> bool {
+        self.overlapping_matches == 0
+    }
+}
+
+impl LZSS {
+    /// Check that `input` decodes cleanly and gather its token statistics
+    ///
+    /// Raw streams are read with this instance's parameters; frames with the
+    /// parameters in their header. Errors are the same as strict decoding
+    /// reports, but no output is produced.
+    pub fn validate(&self, input: &[u8]) -> Result<StreamStats, LzssError> {
+        // Without its dictionary, a dictionary frame's matches can't be
+        // checked
+        if let Some(id) = frame::FrameHeader::parse(input).and_then(|header| header.dictionary_id) {
+            return Err(LzssError::MissingDictionary { id });
+        }
+        self.validate_with_history(input, 0)
+    }
+
+    /// Validate a frame or raw stream whose matches may reach `history`
+    /// bytes before its start (a dictionary)
+    pub(crate) fn validate_with_history(&self, input: &[u8], history: usize) -> Result<StreamStats, LzssError> {
+        if let Some(header) = frame::FrameHeader::parse(input) {
+            let window = header.window_size.unwrap_or(usize::MAX);
+            return header.decoder().validate_stream_within(header.stream(input), history, window);
+        }
+        self.validate_stream(input, history)
+    }
+
+    /// Validate a raw stream whose matches may reach `history` bytes before
+    /// its start
+    pub(crate) fn validate_stream(&self, input: &[u8], history: usize) -> Result<StreamStats, LzssError> {
+        self.validate_stream_within(input, history, usize::MAX)
+    }
+
+    /// Validate like `validate_stream`, also rejecting matches that reach
+    /// farther back than `window`
+    pub(crate) fn validate_stream_within(
+        &self,
+        input: &[u8],
+        history: usize,
+        window: usize,
+    ) -> Result<StreamStats, LzssError> {
+        if input.is_empty() {
+            return Ok(StreamStats::default()); // Empty input compresses to nothing
+        }
+        if input.len() < 5 {
+            return Err(LzssError::TruncatedInput);
+        }
+
+        let mut stats = StreamStats {
+            content_size: u32::from_le_bytes([input[0], input[1], input[2], input[3]]) as usize,
+            ..StreamStats::default()
+        };
+        let mut produced = 0;
+        let mut pos = 4;
+
+        while produced < stats.content_size {
+            if pos >= input.len() {
+                return Err(LzssError::SizeMismatch { expected: stats.content_size, actual: produced });
+            }
+            let control_byte = input[pos];
+            pos += 1;
+
+            for bit in 0..8 {
+                if produced >= stats.content_size {
+                    break;
+                }
+
+                if control_byte & (1 << bit) == 0 {
+                    if pos >= input.len() {
+                        return Err(LzssError::TruncatedInput);
+                    }
+                    stats.literals += 1;
+                    produced += 1;
+                    pos += 1;
+                    continue;
+                }
+
+                let (distance, distance_bytes) = self.read_distance(input, pos).ok_or(LzssError::TruncatedInput)?;
+                let (length, length_bytes) = self.length_field
+                    .read(input, pos + distance_bytes)
+                    .ok_or(LzssError::TruncatedInput)?;
+                let length = length.saturating_add(self.min_match_length);
+                pos += distance_bytes + length_bytes;
+
+                if distance == 0 || distance > produced + history || distance > window {
+                    return Err(LzssError::InvalidDistance { position: produced, distance });
+                }
+
+                // The last match may run past the end, which decoders cut off
+                let length = length.min(stats.content_size - produced);
+                stats.matches += 1;
+                if length > distance {
+                    stats.overlapping_matches += 1;
+                }
+                stats.max_distance = stats.max_distance.max(distance);
+                stats.max_match_length = stats.max_match_length.max(length);
+                produced += length;
+            }
+        }
+
+        stats.compressed_size = pos;
+        Ok(stats)
+    }
+}
+
+/// A fixed compressed stream and the content it must decode to
+#[derive(Debug, Clone, Copy)]
+pub struct ConformanceVector {
+    /// Short description of what the vector exercises
+    pub name: &'static str,
+    /// Compressed input, read with `LZSS::new(4096, 3)` unless it is a frame
+    pub compressed: &'static [u8],
+    /// Expected decoded content
+    pub expected: &'static [u8],
+}
+
+/// Built-in vectors covering each token kind and frame layout
+pub const CONFORMANCE_VECTORS: &[ConformanceVector] = &[
+    ConformanceVector { name: "empty input", compressed: &[], expected: &[] },
+    ConformanceVector {
+        name: "literals only",
+        compressed: &[3, 0, 0, 0, 0b0000_0000, b'a', b'b', b'c'],
+        expected: b"abc",
+    },
+    ConformanceVector {
+        name: "match",
+        compressed: &[9, 0, 0, 0, 0b0000_1000, b'a', b'b', b'c', 3, 0, 3],
+        expected: b"abcabcabc",
+    },
+    ConformanceVector {
+        name: "overlapping match",
+        compressed: &[64, 0, 0, 0, 0b0000_0010, 7, 1, 0, 60],
+        expected: &[7; 64],
+    },
+    ConformanceVector {
+        name: "version 1 frame",
+        compressed: &[b'R', b'L', b'Z', b'S', 1, 3, 2, 64, 0, 0, 0, 0b0000_0010, 7, 1, 0, 60],
+        expected: &[7; 64],
+    },
+    ConformanceVector {
+        name: "frame storing distance minus one",
+        compressed: &[b'R', b'L', b'Z', b'S', 2, 3, 2, 0, 0x01, 64, 0, 0, 0, 0b0000_0010, 7, 0, 0, 60],
+        expected: &[7; 64],
+    },
+    ConformanceVector {
+        name: "frame with extended length field",
+        compressed: &[b'R', b'L', b'Z', b'S', 2, 3, 2, 2, 0x01, 44, 1, 0, 0, 0b0000_0010, 7, 0, 0, 255, 41],
+        expected: &[7; 300],
+    },
+    ConformanceVector {
+        name: "frame with CRC32 checksum",
+        compressed: &[b'R', b'L', b'Z', b'S', 2, 3, 2, 0, 0x09, 184, 89, 60, 213, 64, 0, 0, 0, 0b0000_0010, 7, 0, 0, 60],
+        expected: &[7; 64],
+    },
+    ConformanceVector {
+        name: "padded frame",
+        compressed: &[
+            b'R', b'L', b'Z', b'S', 2, 3, 2, 0, 0x03, 3, 0, 0, 0,
+            3, 0, 0, 0, 0b0000_0000, b'x', b'y', b'z', 0, 0, 0,
+        ],
+        expected: b"xyz",
+    },
+];
+
+impl ConformanceVector {
+    /// Decode the vector with every decoder and report the first mismatch
+    pub fn check(&self) -> Result<(), String> {
+        let lzss = LZSS::new(4096, 3);
+        let stats = lzss.validate(self.compressed).map_err(|e| format!("validation failed: {}", e))?;
+        if stats.content_size != self.expected.len() {
+            return Err(format!("validation reported {} bytes, expected {}", stats.content_size, self.expected.len()));
+        }
+        let decompressed = lzss.try_decompress(self.compressed).map_err(|e| format!("decompression failed: {}", e))?;
+        if decompressed != self.expected {
+            return Err("decompress produced wrong output".to_string());
+        }
+        let chunks = lzss.decompress_iter(self.compressed).collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("chunked decoding failed: {}", e))?;
+        if chunks.concat() != self.expected {
+            return Err("chunked decoding produced wrong output".to_string());
+        }
+        Ok(())
+    }
+}
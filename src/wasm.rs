@@ -0,0 +1,215 @@
+//! WebAssembly bindings generated with wasm-bindgen
+//!
+//! Browser tools such as level editors get the same streams the engine
+//! reads: `compress`, `compressFramed` and `decompress` take and return
+//! `Uint8Array`s, and the `Compressor` and `Decompressor` classes wrap the
+//! persistent-window API. `CompressorStream` and `DecompressorStream` are
+//! Web Streams transformers over the `stream` block format, so large assets
+//! can be piped through `new TransformStream(...)` a chunk at a time.
+//! Decoding errors are thrown as JS `Error`s carrying the `LzssError`
+//! message.
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use wasm_bindgen::prelude::*;
+
+use crate::stream::{self, Decoder, Encoder};
+use crate::{compressor, LzssError, LZSS};
+
+/// Window size used when JS callers leave it out
+const DEFAULT_WINDOW_SIZE: usize = 4096;
+
+/// Minimum match length used when JS callers leave it out
+const DEFAULT_MIN_MATCH_LENGTH: usize = 3;
+
+fn codec(window_size: Option<usize>, min_match_length: Option<usize>) -> LZSS {
+    LZSS::new(
+        window_size.unwrap_or(DEFAULT_WINDOW_SIZE),
+        min_match_length.unwrap_or(DEFAULT_MIN_MATCH_LENGTH),
+    )
+}
+
+#[wasm_bindgen]
+extern "C" {
+    /// Controller a `TransformStream` passes to its transformer
+    #[wasm_bindgen(typescript_type = "TransformStreamDefaultController<Uint8Array>")]
+    pub type TransformStreamDefaultController;
+
+    #[wasm_bindgen(method)]
+    fn enqueue(this: &TransformStreamDefaultController, chunk: Vec<u8>);
+}
+
+/// Pass `output` on unless it is empty
+fn enqueue(controller: &TransformStreamDefaultController, output: Vec<u8>) {
+    if !output.is_empty() {
+        controller.enqueue(output);
+    }
+}
+
+fn js_error(error: LzssError) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+/// Compress `input` into a raw stream (window 4096 and minimum match 3 by
+/// default)
+#[wasm_bindgen]
+pub fn compress(input: &[u8], window_size: Option<usize>, min_match_length: Option<usize>) -> Vec<u8> {
+    codec(window_size, min_match_length).compress(input)
+}
+
+/// Compress `input` into a self-describing frame, which `decompress` reads
+/// without being told the parameters
+#[wasm_bindgen(js_name = compressFramed)]
+pub fn compress_framed(input: &[u8], window_size: Option<usize>, min_match_length: Option<usize>) -> Vec<u8> {
+    codec(window_size, min_match_length).compress_framed(input)
+}
+
+/// Decompress a frame, or a raw stream compressed with the given parameters
+#[wasm_bindgen]
+pub fn decompress(input: &[u8], window_size: Option<usize>, min_match_length: Option<usize>) -> Result<Vec<u8>, JsError> {
+    codec(window_size, min_match_length).try_decompress(input).map_err(js_error)
+}
+
+/// Compressor that keeps its window between `compress()` calls
+#[wasm_bindgen]
+pub struct Compressor {
+    inner: compressor::Compressor,
+}
+
+#[wasm_bindgen]
+impl Compressor {
+    /// Create a compressor, optionally starting from a preset dictionary
+    #[wasm_bindgen(constructor)]
+    pub fn new(window_size: Option<usize>, min_match_length: Option<usize>, dictionary: Option<Vec<u8>>) -> Self {
+        let inner = compressor::Compressor::new(codec(window_size, min_match_length))
+            .with_dictionary(&dictionary.unwrap_or_default());
+        Compressor { inner }
+    }
+
+    /// Compress the next payload, referencing earlier ones
+    pub fn compress(&mut self, input: &[u8]) -> Vec<u8> {
+        self.inner.compress(input)
+    }
+
+    /// Forget all previous payloads
+    #[wasm_bindgen(js_name = resetWindow)]
+    pub fn reset_window(&mut self) {
+        self.inner.reset_window();
+    }
+}
+
+/// Decompressor matching a `Compressor`'s window
+#[wasm_bindgen]
+pub struct Decompressor {
+    inner: compressor::Decompressor,
+}
+
+#[wasm_bindgen]
+impl Decompressor {
+    /// Create a decompressor with the compressor's parameters and dictionary
+    #[wasm_bindgen(constructor)]
+    pub fn new(window_size: Option<usize>, min_match_length: Option<usize>, dictionary: Option<Vec<u8>>) -> Self {
+        let inner = compressor::Decompressor::new(codec(window_size, min_match_length))
+            .with_dictionary(&dictionary.unwrap_or_default());
+        Decompressor { inner }
+    }
+
+    /// Decompress the next payload of the matching `Compressor`
+    pub fn decompress(&mut self, input: &[u8]) -> Result<Vec<u8>, JsError> {
+        self.inner.try_decompress(input).map_err(js_error)
+    }
+
+    /// Forget all previous payloads, mirroring `Compressor.resetWindow`
+    #[wasm_bindgen(js_name = resetWindow)]
+    pub fn reset_window(&mut self) {
+        self.inner.reset_window();
+    }
+}
+
+/// Transformer compressing a byte stream into the `stream` block format
+///
+/// Use it as `new TransformStream(new CompressorStream())`. Input is
+/// compressed a block at a time (64 KiB by default) with the window carried
+/// across blocks, so only one block is held in memory.
+#[wasm_bindgen]
+pub struct CompressorStream {
+    encoder: Encoder,
+}
+
+#[wasm_bindgen]
+impl CompressorStream {
+    /// Create a transformer, optionally with the uncompressed bytes per block
+    #[wasm_bindgen(constructor)]
+    pub fn new(window_size: Option<usize>, min_match_length: Option<usize>, block_size: Option<usize>) -> Self {
+        let encoder = Encoder::new(codec(window_size, min_match_length))
+            .with_block_size(block_size.unwrap_or(stream::DEFAULT_BLOCK_SIZE));
+        CompressorStream { encoder }
+    }
+
+    /// Compress a chunk, enqueueing the blocks it completes
+    pub fn transform(&mut self, chunk: &[u8], controller: &TransformStreamDefaultController) {
+        enqueue(controller, self.encoder.feed(chunk));
+    }
+
+    /// Compress the last partial block at the end of the input
+    pub fn flush(&mut self, controller: &TransformStreamDefaultController) {
+        enqueue(controller, self.encoder.flush());
+    }
+}
+
+/// Transformer decompressing the output of a `CompressorStream`
+///
+/// Use it as `new TransformStream(new DecompressorStream())` with the
+/// compressor's parameters. Each complete block is decoded as soon as it
+/// arrives; a corrupt block or a stream that ends inside a block errors the
+/// stream.
+#[wasm_bindgen]
+pub struct DecompressorStream {
+    decoder: Decoder,
+    pending: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl DecompressorStream {
+    /// Create a transformer for a stream compressed with the given parameters
+    #[wasm_bindgen(constructor)]
+    pub fn new(window_size: Option<usize>, min_match_length: Option<usize>) -> Self {
+        DecompressorStream {
+            decoder: Decoder::new(codec(window_size, min_match_length)),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Decode the blocks completed by a chunk, keeping any partial block
+    pub fn transform(&mut self, chunk: &[u8], controller: &TransformStreamDefaultController) -> Result<(), JsError> {
+        enqueue(controller, self.decode(chunk).map_err(js_error)?);
+        Ok(())
+    }
+
+    /// Check that the stream didn't end inside a block
+    pub fn flush(&mut self) -> Result<(), JsError> {
+        self.finish().map_err(js_error)
+    }
+}
+
+impl DecompressorStream {
+    pub(crate) fn decode(&mut self, chunk: &[u8]) -> Result<Vec<u8>, LzssError> {
+        self.pending.extend_from_slice(chunk);
+        let progress = self.decoder.decode(&self.pending)?;
+        self.pending.drain(..progress.consumed);
+        if !progress.needs_input && !self.pending.is_empty() {
+            // A complete block is left over because it is corrupt
+            self.decoder.decode(&self.pending)?;
+        }
+        Ok(progress.output)
+    }
+
+    pub(crate) fn finish(&self) -> Result<(), LzssError> {
+        if self.pending.is_empty() {
+            Ok(())
+        } else {
+            Err(LzssError::TruncatedInput)
+        }
+    }
+}